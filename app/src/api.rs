@@ -0,0 +1,236 @@
+//! An HTTP API to control the program from scripts and other devices on the network.
+//!
+//! - `GET /state` returns what's being played, like `legion-kb-rgb status --json`
+//! - `POST /profile/{name}` applies a saved profile
+//! - `POST /effect` plays an effect, taking the same JSON as the MQTT command topic
+//! - `POST /trigger/{name}` fires the webhook trigger of that name
+//! - `GET /frames` upgrades to a WebSocket that streams the zone colors as they're sent to the keyboard
+//!
+//! Every request needs the token from the settings, as `Authorization: Bearer <token>` or `?token=<token>` for clients
+//! like browsers that can't set headers on WebSockets.
+
+use serde::{Deserialize, Serialize};
+
+/// Where nothing else is known to listen, on the loopback interface so it has to be exposed on purpose
+pub const DEFAULT_BIND: &str = "127.0.0.1:7645";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiSettings {
+    pub enabled: bool,
+    /// The address and port to listen on
+    pub bind: String,
+    pub token: String,
+}
+
+impl Default for ApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: DEFAULT_BIND.to_string(),
+            token: String::new(),
+        }
+    }
+}
+
+#[cfg(all(feature = "gui", feature = "network"))]
+pub use server::{ApiCommand, ApiServer};
+
+#[cfg(all(feature = "gui", feature = "network"))]
+mod server {
+    use std::{
+        io::{Read, Write},
+        sync::Arc,
+        thread,
+    };
+
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use error_stack::{Report, Result, ResultExt};
+    use sha1::{Digest, Sha1};
+    use thiserror::Error;
+    use tiny_http::{Header, Method, Request, Response, Server};
+    use tracing::{info, warn};
+
+    use super::ApiSettings;
+    use crate::{
+        manager::{preview::OutputPreview, profile::Profile},
+        remote::{self, EffectRequest},
+        status::Status,
+        util::percent_decode,
+    };
+
+    const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+    #[derive(Debug, Error)]
+    #[error("Could not start the HTTP API")]
+    pub struct StartApiError;
+
+    /// What the API asks the rest of the program to do
+    pub enum ApiCommand {
+        /// Apply a saved profile, by name
+        LoadProfile(String),
+        Apply(Profile),
+        /// Fire a webhook trigger, by name
+        Trigger(String),
+    }
+
+    /// Serves the API until dropped
+    pub struct ApiServer {
+        server: Arc<Server>,
+    }
+
+    impl ApiServer {
+        /// Listen on the address in `settings`, passing what's asked for to `on_command` and streaming what `preview`
+        /// shows
+        pub fn start(settings: &ApiSettings, preview: OutputPreview, on_command: impl Fn(ApiCommand) + Send + 'static) -> Result<Self, StartApiError> {
+            if settings.token.is_empty() {
+                return Err(Report::new(StartApiError).attach_printable("Set a token first, anyone on the network could control the keyboard otherwise"));
+            }
+
+            let server = Arc::new(
+                Server::http(&settings.bind)
+                    .map_err(|err| Report::new(StartApiError).attach_printable(err.to_string()))
+                    .attach_printable_lazy(|| format!("Could not listen on {}", settings.bind))?,
+            );
+            info!(bind = %settings.bind, "Serving the HTTP API");
+
+            {
+                let server = server.clone();
+                let token = settings.token.clone();
+                thread::spawn(move || {
+                    for request in server.incoming_requests() {
+                        handle(request, &token, &preview, &on_command);
+                    }
+                });
+            }
+
+            Ok(Self { server })
+        }
+    }
+
+    impl Drop for ApiServer {
+        fn drop(&mut self) {
+            self.server.unblock();
+        }
+    }
+
+    fn handle(mut request: Request, token: &str, preview: &OutputPreview, on_command: &dyn Fn(ApiCommand)) {
+        if !is_authorized(&request, token) {
+            let _ = request.respond(Response::from_string("Missing or wrong token").with_status_code(401));
+            return;
+        }
+
+        let method = request.method().clone();
+        let path = request.url().split('?').next().unwrap_or_default().to_string();
+        let response = match (method, path.as_str()) {
+            (Method::Get, "/state") => match Status::load() {
+                Some(mut status) => {
+                    status.applied = None;
+                    json(&serde_json::to_string(&status).unwrap_or_default())
+                }
+                None => Response::from_string("Nothing was applied yet").with_status_code(404),
+            },
+            (Method::Post, "/effect") => {
+                let mut body = String::new();
+                let _ = request.as_reader().read_to_string(&mut body);
+
+                match serde_json::from_str::<EffectRequest>(&body) {
+                    Ok(effect) => {
+                        on_command(ApiCommand::Apply(effect.into_profile()));
+                        Response::from_string("").with_status_code(204)
+                    }
+                    Err(err) => Response::from_string(format!("Invalid effect: {err}")).with_status_code(400),
+                }
+            }
+            (Method::Post, path) if path.starts_with("/profile/") => {
+                let name = percent_decode(&path["/profile/".len()..]);
+
+                if remote::saved_profile(&name).is_some() {
+                    on_command(ApiCommand::LoadProfile(name));
+                    Response::from_string("").with_status_code(204)
+                } else {
+                    Response::from_string(format!("There's no profile called \"{name}\"")).with_status_code(404)
+                }
+            }
+            (Method::Post, path) if path.starts_with("/trigger/") => {
+                // Whether there's such a trigger is only known to whoever handles it
+                on_command(ApiCommand::Trigger(percent_decode(&path["/trigger/".len()..])));
+                Response::from_string("").with_status_code(202)
+            }
+            (Method::Get, "/frames") => {
+                stream_frames(request, preview.clone());
+                return;
+            }
+            _ => Response::from_string("Not found").with_status_code(404),
+        };
+
+        let _ = request.respond(response);
+    }
+
+    fn is_authorized(request: &Request, token: &str) -> bool {
+        let bearer = format!("Bearer {token}");
+        let header = request.headers().iter().any(|header| header.field.equiv("Authorization") && header.value.as_str() == bearer);
+        let query = request
+            .url()
+            .split_once('?')
+            .is_some_and(|(_, query)| query.split('&').any(|pair| pair.strip_prefix("token=").is_some_and(|value| percent_decode(value) == token)));
+
+        header || query
+    }
+
+    fn json(body: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+        Response::from_string(body).with_header(Header::from_bytes("Content-Type", "application/json").unwrap())
+    }
+
+    /// Send the zone colors as `[[r, g, b], ...]` text messages whenever they change, until the client goes away
+    fn stream_frames(request: Request, preview: OutputPreview) {
+        let Some(key) = request.headers().iter().find(|header| header.field.equiv("Sec-WebSocket-Key")).map(|header| header.value.to_string()) else {
+            let _ = request.respond(Response::from_string("Expected a WebSocket").with_status_code(400));
+            return;
+        };
+
+        let accept = STANDARD.encode(Sha1::digest(format!("{key}{WEBSOCKET_GUID}")));
+        let response = Response::empty(101).with_header(Header::from_bytes("Sec-WebSocket-Accept", accept).unwrap());
+        let mut stream = request.upgrade("websocket", response);
+
+        let states = preview.subscribe();
+        thread::spawn(move || {
+            let mut last_sent = None;
+
+            for state in preview.latest().into_iter().chain(states.iter()) {
+                // Only the newest of the states that piled up while writing matters
+                let state = states.try_iter().last().unwrap_or(state);
+                let rgb = state.rgb_values();
+                let colors = [0, 1, 2, 3].map(|zone| [rgb[zone * 3], rgb[zone * 3 + 1], rgb[zone * 3 + 2]]);
+
+                if last_sent != Some(colors) {
+                    let message = serde_json::to_string(&colors).unwrap_or_default();
+                    if let Err(err) = stream.write_all(&text_frame(&message)).and_then(|()| stream.flush()) {
+                        warn!("Stopped streaming frames: {err}");
+                        return;
+                    }
+                    last_sent = Some(colors);
+                }
+            }
+        });
+    }
+
+    /// A WebSocket text message, unmasked as it goes from the server to the client
+    fn text_frame(message: &str) -> Vec<u8> {
+        let mut frame = vec![0x81];
+        match message.len() {
+            len @ 0..=125 => frame.push(len as u8),
+            len @ 126..=0xffff => {
+                frame.push(126);
+                frame.extend((len as u16).to_be_bytes());
+            }
+            len => {
+                frame.push(127);
+                frame.extend((len as u64).to_be_bytes());
+            }
+        }
+
+        frame.extend(message.as_bytes());
+        frame
+    }
+}
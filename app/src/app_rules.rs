@@ -0,0 +1,68 @@
+//! Automatic profile switching depending on what application is focused.
+
+use serde::{Deserialize, Serialize};
+use strum_macros::{EnumIter, IntoStaticStr};
+
+#[cfg(feature = "gui")]
+use crate::foreground::ForegroundWindow;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FullscreenSettings {
+    pub enabled: bool,
+    /// Name of the profile to switch to while a fullscreen application is focused
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, EnumIter, IntoStaticStr)]
+pub enum RuleTarget {
+    #[default]
+    #[strum(serialize = "Process name")]
+    ProcessName,
+    #[strum(serialize = "Window title")]
+    WindowTitle,
+}
+
+/// Apply `profile` whenever the focused window matches `pattern`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppRule {
+    pub pattern: String,
+    pub target: RuleTarget,
+    pub profile: String,
+}
+
+#[cfg(feature = "gui")]
+impl AppRule {
+    pub fn matches(&self, window: &ForegroundWindow) -> bool {
+        let pattern = self.pattern.trim().to_lowercase();
+        if pattern.is_empty() {
+            return false;
+        }
+
+        match self.target {
+            // Allow writing both "code" and "code.exe"
+            RuleTarget::ProcessName => {
+                let process_name = window.process_name.to_lowercase();
+                process_name == pattern || process_name.strip_suffix(".exe") == Some(pattern.as_str())
+            }
+            RuleTarget::WindowTitle => window.title.to_lowercase().contains(&pattern),
+        }
+    }
+}
+
+/// Find the name of the profile that should be active for the focused window, if any.
+/// Application rules take priority over the fullscreen setting.
+#[cfg(feature = "gui")]
+pub fn find_profile<'a>(rules: &'a [AppRule], fullscreen: &'a FullscreenSettings, window: Option<&ForegroundWindow>) -> Option<&'a str> {
+    let window = window?;
+
+    if let Some(rule) = rules.iter().find(|rule| rule.matches(window)) {
+        return Some(&rule.profile);
+    }
+
+    if fullscreen.enabled && window.fullscreen {
+        return fullscreen.profile.as_deref();
+    }
+
+    None
+}
@@ -0,0 +1,30 @@
+//! Look of the GUI, stored with the rest of the settings so headless builds keep it intact.
+
+use serde::{Deserialize, Serialize};
+use strum_macros::{EnumIter, IntoStaticStr};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, EnumIter, IntoStaticStr)]
+pub enum ThemeMode {
+    #[default]
+    Dark,
+    Light,
+    #[strum(serialize = "Follow system")]
+    System,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Appearance {
+    pub theme: ThemeMode,
+    /// Color of selected items and highlights
+    pub accent: [u8; 3],
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Self {
+            theme: ThemeMode::default(),
+            accent: [40, 100, 160],
+        }
+    }
+}
@@ -0,0 +1,111 @@
+//! Watches the backlight level the laptop itself sets when pressing Fn+Space, so the app can follow along instead of
+//! fighting it.
+//!
+//! On Linux the level is read from the keyboard backlight LED the `ideapad_laptop` or Legion kernel module exposes,
+//! going from 0 (off) to 2 (high). There's no way to tell on other systems yet.
+
+#[cfg(feature = "gui")]
+use std::{thread, time::Duration};
+
+#[cfg(feature = "gui")]
+use crossbeam_channel::{RecvTimeoutError, Sender};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "gui")]
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// How much the brightness hotkeys change the scale by, in percent
+#[cfg(feature = "gui")]
+pub const SCALE_STEP: u8 = 10;
+/// The dimmest the scale goes, turning the lights off is left to the hotkey doing just that
+#[cfg(feature = "gui")]
+pub const MIN_SCALE: u8 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BacklightSettings {
+    /// Percentage every color sent is scaled to, on top of the brightness of the profile
+    ///
+    /// Effects animated by the keyboard itself get their colors once and can't be scaled.
+    pub scale: u8,
+    /// Follow the level set with Fn+Space, turning the lights off or changing the brightness of the profile
+    pub follow_laptop_key: bool,
+}
+
+impl Default for BacklightSettings {
+    fn default() -> Self {
+        Self {
+            scale: 100,
+            follow_laptop_key: false,
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl BacklightSettings {
+    /// Move the scale up (positive) or down (negative) by that many steps
+    pub fn step_scale(&mut self, steps: i8) {
+        let scale = i16::from(self.scale) + i16::from(steps) * i16::from(SCALE_STEP);
+        self.scale = scale.clamp(i16::from(MIN_SCALE), 100) as u8;
+    }
+}
+
+/// Watches the backlight level until dropped
+#[cfg(feature = "gui")]
+pub struct BacklightWatcher {
+    /// Dropped to tell the watching thread to stop
+    stop: Option<Sender<()>>,
+}
+
+#[cfg(feature = "gui")]
+impl Drop for BacklightWatcher {
+    fn drop(&mut self) {
+        self.stop.take();
+    }
+}
+
+/// Spawn a background thread that calls `on_change` with the new level whenever the laptop changes it
+///
+/// The level found when starting isn't reported, as the app sets its own then. Nothing is watched if the laptop doesn't
+/// expose the level.
+#[cfg(feature = "gui")]
+pub fn spawn_watcher<F: Fn(u8) + Send + 'static>(on_change: F) -> BacklightWatcher {
+    let (stop_tx, stop_rx) = crossbeam_channel::bounded::<()>(0);
+
+    thread::spawn(move || {
+        let Some(led) = find_led() else {
+            return;
+        };
+        let mut last = read(&led);
+
+        while stop_rx.recv_timeout(POLL_INTERVAL) == Err(RecvTimeoutError::Timeout) {
+            let level = read(&led);
+            if let Some(level) = level.filter(|level| last.is_some_and(|last| last != *level)) {
+                on_change(level);
+            }
+            last = level;
+        }
+    });
+
+    BacklightWatcher { stop: Some(stop_tx) }
+}
+
+/// The brightness file of the keyboard backlight LED
+#[cfg(all(feature = "gui", target_os = "linux"))]
+fn find_led() -> Option<std::path::PathBuf> {
+    let led = std::fs::read_dir("/sys/class/leds")
+        .ok()?
+        .flatten()
+        .find(|entry| entry.file_name().to_string_lossy().ends_with("::kbd_backlight"))?;
+
+    Some(led.path().join("brightness"))
+}
+
+#[cfg(all(feature = "gui", not(target_os = "linux")))]
+fn find_led() -> Option<std::path::PathBuf> {
+    None
+}
+
+#[cfg(feature = "gui")]
+fn read(led: &std::path::Path) -> Option<u8> {
+    std::fs::read_to_string(led).ok()?.trim().parse().ok()
+}
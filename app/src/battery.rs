@@ -0,0 +1,88 @@
+//! Watches the charge of the battery and whether it's charging.
+//!
+//! On Linux the state is read from the first battery under `/sys/class/power_supply`, while on Windows it's asked to
+//! the system directly.
+
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatteryState {
+    /// From 0 to 100
+    pub percent: u8,
+    pub charging: bool,
+}
+
+/// Spawn a background thread that calls `on_change` with the state of the battery, whenever it changes
+///
+/// Only the first call has an effect. Nothing is reported when there's no battery.
+pub fn spawn_watcher<F: Fn(BatteryState) + Send + 'static>(on_change: F) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    thread::spawn(move || {
+        let mut last = None;
+
+        loop {
+            let state = read();
+            if let Some(state) = state.filter(|state| last != Some(*state)) {
+                on_change(state);
+            }
+            last = state;
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+#[cfg(target_os = "linux")]
+fn read() -> Option<BatteryState> {
+    use std::fs;
+
+    let battery = fs::read_dir("/sys/class/power_supply")
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| fs::read_to_string(path.join("type")).is_ok_and(|kind| kind.trim() == "Battery"))?;
+
+    let percent = fs::read_to_string(battery.join("capacity")).ok()?.trim().parse::<u8>().ok()?;
+    let status = fs::read_to_string(battery.join("status")).unwrap_or_default();
+
+    Some(BatteryState {
+        percent: percent.min(100),
+        charging: status.trim() == "Charging",
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn read() -> Option<BatteryState> {
+    use winapi::um::winbase::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    let mut status: SYSTEM_POWER_STATUS = unsafe { std::mem::zeroed() };
+    if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+        return None;
+    }
+
+    // 128 means there's no battery, and 255 that its charge is unknown
+    if status.BatteryFlag & 128 != 0 || status.BatteryLifePercent > 100 {
+        return None;
+    }
+
+    Some(BatteryState {
+        percent: status.BatteryLifePercent,
+        charging: status.BatteryFlag & 8 != 0,
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn read() -> Option<BatteryState> {
+    None
+}
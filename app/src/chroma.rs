@@ -0,0 +1,163 @@
+//! Receives the lighting that games broadcast through Razer Chroma, so they can drive the keyboard zones.
+//!
+//! Razer Synapse sends what games play on Chroma devices to the apps registered with the Chroma Broadcast API, loaded
+//! from `RzChromaBroadcastAPI64.dll` on Windows. Synapse only talks to apps whose ID Razer approved, which builds pass
+//! in through the `CHROMA_APP_ID` environment variable at compile time, as a GUID.
+
+use serde::{Deserialize, Serialize};
+use strum_macros::{EnumIter, IntoStaticStr};
+
+/// What the games broadcast
+#[cfg(feature = "gui")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChromaEvent {
+    /// The colors to show on the four zones, from left to right
+    Colors([[u8; 3]; 4]),
+    /// No game is broadcasting anymore
+    Ended,
+}
+
+/// Whether the lighting of games or the profiles switched to by the app rules wins when both want the keyboard
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, EnumIter, IntoStaticStr)]
+pub enum ChromaPriority {
+    #[default]
+    #[strum(serialize = "Games")]
+    Games,
+    #[strum(serialize = "App rules")]
+    AppRules,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChromaSettings {
+    pub enabled: bool,
+    pub priority: ChromaPriority,
+}
+
+/// Whether the platform has a Chroma Broadcast API to receive from, and the build an app ID to be let in with
+#[cfg(feature = "gui")]
+pub const fn is_supported() -> bool {
+    cfg!(target_os = "windows") && option_env!("CHROMA_APP_ID").is_some()
+}
+
+/// Start listening for broadcasts, calling `on_event` from the thread of Synapse for every one of them
+///
+/// Only the first call has an effect, as the API takes a single callback for the whole process.
+#[cfg(feature = "gui")]
+pub fn start_receiver<F: Fn(ChromaEvent) + Send + Sync + 'static>(on_event: F) {
+    #[cfg(target_os = "windows")]
+    if let Err(err) = platform::register(Box::new(on_event)) {
+        tracing::warn!("Could not receive Chroma broadcasts: {err}");
+    }
+    #[cfg(not(target_os = "windows"))]
+    let _ = on_event;
+}
+
+/// Chroma colors are `COLORREF`s, laid out as `0x00BBGGRR`
+#[cfg(feature = "gui")]
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn from_colorref(color: u32) -> [u8; 3] {
+    let [r, g, b, _] = color.to_le_bytes();
+    [r, g, b]
+}
+
+/// Split a GUID like `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx` into the four words the API takes it as
+#[cfg(feature = "gui")]
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn parse_app_id(app_id: &str) -> Option<[u32; 4]> {
+    let hex: String = app_id.trim_matches(['{', '}']).chars().filter(|c| *c != '-').collect();
+    if hex.len() != 32 {
+        return None;
+    }
+
+    let mut words = [0; 4];
+    for (i, word) in words.iter_mut().enumerate() {
+        *word = u32::from_str_radix(hex.get(i * 8..i * 8 + 8)?, 16).ok()?;
+    }
+
+    Some(words)
+}
+
+#[cfg(all(feature = "gui", target_os = "windows"))]
+mod platform {
+    use std::{ffi::c_void, sync::OnceLock};
+
+    use winapi::um::libloaderapi::{GetProcAddress, LoadLibraryA};
+
+    use super::{from_colorref, parse_app_id, ChromaEvent};
+
+    const LIBRARY: &[u8] = b"RzChromaBroadcastAPI64.dll\0";
+    const BROADCAST_EFFECT: i32 = 1;
+    const BROADCAST_STATUS: i32 = 2;
+    const STATUS_NOT_LIVE: isize = 2;
+
+    type Handler = Box<dyn Fn(ChromaEvent) + Send + Sync>;
+    type Callback = extern "C" fn(i32, *mut c_void) -> i32;
+    type Init = unsafe extern "C" fn(u32, u32, u32, u32) -> i32;
+    type RegisterEventNotification = unsafe extern "C" fn(Callback) -> i32;
+
+    static HANDLER: OnceLock<Handler> = OnceLock::new();
+
+    /// The start of `CHROMA_BROADCAST_EFFECT`, the first four colors cover the keyboard and the fifth the rest of the
+    /// devices
+    #[repr(C)]
+    struct BroadcastEffect {
+        colors: [u32; 5],
+    }
+
+    pub fn register(handler: Handler) -> Result<(), String> {
+        let app_id = option_env!("CHROMA_APP_ID").ok_or("this build has no Chroma app ID")?;
+        let [a, b, c, d] = parse_app_id(app_id).ok_or("the Chroma app ID of this build is not a valid GUID")?;
+        if HANDLER.set(handler).is_err() {
+            return Ok(());
+        }
+
+        // SAFETY: The library is never unloaded, and the functions are only called with the signatures it documents
+        unsafe {
+            let library = LoadLibraryA(LIBRARY.as_ptr().cast());
+            if library.is_null() {
+                return Err("Razer Synapse is not installed".to_string());
+            }
+
+            let init = GetProcAddress(library, b"Init\0".as_ptr().cast());
+            let register = GetProcAddress(library, b"RegisterEventNotification\0".as_ptr().cast());
+            if init.is_null() || register.is_null() {
+                return Err("the installed Chroma Broadcast API is not supported".to_string());
+            }
+
+            let init: Init = std::mem::transmute(init);
+            let register: RegisterEventNotification = std::mem::transmute(register);
+
+            let result = init(a, b, c, d);
+            if result != 0 {
+                return Err(format!("initializing the Chroma Broadcast API failed with {result}"));
+            }
+            let result = register(on_broadcast);
+            if result != 0 {
+                return Err(format!("registering for broadcasts failed with {result}"));
+            }
+        }
+
+        Ok(())
+    }
+
+    extern "C" fn on_broadcast(kind: i32, data: *mut c_void) -> i32 {
+        let Some(handler) = HANDLER.get() else {
+            return 0;
+        };
+
+        match kind {
+            BROADCAST_EFFECT if !data.is_null() => {
+                // SAFETY: Effect broadcasts point to a `CHROMA_BROADCAST_EFFECT` that outlives the call
+                let effect = unsafe { &*data.cast::<BroadcastEffect>() };
+                let colors = [0, 1, 2, 3].map(|i| from_colorref(effect.colors[i]));
+                handler(ChromaEvent::Colors(colors));
+            }
+            // Status broadcasts carry the status itself instead of pointing to it
+            BROADCAST_STATUS if data as isize == STATUS_NOT_LIVE => handler(ChromaEvent::Ended),
+            _ => {}
+        }
+
+        0
+    }
+}
@@ -1,20 +1,50 @@
-use std::{convert::TryInto, path::PathBuf, str::FromStr};
+use std::{
+    convert::TryInto,
+    ffi::OsStr,
+    io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    thread,
+    time::Duration,
+};
 
-use clap::{arg, command, Parser, Subcommand};
-use error_stack::{Result, ResultExt};
+use clap::{arg, command, Args, CommandFactory, Parser, Subcommand};
+use clap_complete::{
+    engine::{ArgValueCompleter, CompletionCandidate},
+    env::{Bash, Elvish, EnvCompleter, Fish, Powershell, Zsh},
+    CompleteEnv, Shell,
+};
+use error_stack::{Report, Result, ResultExt};
+use legion_kb_rgb_hw::models::{self, Model, Protocol};
+use serde::Serialize;
 use strum::IntoEnumIterator;
 use thiserror::Error;
+use tracing::{level_filters::LevelFilter, warn};
 
 use crate::{
+    crash, diagnostics, effect_library,
     enums::{Brightness, Direction, Effects},
+    instance, logging,
     manager::{
         self,
         custom_effect::CustomEffect,
         profile::{self, Profile},
+        recorder::FrameRecorder,
+        registry::{DeviceRegistry, DeviceSelector},
         ManagerCreationError,
     },
+    persist::Settings,
+    startup::StartupSettings,
+    status::{Applied, Status},
     DENY_HIDING,
 };
+#[cfg(feature = "network")]
+use crate::mqtt;
+#[cfg(target_os = "linux")]
+use crate::udev;
+
+/// Set by the completion scripts when they call the program back
+const COMPLETE_VAR: &str = "LEGION_KB_RGB_COMPLETE";
 
 #[macro_export]
 macro_rules! clap_value_parser {
@@ -44,6 +74,59 @@ struct Cli {
     /// Do not show the window when launching (use along the --gui flag)
     #[arg(short = 'w', long, default_value_t = false)]
     hide_window: bool,
+
+    /// Use a different settings file
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Ask an already running instance to shut down and take control of the keyboard
+    #[arg(long, global = true, default_value_t = false)]
+    takeover: bool,
+
+    /// Start the GUI with the default settings, a static profile and no integrations, to recover from a broken configuration
+    #[arg(long = "safe-mode", default_value_t = false)]
+    safe_mode: bool,
+
+    /// Start the GUI in the tray without sending anything to the keyboard until a profile is picked
+    #[arg(long = "no-apply", default_value_t = false)]
+    no_apply: bool,
+
+    /// Log what the program is doing in more detail (same as --log-level debug)
+    #[arg(short, long, global = true, default_value_t = false)]
+    verbose: bool,
+
+    /// How much detail to log [possible values: error, warn, info, debug, trace]
+    #[arg(long = "log-level", global = true, value_parser = clap_value_parser!(["error", "warn", "info", "debug", "trace"], LevelFilter))]
+    log_level: Option<LevelFilter>,
+
+    /// Only control one of the keyboards, by its number in "list devices" or its product ID (such as c995)
+    #[arg(long, global = true)]
+    device: Option<DeviceSelector>,
+
+    /// Treat any keyboard from the same vendor as the given model, by its product ID or name, to test models that aren't recognized yet.
+    /// Models that don't behave like any known one can be given by their protocol instead, four-zone or five-zone optionally followed by
+    /// the report ID (such as five-zone@ce). Where the lighting interface can't be told apart (hidapi on Linux), only the keyboard picked
+    /// with --device by its product ID is driven
+    #[arg(long = "force-model", global = true, value_parser = parse_model)]
+    force_model: Option<&'static Model>,
+
+    /// Drive a simulated keyboard instead of the real ones, saving every frame it goes through to the given file if any (--simulate=frames.json)
+    #[arg(long, global = true, num_args = 0..=1, require_equals = true, value_name = "FRAMES")]
+    simulate: Option<Option<PathBuf>>,
+
+    /// Talk to the keyboard through /dev/hidraw* directly instead of through hidapi
+    #[cfg(target_os = "linux")]
+    #[arg(long, global = true, default_value_t = false)]
+    hidraw: bool,
+
+    /// Keep a log file next to the settings, which the GUI always does
+    #[arg(long = "log-file", global = true, default_value_t = false)]
+    log_file: bool,
+
+    /// Take commands from an MQTT broker and publish what's being played, until interrupted (mqtt://[user:password@]host[:port][/prefix])
+    #[cfg(feature = "network")]
+    #[arg(long, value_name = "URL", value_parser = mqtt::Broker::parse)]
+    mqtt: Option<mqtt::Broker>,
 }
 
 #[derive(Subcommand)]
@@ -54,47 +137,260 @@ struct Cli {
 enum Commands {
     /// Use an effect from the built-in set
     Set {
-        /// The effect to be set
-        #[arg(short, long, value_enum, value_parser, rename_all = "PascalCase")]
-        effect: Effects,
+        #[command(flatten)]
+        effect: EffectArgs,
 
-        /// List of 4 RGB triplets. Example: 255,0,0,255,255,0,0,0,255,255,128,0
-        #[arg(short, long, default_value = "0,0,0,0,0,0,0,0,0,0,0,0", value_parser = parse_colors)]
-        colors: Option<[u8; 12]>,
+        /// A filename to save the effect at
+        #[arg(long, value_enum)]
+        save: Option<PathBuf>,
 
-        /// The brightness of the effect [possible values: Low, High]
-        #[arg(short, long, default_value = "Low", value_parser)]
-        brightness: Brightness,
+        /// Only keep the effect for this long. Example: 90s, 10m, 1h
+        #[arg(long = "for", value_parser = parse_duration)]
+        duration: Option<Duration>,
 
-        /// The speed of the effect
-        #[arg(short, long, default_value_t = 1, value_parser = clap_value_parser!(["1","2","3","4","5"], u8))]
-        speed: u8,
+        /// Go back to whatever was being played before once the time is up
+        #[arg(long, requires = "duration")]
+        restore_on_exit: bool,
 
-        /// The direction of the effect (If applicable)
-        #[arg(short, long, value_enum)]
-        direction: Option<Direction>,
+        /// Write the effect to the keyboard and exit right away, only for the effects it plays by itself (see "list effects")
+        #[arg(long, conflicts_with = "duration")]
+        oneshot: bool,
+    },
 
-        /// A filename to save the effect at
-        #[arg(long, value_enum)]
-        save: Option<PathBuf>,
+    /// Record what an effect sends to the keyboard for some time into a custom effect that can be shared and played back
+    Record {
+        #[command(flatten)]
+        effect: EffectArgs,
+
+        /// For how long to record
+        #[arg(long, default_value_t = 10)]
+        seconds: u64,
+
+        /// The name to give the custom effect, the file name by default
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Play the recording once instead of looping it
+        #[arg(long, default_value_t = false)]
+        once: bool,
+
+        /// The file to save the custom effect to
+        out: PathBuf,
     },
 
-    /// List all the available effects
-    List,
+    /// Run an effect without a keyboard and save the frames it produces
+    Render {
+        #[command(flatten)]
+        effect: EffectArgs,
 
-    /// Load a profile from a file
+        /// For how long to run the effect
+        #[arg(long, default_value_t = 10)]
+        seconds: u64,
+
+        /// The file to save the frames to
+        #[arg(short, long)]
+        out: PathBuf,
+    },
+
+    /// List the available effects or saved profiles
+    List {
+        #[command(subcommand)]
+        target: Option<ListTarget>,
+
+        /// Print the list as JSON
+        #[arg(long, global = true)]
+        json: bool,
+    },
+
+    /// Show what is currently being played
+    Status {
+        /// Print the status as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Manage saved profiles
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommand,
+    },
+
+    /// Play custom effects
+    Effect {
+        #[command(subcommand)]
+        command: EffectCommand,
+    },
+
+    /// Save a report with system and device information to attach to bug reports
+    Diag {
+        /// The file to save the report to, a new file in the current folder by default
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+
+    /// Give regular users access to the keyboard by installing a udev rule, asking for the password if needed
+    #[cfg(target_os = "linux")]
+    InstallUdevRule,
+
+    /// Print a completion script for the given shell
+    ///
+    /// Saved profile names are asked for while completing, so the script doesn't have to be generated again
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+
+    /// Load a profile from a file (alias of "profile load")
+    #[command(hide = true)]
     LoadProfile {
         #[arg(short, long)]
         path: PathBuf,
     },
 
-    /// Load a custom effect from a file
+    /// Load a custom effect from a file (alias of "effect play")
+    #[command(hide = true)]
     CustomEffect {
         #[arg(short, long)]
         path: PathBuf,
     },
 }
 
+#[derive(Args)]
+struct EffectArgs {
+    /// The effect to be set
+    #[arg(short, long, value_enum, value_parser, rename_all = "PascalCase")]
+    effect: Effects,
+
+    /// List of 4 RGB triplets. Example: 255,0,0,255,255,0,0,0,255,255,128,0
+    #[arg(short, long, default_value = "0,0,0,0,0,0,0,0,0,0,0,0", value_parser = parse_colors)]
+    colors: Option<[u8; 12]>,
+
+    /// The brightness of the effect [possible values: Low, High]
+    #[arg(short, long, default_value = "Low", value_parser)]
+    brightness: Brightness,
+
+    /// The speed of the effect
+    #[arg(short, long, default_value_t = 1, value_parser = clap_value_parser!(["1","2","3","4","5"], u8))]
+    speed: u8,
+
+    /// The direction of the effect (If applicable)
+    #[arg(short, long, value_enum)]
+    direction: Option<Direction>,
+
+    /// Scale the brightness, speed and saturation of the effect at once (0-100)
+    #[arg(short, long, default_value_t = 100, value_parser = clap::value_parser!(u8).range(0..=100))]
+    intensity: u8,
+
+    /// Turn the lights off after this many minutes without keyboard or mouse input, while the program keeps running
+    #[arg(long, value_name = "MINUTES", value_parser = clap::value_parser!(u32).range(1..))]
+    off_after: Option<u32>,
+}
+
+impl EffectArgs {
+    fn into_profile(self) -> Profile {
+        let direction = self.direction.unwrap_or_default();
+        let rgb_array = if self.effect.takes_color_array() {
+            self.colors.unwrap_or_else(|| {
+                println!("This effect requires specifying the colors to use.");
+                std::process::exit(0);
+            })
+        } else {
+            [0; 12]
+        };
+
+        Profile {
+            name: None,
+            rgb_zones: profile::arr_to_zones(rgb_array),
+            effect: self.effect,
+            direction,
+            speed: self.speed,
+            brightness: self.brightness,
+            intensity: self.intensity,
+            group: None,
+            tags: Vec::new(),
+            startup_delay: None,
+            off_after: self.off_after,
+            overlay: None,
+            modulations: Vec::new(),
+            hide_battery_overlay: false,
+            pinned: false,
+            gradient: None,
+            palette: None,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum ListTarget {
+    /// List all the built-in effects
+    Effects,
+
+    /// List the profiles saved in the GUI
+    Profiles,
+
+    /// List the supported keyboards that are connected
+    Devices,
+
+    /// List the custom effects in the effect library, which can be played by name
+    Library,
+}
+
+#[derive(Subcommand)]
+enum ProfileCommand {
+    /// Apply a profile saved in the GUI by its name
+    Apply {
+        #[arg(add = ArgValueCompleter::new(complete_profile_names))]
+        name: String,
+    },
+
+    /// Load a profile from a file
+    Load { path: PathBuf },
+}
+
+#[derive(Subcommand)]
+enum EffectCommand {
+    /// Play a custom effect from a file, or from the effect library by its name
+    Play {
+        /// The file, or the name of an effect in the library as shown by "list library"
+        effect: String,
+    },
+
+    /// Rewrite a custom effect in the current format, older files being converted
+    Convert {
+        path: PathBuf,
+
+        /// Where to write the converted effect, the file itself if left out
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+
+    /// Play an effect from a dynamic library, reloading it whenever it's rebuilt
+    #[cfg(feature = "dev-effects")]
+    Dev {
+        /// The library built from dev-effect-template or a copy of it
+        path: PathBuf,
+
+        #[command(flatten)]
+        effect: EffectArgs,
+    },
+}
+
+fn parse_duration(arg: &str) -> std::result::Result<Duration, String> {
+    let split_at = arg.find(|c: char| !c.is_ascii_digit()).unwrap_or(arg.len());
+    let (amount, unit) = arg.split_at(split_at);
+    let amount: u64 = amount.parse().map_err(|_| "Invalid duration, use a number followed by ms, s, m or h".to_string())?;
+
+    let seconds_per_unit = match unit {
+        "ms" => return Ok(Duration::from_millis(amount)),
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        _ => return Err(format!("Unknown unit \"{unit}\", use ms, s, m or h")),
+    };
+
+    amount.checked_mul(seconds_per_unit).map(Duration::from_secs).ok_or_else(|| "Duration too long".to_string())
+}
+
 fn parse_colors(arg: &str) -> std::result::Result<[u8; 12], String> {
     fn input_err<E>(_e: E) -> String {
         "Invalid input, please check you used the correct format for the colors".to_string()
@@ -134,7 +430,18 @@ pub enum GuiCommand {
 pub enum OutputType {
     Profile(Profile),
     Custom(CustomEffect),
+    /// Play a profile for a limited time, optionally restoring the previous state afterwards
+    Timed { profile: Profile, duration: Duration, restore: bool },
+    /// Send a profile the keyboard plays by itself and exit, without anything else running meanwhile
+    Oneshot(Profile),
+    /// Start without reading the settings or running anything but a static profile
+    SafeMode,
+    /// Stay connected to an MQTT broker, applying what it sends
+    #[cfg(feature = "network")]
+    Mqtt(mqtt::Broker),
     NoArgs,
+    /// Same as [`Self::NoArgs`], leaving the keyboard as it is until a profile is picked
+    NoApply,
     Exit,
 }
 
@@ -157,21 +464,52 @@ pub fn try_cli() -> Result<GuiCommand, CliError> {
 }
 
 fn handle_cli_output(output_type: OutputType) -> Result<GuiCommand, CliError> {
-    let manager_result = manager::EffectManager::new(manager::OperationMode::Cli);
+    let settings = Settings::load();
+
+    // Commands that apply something may be run right as the system boots, before the keyboard is ready
+    let startup = match &output_type {
+        OutputType::Profile(profile) | OutputType::Timed { profile, .. } | OutputType::Oneshot(profile) => settings.preferences.startup.for_launch(profile.startup_delay),
+        OutputType::Custom(..) => settings.preferences.startup.for_launch(None),
+        OutputType::Exit | OutputType::SafeMode | OutputType::NoArgs | OutputType::NoApply => StartupSettings::immediate(),
+        #[cfg(feature = "network")]
+        OutputType::Mqtt(..) => settings.preferences.startup.for_launch(None),
+    };
+
+    let manager_result = DeviceRegistry::new_at_startup(manager::OperationMode::Cli, startup, |attempt| {
+        println!("Keyboard not found yet, retrying (attempt {attempt})...");
+    });
     let instance_not_unique = manager_result
         .as_ref()
         .err()
         .map_or(false, |err| &ManagerCreationError::InstanceAlreadyRunning == err.current_context());
 
-    if matches!(output_type, OutputType::Profile(..) | OutputType::Custom(..)) && instance_not_unique {
-        println!("Another instance of the program is already running, please close it before starting a new one.");
+    if !matches!(output_type, OutputType::Exit) && instance_not_unique {
+        println!("Another instance of the program is already running, please close it before starting a new one or use --takeover.");
         return Ok(GuiCommand::Exit);
     }
 
     let mut effect_manager = manager_result.change_context(CliError)?;
 
+    // Let a later instance started with --takeover stop whatever is being played, a oneshot is gone before it could
+    if !matches!(output_type, OutputType::Exit | OutputType::Oneshot(..)) {
+        let exit = effect_manager.exit_handle();
+        instance::serve(false, move |request| match request {
+            instance::Request::Shutdown => exit(),
+            instance::Request::ShowWindow => {}
+        });
+    }
+    effect_manager.set_reduced_motion(settings.preferences.reduced_motion.is_active());
+    effect_manager.set_speed_multiplier(settings.preferences.effect_speed);
+    effect_manager.set_frame_rate(settings.preferences.frame_rate);
+    effect_manager.set_calibrations(&settings.calibrations);
+    effect_manager.set_synced_lights(&settings.preferences.synced_lights);
+    effect_manager.set_weather(&settings.preferences.weather);
+    #[cfg(feature = "network")]
+    crate::util::set_online(!settings.preferences.offline);
+    effect_manager.set_exit_settings(settings.preferences.exit);
+
     let command_result = match output_type {
-        OutputType::Profile(profile) => {
+        OutputType::Profile(profile) | OutputType::Oneshot(profile) => {
             effect_manager.set_profile(profile);
             Ok(GuiCommand::Exit)
         }
@@ -179,67 +517,317 @@ fn handle_cli_output(output_type: OutputType) -> Result<GuiCommand, CliError> {
             effect_manager.custom_effect(effect);
             Ok(GuiCommand::Exit)
         }
+        OutputType::Timed { profile, duration, restore } => {
+            let previous = if restore { Status::load().and_then(|status| status.applied) } else { None };
+
+            // Being asked to terminate only cuts the wait short, so the previous state is still restored
+            let (terminate_tx, terminate_rx) = crossbeam_channel::bounded(1);
+            crash::set_on_terminate(move || {
+                let _ = terminate_tx.send(());
+            });
+
+            effect_manager.set_profile(profile);
+            let _ = terminate_rx.recv_timeout(duration);
+            crash::clear_on_terminate();
+
+            match previous {
+                Some(Applied::Profile(profile)) => effect_manager.set_profile(profile),
+                Some(Applied::CustomEffect(effect)) => effect_manager.custom_effect(effect),
+                None => {
+                    if restore {
+                        println!("There was no previous state to restore.");
+                    }
+                    effect_manager.stop();
+                    return Ok(GuiCommand::Exit);
+                }
+            }
+
+            Ok(GuiCommand::Exit)
+        }
+        #[cfg(feature = "network")]
+        OutputType::Mqtt(broker) => {
+            mqtt::run(&broker, &mut effect_manager);
+            Ok(GuiCommand::Exit)
+        }
         OutputType::Exit => Ok(GuiCommand::Exit),
-        OutputType::NoArgs => unreachable!("No arguments were provided but the app is in CLI mode"),
+        OutputType::SafeMode => unreachable!("Safe mode always starts the GUI"),
+        OutputType::NoArgs | OutputType::NoApply => unreachable!("No arguments were provided but the app is in CLI mode"),
     };
 
     effect_manager.shutdown();
     command_result
 }
 
+fn parse_model(arg: &str) -> std::result::Result<&'static Model, String> {
+    models::lookup(arg).or_else(|| Protocol::parse(arg).map(models::custom)).ok_or_else(|| {
+        let known: Vec<String> = models::MODELS.iter().map(|model| format!("{:04x} ({})", model.product_id, model.name)).collect();
+        format!("Unknown model, use one of: {}, or a protocol such as four-zone@cc or five-zone@cc", known.join(", "))
+    })
+}
+
 fn parse_cli() -> Result<CliOutput, CliError> {
-    let cli = Cli::parse();
+    // Answers the completion scripts and exits when they're the ones calling
+    CompleteEnv::with_factory(Cli::command).var(COMPLETE_VAR).complete();
+
+    let mut cli = Cli::parse();
+
+    if let Some(path) = cli.config.take() {
+        Settings::set_location(path);
+    }
+
+    let starts_gui = cli.gui || cli.safe_mode || matches!(cli.command, None | Some(Commands::LoadProfile { .. } | Commands::CustomEffect { .. }));
+    let log_level = cli.log_level.or(cli.verbose.then_some(LevelFilter::DEBUG));
+    logging::init(log_level, cli.log_file || starts_gui);
 
-    if let Some(subcommand) = cli.command {
+    if let Some(selector) = cli.device {
+        DeviceRegistry::set_selector(selector);
+    }
+
+    if let Some(model) = cli.force_model {
+        let product_id = match cli.device {
+            Some(DeviceSelector::ProductId(product_id)) => Some(product_id),
+            _ => None,
+        };
+        warn!("Treating every keyboard as the {} model ({:?}), this is meant for testing", model.name, model.protocol);
+        models::force(model, product_id);
+    }
+
+    if let Some(out) = cli.simulate.take() {
+        DeviceRegistry::simulate(out);
+    }
+
+    #[cfg(target_os = "linux")]
+    if cli.hidraw {
+        legion_kb_rgb_hw::set_backend(legion_kb_rgb_hw::Backend::Hidraw);
+    }
+
+    if cli.takeover {
+        instance::take_over();
+    }
+
+    // Anything else that was asked for might be what's causing trouble
+    if cli.safe_mode {
+        return Ok(CliOutput::Gui {
+            hide_window: false,
+            output_type: OutputType::SafeMode,
+        });
+    }
+
+    #[cfg(feature = "network")]
+    if let Some(broker) = cli.mqtt.take() {
+        return Ok(CliOutput::Cli(OutputType::Mqtt(broker)));
+    }
+
+    if let Some(subcommand) = cli.command.take() {
         match subcommand {
             Commands::Set {
                 effect,
-                colors,
-                brightness,
-                speed,
-                direction,
                 save,
+                duration,
+                restore_on_exit,
+                oneshot,
             } => {
-                let direction = direction.unwrap_or_default();
-                let rgb_array = if effect.takes_color_array() {
-                    colors.unwrap_or_else(|| {
-                        println!("This effect requires specifying the colors to use.");
-                        std::process::exit(0);
-                    })
-                } else {
-                    [0; 12]
-                };
-
-                let mut profile = Profile {
-                    name: None,
-                    rgb_zones: profile::arr_to_zones(rgb_array),
-                    effect,
-                    direction,
-                    speed,
-                    brightness,
-                };
+                let mut profile = effect.into_profile();
 
                 if let Some(filename) = save {
                     profile.save_profile(&filename).expect("Failed to save.");
                 }
 
-                if cli.gui {
-                    return Ok(CliOutput::Gui {
-                        hide_window: cli.hide_window,
-                        output_type: OutputType::Profile(profile),
-                    });
-                } else {
-                    return Ok(CliOutput::Cli(OutputType::Profile(profile)));
+                if oneshot {
+                    if !profile.plays_on_keyboard() {
+                        return Err(Report::new(CliError).attach_printable(format!(
+                            "The {} effect needs the program to keep running, leave out --oneshot (and --off-after) to play it.",
+                            profile.effect
+                        )));
+                    }
+                    return Ok(CliOutput::Cli(OutputType::Oneshot(profile)));
                 }
+
+                // Timing is handled by the CLI itself, so it takes precedence over starting the GUI
+                if let Some(duration) = duration {
+                    return Ok(CliOutput::Cli(OutputType::Timed {
+                        profile,
+                        duration,
+                        restore: restore_on_exit,
+                    }));
+                }
+
+                return Ok(output_for(&cli, OutputType::Profile(profile)));
             }
-            Commands::List => {
-                println!("List of available effects:");
-                for (i, effect) in Effects::iter().enumerate() {
-                    println!("{}. {effect}", i + 1);
+            Commands::Record {
+                effect,
+                seconds,
+                name,
+                once,
+                out,
+            } => {
+                let name = name.or_else(|| out.file_stem().map(|stem| stem.to_string_lossy().into_owned()));
+                record(effect.into_profile(), seconds, name, !once, &out)?;
+                println!("Saved the custom effect to {}, play it with \"effect play {}\"", out.display(), out.display());
+                return Ok(CliOutput::Cli(OutputType::Exit));
+            }
+
+            Commands::Render { effect, seconds, out } => {
+                render(effect.into_profile(), seconds, &out)?;
+                println!("Saved the frames to {}", out.display());
+                return Ok(CliOutput::Cli(OutputType::Exit));
+            }
+            Commands::List { target, json } => {
+                match target.unwrap_or(ListTarget::Effects) {
+                    ListTarget::Effects if json => {
+                        let effects: Vec<ListedEffect> = Effects::iter()
+                            .map(|effect| ListedEffect {
+                                name: effect.into(),
+                                needs_process: !effect.is_built_in(),
+                            })
+                            .collect();
+                        print_json(&effects)?;
+                    }
+                    ListTarget::Effects => {
+                        println!("List of available effects (* can be set with --oneshot, as the keyboard plays them by itself):");
+                        for (i, effect) in Effects::iter().enumerate() {
+                            let marker = if effect.is_built_in() { " *" } else { "" };
+                            println!("{}. {effect}{marker}", i + 1);
+                        }
+                    }
+                    ListTarget::Devices => {
+                        let devices = legion_kb_rgb_hw::find_keyboards().change_context(CliError)?;
+                        if json {
+                            let devices: Vec<ListedDevice> = devices
+                                .iter()
+                                .map(|device| ListedDevice {
+                                    id: device.id(),
+                                    name: device.model.name,
+                                    path: &device.path,
+                                })
+                                .collect();
+                            print_json(&devices)?;
+                        } else if devices.is_empty() {
+                            println!("No supported keyboards were found.");
+                        } else {
+                            println!("List of connected keyboards:");
+                            for (i, device) in devices.iter().enumerate() {
+                                println!("{}. {} ({}) at {}", i + 1, device.model.name, device.id(), device.path);
+                            }
+                        }
+                    }
+                    ListTarget::Library if json => {
+                        let effects: Vec<ListedLibraryEffect> = effect_library::scan()
+                            .iter()
+                            .map(|effect| ListedLibraryEffect {
+                                name: effect.name(),
+                                path: effect.path.clone(),
+                            })
+                            .collect();
+                        print_json(&effects)?;
+                    }
+                    ListTarget::Library => {
+                        let effects = effect_library::scan();
+                        if effects.is_empty() {
+                            println!("The effect library at {} is empty.", effect_library::folder().display());
+                        } else {
+                            println!("List of effects in the library at {}:", effect_library::folder().display());
+                            for (i, effect) in effects.iter().enumerate() {
+                                println!("{}. {}", i + 1, effect.name());
+                            }
+                        }
+                    }
+                    ListTarget::Profiles if json => print_json(&Settings::load().profiles)?,
+                    ListTarget::Profiles => {
+                        let profiles = Settings::load().profiles;
+                        if profiles.is_empty() {
+                            println!("No profiles have been saved yet.");
+                        } else {
+                            println!("List of saved profiles:");
+                            for (i, profile) in profiles.iter().enumerate() {
+                                println!("{}. {} ({})", i + 1, profile.name.as_deref().unwrap_or("Unnamed"), profile.effect);
+                            }
+                        }
+                    }
+                }
+                return Ok(CliOutput::Cli(OutputType::Exit));
+            }
+
+            Commands::Status { json } => {
+                // Fall back to whatever the GUI last saved if nothing was ever published
+                let status = Status::load().unwrap_or_else(|| Status::from_profile(&Settings::load().current_profile));
+
+                if json {
+                    print_json(&status)?;
+                } else {
+                    print_status(&status);
                 }
                 return Ok(CliOutput::Cli(OutputType::Exit));
             }
 
+            Commands::Diag { out } => {
+                let out = out.unwrap_or_else(|| PathBuf::from(diagnostics::default_file_name()));
+                diagnostics::save(&out).change_context(CliError)?;
+                println!("Saved the diagnostics report to {}", out.display());
+                return Ok(CliOutput::Cli(OutputType::Exit));
+            }
+
+            #[cfg(target_os = "linux")]
+            Commands::InstallUdevRule => {
+                udev::install().change_context(CliError)?;
+                println!("Installed the udev rule at {}, the keyboard and the other lights can now be used without root", udev::RULE_PATH);
+                return Ok(CliOutput::Cli(OutputType::Exit));
+            }
+
+            Commands::Completions { shell } => {
+                print_completions(shell);
+                return Ok(CliOutput::Cli(OutputType::Exit));
+            }
+
+            Commands::Profile { command } => {
+                let profile = match command {
+                    ProfileCommand::Apply { name } => {
+                        let profiles = Settings::load().profiles;
+                        let Some(profile) = profiles.into_iter().find(|profile| profile.name.as_deref() == Some(name.as_str())) else {
+                            return Err(Report::new(CliError).attach_printable(format!("No profile named \"{name}\" was found. Use \"list profiles\" to see the saved profiles.")));
+                        };
+                        profile
+                    }
+                    ProfileCommand::Load { path } => Profile::load_profile(&path).change_context(CliError)?,
+                };
+                return Ok(output_for(&cli, OutputType::Profile(profile)));
+            }
+
+            Commands::Effect {
+                command: EffectCommand::Play { effect },
+            } => {
+                let path = Path::new(&effect);
+                let effect = if path.is_file() {
+                    CustomEffect::from_file(path).change_context(CliError)?
+                } else if let Some(found) = effect_library::find(&effect) {
+                    found.effect
+                } else {
+                    return Err(Report::new(CliError).attach_printable(format!(
+                        "No file or effect in the library named \"{effect}\" was found. Use \"list library\" to see the effects in the library."
+                    )));
+                };
+                return Ok(output_for(&cli, OutputType::Custom(effect)));
+            }
+
+            Commands::Effect {
+                command: EffectCommand::Convert { path, out },
+            } => {
+                let effect = CustomEffect::from_file(&path).change_context(CliError)?;
+                effect.save_file(out.as_ref().unwrap_or(&path)).change_context(CliError)?;
+                return Ok(CliOutput::Cli(OutputType::Exit));
+            }
+
+            #[cfg(feature = "dev-effects")]
+            Commands::Effect {
+                command: EffectCommand::Dev { path, effect },
+            } => {
+                let mut effect_manager = DeviceRegistry::new(manager::OperationMode::Cli).change_context(CliError)?;
+                effect_manager.dev_effect(path, effect.into_profile());
+                // Runs until the program gets interrupted
+                effect_manager.shutdown();
+                return Ok(CliOutput::Cli(OutputType::Exit));
+            }
+
             Commands::LoadProfile { path } => {
                 let profile = Profile::load_profile(&path).change_context(CliError)?;
                 return Ok(CliOutput::Gui {
@@ -258,6 +846,13 @@ fn parse_cli() -> Result<CliOutput, CliError> {
         }
     }
 
+    if cli.no_apply {
+        return Ok(CliOutput::Gui {
+            hide_window: true,
+            output_type: OutputType::NoApply,
+        });
+    }
+
     // If no subcommands were found, start in GUI mode
     let exec_name = std::env::current_exe().unwrap().file_name().unwrap().to_string_lossy().into_owned();
     println!("No subcommands found, starting in GUI mode. To view the possible subcommands type \"{exec_name} --help\".");
@@ -266,3 +861,117 @@ fn parse_cli() -> Result<CliOutput, CliError> {
         output_type: OutputType::NoArgs,
     })
 }
+
+/// Print a script that has the shell call the program back whenever something has to be completed
+fn print_completions(shell: Shell) {
+    let completer: &dyn EnvCompleter = match shell {
+        Shell::Bash => &Bash,
+        Shell::Elvish => &Elvish,
+        Shell::Fish => &Fish,
+        Shell::PowerShell => &Powershell,
+        Shell::Zsh => &Zsh,
+        _ => {
+            warn!("Completions aren't supported for {shell}");
+            return;
+        }
+    };
+
+    let bin = env!("CARGO_BIN_NAME");
+    let completer_path = std::env::current_exe().map_or_else(|_| bin.to_string(), |path| path.to_string_lossy().into_owned());
+    if let Err(err) = completer.write_registration(COMPLETE_VAR, bin, bin, &completer_path, &mut io::stdout()) {
+        warn!("Could not print the completion script: {err}");
+    }
+}
+
+/// The names of the saved profiles starting with what was typed so far
+fn complete_profile_names(current: &OsStr) -> Vec<CompletionCandidate> {
+    let current = current.to_string_lossy();
+
+    Settings::load()
+        .profiles
+        .into_iter()
+        .filter_map(|profile| profile.name)
+        .filter(|name| name.starts_with(current.as_ref()))
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+#[derive(Serialize)]
+struct ListedEffect {
+    name: &'static str,
+    /// Whether the program has to keep running to play it, otherwise it can be set with `--oneshot`
+    needs_process: bool,
+}
+
+#[derive(Serialize)]
+struct ListedLibraryEffect {
+    name: String,
+    path: PathBuf,
+}
+
+#[derive(Serialize)]
+struct ListedDevice<'a> {
+    id: String,
+    name: &'a str,
+    path: &'a str,
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<(), CliError> {
+    let json = serde_json::to_string(value).change_context(CliError)?;
+    println!("{json}");
+    Ok(())
+}
+
+fn print_status(status: &Status) {
+    match (&status.custom_effect, &status.profile) {
+        (Some(custom_effect), _) => println!("Custom effect: {custom_effect}"),
+        (None, Some(profile)) => println!("Profile: {profile}"),
+        (None, None) => println!("Profile: Unnamed"),
+    }
+    println!("Effect: {}", status.effect);
+    println!("Brightness: {}", status.brightness);
+    println!("Speed: {}", status.speed);
+
+    let colors: Vec<String> = status.rgb_array.chunks(3).map(|rgb| format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2])).collect();
+    println!("Colors: {}", colors.join(" "));
+}
+
+/// Play a profile on a virtual keyboard for some time, then save every frame it produced
+fn render(profile: Profile, seconds: u64, out: &Path) -> Result<(), CliError> {
+    let recorder = FrameRecorder::new();
+    let mut effect_manager = manager::EffectManager::new_virtual(recorder.observer());
+
+    effect_manager.set_profile(profile);
+    thread::sleep(Duration::from_secs(seconds));
+    effect_manager.stop();
+
+    recorder.save(out).change_context(CliError)
+}
+
+/// Play a profile on a virtual keyboard for some time, then save what it went through as a custom effect
+fn record(profile: Profile, seconds: u64, name: Option<String>, should_loop: bool, out: &Path) -> Result<(), CliError> {
+    let recorder = FrameRecorder::new();
+    let mut effect_manager = manager::EffectManager::new_virtual(recorder.observer());
+
+    effect_manager.set_profile(profile);
+    thread::sleep(Duration::from_secs(seconds));
+    effect_manager.stop();
+
+    if recorder.has_hardware_animation() {
+        warn!("The effect relies on an animation played by the keyboard itself, which can't be recorded");
+    }
+
+    recorder.to_custom_effect(name, seconds * 1000, should_loop).save_file(out).change_context(CliError)
+}
+
+/// Apply the instruction directly, or hand it to the GUI if it was requested
+fn output_for(cli: &Cli, output_type: OutputType) -> CliOutput {
+    if cli.gui {
+        CliOutput::Gui {
+            hide_window: cli.hide_window,
+            output_type,
+        }
+    } else {
+        CliOutput::Cli(output_type)
+    }
+}
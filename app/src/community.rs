@@ -0,0 +1,128 @@
+//! Effects shared by others in a community repository on GitHub, listed in an `index.json` next to their files.
+//!
+//! The index looks like `{"effects": [{"name": "Police", "author": "...", "description": "...", "file": "police.json"}]}`,
+//! the files being custom effects in any format the program reads, found relative to the index or at a full URL.
+//!
+//! There's no repository set up front, the index to browse has to be picked in the settings first.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct CommunitySettings {
+    /// The index listing the effects, empty until one is picked
+    pub index_url: String,
+}
+
+impl CommunitySettings {
+    /// Whether there's an index to browse
+    pub fn has_index(&self) -> bool {
+        !self.index_url.trim().is_empty()
+    }
+}
+
+/// An effect listed in the index
+#[cfg(feature = "gui")]
+#[cfg_attr(not(feature = "network"), allow(dead_code))]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct CommunityEffect {
+    pub name: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Where the effect is, relative to the index or as a full URL
+    pub file: String,
+}
+
+#[cfg(feature = "gui")]
+#[cfg_attr(not(feature = "network"), allow(dead_code))]
+impl CommunityEffect {
+    /// Where to download the effect from, given where the index was
+    pub fn url(&self, index_url: &str) -> String {
+        if self.file.starts_with("https://") || self.file.starts_with("http://") {
+            return self.file.clone();
+        }
+
+        let base = index_url.rsplit_once('/').map_or(index_url, |(base, _)| base);
+        format!("{base}/{}", self.file.trim_start_matches('/'))
+    }
+
+    pub fn matches_search(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        query.is_empty()
+            || self.name.to_lowercase().contains(&query)
+            || self.author.as_ref().is_some_and(|author| author.to_lowercase().contains(&query))
+            || self.tags.iter().any(|tag| tag.to_lowercase().contains(&query))
+    }
+}
+
+#[cfg(all(feature = "gui", feature = "network"))]
+pub use client::{download, fetch_index, CommunityError};
+
+#[cfg(all(feature = "gui", feature = "network"))]
+mod client {
+    use std::time::Duration;
+
+    use error_stack::{Report, Result, ResultExt};
+    use serde::Deserialize;
+    use thiserror::Error;
+
+    use super::CommunityEffect;
+    use crate::{manager::custom_effect::CustomEffect, util::is_online};
+
+    const TIMEOUT: Duration = Duration::from_secs(15);
+
+    #[derive(Debug, Error)]
+    #[error("Could not reach the community effects")]
+    pub struct CommunityError;
+
+    #[derive(Deserialize)]
+    struct Index {
+        effects: Vec<CommunityEffect>,
+    }
+
+    fn get(url: &str) -> Result<String, CommunityError> {
+        if !is_online() {
+            return Err(Report::new(CommunityError)).attach_printable("Working offline");
+        }
+
+        ureq::AgentBuilder::new()
+            .timeout(TIMEOUT)
+            .user_agent(concat!("legion-kb-rgb/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .get(url)
+            .call()
+            .change_context(CommunityError)
+            .attach_printable_lazy(|| format!("Requested {url}"))?
+            .into_string()
+            .change_context(CommunityError)
+    }
+
+    /// The effects listed in the index at `index_url`
+    pub fn fetch_index(index_url: &str) -> Result<Vec<CommunityEffect>, CommunityError> {
+        let index: Index = serde_json::from_str(&get(index_url)?).change_context(CommunityError).attach_printable("Unexpected index format")?;
+        Ok(index.effects)
+    }
+
+    /// Get the file of `effect`, named after its entry in the index if the file itself has no name
+    pub fn download(index_url: &str, effect: &CommunityEffect) -> Result<CustomEffect, CommunityError> {
+        let value: serde_json::Value = serde_json::from_str(&get(&effect.url(index_url))?).change_context(CommunityError)?;
+        let mut downloaded = CustomEffect::try_from(value).change_context(CommunityError)?;
+
+        downloaded.name.get_or_insert_with(|| effect.name.clone());
+        if downloaded.author.is_none() {
+            downloaded.author.clone_from(&effect.author);
+        }
+        if downloaded.description.is_none() {
+            downloaded.description.clone_from(&effect.description);
+        }
+        if downloaded.thumbnail.is_none() {
+            downloaded.thumbnail = downloaded.render_thumbnail();
+        }
+
+        Ok(downloaded)
+    }
+}
@@ -0,0 +1,486 @@
+//! Lets Stream Decks and MIDI controllers switch profiles and change the brightness.
+//!
+//! Stream Decks are read through hidapi. MIDI devices are read from the raw ALSA devices (`/dev/snd/midiC*D*`) on
+//! Linux and through the multimedia API on Windows, only the ones picked in the settings. A control triggers its binding when a key or note gets pressed, or
+//! when a CC goes from below to above the middle of its range, which is what pads and buttons set to CC send.
+
+#[cfg(feature = "gui")]
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+#[cfg(feature = "gui")]
+use crossbeam_channel::{Receiver, Sender, TryRecvError};
+use serde::{Deserialize, Serialize};
+
+use crate::enums::Brightness;
+
+#[cfg(feature = "gui")]
+pub use midi::devices as midi_devices;
+
+/// How often devices are looked for while none are connected
+#[cfg(feature = "gui")]
+const RESCAN_INTERVAL: Duration = Duration::from_secs(5);
+/// How long a read waits for input before checking whether the listener was stopped
+#[cfg(feature = "gui")]
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A button, pad or key of a controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Control {
+    StreamDeck { key: u8 },
+    MidiNote { channel: u8, note: u8 },
+    MidiCc { channel: u8, controller: u8 },
+}
+
+/// What a control does when pressed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ControlAction {
+    #[default]
+    CycleProfiles,
+    /// Apply a saved profile, by name
+    Profile(String),
+    Brightness(Brightness),
+    ToggleBrightness,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ControlBinding {
+    /// Empty until a control is learned for it
+    pub control: Option<Control>,
+    pub action: ControlAction,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ControllerSettings {
+    pub enabled: bool,
+    pub bindings: Vec<ControlBinding>,
+    /// The names of the MIDI devices to read, as listed by [`midi_devices`]
+    pub midi_devices: Vec<String>,
+}
+
+#[cfg(feature = "gui")]
+impl ControllerSettings {
+    /// The actions bound to `control`, in the order they were added
+    pub fn actions_for(&self, control: Control) -> Vec<ControlAction> {
+        self.bindings.iter().filter(|binding| binding.control == Some(control)).map(|binding| binding.action.clone()).collect()
+    }
+}
+
+/// Reads the controllers until dropped
+#[cfg(feature = "gui")]
+pub struct ControllerListener {
+    /// Dropped to tell the reading threads to stop
+    stop: Option<Sender<()>>,
+    midi: Option<JoinHandle<()>>,
+}
+
+#[cfg(feature = "gui")]
+impl Drop for ControllerListener {
+    fn drop(&mut self) {
+        self.stop.take();
+        // MIDI devices can only be opened once, so they have to be closed before another listener gets to them
+        if let Some(midi) = self.midi.take() {
+            let _ = midi.join();
+        }
+    }
+}
+
+/// Start reading the Stream Deck and the MIDI devices named in `midi_devices` in the background, calling `on_press` from
+/// the reading threads
+#[cfg(feature = "gui")]
+pub fn spawn_listener<F: Fn(Control) + Send + Sync + 'static>(midi_devices: Vec<String>, on_press: F) -> ControllerListener {
+    let (stop_tx, stop_rx) = crossbeam_channel::bounded(0);
+    let on_press: Arc<dyn Fn(Control) + Send + Sync> = Arc::new(on_press);
+    {
+        let on_press = on_press.clone();
+        let stop = stop_rx.clone();
+        thread::spawn(move || stream_deck::listen(&stop, &*on_press));
+    }
+    let midi = (!midi_devices.is_empty()).then(|| midi::listen(midi_devices, stop_rx, on_press));
+
+    ControllerListener { stop: Some(stop_tx), midi }
+}
+
+/// Whether the [`ControllerListener`] the threads read for was dropped
+#[cfg(feature = "gui")]
+fn stopped(stop: &Receiver<()>) -> bool {
+    matches!(stop.try_recv(), Err(TryRecvError::Disconnected))
+}
+
+/// Turns the bytes coming from a MIDI device into presses, remembering the last status byte as devices are allowed to
+/// skip repeating it
+#[cfg(feature = "gui")]
+#[derive(Default)]
+struct MidiParser {
+    status: u8,
+    data: Vec<u8>,
+    /// The last value of each CC, to only trigger when crossing the middle upwards
+    cc_values: HashMap<(u8, u8), u8>,
+}
+
+#[cfg(feature = "gui")]
+impl MidiParser {
+    fn push(&mut self, byte: u8) -> Option<Control> {
+        match byte {
+            // Clock and other real time messages can show up anywhere, even between the bytes of another message
+            0xf8.. => return None,
+            0x80.. => {
+                self.status = byte;
+                self.data.clear();
+                return None;
+            }
+            _ => self.data.push(byte),
+        }
+
+        let expected = match self.status & 0xf0 {
+            0xc0 | 0xd0 => 1,
+            // System exclusive and the like, which can't be bound
+            0xf0 => return None,
+            _ => 2,
+        };
+        if self.data.len() < expected {
+            return None;
+        }
+
+        let data = std::mem::take(&mut self.data);
+        let channel = self.status & 0x0f;
+        match (self.status & 0xf0, data.as_slice()) {
+            (0x90, &[note, velocity]) if velocity > 0 => Some(Control::MidiNote { channel, note }),
+            (0xb0, &[controller, value]) => {
+                let previous = self.cc_values.insert((channel, controller), value).unwrap_or(0);
+                (previous < 64 && value >= 64).then_some(Control::MidiCc { channel, controller })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+mod stream_deck {
+    use crossbeam_channel::Receiver;
+    use hidapi::{HidApi, HidDevice};
+    use tracing::{info, warn};
+
+    use super::{stopped, Control, RESCAN_INTERVAL, STOP_CHECK_INTERVAL};
+
+    const VENDOR_ID: u16 = 0x0fd9;
+    /// Every Stream Deck with keys, and where the key states start in its reports. The original Stream Deck and the
+    /// Minis put them right after the report ID, the rest have a 3 byte header before them
+    const PRODUCTS: [(u16, usize); 10] = [
+        (0x0060, 1), // Stream Deck
+        (0x0063, 1), // Mini
+        (0x006c, 4), // XL
+        (0x006d, 4), // Stream Deck V2
+        (0x0080, 4), // MK.2
+        (0x0084, 4), // Plus
+        (0x0086, 4), // Pedal
+        (0x008f, 4), // XL V2
+        (0x0090, 1), // Mini V2
+        (0x009a, 4), // Neo
+    ];
+
+    pub fn listen(stop: &Receiver<()>, on_press: &dyn Fn(Control)) {
+        while !stopped(stop) {
+            match open() {
+                Some((device, offset)) => read(&device, offset, stop, on_press),
+                None => {
+                    let _ = stop.recv_timeout(RESCAN_INTERVAL);
+                }
+            }
+        }
+    }
+
+    fn open() -> Option<(HidDevice, usize)> {
+        let api = HidApi::new().ok()?;
+        let (info, offset) = api.device_list().find_map(|info| {
+            let (_, offset) = PRODUCTS.iter().find(|(product_id, _)| info.vendor_id() == VENDOR_ID && info.product_id() == *product_id)?;
+            Some((info, *offset))
+        })?;
+        let device = info.open_device(&api).map_err(|err| warn!("Could not open the Stream Deck: {err}")).ok()?;
+        info!(product_id = info.product_id(), "Reading the Stream Deck");
+
+        Some((device, offset))
+    }
+
+    /// Report every key that goes down until the device gets unplugged or the listener is stopped
+    fn read(device: &HidDevice, offset: usize, stop: &Receiver<()>, on_press: &dyn Fn(Control)) {
+        let mut buffer = [0; 512];
+        let mut held = Vec::new();
+
+        while !stopped(stop) {
+            let len = match device.read_timeout(&mut buffer, STOP_CHECK_INTERVAL.as_millis() as i32) {
+                Ok(0) => continue,
+                Ok(len) => len,
+                Err(err) => {
+                    warn!("Lost the Stream Deck: {err}");
+                    return;
+                }
+            };
+            let Some(keys) = buffer.get(offset..len) else {
+                continue;
+            };
+
+            for (key, state) in keys.iter().enumerate() {
+                if *state != 0 && held.get(key).copied().unwrap_or(0) == 0 {
+                    on_press(Control::StreamDeck { key: key as u8 });
+                }
+            }
+            held = keys.to_vec();
+        }
+    }
+}
+
+#[cfg(all(feature = "gui", target_os = "linux"))]
+mod midi {
+    use std::{
+        fs::{self, File},
+        io::Read,
+        os::fd::AsRawFd,
+        path::{Path, PathBuf},
+        sync::Arc,
+        thread::{self, JoinHandle},
+    };
+
+    use crossbeam_channel::{Receiver, RecvTimeoutError};
+    use tracing::{info, warn};
+
+    use super::{stopped, Control, MidiParser, RESCAN_INTERVAL, STOP_CHECK_INTERVAL};
+
+    /// The names of the raw MIDI devices plugged in
+    pub fn devices() -> Vec<String> {
+        paths().into_iter().map(|(_, name)| name).collect()
+    }
+
+    /// Every raw MIDI device along with its name, which unlike the path stays the same when other cards come and go
+    fn paths() -> Vec<(PathBuf, String)> {
+        let Ok(entries) = fs::read_dir("/dev/snd") else {
+            return Vec::new();
+        };
+
+        let mut paths: Vec<_> = entries.flatten().map(|entry| entry.path()).filter_map(|path| Some((path.clone(), name(&path)?))).collect();
+        paths.sort();
+        paths
+    }
+
+    /// The ID of the sound card a `midiC<card>D<device>` belongs to, followed by the device number if it's not the first
+    fn name(path: &Path) -> Option<String> {
+        let (card, device) = path.file_name()?.to_str()?.strip_prefix("midiC")?.split_once('D')?;
+        let id = fs::read_to_string(format!("/proc/asound/card{card}/id")).ok()?;
+
+        Some(if device == "0" { id.trim().to_string() } else { format!("{} {device}", id.trim()) })
+    }
+
+    /// Read the raw MIDI devices called one of `names`, including the ones plugged in later, until `stop` is dropped
+    pub fn listen(names: Vec<String>, stop: Receiver<()>, on_press: Arc<dyn Fn(Control) + Send + Sync>) -> JoinHandle<()> {
+        thread::spawn(move || {
+            let mut reading: Vec<(PathBuf, JoinHandle<()>)> = Vec::new();
+
+            loop {
+                reading.retain(|(_, reader)| !reader.is_finished());
+                for (path, name) in paths() {
+                    if !names.contains(&name) || reading.iter().any(|(read, _)| *read == path) {
+                        continue;
+                    }
+
+                    let on_press = on_press.clone();
+                    let stop = stop.clone();
+                    let reader = {
+                        let path = path.clone();
+                        thread::spawn(move || read(&path, &stop, &*on_press))
+                    };
+                    reading.push((path, reader));
+                }
+
+                if stop.recv_timeout(RESCAN_INTERVAL) == Err(RecvTimeoutError::Disconnected) {
+                    break;
+                }
+            }
+
+            for (_, reader) in reading {
+                let _ = reader.join();
+            }
+        })
+    }
+
+    fn read(path: &Path, stop: &Receiver<()>, on_press: &dyn Fn(Control)) {
+        let mut file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) => {
+                warn!("Could not open {}: {err}", path.display());
+                return;
+            }
+        };
+        info!("Reading MIDI from {}", path.display());
+
+        let mut parser = MidiParser::default();
+        let mut buffer = [0; 64];
+        let mut poll = libc::pollfd {
+            fd: file.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        while !stopped(stop) {
+            // Waiting for input with a timeout, as a blocking read would never get to see the listener being stopped
+            // SAFETY: The descriptor stays open for as long as `file` does
+            if unsafe { libc::poll(&mut poll, 1, STOP_CHECK_INTERVAL.as_millis() as i32) } <= 0 {
+                continue;
+            }
+
+            let Ok(len @ 1..) = file.read(&mut buffer) else {
+                return;
+            };
+            for byte in &buffer[..len] {
+                if let Some(control) = parser.push(*byte) {
+                    on_press(control);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "gui", target_os = "windows"))]
+mod midi {
+    use std::{
+        mem,
+        ptr,
+        sync::{Arc, Mutex},
+        thread::{self, JoinHandle},
+    };
+
+    use crossbeam_channel::{Receiver, RecvTimeoutError};
+    use tracing::{info, warn};
+    use winapi::{
+        shared::{
+            basetsd::{DWORD_PTR, UINT_PTR},
+            minwindef::{DWORD, UINT},
+        },
+        um::{
+            mmeapi::{midiInClose, midiInGetDevCapsW, midiInGetNumDevs, midiInOpen, midiInStart, midiInStop},
+            mmsystem::{CALLBACK_FUNCTION, HMIDIIN, MIDIINCAPSW, MIM_DATA, MMSYSERR_NOERROR},
+        },
+    };
+
+    use super::{Control, MidiParser, RESCAN_INTERVAL};
+
+    type Handler = Arc<dyn Fn(Control) + Send + Sync>;
+
+    /// What the callback of an open device gets through its instance data
+    struct Input {
+        parser: Mutex<MidiParser>,
+        on_press: Handler,
+    }
+
+    /// The names of the MIDI inputs plugged in, in the order of their device IDs
+    pub fn devices() -> Vec<String> {
+        // SAFETY: The capabilities are written into a struct of the size given
+        unsafe {
+            (0..midiInGetNumDevs())
+                .map(|id| {
+                    let mut caps: MIDIINCAPSW = mem::zeroed();
+                    if midiInGetDevCapsW(id as UINT_PTR, &mut caps, mem::size_of::<MIDIINCAPSW>() as UINT) != MMSYSERR_NOERROR {
+                        return String::new();
+                    }
+
+                    let len = caps.szPname.iter().position(|c| *c == 0).unwrap_or(caps.szPname.len());
+                    String::from_utf16_lossy(&caps.szPname[..len])
+                })
+                .collect()
+        }
+    }
+
+    /// Open the MIDI inputs called one of `names`, opening them again whenever one is plugged in or out as the device IDs
+    /// shift then, until `stop` is dropped
+    pub fn listen(names: Vec<String>, stop: Receiver<()>, on_press: Handler) -> JoinHandle<()> {
+        thread::spawn(move || {
+            let mut opened: Vec<(HMIDIIN, Box<Input>)> = Vec::new();
+            let mut count = None;
+
+            loop {
+                let current = devices();
+                if count != Some(current.len()) {
+                    close(&mut opened);
+
+                    count = Some(current.len());
+                    for (id, _) in current.iter().enumerate().filter(|(_, name)| names.contains(name)) {
+                        let input = Box::new(Input {
+                            parser: Mutex::new(MidiParser::default()),
+                            on_press: on_press.clone(),
+                        });
+
+                        // SAFETY: The input given as instance data is kept alive until the handle is closed
+                        unsafe {
+                            let mut handle = ptr::null_mut();
+                            let instance = &*input as *const Input as DWORD_PTR;
+                            let result = midiInOpen(&mut handle, id as UINT, on_message as usize, instance, CALLBACK_FUNCTION);
+                            if result == MMSYSERR_NOERROR && midiInStart(handle) == MMSYSERR_NOERROR {
+                                info!(id, "Reading MIDI");
+                                opened.push((handle, input));
+                            } else {
+                                warn!(id, result, "Could not open the MIDI input");
+                            }
+                        }
+                    }
+                }
+
+                if stop.recv_timeout(RESCAN_INTERVAL) == Err(RecvTimeoutError::Disconnected) {
+                    close(&mut opened);
+                    return;
+                }
+            }
+        })
+    }
+
+    fn close(opened: &mut Vec<(HMIDIIN, Box<Input>)>) {
+        for (handle, input) in opened.drain(..) {
+            // SAFETY: Only handles opened by `listen` end up here, and once closed no more callbacks come for them
+            unsafe {
+                midiInStop(handle);
+                midiInClose(handle);
+            }
+            drop(input);
+        }
+    }
+
+    extern "system" fn on_message(_handle: HMIDIIN, message: UINT, instance: DWORD_PTR, data: DWORD_PTR, _timestamp: DWORD_PTR) {
+        if message != MIM_DATA {
+            return;
+        }
+        // SAFETY: The instance data is the input the handle was opened with, which outlives the handle
+        let input = unsafe { &*(instance as *const Input) };
+
+        // Short messages are packed as the status byte followed by up to two data bytes
+        let [status, first, second, _] = (data as DWORD).to_le_bytes();
+        let control = {
+            let mut parser = input.parser.lock().unwrap();
+            [status, first, second].into_iter().find_map(|byte| parser.push(byte))
+        };
+        if let Some(control) = control {
+            (input.on_press)(control);
+        }
+    }
+}
+
+#[cfg(all(feature = "gui", not(any(target_os = "linux", target_os = "windows"))))]
+mod midi {
+    use std::{
+        sync::Arc,
+        thread::{self, JoinHandle},
+    };
+
+    use crossbeam_channel::Receiver;
+
+    use super::Control;
+
+    pub fn devices() -> Vec<String> {
+        Vec::new()
+    }
+
+    pub fn listen(_names: Vec<String>, _stop: Receiver<()>, _on_press: Arc<dyn Fn(Control) + Send + Sync>) -> JoinHandle<()> {
+        thread::spawn(|| {})
+    }
+}
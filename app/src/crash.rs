@@ -0,0 +1,62 @@
+//! Keeps the keyboard from freezing mid-frame when the app goes down unexpectedly, be it from a panic or from being
+//! asked to terminate.
+
+use std::{collections::BTreeMap, panic, process, sync::Mutex};
+
+type Restore = Box<dyn Fn() + Send>;
+
+/// Set by every effect manager that drives a real keyboard, by the path of the device
+static RESTORE: Mutex<BTreeMap<String, Restore>> = Mutex::new(BTreeMap::new());
+/// Set by whatever can shut the app down cleanly by itself, see [`set_on_terminate`]
+static ON_TERMINATE: Mutex<Option<Restore>> = Mutex::new(None);
+
+/// Hook into panics and termination requests, running after whatever panic hook was installed before
+pub fn install() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        tracing::error!("{info}");
+        restore();
+        previous(info);
+    }));
+
+    // SIGINT, SIGTERM and SIGHUP, or the console being closed on Windows
+    let _ = ctrlc::set_handler(|| {
+        // Only handed over once, so asking again goes down right away if the clean shutdown got stuck
+        if let Some(on_terminate) = ON_TERMINATE.lock().unwrap().take() {
+            on_terminate();
+            return;
+        }
+
+        restore();
+        process::exit(130);
+    });
+}
+
+/// Call `on_terminate` when asked to terminate instead of restoring the keyboard and exiting right away, leaving it to
+/// shut the app down
+pub fn set_on_terminate(on_terminate: impl Fn() + Send + 'static) {
+    *ON_TERMINATE.lock().unwrap() = Some(Box::new(on_terminate));
+}
+
+/// Go back to exiting right away when asked to terminate
+pub fn clear_on_terminate() {
+    ON_TERMINATE.lock().unwrap().take();
+}
+
+/// Run `restore` if the app goes down from now on
+pub fn set_restore(device: &str, restore: impl Fn() + Send + 'static) {
+    RESTORE.lock().unwrap().insert(device.to_string(), Box::new(restore));
+}
+
+pub fn clear_restore(device: &str) {
+    RESTORE.lock().unwrap().remove(device);
+}
+
+fn restore() {
+    // Only ever restore once, and don't wait on a lock the panicking thread might be holding
+    let restores = RESTORE.try_lock().ok().map(|mut restores| std::mem::take(&mut *restores)).unwrap_or_default();
+
+    for restore in restores.into_values() {
+        restore();
+    }
+}
@@ -0,0 +1,129 @@
+//! A plain text report to attach to bug reports, with what's usually asked for first: the system, the HID devices
+//! that were found, whether the keyboard can be opened and the last lines of the log.
+
+use std::{
+    fmt::Write as _,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use sysinfo::System;
+
+use crate::logging;
+
+/// How much of the latest log file to include
+const LOG_LINES: usize = 200;
+
+/// Collect everything into a report
+pub fn report() -> String {
+    let mut report = String::new();
+
+    let _ = writeln!(report, "Legion Keyboard Control diagnostics");
+    let _ = writeln!(report, "Generated: {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S %z"));
+    let _ = writeln!(report, "Version: {} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+
+    let _ = writeln!(report, "\n## System");
+    let _ = writeln!(report, "OS: {}", System::long_os_version().unwrap_or_else(|| std::env::consts::OS.to_string()));
+    let _ = writeln!(report, "Kernel: {}", System::kernel_version().unwrap_or_else(|| "Unknown".to_string()));
+    let _ = writeln!(report, "Architecture: {}", std::env::consts::ARCH);
+    #[cfg(target_os = "linux")]
+    let _ = writeln!(report, "Session: {}", if std::env::var_os("WAYLAND_DISPLAY").is_some() { "Wayland" } else { "X11" });
+
+    let _ = writeln!(report, "\n## Keyboard");
+    match legion_kb_rgb_hw::check_access() {
+        Ok(()) => {
+            let _ = writeln!(report, "Access: OK");
+        }
+        Err(err) => {
+            let _ = writeln!(report, "Access: {err}");
+        }
+    }
+    #[cfg(target_os = "linux")]
+    let _ = writeln!(
+        report,
+        "udev rule: {}",
+        find_udev_rule().map_or_else(|| "Not found".to_string(), |path| path.display().to_string())
+    );
+
+    let _ = writeln!(report, "\n## HID devices");
+    match legion_kb_rgb_hw::list_devices() {
+        Ok(mut devices) => {
+            devices.sort_by(|a, b| (a.vendor_id, a.product_id, &a.path).cmp(&(b.vendor_id, b.product_id, &b.path)));
+            devices.dedup_by(|a, b| a.path == b.path);
+
+            for device in devices {
+                let _ = write!(
+                    report,
+                    "{:#06x}:{:#06x} usage {:#06x}:{:#06x} \"{}\" {}",
+                    device.vendor_id,
+                    device.product_id,
+                    device.usage_page,
+                    device.usage,
+                    device.product.as_deref().unwrap_or_default(),
+                    device.path
+                );
+                #[cfg(target_os = "linux")]
+                if let Some(mode) = permissions(&device.path) {
+                    let _ = write!(report, " ({mode:o})");
+                }
+                let _ = writeln!(report, "{}", if device.supported { " [supported]" } else { "" });
+            }
+        }
+        Err(err) => {
+            let _ = writeln!(report, "Could not list the devices: {err}");
+        }
+    }
+
+    let _ = writeln!(report, "\n## Recent log");
+    match latest_log().and_then(|path| fs::read_to_string(path).ok()) {
+        Some(log) => {
+            let lines: Vec<&str> = log.lines().collect();
+            for line in &lines[lines.len().saturating_sub(LOG_LINES)..] {
+                let _ = writeln!(report, "{line}");
+            }
+        }
+        None => {
+            let _ = writeln!(report, "No log file was found in {}", logging::folder().display());
+        }
+    }
+
+    report
+}
+
+/// Write the report to `path`
+pub fn save(path: &Path) -> io::Result<()> {
+    fs::write(path, report())
+}
+
+/// A name for the report that won't clash with earlier ones
+pub fn default_file_name() -> String {
+    format!("{}-diagnostics-{}.txt", env!("CARGO_PKG_NAME"), chrono::Local::now().format("%Y%m%d-%H%M%S"))
+}
+
+fn latest_log() -> Option<PathBuf> {
+    fs::read_dir(logging::folder())
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().extension().is_some_and(|extension| extension == "log"))
+        .max_by_key(|entry| entry.metadata().and_then(|metadata| metadata.modified()).ok())
+        .map(|entry| entry.path())
+}
+
+/// Access to the keyboard is usually granted through a rule matching its vendor ID
+#[cfg(target_os = "linux")]
+fn find_udev_rule() -> Option<PathBuf> {
+    ["/etc/udev/rules.d", "/usr/lib/udev/rules.d", "/lib/udev/rules.d"]
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .find(|path| fs::read_to_string(path).is_ok_and(|rules| rules.to_lowercase().contains("048d")))
+}
+
+#[cfg(target_os = "linux")]
+fn permissions(path: &str) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::metadata(path).ok().map(|metadata| metadata.permissions().mode() & 0o777)
+}
@@ -0,0 +1,160 @@
+//! The folder custom effects are kept in, next to the settings. Every effect file dropped in it shows up in the list of
+//! effects and can be played by name from the command line. Effects got from the community browser or written in the
+//! expression editor are saved there too.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use error_stack::{Result, ResultExt};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use thiserror::Error;
+use tracing::warn;
+
+use crate::{manager::custom_effect::CustomEffect, persist::Settings};
+
+/// How long the folder has to be left alone before it's read again, as saving a single file takes a few events
+const SETTLE_TIME: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Error)]
+#[error("Could not add the effect to the library")]
+pub struct InstallEffectError;
+
+/// An effect read from the library
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibraryEffect {
+    /// The file the effect was read from
+    pub path: PathBuf,
+    pub effect: CustomEffect,
+}
+
+impl LibraryEffect {
+    /// The name of the effect, or of its file if it has none
+    pub fn name(&self) -> String {
+        self.effect
+            .name
+            .clone()
+            .unwrap_or_else(|| self.path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default())
+    }
+
+    /// Whether the effect goes by `name`, ignoring case, or is in a file named that
+    pub fn is_named(&self, name: &str) -> bool {
+        let name = name.trim();
+        self.name().eq_ignore_ascii_case(name) || self.path.file_stem().is_some_and(|stem| stem.to_string_lossy().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Where the library is kept
+pub fn folder() -> PathBuf {
+    Settings::get_location().parent().map_or_else(|| PathBuf::from("effects"), |dir| dir.join("effects"))
+}
+
+/// Read every effect in the library, sorted by name, leaving out the files that aren't effects
+pub fn scan() -> Vec<LibraryEffect> {
+    let Ok(entries) = fs::read_dir(folder()) else {
+        return Vec::new();
+    };
+
+    let mut effects: Vec<LibraryEffect> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| is_effect_file(path))
+        .filter_map(|path| match CustomEffect::from_file(&path) {
+            Ok(effect) => Some(LibraryEffect { path, effect }),
+            Err(err) => {
+                warn!("Skipping {} in the effect library: {err:?}", path.display());
+                None
+            }
+        })
+        .collect();
+
+    effects.sort_by_cached_key(|effect| effect.name().to_lowercase());
+    effects
+}
+
+/// The effect in the library going by `name`, see [`LibraryEffect::is_named`]
+pub fn find(name: &str) -> Option<LibraryEffect> {
+    scan().into_iter().find(|effect| effect.is_named(name))
+}
+
+/// Call `on_change` with the library read again whenever an effect is added to it, changed or removed from it
+///
+/// The folder is made if it isn't there yet, so there's something to watch. It's read on a thread of its own once the
+/// changes settle, instead of once for every event. Stops watching once dropped.
+pub fn watch<F: Fn(Vec<LibraryEffect>) + Send + 'static>(on_change: F) -> Option<RecommendedWatcher> {
+    let folder = folder();
+    fs::create_dir_all(&folder).ok()?;
+
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+
+        if !event.kind.is_access() && event.paths.iter().any(|path| is_effect_file(path)) {
+            let _ = tx.send(());
+        }
+    })
+    .ok()?;
+
+    watcher.watch(&folder, RecursiveMode::NonRecursive).ok()?;
+
+    // Ends once the watcher is dropped along with the sender
+    thread::spawn(move || {
+        while rx.recv().is_ok() {
+            while rx.recv_timeout(SETTLE_TIME).is_ok() {}
+            on_change(scan());
+        }
+    });
+
+    Some(watcher)
+}
+
+/// Save `effect` in the library, in a file named after it, returning it as it was saved
+///
+/// Effects already in the library are left alone, a name that's taken gets a number after it, both in the file and in
+/// the effect, so the two can be told apart.
+pub fn install(effect: &CustomEffect) -> Result<LibraryEffect, InstallEffectError> {
+    let folder = folder();
+    fs::create_dir_all(&folder).change_context(InstallEffectError)?;
+
+    let taken = scan();
+    let name = effect.name.as_deref().unwrap_or_default().trim();
+    let is_free = |name: &str| {
+        let stem = file_stem(name);
+        !folder.join(format!("{stem}.json")).exists() && !taken.iter().any(|effect| effect.is_named(name) || effect.is_named(&stem))
+    };
+
+    let mut installed = effect.clone();
+    if !is_free(name) {
+        let name = if name.is_empty() { file_stem(name) } else { name.to_string() };
+        installed.name = (2..).map(|n| format!("{name} ({n})")).find(|name| is_free(name));
+    }
+
+    let path = folder.join(format!("{}.json", file_stem(installed.name.as_deref().unwrap_or_default())));
+    installed.save_file(&path).change_context(InstallEffectError)?;
+
+    Ok(LibraryEffect { path, effect: installed })
+}
+
+fn is_effect_file(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+}
+
+/// The name of an effect with only the characters every file system accepts
+fn file_stem(name: &str) -> String {
+    let stem: String = name
+        .trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '(' | ')') { c } else { '_' })
+        .collect();
+
+    if stem.is_empty() {
+        "effect".to_string()
+    } else {
+        stem
+    }
+}
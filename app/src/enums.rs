@@ -1,7 +1,20 @@
-use crate::manager::{custom_effect::CustomEffect, profile::Profile};
+use crate::manager::{custom_effect::CustomEffect, flash::FlashPattern, profile::Profile};
+use crossbeam_channel::Sender;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use strum_macros::{Display, EnumIter, EnumString, IntoStaticStr};
 
+/// What the `max_mbps` of the `Network` effect stands for when left at 0
+pub const DEFAULT_MAX_MBPS: u16 = 100;
+/// What the speed range of the `TypingSpeed` effect stands for when its top is left at 0
+pub const DEFAULT_MIN_WPM: u16 = 20;
+pub const DEFAULT_MAX_WPM: u16 = 100;
+/// What the rule of the `Automaton` effect stands for when left at 0, which would only ever go dark
+pub const DEFAULT_AUTOMATON_RULE: u8 = 30;
+/// What the flicker and hue of the `Fire` effect stand for when its intensity is left at 0, which would hold still
+pub const DEFAULT_FIRE_INTENSITY: f32 = 0.5;
+pub const DEFAULT_FIRE_HUE: f32 = 30.0;
+
 #[derive(Clone, Copy, EnumString, Serialize, Deserialize, Display, EnumIter, Debug, IntoStaticStr, Default)]
 pub enum Effects {
     #[default]
@@ -21,6 +34,42 @@ pub enum Effects {
     Fade,
     Temperature,
     Ripple,
+    Fire {
+        intensity: f32,
+        hue: f32,
+    },
+    Daylight {
+        latitude: f32,
+        longitude: f32,
+    },
+    Weather {
+        latitude: f32,
+        longitude: f32,
+        refresh_minutes: u16,
+    },
+    Clock {
+        /// Count down from this many minutes instead of showing the time, if not 0
+        countdown_minutes: u16,
+    },
+    Network {
+        /// Throughput lighting the zones up fully, in Mbit/s
+        max_mbps: u16,
+        /// Move a lit zone around faster the more data goes through, instead of lighting the zones up
+        chase: bool,
+    },
+    Heatmap,
+    TypingSpeed {
+        /// Words per minute at which the meter starts going up
+        min_wpm: u16,
+        /// Words per minute at which the meter is full
+        max_wpm: u16,
+        /// Light the zones up one after the other, instead of changing the color of the whole keyboard
+        gauge: bool,
+    },
+    Automaton {
+        /// Elementary cellular automaton rule, in Wolfram's numbering
+        rule: u8,
+    },
 }
 
 impl PartialEq for Effects {
@@ -32,7 +81,18 @@ impl PartialEq for Effects {
 #[allow(dead_code)]
 impl Effects {
     pub fn takes_color_array(self) -> bool {
-        matches!(self, Self::Static | Self::Breath | Self::Lightning | Self::Swipe { .. } | Self::Fade | Self::Ripple)
+        matches!(
+            self,
+            Self::Static
+                | Self::Breath
+                | Self::Lightning
+                | Self::Swipe { .. }
+                | Self::Fade
+                | Self::Ripple
+                | Self::Network { .. }
+                | Self::TypingSpeed { .. }
+                | Self::Automaton { .. }
+        )
     }
 
     pub fn takes_direction(self) -> bool {
@@ -42,13 +102,97 @@ impl Effects {
     pub fn takes_speed(self) -> bool {
         matches!(
             self,
-            Self::Breath | Self::Smooth | Self::Wave | Self::Lightning | Self::SmoothWave | Self::Swipe | Self::Disco | Self::Fade | Self::Ripple
+            Self::Breath
+                | Self::Smooth
+                | Self::Wave
+                | Self::Lightning
+                | Self::SmoothWave
+                | Self::Swipe
+                | Self::Disco
+                | Self::Fade
+                | Self::Ripple
+                | Self::Fire { .. }
+                | Self::Weather { .. }
+                | Self::Automaton { .. }
+        )
+    }
+
+    /// Whether the effect depends on the screen, the sensors, the time of day or key presses, so it never plays the same
+    /// way twice and can't be shown ahead of time
+    pub fn reads_environment(self) -> bool {
+        matches!(
+            self,
+            Self::AmbientLight { .. }
+                | Self::Temperature
+                | Self::Daylight { .. }
+                | Self::Weather { .. }
+                | Self::Clock { .. }
+                | Self::Network { .. }
+                | Self::Heatmap
+                | Self::TypingSpeed { .. }
+                | Self::Fade
+                | Self::Ripple
         )
     }
 
     pub fn is_built_in(self) -> bool {
         matches!(self, Self::Static | Self::Breath | Self::Smooth | Self::Wave)
     }
+
+    /// Which parameters the intensity knob of a profile scales for this effect
+    pub fn intensity_mapping(self) -> IntensityMapping {
+        match self {
+            Self::Static | Self::AmbientLight { .. } | Self::Network { .. } | Self::TypingSpeed { .. } => IntensityMapping {
+                brightness: true,
+                speed: false,
+                saturation: true,
+            },
+            Self::Breath | Self::Lightning | Self::Swipe | Self::Fade | Self::Ripple | Self::Automaton { .. } => IntensityMapping {
+                brightness: true,
+                speed: true,
+                saturation: true,
+            },
+            Self::Smooth | Self::Wave | Self::SmoothWave | Self::Disco | Self::Fire { .. } | Self::Weather { .. } => IntensityMapping {
+                brightness: true,
+                speed: true,
+                saturation: false,
+            },
+            Self::Christmas | Self::Temperature | Self::Daylight { .. } | Self::Clock { .. } | Self::Heatmap => IntensityMapping {
+                brightness: true,
+                speed: false,
+                saturation: false,
+            },
+        }
+    }
+}
+
+impl Effects {
+    /// Rough number of times per second the effect sends new colors to the keyboard, 0 for effects handled by the hardware
+    pub fn update_rate(self, speed: u8) -> f32 {
+        let speed = f32::from(speed.max(1));
+
+        match self {
+            Self::Static | Self::Breath | Self::Smooth | Self::Wave => 0.0,
+            Self::AmbientLight { fps, .. } => f32::from(fps.clamp(1, 60)),
+            Self::Lightning | Self::Swipe | Self::SmoothWave => 20.0 * speed,
+            Self::Disco => 2.0 * speed,
+            Self::Christmas | Self::Ripple | Self::Automaton { .. } => 20.0,
+            Self::Fade => 50.0,
+            Self::Temperature => 5.0,
+            Self::Fire { .. } => 10.0 * speed,
+            Self::Daylight { .. } => 0.1,
+            Self::Weather { .. } => 20.0,
+            Self::Clock { .. } | Self::Heatmap => 1.0,
+            Self::Network { .. } | Self::TypingSpeed { .. } => 20.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IntensityMapping {
+    pub brightness: bool,
+    pub speed: bool,
+    pub saturation: bool,
 }
 
 #[derive(Clone, Copy, EnumString, Serialize, Deserialize, Debug, EnumIter, IntoStaticStr, PartialEq, Eq, Default)]
@@ -68,6 +212,21 @@ pub enum Brightness {
 #[derive(Debug)]
 pub enum Message {
     CustomEffect { effect: CustomEffect },
-    Profile { profile: Profile },
+    /// Play a profile, fading to its colors first when `crossfade` is set. Profiles that only take over for a moment aren't
+    /// `publish`ed as the status, as the colors games broadcast would otherwise rewrite it every frame.
+    Profile { profile: Profile, crossfade: bool, publish: bool },
+    Flash { pattern: FlashPattern },
+    /// Show each of these zone colors for `hold`, to tell the zones apart
+    FlashZones { frames: Vec<[u8; 12]>, hold: Duration },
+    #[cfg(feature = "dev-effects")]
+    DevEffect { path: std::path::PathBuf, profile: Profile },
+    /// Turn the lights off until woken up
+    Sleep,
+    /// Go back to whatever was playing before sleeping
+    Wake,
+    /// Send the current state again, so a change to the overlay shows while nothing is playing
+    Refresh,
+    /// Leave the keyboard in a state that makes sense without the app and exit, letting `done` know once finished
+    Restore { done: Sender<()> },
     Exit,
 }
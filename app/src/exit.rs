@@ -0,0 +1,45 @@
+//! What is left on the keyboard when the program goes away, be it by closing it or from a crash or termination request.
+
+use serde::{Deserialize, Serialize};
+use strum_macros::{EnumIter, IntoStaticStr};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, EnumIter, IntoStaticStr)]
+pub enum ExitBehavior {
+    /// The effects of the keyboard keep playing, the others stay on the colors of the profile
+    #[default]
+    #[strum(serialize = "Keep")]
+    Keep,
+    #[strum(serialize = "Turn off")]
+    TurnOff,
+    /// A single color on every zone, like the keyboard shows on its own
+    #[strum(serialize = "Fallback color")]
+    Fallback,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExitSettings {
+    pub behavior: ExitBehavior,
+    /// Shown with [`ExitBehavior::Fallback`]
+    pub fallback_color: [u8; 3],
+}
+
+impl Default for ExitSettings {
+    fn default() -> Self {
+        Self {
+            behavior: ExitBehavior::default(),
+            fallback_color: [255, 255, 255],
+        }
+    }
+}
+
+impl ExitSettings {
+    /// The colors to leave on the zones, `None` to keep what the profile shows
+    pub fn colors(&self) -> Option<[u8; 12]> {
+        match self.behavior {
+            ExitBehavior::Keep => None,
+            ExitBehavior::TurnOff => Some([0; 12]),
+            ExitBehavior::Fallback => Some(self.fallback_color.repeat(4).try_into().unwrap()),
+        }
+    }
+}
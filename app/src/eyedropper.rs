@@ -0,0 +1,115 @@
+//! Picks the color of any pixel on the screen, which the color pickers of the window can't reach.
+//!
+//! The mouse is polled through device_query until the next click, then the screen under it is captured the same way
+//! the `AmbientLight` effect does. Wayland doesn't let the mouse be followed outside the window, so it can't work there.
+
+use std::{
+    io::ErrorKind,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use device_query::{DeviceQuery, DeviceState, Keycode};
+use scrap::{Capturer, Display, Frame, TraitCapturer, TraitPixelBuffer};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(15);
+/// Give up on picking after this long without a click
+const TIMEOUT: Duration = Duration::from_secs(30);
+/// How long to wait for the first frame of the screen, capturers can take a few tries to get going
+const CAPTURE_TIMEOUT: Duration = Duration::from_secs(2);
+
+static PICKING: AtomicBool = AtomicBool::new(false);
+
+/// How picking a color went
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pick {
+    Color([u8; 3]),
+    /// Escape was pressed, or nothing was clicked in time
+    Cancelled,
+    /// The mouse or the screen couldn't be read
+    Failed,
+}
+
+/// Spawn a background thread that waits for the next left click anywhere on the screen and calls `on_pick` with the
+/// color under it
+///
+/// Only one pick runs at a time, calling this while another is waiting for a click does nothing.
+pub fn spawn_picker<F: FnOnce(Pick) + Send + 'static>(on_pick: F) {
+    if PICKING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    thread::spawn(move || {
+        let pick = wait_for_click().map_or(Pick::Failed, |click| click.map_or(Pick::Cancelled, |(x, y)| sample(x, y).map_or(Pick::Failed, Pick::Color)));
+        PICKING.store(false, Ordering::SeqCst);
+        on_pick(pick);
+    });
+}
+
+/// Where the next left click lands, `None` if picking got cancelled, or `Err` if the mouse can't be read
+fn wait_for_click() -> Result<Option<(i32, i32)>, ()> {
+    #[cfg(target_os = "linux")]
+    let state = DeviceState::checked_new().ok_or(())?;
+    #[cfg(not(target_os = "linux"))]
+    let state = DeviceState::new();
+
+    // The button is likely still down from the click that started picking, wait for it to be let go first
+    let mut was_pressed = true;
+    let started = Instant::now();
+
+    while started.elapsed() < TIMEOUT {
+        if state.get_keys().contains(&Keycode::Escape) {
+            return Ok(None);
+        }
+
+        let mouse = state.get_mouse();
+        // The first button is a placeholder, the left one comes after it
+        let pressed = mouse.button_pressed.get(1).copied().unwrap_or(false);
+        if pressed && !was_pressed {
+            return Ok(Some(mouse.coords));
+        }
+        was_pressed = pressed;
+
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    Ok(None)
+}
+
+/// The color of the pixel at `x`, `y` of the desktop, going through every display to find the one it's on
+fn sample(x: i32, y: i32) -> Option<[u8; 3]> {
+    let display = Display::all().ok()?.into_iter().find(|display| {
+        let (left, top) = display.origin();
+        (left..left + display.width() as i32).contains(&x) && (top..top + display.height() as i32).contains(&y)
+    })?;
+    let (left, top) = display.origin();
+    let (x, y) = ((x - left) as usize, (y - top) as usize);
+
+    let mut capturer = Capturer::new(display).ok()?;
+    let width = capturer.width();
+    let started = Instant::now();
+
+    while started.elapsed() < CAPTURE_TIMEOUT {
+        match capturer.frame(POLL_INTERVAL) {
+            Ok(Frame::PixelBuffer(buffer)) => {
+                // The pixels are stored as BGRA, row after row
+                let data = buffer.data();
+                let i = (y * width + x) * 4;
+                return data.get(i..i + 3).map(|bgr| [bgr[2], bgr[1], bgr[0]]);
+            }
+            Ok(_) => return None,
+            Err(err) if err.kind() == ErrorKind::WouldBlock => {}
+            Err(_) => {
+                #[cfg(target_os = "windows")]
+                if !capturer.is_gdi() {
+                    capturer.set_gdi();
+                    continue;
+                }
+                return None;
+            }
+        }
+    }
+
+    None
+}
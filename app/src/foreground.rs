@@ -0,0 +1,212 @@
+//! Watches the focused window to know which application is in front of the user.
+
+use std::{thread, time::Duration};
+
+use crate::ticks;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForegroundWindow {
+    /// File name of the executable that owns the window
+    pub process_name: String,
+    pub title: String,
+    /// Whether the window covers the entire screen it's on
+    pub fullscreen: bool,
+}
+
+/// Spawn a background thread that calls `on_change` whenever the focused window changes
+pub fn spawn_watcher<F: Fn(Option<ForegroundWindow>) + Send + 'static>(on_change: F) {
+    thread::spawn(move || {
+        let Some(monitor) = platform::Monitor::new() else {
+            return;
+        };
+
+        let mut last_window = None;
+
+        loop {
+            let window = monitor.foreground_window();
+
+            if window != last_window {
+                on_change(window.clone());
+                last_window = window;
+            }
+
+            ticks::sleep_until_next(POLL_INTERVAL);
+        }
+    });
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use x11rb::{
+        connection::Connection,
+        protocol::xproto::{Atom, AtomEnum, ConnectionExt, Window},
+        rust_connection::RustConnection,
+    };
+
+    use super::ForegroundWindow;
+
+    /// Uses the EWMH hints set by the window manager, so this only sees X11 (or XWayland) windows
+    pub struct Monitor {
+        connection: RustConnection,
+        root: Window,
+        active_window_atom: Atom,
+        wm_state_atom: Atom,
+        fullscreen_atom: Atom,
+        wm_pid_atom: Atom,
+        wm_name_atom: Atom,
+    }
+
+    impl Monitor {
+        pub fn new() -> Option<Self> {
+            let (connection, screen_num) = x11rb::connect(None).ok()?;
+            let root = connection.setup().roots.get(screen_num)?.root;
+
+            let intern = |name: &[u8]| connection.intern_atom(false, name).ok()?.reply().ok().map(|reply| reply.atom);
+            let active_window_atom = intern(b"_NET_ACTIVE_WINDOW")?;
+            let wm_state_atom = intern(b"_NET_WM_STATE")?;
+            let fullscreen_atom = intern(b"_NET_WM_STATE_FULLSCREEN")?;
+            let wm_pid_atom = intern(b"_NET_WM_PID")?;
+            let wm_name_atom = intern(b"_NET_WM_NAME")?;
+
+            Some(Self {
+                connection,
+                root,
+                active_window_atom,
+                wm_state_atom,
+                fullscreen_atom,
+                wm_pid_atom,
+                wm_name_atom,
+            })
+        }
+
+        pub fn foreground_window(&self) -> Option<ForegroundWindow> {
+            let active = self.connection.get_property(false, self.root, self.active_window_atom, AtomEnum::WINDOW, 0, 1).ok()?.reply().ok()?;
+            let window = active.value32()?.next()?;
+
+            if window == x11rb::NONE {
+                return None;
+            }
+
+            let state = self.connection.get_property(false, window, self.wm_state_atom, AtomEnum::ATOM, 0, 32).ok()?.reply().ok()?;
+            let fullscreen = state.value32().is_some_and(|mut atoms| atoms.any(|atom| atom == self.fullscreen_atom));
+
+            let title = self
+                .connection
+                .get_property(false, window, self.wm_name_atom, AtomEnum::ANY, 0, 1024)
+                .ok()
+                .and_then(|cookie| cookie.reply().ok())
+                .map(|reply| String::from_utf8_lossy(&reply.value).into_owned())
+                .unwrap_or_default();
+
+            let process_name = self
+                .connection
+                .get_property(false, window, self.wm_pid_atom, AtomEnum::CARDINAL, 0, 1)
+                .ok()
+                .and_then(|cookie| cookie.reply().ok())
+                .and_then(|reply| reply.value32()?.next())
+                .and_then(|pid| std::fs::read_to_string(format!("/proc/{pid}/comm")).ok())
+                .map(|name| name.trim().to_string())
+                .unwrap_or_default();
+
+            Some(ForegroundWindow { process_name, title, fullscreen })
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::{ffi::OsString, os::windows::ffi::OsStringExt, path::PathBuf};
+
+    use winapi::{
+        shared::{minwindef::DWORD, windef::RECT},
+        um::{
+            handleapi::CloseHandle,
+            processthreadsapi::OpenProcess,
+            winbase::QueryFullProcessImageNameW,
+            winnt::PROCESS_QUERY_LIMITED_INFORMATION,
+            winuser::{
+                GetDesktopWindow, GetForegroundWindow, GetMonitorInfoW, GetShellWindow, GetWindowRect, GetWindowTextW, GetWindowThreadProcessId, MonitorFromWindow, MONITORINFO,
+                MONITOR_DEFAULTTONEAREST,
+            },
+        },
+    };
+
+    use super::ForegroundWindow;
+
+    pub struct Monitor;
+
+    impl Monitor {
+        pub fn new() -> Option<Self> {
+            Some(Self)
+        }
+
+        pub fn foreground_window(&self) -> Option<ForegroundWindow> {
+            unsafe {
+                let window = GetForegroundWindow();
+
+                if window.is_null() || window == GetShellWindow() || window == GetDesktopWindow() {
+                    return None;
+                }
+
+                let mut title_buffer = [0u16; 512];
+                let title_len = GetWindowTextW(window, title_buffer.as_mut_ptr(), title_buffer.len() as i32);
+                let title = String::from_utf16_lossy(&title_buffer[..title_len.max(0) as usize]);
+
+                let mut pid: DWORD = 0;
+                GetWindowThreadProcessId(window, &mut pid);
+                let process_name = process_name(pid).unwrap_or_default();
+
+                // A window is considered fullscreen if it covers the entire monitor it's on
+                let mut window_rect: RECT = std::mem::zeroed();
+                let monitor = MonitorFromWindow(window, MONITOR_DEFAULTTONEAREST);
+                let mut monitor_info: MONITORINFO = std::mem::zeroed();
+                monitor_info.cbSize = std::mem::size_of::<MONITORINFO>() as u32;
+
+                let fullscreen = GetWindowRect(window, &mut window_rect) != 0 && GetMonitorInfoW(monitor, &mut monitor_info) != 0 && {
+                    let monitor_rect = monitor_info.rcMonitor;
+                    window_rect.left <= monitor_rect.left && window_rect.top <= monitor_rect.top && window_rect.right >= monitor_rect.right && window_rect.bottom >= monitor_rect.bottom
+                };
+
+                Some(ForegroundWindow { process_name, title, fullscreen })
+            }
+        }
+    }
+
+    unsafe fn process_name(pid: DWORD) -> Option<String> {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if handle.is_null() {
+            return None;
+        }
+
+        let mut buffer = [0u16; 1024];
+        let mut len = buffer.len() as DWORD;
+        let success = QueryFullProcessImageNameW(handle, 0, buffer.as_mut_ptr(), &mut len) != 0;
+        CloseHandle(handle);
+
+        if !success {
+            return None;
+        }
+
+        let path = PathBuf::from(OsString::from_wide(&buffer[..len as usize]));
+        path.file_name().map(|name| name.to_string_lossy().into_owned())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+mod platform {
+    use super::ForegroundWindow;
+
+    pub struct Monitor;
+
+    impl Monitor {
+        pub fn new() -> Option<Self> {
+            None
+        }
+
+        pub fn foreground_window(&self) -> Option<ForegroundWindow> {
+            None
+        }
+    }
+}
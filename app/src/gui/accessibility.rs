@@ -0,0 +1,9 @@
+//! Screen reader support for the widgets egui can't describe on its own.
+
+use eframe::egui::{Response, WidgetInfo, WidgetType};
+
+/// Replace what a screen reader announces for a widget, keeping whether it is enabled
+pub fn describe(response: &Response, typ: WidgetType, label: impl Into<String>) {
+    let label = label.into();
+    response.widget_info(|| WidgetInfo::labeled(typ, response.enabled(), &label));
+}
@@ -0,0 +1,38 @@
+use eframe::egui::{Button, Context, Grid, Slider, Window};
+use legion_kb_rgb_hw::Calibration;
+use rust_i18n::t;
+
+const CHANNELS: [&str; 3] = ["calibration.red", "calibration.green", "calibration.blue"];
+
+/// Edit the color correction of the shown keyboard, returns whether it changed
+pub fn show(ctx: &Context, open: &mut bool, device: Option<&str>, calibration: &mut Calibration) -> bool {
+    let mut changed = false;
+
+    Window::new(t!("calibration.title")).open(open).resizable(false).collapsible(false).show(ctx, |ui| {
+        if let Some(device) = device {
+            ui.label(t!("calibration.device", device = device));
+        }
+        ui.label(t!("calibration.hint"));
+
+        Grid::new("calibration_grid").num_columns(3).show(ui, |ui| {
+            ui.label("");
+            ui.label(t!("calibration.gain"));
+            ui.label(t!("calibration.gamma"));
+            ui.end_row();
+
+            for (channel, name) in CHANNELS.into_iter().enumerate() {
+                ui.label(t!(name));
+                changed |= ui.add(Slider::new(&mut calibration.gain[channel], 0.0..=1.0)).changed();
+                changed |= ui.add(Slider::new(&mut calibration.gamma[channel], 0.5..=3.0).logarithmic(true)).changed();
+                ui.end_row();
+            }
+        });
+
+        if ui.add_enabled(!calibration.is_identity(), Button::new(t!("calibration.reset"))).clicked() {
+            *calibration = Calibration::default();
+            changed = true;
+        }
+    });
+
+    changed
+}
@@ -0,0 +1,193 @@
+use crossbeam_channel::Sender;
+use eframe::{
+    egui::{ecolor::Hsva, Button, CollapsingHeader, ComboBox, Context, Slider, TextEdit, Ui},
+    epaint::Color32,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rust_i18n::t;
+use strum::IntoEnumIterator;
+
+use crate::{
+    eyedropper,
+    harmony::Harmony,
+    manager::{
+        profile::Profile,
+        swatch::{parse_hex, to_hex},
+    },
+    util::rgb_to_hsv,
+};
+
+use super::{i18n, GuiMessage};
+
+/// Precise editing of each zone's color, through hex codes and HSV sliders
+pub struct ColorEditor {
+    gui_sender: Sender<GuiMessage>,
+    zones: [ZoneEditor; 4],
+    /// Color copied from a zone, waiting to be pasted into another
+    copied: Option<[u8; 3]>,
+    /// The rule the other zones are filled with from the color of `seed_zone`
+    harmony: Harmony,
+    seed_zone: usize,
+    /// Seeds the chance part of the rule, so filling again gives the same colors until re-rolled
+    roll: u64,
+    /// The zone waiting for a color to be picked from the screen
+    picking: Option<usize>,
+}
+
+#[derive(Default)]
+struct ZoneEditor {
+    hex: String,
+    /// Kept separately from the color so the hue isn't lost while the saturation or value are at 0
+    hsv: [f32; 3],
+    /// The color the fields above were last synced with
+    synced: Option<[u8; 3]>,
+}
+
+impl ZoneEditor {
+    /// Pick up changes made to the color elsewhere
+    fn sync(&mut self, rgb: [u8; 3], hex_focused: bool) {
+        if self.synced == Some(rgb) {
+            return;
+        }
+
+        if !hex_focused {
+            self.hex = to_hex(rgb);
+        }
+        let hsva = Hsva::from_srgb(rgb);
+        self.hsv = [hsva.h, hsva.s, hsva.v];
+        self.synced = Some(rgb);
+    }
+}
+
+impl ColorEditor {
+    pub fn new(gui_sender: Sender<GuiMessage>) -> Self {
+        Self {
+            gui_sender,
+            zones: Default::default(),
+            copied: None,
+            harmony: Harmony::default(),
+            seed_zone: 0,
+            roll: 0,
+            picking: None,
+        }
+    }
+
+    /// Wait for a click anywhere on the screen to color `zone` with what's under it
+    fn pick_from_screen(&mut self, ctx: &Context, zone: usize) {
+        self.picking = Some(zone);
+
+        let ctx = ctx.clone();
+        let gui_sender = self.gui_sender.clone();
+        eyedropper::spawn_picker(move |pick| {
+            let _ = gui_sender.send(GuiMessage::PickedColor(zone, pick));
+            ctx.request_repaint();
+        });
+    }
+
+    /// The pick started from the color editor is over, whichever way it went
+    pub fn finish_pick(&mut self) {
+        self.picking = None;
+    }
+
+    pub fn show(&mut self, ui: &mut Ui, current_profile: &mut Profile, changed: &mut bool) {
+        CollapsingHeader::new(t!("color_editor.title")).id_salt("color_editor").show(ui, |ui| {
+            for (i, (zone, editor)) in current_profile.rgb_zones.iter_mut().zip(&mut self.zones).enumerate() {
+                let hex_id = ui.id().with(("zone_hex", i));
+                editor.sync(zone.rgb, ui.memory(|memory| memory.has_focus(hex_id)));
+
+                ui.horizontal(|ui| {
+                    let [r, g, b] = zone.rgb;
+                    ui.label(t!("color_editor.zone", zone = i + 1));
+                    ui.colored_label(Color32::from_rgb(r, g, b), "⏺");
+
+                    let response = ui.add(TextEdit::singleline(&mut editor.hex).id(hex_id).desired_width(70.0).hint_text("#RRGGBB"));
+                    if response.changed() {
+                        if let Some(rgb) = parse_hex(&editor.hex) {
+                            zone.rgb = rgb;
+                            *changed = true;
+                        }
+                    }
+                    if response.lost_focus() {
+                        editor.hex = to_hex(zone.rgb);
+                    }
+
+                    if ui.button(t!("color_editor.copy")).on_hover_text(t!("color_editor.copy_hint")).clicked() {
+                        self.copied = Some(zone.rgb);
+                        ui.ctx().copy_text(to_hex(zone.rgb));
+                    }
+                    if ui.add_enabled(self.copied.is_some(), Button::new(t!("color_editor.paste"))).clicked() {
+                        if let Some(rgb) = self.copied {
+                            zone.rgb = rgb;
+                            *changed = true;
+                        }
+                    }
+
+                    if self.picking == Some(i) {
+                        ui.spinner().on_hover_text(t!("color_editor.picking"));
+                    } else if ui.add_enabled(self.picking.is_none(), Button::new("🔍")).on_hover_text(t!("color_editor.pick_from_screen")).clicked() {
+                        self.pick_from_screen(ui.ctx(), i);
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    // Fit the three of them in the width taken by the zone buttons
+                    ui.spacing_mut().slider_width = 60.0;
+                    let [h, s, v] = &mut editor.hsv;
+                    let mut hsv_changed = ui.add(Slider::new(h, 0.0..=1.0).text(t!("color_editor.hue")).show_value(false)).changed();
+                    hsv_changed |= ui.add(Slider::new(s, 0.0..=1.0).text(t!("color_editor.saturation")).show_value(false)).changed();
+                    hsv_changed |= ui.add(Slider::new(v, 0.0..=1.0).text(t!("color_editor.value")).show_value(false)).changed();
+
+                    if hsv_changed {
+                        zone.rgb = Hsva::new(*h, *s, *v, 1.0).to_srgb();
+                        // Don't let the sync overwrite the hue with the one computed back from the color
+                        editor.synced = Some(zone.rgb);
+                        editor.hex = to_hex(zone.rgb);
+                        *changed = true;
+                    }
+                });
+            }
+
+            ui.separator();
+            self.show_harmony(ui, current_profile, changed);
+        });
+    }
+
+    fn show_harmony(&mut self, ui: &mut Ui, current_profile: &mut Profile, changed: &mut bool) {
+        ui.label(t!("color_editor.harmony")).on_hover_text(t!("color_editor.harmony_hint"));
+        ui.horizontal(|ui| {
+            ComboBox::from_id_salt("harmony_seed")
+                .selected_text(t!("color_editor.zone", zone = self.seed_zone + 1))
+                .show_ui(ui, |ui| {
+                    for zone in 0..4 {
+                        ui.selectable_value(&mut self.seed_zone, zone, t!("color_editor.zone", zone = zone + 1));
+                    }
+                });
+            ComboBox::from_id_salt("harmony_rule").selected_text(i18n::name_of("harmony", self.harmony)).show_ui(ui, |ui| {
+                for harmony in Harmony::iter() {
+                    ui.selectable_value(&mut self.harmony, harmony, i18n::name_of("harmony", harmony));
+                }
+            });
+
+            if ui.button(t!("color_editor.fill")).clicked() {
+                self.fill(current_profile);
+                *changed = true;
+            }
+            if ui.button("🎲").on_hover_text(t!("color_editor.reroll")).clicked() {
+                self.roll = rand::thread_rng().gen();
+                self.fill(current_profile);
+                *changed = true;
+            }
+        });
+    }
+
+    /// Color every zone but the seed one following the chosen harmony
+    fn fill(&self, current_profile: &mut Profile) {
+        let seed = rgb_to_hsv(current_profile.rgb_zones[self.seed_zone].rgb);
+        let colors = self.harmony.zones(seed, &mut StdRng::seed_from_u64(self.roll));
+
+        let others = (0..4).filter(|zone| *zone != self.seed_zone);
+        for (zone, rgb) in others.zip(&colors[1..]) {
+            current_profile.rgb_zones[zone].rgb = *rgb;
+        }
+    }
+}
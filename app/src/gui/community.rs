@@ -0,0 +1,201 @@
+//! Window listing the effects of the community repository, playing them on a simulated keyboard before installing
+//! them into the effect library.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    thread,
+    time::Duration,
+};
+
+use crossbeam_channel::Sender;
+use eframe::egui::{Align, Button, Context, Layout, RichText, ScrollArea, TextEdit, Window};
+use egui_notify::Toasts;
+use rust_i18n::t;
+use tracing::error;
+
+use crate::{
+    community::{self, CommunityEffect},
+    manager::custom_effect::{CustomEffect, Sampler},
+    ticks,
+};
+
+use super::{keyboard_preview::KeyboardLayout, GuiMessage};
+
+/// How often the preview is redrawn while playing
+const REPAINT_INTERVAL: Duration = Duration::from_millis(33);
+
+/// What the background requests report back
+pub enum CommunityEvent {
+    /// The effects listed in the index, `None` if it couldn't be got
+    Index(Option<Vec<CommunityEffect>>),
+    /// An effect was downloaded, by its name in the index, or `None` if that failed
+    Downloaded(String, Option<CustomEffect>),
+}
+
+pub struct CommunityBrowser {
+    pub open: bool,
+    gui_sender: Sender<GuiMessage>,
+    index: Option<Vec<CommunityEffect>>,
+    loading: bool,
+    search: String,
+    /// The effects downloaded so far, by their name in the index
+    downloaded: BTreeMap<String, CustomEffect>,
+    downloading: BTreeSet<String>,
+    /// The colors of the effect playing in the preview, and when it started
+    previewing: Option<(Sampler, f64)>,
+}
+
+impl CommunityBrowser {
+    pub fn new(gui_sender: Sender<GuiMessage>) -> Self {
+        Self {
+            open: false,
+            gui_sender,
+            index: None,
+            loading: false,
+            search: String::new(),
+            downloaded: BTreeMap::new(),
+            downloading: BTreeSet::new(),
+            previewing: None,
+        }
+    }
+
+    /// Open the window, getting the index if it wasn't already
+    pub fn open(&mut self, ctx: &Context, index_url: &str) {
+        self.open = true;
+        if self.index.is_none() {
+            self.refresh(ctx, index_url);
+        }
+    }
+
+    fn refresh(&mut self, ctx: &Context, index_url: &str) {
+        if self.loading {
+            return;
+        }
+        self.loading = true;
+
+        let ctx = ctx.clone();
+        let gui_sender = self.gui_sender.clone();
+        let index_url = index_url.to_string();
+        thread::spawn(move || {
+            let index = community::fetch_index(&index_url).map_err(|err| error!("Could not get the community effects: {err:?}")).ok();
+            let _ = gui_sender.send(GuiMessage::Community(CommunityEvent::Index(index)));
+            ctx.request_repaint();
+        });
+    }
+
+    fn download(&mut self, ctx: &Context, index_url: &str, effect: &CommunityEffect) {
+        if !self.downloading.insert(effect.name.clone()) {
+            return;
+        }
+
+        let ctx = ctx.clone();
+        let gui_sender = self.gui_sender.clone();
+        let index_url = index_url.to_string();
+        let effect = effect.clone();
+        thread::spawn(move || {
+            let downloaded = community::download(&index_url, &effect).map_err(|err| error!("Could not download {}: {err:?}", effect.name)).ok();
+            let _ = gui_sender.send(GuiMessage::Community(CommunityEvent::Downloaded(effect.name, downloaded)));
+            ctx.request_repaint();
+        });
+    }
+
+    pub fn handle(&mut self, ctx: &Context, event: CommunityEvent, toasts: &mut Toasts) {
+        match event {
+            CommunityEvent::Index(index) => {
+                self.loading = false;
+                if index.is_none() {
+                    toasts.error(t!("community.index_failed")).duration(Some(Duration::from_millis(5000))).closable(true);
+                }
+                self.index = index.or_else(|| self.index.take());
+            }
+            CommunityEvent::Downloaded(name, Some(effect)) => {
+                self.downloading.remove(&name);
+                self.previewing = Some((effect.sampler(), ctx.input(|input| input.time)));
+                self.downloaded.insert(name, effect);
+            }
+            CommunityEvent::Downloaded(name, None) => {
+                self.downloading.remove(&name);
+                toasts.error(t!("community.download_failed", name = name)).duration(Some(Duration::from_millis(5000))).closable(true);
+            }
+        }
+    }
+
+    /// Show the list, returning the effect picked to install
+    pub fn show(&mut self, ctx: &Context, layout: &KeyboardLayout, index_url: &str) -> Option<CustomEffect> {
+        let mut install = None;
+        let mut open = self.open;
+        let time = ctx.input(|input| input.time);
+
+        Window::new(t!("community.title")).open(&mut open).collapsible(false).default_width(420.0).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.add(TextEdit::singleline(&mut self.search).hint_text(t!("community.search_hint")).desired_width(260.0));
+                if ui.add_enabled(!self.loading, Button::new(t!("community.refresh"))).clicked() {
+                    self.refresh(ctx, index_url);
+                }
+            });
+
+            // What's playing in the preview
+            match &self.previewing {
+                Some((sampler, started)) => {
+                    let rgb = sampler.colors_at(((time - started) * 1000.0) as u64);
+                    layout.show(ui, [0, 1, 2, 3].map(|zone| [rgb[zone * 3], rgb[zone * 3 + 1], rgb[zone * 3 + 2]]));
+                    ctx.request_repaint_after(ticks::until_next(REPAINT_INTERVAL));
+                }
+                None => {
+                    ui.label(t!("community.preview_hint"));
+                }
+            }
+            ui.separator();
+
+            let Some(index) = self.index.clone() else {
+                if self.loading {
+                    ui.spinner();
+                } else {
+                    ui.label(t!("community.index_failed"));
+                }
+                return;
+            };
+
+            ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+                for effect in index.iter().filter(|effect| effect.matches_search(&self.search)) {
+                    ui.horizontal(|ui| {
+                        ui.vertical(|ui| {
+                            ui.label(RichText::new(&effect.name).strong());
+                            if let Some(author) = &effect.author {
+                                ui.small(t!("saved_items.effect_author", author = author));
+                            }
+                        });
+
+                        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                            match self.downloaded.get(&effect.name) {
+                                Some(downloaded) => {
+                                    if ui.button(t!("community.install")).clicked() {
+                                        install = Some(downloaded.clone());
+                                    }
+                                    if ui.button(t!("community.preview")).clicked() {
+                                        self.previewing = Some((downloaded.sampler(), time));
+                                    }
+                                }
+                                None if self.downloading.contains(&effect.name) => {
+                                    ui.spinner();
+                                }
+                                None => {
+                                    if ui.button(t!("community.preview")).clicked() {
+                                        self.download(ctx, index_url, effect);
+                                    }
+                                }
+                            }
+                        });
+                    });
+                    if let Some(description) = &effect.description {
+                        ui.label(description);
+                    }
+                    ui.separator();
+                }
+            });
+        });
+
+        self.open = open;
+        install
+    }
+}
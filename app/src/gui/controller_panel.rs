@@ -0,0 +1,143 @@
+use eframe::egui::{Button, ComboBox, Context, Grid, Window};
+use rust_i18n::t;
+
+use crate::{
+    controllers::{self, Control, ControlAction, ControlBinding, ControllerSettings},
+    enums::Brightness,
+    manager::profile::Profile,
+};
+
+/// Assigns the buttons of Stream Decks and MIDI controllers to actions
+#[derive(Default)]
+pub struct ControllerPanel {
+    pub open: bool,
+    /// The binding waiting for a control to be pressed
+    learning: Option<usize>,
+    /// The MIDI devices plugged in, looked up when the panel opens
+    midi_devices: Option<Vec<String>>,
+}
+
+impl ControllerPanel {
+    /// Returns whether the devices to read changed, or reading them was turned on or off
+    pub fn show(&mut self, ctx: &Context, settings: &mut ControllerSettings, profiles: &[Profile]) -> bool {
+        let mut open = self.open;
+        let mut changed = false;
+
+        Window::new(t!("controllers.title")).open(&mut open).resizable(false).collapsible(false).show(ctx, |ui| {
+            changed |= ui.checkbox(&mut settings.enabled, t!("controllers.enabled")).changed();
+            ui.label(t!("controllers.hint"));
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label(t!("controllers.midi_devices"));
+                if ui.button(t!("controllers.refresh")).clicked() {
+                    self.midi_devices = None;
+                }
+            });
+            let plugged_in = self.midi_devices.get_or_insert_with(controllers::midi_devices);
+            // The ones picked before stay listed while unplugged, so they can still be unpicked
+            let mut names = plugged_in.clone();
+            names.extend(settings.midi_devices.iter().filter(|name| !plugged_in.contains(name)).cloned());
+            if names.is_empty() {
+                ui.weak(t!("controllers.no_midi_devices"));
+            }
+            for name in names {
+                let mut read = settings.midi_devices.contains(&name);
+                if ui.checkbox(&mut read, &name).changed() {
+                    if read {
+                        settings.midi_devices.push(name);
+                    } else {
+                        settings.midi_devices.retain(|picked| *picked != name);
+                    }
+                    changed = true;
+                }
+            }
+            ui.separator();
+
+            let mut remove = None;
+            Grid::new("controllers_grid").num_columns(3).show(ui, |ui| {
+                for (i, binding) in settings.bindings.iter_mut().enumerate() {
+                    let learning = self.learning == Some(i);
+                    let label = if learning {
+                        t!("controllers.listening")
+                    } else {
+                        binding.control.map_or_else(|| t!("controllers.learn"), control_name)
+                    };
+                    if ui.add(Button::new(label).selected(learning)).on_hover_text(t!("controllers.learn_hint")).clicked() {
+                        self.learning = if learning { None } else { Some(i) };
+                    }
+
+                    ComboBox::from_id_salt(("controller_action", i))
+                        .selected_text(action_name(&binding.action))
+                        .show_ui(ui, |ui| {
+                            let actions = [
+                                ControlAction::CycleProfiles,
+                                ControlAction::Brightness(Brightness::Low),
+                                ControlAction::Brightness(Brightness::High),
+                                ControlAction::ToggleBrightness,
+                            ]
+                            .into_iter()
+                            .chain(profiles.iter().filter_map(|profile| profile.name.clone()).map(ControlAction::Profile));
+
+                            for action in actions {
+                                let name = action_name(&action);
+                                ui.selectable_value(&mut binding.action, action, name);
+                            }
+                        });
+
+                    if ui.button("-").clicked() {
+                        remove = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+
+            if let Some(i) = remove {
+                settings.bindings.remove(i);
+                self.learning = None;
+            }
+
+            if ui.button(t!("controllers.add")).clicked() {
+                settings.bindings.push(ControlBinding::default());
+                self.learning = Some(settings.bindings.len() - 1);
+            }
+        });
+
+        self.open = open;
+        if !self.open {
+            self.learning = None;
+            self.midi_devices = None;
+        }
+
+        changed
+    }
+
+    /// Assign `control` to the binding being learned, returns whether one was
+    pub fn learn(&mut self, control: Control, settings: &mut ControllerSettings) -> bool {
+        match self.learning.take().and_then(|i| settings.bindings.get_mut(i)) {
+            Some(binding) => {
+                binding.control = Some(control);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+fn control_name(control: Control) -> String {
+    match control {
+        Control::StreamDeck { key } => t!("controllers.stream_deck_key", key = key + 1),
+        Control::MidiNote { channel, note } => t!("controllers.midi_note", note = note, channel = channel + 1),
+        Control::MidiCc { channel, controller } => t!("controllers.midi_cc", controller = controller, channel = channel + 1),
+    }
+}
+
+fn action_name(action: &ControlAction) -> String {
+    match action {
+        ControlAction::CycleProfiles => t!("controllers.cycle_profiles"),
+        ControlAction::Profile(name) => t!("controllers.apply_profile", name = name),
+        ControlAction::Brightness(Brightness::Low) => t!("controllers.brightness_low"),
+        ControlAction::Brightness(Brightness::High) => t!("controllers.brightness_high"),
+        ControlAction::ToggleBrightness => t!("controllers.toggle_brightness"),
+    }
+}
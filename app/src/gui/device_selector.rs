@@ -0,0 +1,32 @@
+use eframe::egui::{ComboBox, Ui};
+use legion_kb_rgb_hw::KeyboardInfo;
+use rust_i18n::t;
+
+use crate::manager::registry::DeviceRegistry;
+
+/// Pick which keyboard the controls apply to, only shown when there's more than one. Returns the id of the picked one.
+pub fn show(ui: &mut Ui, registry: &DeviceRegistry) -> Option<String> {
+    let selected = registry.selected()?;
+    if registry.devices().count() < 2 {
+        return None;
+    }
+
+    let mut picked = None;
+
+    ui.horizontal(|ui| {
+        ui.label(t!("devices.label"));
+        ComboBox::from_id_salt("device-selector").selected_text(label(selected)).show_ui(ui, |ui| {
+            for device in registry.devices() {
+                if ui.selectable_label(device == selected, label(device)).clicked() && device != selected {
+                    picked = Some(device.id());
+                }
+            }
+        });
+    });
+
+    picked
+}
+
+fn label(device: &KeyboardInfo) -> String {
+    format!("{} ({})", device.model.name, device.id())
+}
@@ -0,0 +1,77 @@
+use eframe::egui::{ComboBox, DragValue, Slider, Ui};
+use legion_kb_rgb_hw::SPEED_RANGE;
+use rust_i18n::t;
+use strum::IntoEnumIterator;
+
+use crate::{
+    enums::{Brightness, Direction},
+    gui::{i18n, style::SpacingStyle},
+    manager::profile::Profile,
+};
+
+const COMBOBOX_WIDTH: f32 = 20.0;
+
+pub fn show(ui: &mut Ui, profile: &mut Profile, update_lights: &mut bool, spacing: &SpacingStyle) {
+    ui.scope(|ui| {
+        ui.style_mut().spacing.item_spacing = spacing.default;
+
+        show_brightness(ui, profile, update_lights);
+        show_direction(ui, profile, update_lights);
+        show_effect_settings(ui, profile, update_lights);
+        show_intensity(ui, profile, update_lights);
+        show_off_after(ui, profile, update_lights);
+    });
+}
+
+pub fn show_brightness(ui: &mut Ui, profile: &mut Profile, update_lights: &mut bool) {
+    ComboBox::from_label(t!("effect_options.brightness"))
+        .width(COMBOBOX_WIDTH)
+        .selected_text(i18n::name_of("brightness", profile.brightness))
+        .show_ui(ui, |ui| {
+            for val in Brightness::iter() {
+                *update_lights |= ui.selectable_value(&mut profile.brightness, val, i18n::name_of("brightness", val)).changed();
+            }
+        });
+}
+
+pub fn show_direction(ui: &mut Ui, profile: &mut Profile, update_lights: &mut bool) {
+    ui.add_enabled_ui(profile.effect.takes_direction(), |ui| {
+        ComboBox::from_label(t!("effect_options.direction"))
+            .width(COMBOBOX_WIDTH)
+            .selected_text(i18n::name_of("direction", profile.direction))
+            .show_ui(ui, |ui| {
+                for val in Direction::iter() {
+                    *update_lights |= ui.selectable_value(&mut profile.direction, val, i18n::name_of("direction", val)).changed();
+                }
+            });
+    });
+}
+
+pub fn show_effect_settings(ui: &mut Ui, profile: &mut Profile, update_lights: &mut bool) {
+    let range = if profile.effect.is_built_in() { SPEED_RANGE } else { 1..=10 };
+
+    ui.horizontal(|ui| {
+        *update_lights |= ui.add_enabled(profile.effect.takes_speed(), Slider::new(&mut profile.speed, range)).changed();
+        ui.label(t!("effect_options.speed"));
+    });
+}
+
+pub fn show_intensity(ui: &mut Ui, profile: &mut Profile, update_lights: &mut bool) {
+    ui.horizontal(|ui| {
+        *update_lights |= ui.add(Slider::new(&mut profile.intensity, 0..=100)).changed();
+        ui.label(t!("effect_options.intensity"));
+    });
+}
+
+pub fn show_off_after(ui: &mut Ui, profile: &mut Profile, update_lights: &mut bool) {
+    ui.horizontal(|ui| {
+        let mut enabled = profile.off_after.is_some();
+        if ui.checkbox(&mut enabled, t!("effect_options.off_when_idle")).on_hover_text(t!("effect_options.off_when_idle_hint")).changed() {
+            profile.off_after = enabled.then_some(5);
+            *update_lights = true;
+        }
+        if let Some(minutes) = &mut profile.off_after {
+            *update_lights |= ui.add(DragValue::new(minutes).range(1..=240).suffix(t!("effect_options.minutes_suffix"))).changed();
+        }
+    });
+}
@@ -0,0 +1,180 @@
+use default_ui::{show_brightness, show_direction, show_intensity, show_off_after};
+use eframe::egui::{self, Slider};
+use rust_i18n::t;
+
+use crate::{
+    enums::{Effects, DEFAULT_AUTOMATON_RULE, DEFAULT_FIRE_HUE, DEFAULT_FIRE_INTENSITY, DEFAULT_MAX_MBPS, DEFAULT_MAX_WPM, DEFAULT_MIN_WPM},
+    gui::style::Theme,
+    manager::profile::Profile,
+};
+
+mod default_ui;
+pub mod modulation_ui;
+pub mod overlay_ui;
+
+impl Effects {
+    pub fn show_ui(&mut self, ui: &mut egui::Ui, profile: &mut Profile, update_lights: &mut bool, theme: &Theme) {
+        match self {
+            Effects::AmbientLight { fps, saturation_boost } => {
+                ui.scope(|ui| {
+                    ui.style_mut().spacing.item_spacing = theme.spacing.default;
+
+                    show_brightness(ui, profile, update_lights);
+                    show_direction(ui, profile, update_lights);
+
+                    ui.horizontal(|ui| {
+                        *update_lights |= ui.add(Slider::new(fps, 1..=60)).changed();
+                        ui.label(t!("effect_options.fps"));
+                    });
+                    ui.horizontal(|ui| {
+                        *update_lights |= ui.add(Slider::new(saturation_boost, 0.0..=1.0)).changed();
+                        ui.label(t!("effect_options.saturation_boost"));
+                    });
+                    show_intensity(ui, profile, update_lights);
+                    show_off_after(ui, profile, update_lights);
+                });
+            }
+            Effects::Fire { intensity, hue } => {
+                ui.scope(|ui| {
+                    ui.style_mut().spacing.item_spacing = theme.spacing.default;
+
+                    default_ui::show(ui, profile, update_lights, &theme.spacing);
+
+                    if *intensity == 0.0 {
+                        (*intensity, *hue) = (DEFAULT_FIRE_INTENSITY, DEFAULT_FIRE_HUE);
+                    }
+                    ui.horizontal(|ui| {
+                        *update_lights |= ui.add(Slider::new(intensity, 0.05..=1.0)).changed();
+                        ui.label(t!("effect_options.fire_intensity"));
+                    });
+                    ui.horizontal(|ui| {
+                        *update_lights |= ui.add(Slider::new(hue, 0.0..=60.0)).changed();
+                        ui.label(t!("effect_options.hue"));
+                    });
+                });
+            }
+            Effects::Daylight { latitude, longitude } => {
+                ui.scope(|ui| {
+                    ui.style_mut().spacing.item_spacing = theme.spacing.default;
+
+                    default_ui::show(ui, profile, update_lights, &theme.spacing);
+
+                    ui.horizontal(|ui| {
+                        *update_lights |= ui.add(Slider::new(latitude, -90.0..=90.0)).changed();
+                        ui.label(t!("effect_options.latitude"));
+                    });
+                    ui.horizontal(|ui| {
+                        *update_lights |= ui.add(Slider::new(longitude, -180.0..=180.0)).changed();
+                        ui.label(t!("effect_options.longitude"));
+                    });
+                    ui.label(t!("effect_options.location_hint"));
+                });
+            }
+            Effects::Weather {
+                latitude,
+                longitude,
+                refresh_minutes,
+            } => {
+                ui.scope(|ui| {
+                    ui.style_mut().spacing.item_spacing = theme.spacing.default;
+
+                    default_ui::show(ui, profile, update_lights, &theme.spacing);
+
+                    ui.horizontal(|ui| {
+                        *update_lights |= ui.add(Slider::new(latitude, -90.0..=90.0)).changed();
+                        ui.label(t!("effect_options.latitude"));
+                    });
+                    ui.horizontal(|ui| {
+                        *update_lights |= ui.add(Slider::new(longitude, -180.0..=180.0)).changed();
+                        ui.label(t!("effect_options.longitude"));
+                    });
+                    ui.horizontal(|ui| {
+                        *update_lights |= ui.add(Slider::new(refresh_minutes, 5..=120)).changed();
+                        ui.label(t!("effect_options.refresh_minutes"));
+                    });
+                    ui.label(t!("effect_options.weather_hint"));
+                });
+            }
+            Effects::Clock { countdown_minutes } => {
+                ui.scope(|ui| {
+                    ui.style_mut().spacing.item_spacing = theme.spacing.default;
+
+                    default_ui::show(ui, profile, update_lights, &theme.spacing);
+
+                    ui.horizontal(|ui| {
+                        *update_lights |= ui.add(Slider::new(countdown_minutes, 0..=240)).changed();
+                        ui.label(t!("effect_options.countdown_minutes"));
+                    });
+                    ui.label(t!("effect_options.clock_hint"));
+                });
+            }
+            Effects::Network { max_mbps, chase } => {
+                ui.scope(|ui| {
+                    ui.style_mut().spacing.item_spacing = theme.spacing.default;
+
+                    default_ui::show(ui, profile, update_lights, &theme.spacing);
+
+                    if *max_mbps == 0 {
+                        *max_mbps = DEFAULT_MAX_MBPS;
+                    }
+                    ui.horizontal(|ui| {
+                        *update_lights |= ui.add(Slider::new(max_mbps, 1..=1000).logarithmic(true)).changed();
+                        ui.label(t!("effect_options.max_mbps"));
+                    });
+                    *update_lights |= ui.checkbox(chase, t!("effect_options.network_chase")).changed();
+                    ui.label(t!("effect_options.network_hint"));
+                });
+            }
+            Effects::Heatmap => {
+                ui.scope(|ui| {
+                    ui.style_mut().spacing.item_spacing = theme.spacing.default;
+
+                    default_ui::show(ui, profile, update_lights, &theme.spacing);
+
+                    ui.label(t!("effect_options.heatmap_hint"));
+                });
+            }
+            Effects::TypingSpeed { min_wpm, max_wpm, gauge } => {
+                ui.scope(|ui| {
+                    ui.style_mut().spacing.item_spacing = theme.spacing.default;
+
+                    default_ui::show(ui, profile, update_lights, &theme.spacing);
+
+                    if *max_wpm == 0 {
+                        (*min_wpm, *max_wpm) = (DEFAULT_MIN_WPM, DEFAULT_MAX_WPM);
+                    }
+                    ui.horizontal(|ui| {
+                        *update_lights |= ui.add(Slider::new(min_wpm, 0..=200)).changed();
+                        ui.label(t!("effect_options.min_wpm"));
+                    });
+                    ui.horizontal(|ui| {
+                        *update_lights |= ui.add(Slider::new(max_wpm, 10..=250)).changed();
+                        ui.label(t!("effect_options.max_wpm"));
+                    });
+                    *max_wpm = (*max_wpm).max(*min_wpm + 10);
+                    *update_lights |= ui.checkbox(gauge, t!("effect_options.typing_gauge")).changed();
+                    ui.label(t!("effect_options.typing_speed_hint"));
+                });
+            }
+            Effects::Automaton { rule } => {
+                ui.scope(|ui| {
+                    ui.style_mut().spacing.item_spacing = theme.spacing.default;
+
+                    default_ui::show(ui, profile, update_lights, &theme.spacing);
+
+                    if *rule == 0 {
+                        *rule = DEFAULT_AUTOMATON_RULE;
+                    }
+                    ui.horizontal(|ui| {
+                        *update_lights |= ui.add(Slider::new(rule, 1..=255)).changed();
+                        ui.label(t!("effect_options.automaton_rule"));
+                    });
+                    ui.label(t!("effect_options.automaton_hint"));
+                });
+            }
+            _ => {
+                default_ui::show(ui, profile, update_lights, &theme.spacing);
+            }
+        }
+    }
+}
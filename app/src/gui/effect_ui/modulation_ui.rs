@@ -0,0 +1,60 @@
+use eframe::egui::{CollapsingHeader, ComboBox, DragValue, Grid, Slider, Ui};
+use rust_i18n::t;
+use strum::IntoEnumIterator;
+
+use crate::{
+    gui::{i18n, style::SpacingStyle},
+    manager::{
+        modulation::{Modulation, ModulationTarget, Waveform},
+        profile::Profile,
+    },
+};
+
+/// Edit the oscillators driving the parameters of the profile
+pub fn show(ui: &mut Ui, profile: &mut Profile, update_lights: &mut bool, spacing: &SpacingStyle) {
+    CollapsingHeader::new(t!("modulation.title")).id_salt("modulation").show(ui, |ui| {
+        ui.style_mut().spacing.item_spacing = spacing.default;
+        let mut remove = None;
+
+        Grid::new("modulation_grid").num_columns(5).show(ui, |ui| {
+            for (i, modulation) in profile.modulations.iter_mut().enumerate() {
+                ComboBox::from_id_salt(("modulation_target", i))
+                    .selected_text(i18n::name_of("modulation_target", modulation.target))
+                    .show_ui(ui, |ui| {
+                        for target in ModulationTarget::iter() {
+                            *update_lights |= ui.selectable_value(&mut modulation.target, target, i18n::name_of("modulation_target", target)).changed();
+                        }
+                    });
+
+                ComboBox::from_id_salt(("modulation_waveform", i))
+                    .selected_text(i18n::name_of("waveform", modulation.waveform))
+                    .show_ui(ui, |ui| {
+                        for waveform in Waveform::iter() {
+                            *update_lights |= ui.selectable_value(&mut modulation.waveform, waveform, i18n::name_of("waveform", waveform)).changed();
+                        }
+                    });
+
+                *update_lights |= ui
+                    .add(DragValue::new(&mut modulation.period).range(0.1..=600.0).speed(0.1).suffix(" s"))
+                    .on_hover_text(t!("modulation.period"))
+                    .changed();
+                *update_lights |= ui.add(Slider::new(&mut modulation.depth, 0.0..=1.0)).on_hover_text(t!("modulation.depth")).changed();
+
+                if ui.button("-").clicked() {
+                    remove = Some(i);
+                }
+                ui.end_row();
+            }
+        });
+
+        if let Some(i) = remove {
+            profile.modulations.remove(i);
+            *update_lights = true;
+        }
+
+        if ui.button(t!("modulation.add")).clicked() {
+            profile.modulations.push(Modulation::default());
+            *update_lights = true;
+        }
+    });
+}
@@ -0,0 +1,68 @@
+use eframe::egui::{CollapsingHeader, ComboBox, Slider, Ui};
+use legion_kb_rgb_hw::SPEED_RANGE;
+use rust_i18n::t;
+use strum::IntoEnumIterator;
+
+use crate::{
+    enums::Effects,
+    gui::{i18n, style::SpacingStyle},
+    manager::{
+        layers::{BlendMode, Overlay},
+        profile::Profile,
+    },
+};
+
+/// Pick an effect to play on top of the one of the profile
+pub fn show(ui: &mut Ui, profile: &mut Profile, update_lights: &mut bool, spacing: &SpacingStyle) {
+    CollapsingHeader::new(t!("overlay.title")).id_salt("overlay").show(ui, |ui| {
+        ui.style_mut().spacing.item_spacing = spacing.default;
+
+        let mut battery = !profile.hide_battery_overlay;
+        if ui.checkbox(&mut battery, t!("overlay.battery")).on_hover_text(t!("overlay.battery_hint")).changed() {
+            profile.hide_battery_overlay = !battery;
+            *update_lights = true;
+        }
+
+        let mut enabled = profile.overlay.is_some();
+        if ui.checkbox(&mut enabled, t!("overlay.enabled")).changed() {
+            profile.overlay = enabled.then(Overlay::default);
+            *update_lights = true;
+        }
+
+        let Some(overlay) = &mut profile.overlay else {
+            return;
+        };
+
+        ComboBox::from_label(t!("overlay.effect"))
+            .selected_text(i18n::name_of("effects", overlay.effect))
+            .show_ui(ui, |ui| {
+                for effect in Effects::iter() {
+                    *update_lights |= ui.selectable_value(&mut overlay.effect, effect, i18n::name_of("effects", effect)).changed();
+                }
+            });
+
+        ComboBox::from_label(t!("overlay.blend"))
+            .selected_text(i18n::name_of("blend_mode", overlay.blend))
+            .show_ui(ui, |ui| {
+                for blend in BlendMode::iter() {
+                    *update_lights |= ui.selectable_value(&mut overlay.blend, blend, i18n::name_of("blend_mode", blend)).changed();
+                }
+            })
+            .response
+            .on_hover_text(t!("overlay.blend_hint"));
+
+        let range = if overlay.effect.is_built_in() { SPEED_RANGE } else { 1..=10 };
+        ui.horizontal(|ui| {
+            *update_lights |= ui.add_enabled(overlay.effect.takes_speed(), Slider::new(&mut overlay.speed, range)).changed();
+            ui.label(t!("effect_options.speed"));
+        });
+
+        ui.add_enabled_ui(overlay.effect.takes_color_array(), |ui| {
+            ui.horizontal(|ui| {
+                for zone in &mut overlay.rgb_zones {
+                    *update_lights |= ui.color_edit_button_srgb(&mut zone.rgb).changed();
+                }
+            });
+        });
+    });
+}
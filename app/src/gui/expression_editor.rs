@@ -0,0 +1,130 @@
+//! Window for writing an effect in the expression language, parsing it on every keystroke to play it on a simulated
+//! keyboard right away or point at what's wrong.
+
+use std::time::Duration;
+
+use eframe::egui::{Button, CollapsingHeader, Context, RichText, TextEdit, Window};
+use rust_i18n::t;
+
+use crate::{
+    manager::{
+        custom_effect::CustomEffect,
+        expression::{ParseError, Program},
+    },
+    ticks,
+};
+
+use super::keyboard_preview::KeyboardLayout;
+
+/// How often the preview is redrawn while the window is open
+const REPAINT_INTERVAL: Duration = Duration::from_millis(33);
+const STARTER: &str = "speed = 2\nzone.r = sin(t * speed + i) * 127 + 128\nzone.b = x * 255\n";
+
+/// Something the editor needs the rest of the app to do
+pub enum ExpressionAction {
+    /// Play the effect on the keyboard
+    Play(CustomEffect),
+    /// Add the effect to the effect library
+    Save(CustomEffect),
+}
+
+pub struct ExpressionEditor {
+    pub open: bool,
+    source: String,
+    /// The last program that parsed, kept playing in the preview while the source has errors
+    program: Option<Program>,
+    error: Option<ParseError>,
+    name: String,
+    high_brightness: bool,
+}
+
+impl Default for ExpressionEditor {
+    fn default() -> Self {
+        Self {
+            open: false,
+            source: STARTER.to_string(),
+            program: Program::parse(STARTER).ok(),
+            error: None,
+            name: String::new(),
+            high_brightness: false,
+        }
+    }
+}
+
+impl ExpressionEditor {
+    pub fn show(&mut self, ctx: &Context, layout: &KeyboardLayout) -> Option<ExpressionAction> {
+        let mut action = None;
+        let mut open = self.open;
+        let time = ctx.input(|input| input.time);
+
+        Window::new(t!("expression.title")).open(&mut open).collapsible(false).default_width(420.0).show(ctx, |ui| {
+            ui.label(t!("expression.hint"));
+
+            let rgb = self.program.as_ref().map_or([0; 12], |program| program.colors_at(time as f32));
+            layout.show(ui, [0, 1, 2, 3].map(|zone| [rgb[zone * 3], rgb[zone * 3 + 1], rgb[zone * 3 + 2]]));
+            ui.separator();
+
+            let response = ui.add(TextEdit::multiline(&mut self.source).code_editor().desired_rows(8).desired_width(f32::INFINITY));
+            if response.changed() {
+                self.parse();
+            }
+            if let Some(error) = &self.error {
+                let message = t!("expression.error", line = error.line, column = error.column, message = error.kind);
+                ui.label(RichText::new(message).color(ui.visuals().error_fg_color));
+            }
+
+            CollapsingHeader::new(t!("expression.reference")).show(ui, |ui| {
+                ui.label(t!("expression.reference_inputs"));
+                ui.label(t!("expression.reference_outputs"));
+                ui.label(t!("expression.reference_functions"));
+                ui.label(t!("expression.reference_operators"));
+            });
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.label(t!("expression.name"));
+                ui.add(TextEdit::singleline(&mut self.name).desired_width(160.0));
+                ui.checkbox(&mut self.high_brightness, t!("expression.high_brightness"));
+            });
+
+            ui.horizontal(|ui| {
+                let valid = self.error.is_none();
+                if ui.add_enabled(valid, Button::new(t!("expression.play"))).clicked() {
+                    action = Some(ExpressionAction::Play(self.effect()));
+                }
+                if ui.add_enabled(valid && !self.name.trim().is_empty(), Button::new(t!("expression.save"))).clicked() {
+                    action = Some(ExpressionAction::Save(self.effect()));
+                }
+            });
+        });
+
+        if open {
+            ctx.request_repaint_after(ticks::until_next(REPAINT_INTERVAL));
+        }
+        self.open = open;
+
+        action
+    }
+
+    fn parse(&mut self) {
+        match Program::parse(&self.source) {
+            Ok(program) => {
+                self.program = Some(program);
+                self.error = None;
+            }
+            Err(error) => self.error = Some(error),
+        }
+    }
+
+    fn effect(&self) -> CustomEffect {
+        let name = self.name.trim();
+        let mut effect = CustomEffect {
+            name: (!name.is_empty()).then(|| name.to_string()),
+            brightness: if self.high_brightness { 2 } else { 1 },
+            expression: Some(self.source.clone()),
+            ..CustomEffect::default()
+        };
+        effect.thumbnail = effect.render_thumbnail();
+        effect
+    }
+}
@@ -0,0 +1,47 @@
+//! Bar of the pinned profiles, shown above everything else so they can be switched to in one click, or with Ctrl+1
+//! to Ctrl+9.
+
+use eframe::egui::{Context, Key, KeyboardShortcut, Modifiers, Ui};
+use rust_i18n::t;
+
+use crate::manager::profile::Profile;
+
+pub const MAX_FAVORITES: usize = 9;
+
+const SHORTCUT_KEYS: [Key; MAX_FAVORITES] = [Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5, Key::Num6, Key::Num7, Key::Num8, Key::Num9];
+
+/// The pinned profiles in the order of the profile list, past the ones that get a shortcut left out
+pub fn favorites(profiles: &[Profile]) -> impl Iterator<Item = &Profile> {
+    profiles.iter().filter(|profile| profile.pinned).take(MAX_FAVORITES)
+}
+
+fn shortcut(position: usize) -> KeyboardShortcut {
+    KeyboardShortcut::new(Modifiers::COMMAND, SHORTCUT_KEYS[position])
+}
+
+/// Show a button for each favorite, returning the name of the one picked
+pub fn show(ctx: &Context, ui: &mut Ui, profiles: &[Profile], current_profile: &Profile) -> Option<String> {
+    let mut picked = None;
+
+    ui.horizontal_wrapped(|ui| {
+        ui.label(t!("favorites.title"));
+
+        for (position, profile) in favorites(profiles).enumerate() {
+            let name = profile.name.clone().unwrap_or_else(|| t!("saved_items.unnamed"));
+            let response = ui.selectable_label(current_profile == profile, name).on_hover_text(ctx.format_shortcut(&shortcut(position)));
+            if response.clicked() {
+                picked.clone_from(&profile.name);
+            }
+        }
+    });
+
+    picked
+}
+
+/// The name of the favorite whose shortcut was pressed this frame
+pub fn shortcut_pressed(ctx: &Context, profiles: &[Profile]) -> Option<String> {
+    favorites(profiles)
+        .enumerate()
+        .find(|(position, _)| ctx.input_mut(|input| input.consume_shortcut(&shortcut(*position))))
+        .and_then(|(_, profile)| profile.name.clone())
+}
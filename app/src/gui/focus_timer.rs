@@ -0,0 +1,55 @@
+use eframe::egui::{Button, Context, DragValue, Grid, RichText, Window};
+use rust_i18n::t;
+
+use crate::pomodoro::{Phase, Pomodoro, PomodoroCommand, PomodoroSettings};
+
+/// The controls and durations of the focus timer, returns what was asked for
+pub fn show(ctx: &Context, open: &mut bool, pomodoro: &Pomodoro, settings: &mut PomodoroSettings) -> Option<PomodoroCommand> {
+    let mut command = None;
+
+    Window::new(t!("focus_timer.title")).open(open).resizable(false).collapsible(false).show(ctx, |ui| {
+        let status = match pomodoro.phase() {
+            Some(Phase::Work) => t!("focus_timer.work"),
+            Some(Phase::Break) => t!("focus_timer.break"),
+            None => t!("focus_timer.stopped"),
+        };
+        let remaining = pomodoro.remaining().unwrap_or_default().as_secs();
+        ui.label(RichText::new(format!("{status}  {:02}:{:02}", remaining / 60, remaining % 60)).heading());
+
+        ui.horizontal(|ui| {
+            let label = if !pomodoro.is_active() {
+                t!("focus_timer.start")
+            } else if pomodoro.is_paused() {
+                t!("focus_timer.resume")
+            } else {
+                t!("focus_timer.pause")
+            };
+            if ui.button(label).clicked() {
+                command = Some(PomodoroCommand::StartPause);
+            }
+            if ui.add_enabled(pomodoro.is_active(), Button::new(t!("focus_timer.skip"))).clicked() {
+                command = Some(PomodoroCommand::Skip);
+            }
+            if ui.add_enabled(pomodoro.is_active(), Button::new(t!("focus_timer.stop"))).clicked() {
+                command = Some(PomodoroCommand::Stop);
+            }
+        });
+
+        ui.separator();
+
+        Grid::new("focus_timer_grid").num_columns(3).show(ui, |ui| {
+            ui.label(t!("focus_timer.work"));
+            ui.add(DragValue::new(&mut settings.work_minutes).range(1..=180).suffix(" min"));
+            ui.color_edit_button_srgb(&mut settings.work_color);
+            ui.end_row();
+
+            ui.label(t!("focus_timer.break"));
+            ui.add(DragValue::new(&mut settings.break_minutes).range(1..=60).suffix(" min"));
+            ui.color_edit_button_srgb(&mut settings.break_color);
+            ui.end_row();
+        });
+        ui.label(t!("focus_timer.hint"));
+    });
+
+    command
+}
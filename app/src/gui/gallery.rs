@@ -0,0 +1,173 @@
+//! Every effect side by side, each looping a short recording made on a virtual keyboard, so they can be told apart before
+//! picking one.
+//!
+//! The recordings are made in the background when the window opens, with the colors and speed of the current profile.
+//! The animations the keyboard plays by itself only get recorded as a mode and a speed, so they're approximated here.
+
+use std::{
+    collections::BTreeMap,
+    f32::consts::TAU,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use eframe::egui::{Context, Grid, ScrollArea, Window};
+use rust_i18n::t;
+use strum::IntoEnumIterator;
+
+use crate::{
+    enums::Effects,
+    manager::{
+        profile::Profile,
+        recorder::{self, Frame},
+    },
+    ticks,
+    util::hsv_to_rgb,
+};
+
+use super::{i18n, keyboard_preview::KeyboardLayout};
+
+const SEED: u64 = 0;
+/// Frames recorded of each effect, played in a loop
+const FRAMES: usize = 200;
+const COLUMNS: usize = 3;
+const TILE_WIDTH: f32 = 150.0;
+/// How often the previews are redrawn while the window is open
+const REPAINT_INTERVAL: Duration = Duration::from_millis(33);
+
+#[derive(Default)]
+pub struct Gallery {
+    /// The frames of each effect, by name, filled in as they get recorded
+    recordings: Arc<Mutex<BTreeMap<&'static str, Vec<Frame>>>>,
+    /// Bumped whenever the recordings start over, so the ones still going for an older profile are dropped
+    generation: Arc<AtomicU64>,
+    /// The colors of the profile the recordings were made with, shown still for the effects that can't be recorded
+    colors: [u8; 12],
+}
+
+impl Gallery {
+    /// Record every effect again, with the colors, speed and direction of `current`
+    pub fn record(&mut self, current: &Profile) {
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        self.recordings.lock().unwrap().clear();
+        self.colors = current.rgb_array();
+
+        let base = Profile {
+            rgb_zones: current.rgb_zones.clone(),
+            direction: current.direction,
+            speed: current.speed,
+            ..Profile::default()
+        };
+        let recordings = self.recordings.clone();
+        let latest_generation = self.generation.clone();
+
+        thread::spawn(move || {
+            for effect in Effects::iter().filter(|effect| !effect.reads_environment()) {
+                if latest_generation.load(Ordering::SeqCst) != generation {
+                    return;
+                }
+
+                let frames = recorder::record(Profile { effect, ..base.clone() }, SEED, FRAMES);
+                if latest_generation.load(Ordering::SeqCst) == generation {
+                    recordings.lock().unwrap().insert(effect.into(), frames);
+                }
+            }
+        });
+    }
+
+    /// Show the previews, returning the effect picked to play
+    pub fn show(&self, ctx: &Context, open: &mut bool, layout: &KeyboardLayout) -> Option<Effects> {
+        let mut picked = None;
+        let time = ctx.input(|input| input.time);
+
+        Window::new(t!("gallery.title")).open(open).collapsible(false).default_width(500.0).show(ctx, |ui| {
+            ui.label(t!("gallery.hint"));
+            ui.separator();
+
+            ScrollArea::vertical().max_height(480.0).show(ui, |ui| {
+                let recordings = self.recordings.lock().unwrap();
+
+                Grid::new("effect_gallery").num_columns(COLUMNS).spacing([12.0, 12.0]).show(ui, |ui| {
+                    for (i, effect) in Effects::iter().enumerate() {
+                        ui.vertical(|ui| {
+                            ui.set_width(TILE_WIDTH);
+
+                            let name: &'static str = effect.into();
+                            match recordings.get(name) {
+                                Some(frames) => layout.show(ui, zone_colors_at(frames, time)),
+                                None if effect.reads_environment() => layout.show(ui, split_zones(self.colors)),
+                                None => {
+                                    ui.spinner();
+                                }
+                            }
+
+                            ui.horizontal(|ui| {
+                                ui.label(i18n::name_of("effects", effect));
+                                if ui.small_button(t!("gallery.apply")).clicked() {
+                                    picked = Some(effect);
+                                }
+                            });
+                            if effect.reads_environment() {
+                                ui.small(t!("gallery.live"));
+                            }
+                        });
+
+                        if (i + 1) % COLUMNS == 0 {
+                            ui.end_row();
+                        }
+                    }
+                });
+            });
+        });
+
+        if *open {
+            ctx.request_repaint_after(ticks::until_next(REPAINT_INTERVAL));
+        }
+
+        picked
+    }
+}
+
+/// The zone colors of a recording `time` seconds into playing it in a loop
+fn zone_colors_at(frames: &[Frame], time: f64) -> [[u8; 3]; 4] {
+    let Some(last) = frames.last() else {
+        return [[0; 3]; 4];
+    };
+
+    // The last frame lasts as long as the one before it did
+    let hold = frames.iter().rev().nth(1).map_or(1, |previous| (last.time - previous.time).max(1));
+    let at = (time * 1000.0) as u64 % (last.time + hold);
+    let frame = frames.iter().rev().find(|frame| frame.time <= at).unwrap_or(&frames[0]);
+
+    match frame.mode.as_str() {
+        "Static" => split_zones(frame.rgb_array),
+        _ => hardware_zone_colors(frame, time),
+    }
+}
+
+/// Roughly what the keyboard shows while animating by itself, going by its speed from 1 (slowest) to 4
+fn hardware_zone_colors(frame: &Frame, time: f64) -> [[u8; 3]; 4] {
+    let period = f64::from(5 - frame.speed.clamp(1, 4)) * 2.0;
+    let phase = (time / period).fract() as f32;
+    let hue = |offset: f32| hsv_to_rgb(offset.mul_add(90.0, phase * 360.0).rem_euclid(360.0), 1.0, 1.0);
+
+    match frame.mode.as_str() {
+        "Breath" => {
+            let level = 0.5 - 0.5 * (phase * TAU).cos();
+            split_zones(frame.rgb_array).map(|rgb| rgb.map(|channel| (f32::from(channel) * level) as u8))
+        }
+        "Smooth" => [hue(0.0); 4],
+        // The hues move towards the first zone
+        "LeftWave" => [0.0, 1.0, 2.0, 3.0].map(hue),
+        "RightWave" => [0.0, -1.0, -2.0, -3.0].map(hue),
+        _ => split_zones(frame.rgb_array),
+    }
+}
+
+fn split_zones(rgb: [u8; 12]) -> [[u8; 3]; 4] {
+    [0, 1, 2, 3].map(|zone| [rgb[zone * 3], rgb[zone * 3 + 1], rgb[zone * 3 + 2]])
+}
@@ -0,0 +1,97 @@
+//! Lays a gradient of two or more colors over the keyboard, at an angle, and samples it into the zone colors.
+
+use eframe::{
+    egui::{Button, CollapsingHeader, Pos2, Rect, Sense, Slider, Ui},
+    epaint::{Color32, Rounding, Stroke, Vec2},
+};
+use rust_i18n::t;
+
+use crate::manager::{
+    gradient::{Gradient, MIN_STOPS},
+    profile::Profile,
+};
+
+const PREVIEW_HEIGHT: f32 = 24.0;
+/// Bands the preview is drawn with, enough for the steps not to show
+const PREVIEW_STEPS: usize = 64;
+
+pub fn show(ui: &mut Ui, current_profile: &mut Profile, changed: &mut bool) {
+    CollapsingHeader::new(t!("gradient.title")).id_salt("gradient_editor").show(ui, |ui| {
+        let Some(gradient) = &mut current_profile.gradient else {
+            ui.label(t!("gradient.hint"));
+            if ui.button(t!("gradient.create")).clicked() {
+                let zones = &current_profile.rgb_zones;
+                current_profile.gradient = Some(Gradient::new(zones[0].rgb, zones[3].rgb));
+                apply(current_profile);
+                *changed = true;
+            }
+            return;
+        };
+
+        show_preview(ui, gradient);
+
+        let mut edited = ui.add(Slider::new(&mut gradient.angle, -180.0..=180.0).suffix("°").text(t!("gradient.angle"))).changed();
+
+        let can_remove = gradient.stops.len() > MIN_STOPS;
+        let mut remove = None;
+        for (i, stop) in gradient.stops.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                edited |= ui.color_edit_button_srgb(&mut stop.color).changed();
+                edited |= ui.add(Slider::new(&mut stop.position, 0.0..=1.0).text(t!("gradient.position")).show_value(false)).changed();
+                if ui.add_enabled(can_remove, Button::new("-")).on_hover_text(t!("gradient.remove_stop")).clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+        if let Some(i) = remove {
+            gradient.stops.remove(i);
+            edited = true;
+        }
+
+        let mut stop_using = false;
+        ui.horizontal(|ui| {
+            if ui.button(t!("gradient.add_stop")).clicked() {
+                gradient.split_widest_gap();
+                edited = true;
+            }
+            stop_using = ui.button(t!("gradient.stop_using")).on_hover_text(t!("gradient.stop_using_hint")).clicked();
+        });
+
+        if stop_using {
+            current_profile.gradient = None;
+            *changed = true;
+        } else if edited {
+            apply(current_profile);
+            *changed = true;
+        }
+    });
+}
+
+/// Color the zones from the gradient of the profile
+fn apply(current_profile: &mut Profile) {
+    if let Some(gradient) = &current_profile.gradient {
+        for (zone, rgb) in current_profile.rgb_zones.iter_mut().zip(gradient.to_zone_colors()) {
+            zone.rgb = rgb;
+        }
+    }
+}
+
+/// The gradient from start to end, with a dot where each zone picks its color
+fn show_preview(ui: &mut Ui, gradient: &Gradient) {
+    let (rect, _) = ui.allocate_exact_size(Vec2::new(ui.available_width(), PREVIEW_HEIGHT), Sense::hover());
+    let painter = ui.painter();
+    let step_width = rect.width() / PREVIEW_STEPS as f32;
+
+    for step in 0..PREVIEW_STEPS {
+        let [r, g, b] = gradient.sample(step as f32 / (PREVIEW_STEPS - 1) as f32);
+        let left = (step as f32).mul_add(step_width, rect.left());
+        // Overlap the bands a little so no gap shows between them
+        let band = Rect::from_min_max(Pos2::new(left, rect.top()), Pos2::new(left + step_width + 1.0, rect.bottom()));
+        painter.rect_filled(band, Rounding::ZERO, Color32::from_rgb(r, g, b));
+    }
+
+    for (position, [r, g, b]) in gradient.zone_positions().into_iter().zip(gradient.to_zone_colors()) {
+        let center = Pos2::new(position.mul_add(rect.width(), rect.left()), rect.center().y);
+        painter.circle(center, 5.0, Color32::from_rgb(r, g, b), Stroke::new(1.5, Color32::WHITE));
+    }
+}
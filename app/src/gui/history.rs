@@ -0,0 +1,77 @@
+use std::{
+    mem,
+    time::{Duration, Instant},
+};
+
+use crate::manager::profile::Profile;
+
+/// Maximum amount of edits that can be undone
+const MAX_ENTRIES: usize = 100;
+/// Changes closer together than this are undone at once, so dragging a slider doesn't take an undo per frame
+const COALESCE_WINDOW: Duration = Duration::from_millis(500);
+
+/// Undo and redo stacks for the edits made to the current profile
+pub struct ProfileHistory {
+    undo: Vec<Profile>,
+    redo: Vec<Profile>,
+    last_seen: Profile,
+    last_change: Option<Instant>,
+}
+
+impl ProfileHistory {
+    pub fn new(profile: &Profile) -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            last_seen: profile.clone(),
+            last_change: None,
+        }
+    }
+
+    /// Record the profile if it changed since the last time it was seen, meant to be called every frame
+    pub fn track(&mut self, profile: &Profile) {
+        if *profile == self.last_seen {
+            return;
+        }
+
+        let coalesce = self.last_change.is_some_and(|last_change| last_change.elapsed() < COALESCE_WINDOW);
+        if !coalesce {
+            self.undo.push(self.last_seen.clone());
+            if self.undo.len() > MAX_ENTRIES {
+                self.undo.remove(0);
+            }
+        }
+
+        self.redo.clear();
+        self.last_seen = profile.clone();
+        self.last_change = Some(Instant::now());
+    }
+
+    /// Go back to the state before the last edit, returns whether there was anything to undo
+    pub fn undo(&mut self, profile: &mut Profile) -> bool {
+        let Some(previous) = self.undo.pop() else {
+            return false;
+        };
+
+        self.redo.push(mem::replace(profile, previous));
+        self.settle(profile);
+        true
+    }
+
+    /// Reapply the last undone edit, returns whether there was anything to redo
+    pub fn redo(&mut self, profile: &mut Profile) -> bool {
+        let Some(next) = self.redo.pop() else {
+            return false;
+        };
+
+        self.undo.push(mem::replace(profile, next));
+        self.settle(profile);
+        true
+    }
+
+    /// Take the profile as it is without it counting as an edit, for switches made by rules, the tray, hotkeys and such
+    pub fn settle(&mut self, profile: &Profile) {
+        self.last_seen = profile.clone();
+        self.last_change = None;
+    }
+}
@@ -0,0 +1,45 @@
+//! Translations of the GUI, kept in `res/locales` with one file per language.
+
+use rust_i18n::t;
+
+use crate::power::PowerEstimate;
+
+/// Used whenever a string is missing from the chosen language
+const FALLBACK: &str = "en";
+
+/// The languages the GUI can be shown in
+pub fn languages() -> Vec<&'static str> {
+    let mut languages = rust_i18n::available_locales!();
+    languages.sort_unstable();
+    languages
+}
+
+/// Switch the GUI to the given language, following the system one if there's none
+pub fn apply(language: Option<&str>) {
+    let requested = language.map(ToOwned::to_owned).or_else(sys_locale::get_locale).unwrap_or_default();
+
+    // Only the language part of tags like "es-ES" matters
+    let requested = requested.split(['-', '_']).next().unwrap_or_default().to_lowercase();
+    let language = languages().into_iter().find(|language| *language == requested).unwrap_or(FALLBACK);
+
+    rust_i18n::set_locale(language);
+}
+
+/// The name of a language, written in that language
+pub fn native_name(language: &str) -> String {
+    t!("language.name", locale = language)
+}
+
+/// Something like "~0.8 W (Medium impact)"
+pub fn power_estimate(estimate: &PowerEstimate) -> String {
+    t!(
+        "power.estimate",
+        watts = format!("{:.1}", estimate.total()),
+        level = name_of("power.level", estimate.level())
+    )
+}
+
+/// Translate the name of an enum variant, found under `<section>.<variant name>`
+pub fn name_of(section: &str, variant: impl Into<&'static str>) -> String {
+    t!(&format!("{section}.{}", variant.into()))
+}
@@ -0,0 +1,127 @@
+use eframe::{
+    egui::{Sense, Ui, WidgetType},
+    epaint::{Color32, Rect, Rounding, Vec2},
+};
+use rust_i18n::t;
+use serde::Deserialize;
+
+use super::accessibility;
+
+// Add new models here, see the existing files for the format. None of the four zone controllers light anything besides
+// the keys, so none of them have a lightbar yet.
+const BUNDLED_LAYOUTS: [&str; 2] = [include_str!("../../res/layouts/ansi.json"), include_str!("../../res/layouts/iso.json")];
+
+/// Space left between keys, relative to the size of a standard key
+const KEY_GAP: f32 = 0.12;
+const LIGHTBAR_HEIGHT: f32 = 0.35;
+
+/// The physical arrangement of a keyboard, measured in standard key units
+#[derive(Deserialize)]
+pub struct KeyboardLayout {
+    pub name: String,
+    /// USB product IDs (in hex) this layout is picked for automatically
+    #[serde(default)]
+    product_ids: Vec<String>,
+    /// Countries (as in `en-US`) the model is sold with this layout in, when it also comes with another one under the
+    /// same product ID. Left empty for the layout used everywhere else.
+    #[serde(default)]
+    regions: Vec<String>,
+    #[serde(default)]
+    lightbar: Option<LightbarPosition>,
+    rows: Vec<Row>,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum LightbarPosition {
+    Front,
+    Rear,
+}
+
+#[derive(Deserialize)]
+struct Row {
+    height: f32,
+    /// Each key is a `[width, zone]` pair, with zones going from 1 to 4. Negative widths leave a gap instead.
+    keys: Vec<(f32, usize)>,
+}
+
+pub fn bundled_layouts() -> Vec<KeyboardLayout> {
+    BUNDLED_LAYOUTS
+        .iter()
+        .map(|layout| serde_json::from_str(layout).expect("Bundled keyboard layouts should be valid"))
+        .collect()
+}
+
+/// Pick the layout a keyboard most likely has, telling the regional versions of a model apart through the country of
+/// the system locale
+pub fn detect(layouts: &[KeyboardLayout], product_id: u16) -> Option<usize> {
+    let region = sys_locale::get_locale().and_then(|locale| locale.split(['-', '_']).nth(1).map(str::to_uppercase));
+    let candidates = || layouts.iter().enumerate().filter(|(_, layout)| layout.matches(product_id));
+
+    candidates()
+        .find(|(_, layout)| region.as_ref().is_some_and(|region| layout.regions.contains(region)))
+        .or_else(|| candidates().find(|(_, layout)| layout.regions.is_empty()))
+        .or_else(|| candidates().next())
+        .map(|(i, _)| i)
+}
+
+impl KeyboardLayout {
+    pub fn matches(&self, product_id: u16) -> bool {
+        self.product_ids.iter().any(|id| u16::from_str_radix(id, 16) == Ok(product_id))
+    }
+
+    fn width(&self) -> f32 {
+        self.rows.iter().map(|row| row.keys.iter().map(|(width, _)| width.abs()).sum::<f32>()).fold(0.0, f32::max)
+    }
+
+    fn height(&self) -> f32 {
+        let lightbar = if self.lightbar.is_some() { LIGHTBAR_HEIGHT } else { 0.0 };
+        self.rows.iter().map(|row| row.height).sum::<f32>() + lightbar
+    }
+
+    /// Paint the keyboard with the given zone colors, filling the available width
+    pub fn show(&self, ui: &mut Ui, zone_colors: [[u8; 3]; 4]) {
+        let unit = ui.available_width() / self.width();
+        let (response, painter) = ui.allocate_painter(Vec2::new(ui.available_width(), self.height() * unit), Sense::hover());
+        let colors = zone_colors.iter().enumerate().map(|(i, [r, g, b])| t!("accessibility.zone_rgb", zone = i + 1, r = r, g = g, b = b));
+        accessibility::describe(&response, WidgetType::Other, t!("accessibility.keyboard_preview", zones = colors.collect::<Vec<_>>().join(", ")));
+
+        let origin = response.rect.min;
+        let rounding = Rounding::same(unit * 0.15);
+        let color = |zone: usize| {
+            let [r, g, b] = zone_colors[zone.clamp(1, 4) - 1];
+            Color32::from_rgb(r, g, b)
+        };
+
+        let mut y = if self.lightbar == Some(LightbarPosition::Rear) { LIGHTBAR_HEIGHT } else { 0.0 };
+
+        for row in &self.rows {
+            let mut x = 0.0;
+
+            for &(width, zone) in &row.keys {
+                if width > 0.0 {
+                    let rect = Rect::from_min_size(origin + Vec2::new(x, y) * unit, Vec2::new(width, row.height) * unit).shrink(KEY_GAP * unit / 2.0);
+                    painter.rect_filled(rect, rounding, color(zone));
+                }
+
+                x += width.abs();
+            }
+
+            y += row.height;
+        }
+
+        if let Some(position) = self.lightbar {
+            let bar_y = match position {
+                LightbarPosition::Front => y,
+                LightbarPosition::Rear => 0.0,
+            };
+            let segment_width = self.width() / 4.0;
+
+            for zone in 1..=4 {
+                let min = origin + Vec2::new(segment_width * (zone - 1) as f32, bar_y) * unit;
+                let rect = Rect::from_min_size(min, Vec2::new(segment_width, LIGHTBAR_HEIGHT) * unit).shrink(KEY_GAP * unit);
+                painter.rect_filled(rect, rounding, color(zone));
+            }
+        }
+    }
+}
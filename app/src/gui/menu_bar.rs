@@ -5,10 +5,13 @@ use eframe::{
 };
 use egui_file::FileDialog;
 use egui_notify::Toasts;
+use rust_i18n::t;
 use std::{path::PathBuf, time::Duration};
 
 use crate::{
+    diagnostics, effect_library,
     gui::modals,
+    logging,
     manager::{custom_effect::CustomEffect, profile::Profile},
     DENY_HIDING,
 };
@@ -20,6 +23,7 @@ pub struct MenuBarState {
     load_profile_dialog: FileDialog,
     load_effect_dialog: FileDialog,
     save_profile_dialog: FileDialog,
+    save_diagnostics_dialog: FileDialog,
 }
 
 impl MenuBarState {
@@ -29,6 +33,7 @@ impl MenuBarState {
             load_profile_dialog: FileDialog::open_file(None).default_size(Vec2::splat(300.0)),
             load_effect_dialog: FileDialog::open_file(None).default_size(Vec2::splat(300.0)),
             save_profile_dialog: FileDialog::save_file(None).default_size(Vec2::splat(300.0)),
+            save_diagnostics_dialog: FileDialog::save_file(None).default_size(Vec2::splat(300.0)),
         }
     }
 
@@ -37,6 +42,7 @@ impl MenuBarState {
         self.handle_load_profile(ctx, current_profile, changed, toasts);
         self.handle_save_profile(ctx, current_profile, toasts);
         self.handle_load_effect(ctx, current_effect, changed, toasts);
+        self.handle_save_diagnostics(ctx, toasts);
     }
 
     fn handle_load_profile(&mut self, ctx: &Context, current_profile: &mut Profile, changed: &mut bool, toasts: &mut Toasts) {
@@ -48,7 +54,7 @@ impl MenuBarState {
                         *changed = true;
                     }
                     Err(_) => {
-                        toasts.error("Could not load profile.").duration(Some(Duration::from_millis(5000))).closable(true);
+                        toasts.error(t!("menu.load_profile_failed")).duration(Some(Duration::from_millis(5000))).closable(true);
                     }
                 }
                 self.update_paths(path);
@@ -60,7 +66,7 @@ impl MenuBarState {
         if self.save_profile_dialog.show(ctx).selected() {
             if let Some(path) = self.save_profile_dialog.path().map(|p| p.to_path_buf()) {
                 if current_profile.save_profile(&path).is_err() {
-                    toasts.error("Could not save profile.").duration(Some(Duration::from_millis(5000))).closable(true);
+                    toasts.error(t!("menu.save_profile_failed")).duration(Some(Duration::from_millis(5000))).closable(true);
                 }
                 self.update_paths(path);
             }
@@ -76,7 +82,7 @@ impl MenuBarState {
                         *changed = true;
                     }
                     Err(_) => {
-                        toasts.error("Could not load custom effect.").duration(Some(Duration::from_millis(5000))).closable(true);
+                        toasts.error(t!("menu.load_effect_failed")).duration(Some(Duration::from_millis(5000))).closable(true);
                     }
                 }
                 self.update_paths(path);
@@ -84,6 +90,21 @@ impl MenuBarState {
         }
     }
 
+    fn handle_save_diagnostics(&mut self, ctx: &Context, toasts: &mut Toasts) {
+        if self.save_diagnostics_dialog.show(ctx).selected() {
+            if let Some(path) = self.save_diagnostics_dialog.path().map(|p| p.to_path_buf()) {
+                if diagnostics::save(&path).is_ok() {
+                    toasts
+                        .success(t!("menu.diagnostics_saved", path = path.display()))
+                        .duration(Some(Duration::from_millis(5000)))
+                        .closable(true);
+                } else {
+                    toasts.error(t!("menu.diagnostics_failed")).duration(Some(Duration::from_millis(5000))).closable(true);
+                }
+            }
+        }
+    }
+
     fn update_paths(&mut self, path: PathBuf) {
         let mut save_paths = |path: PathBuf| {
             self.load_profile_dialog.set_path(path.clone());
@@ -107,31 +128,127 @@ impl MenuBarState {
         use egui::menu;
 
         menu::bar(ui, |ui| {
-            ui.menu_button("Profile", |ui| {
-                if ui.button("Open").clicked() {
+            ui.menu_button(t!("menu.profile"), |ui| {
+                if ui.button(t!("menu.open")).clicked() {
                     self.load_profile_dialog.open();
                 }
-                if ui.button("Save").clicked() {
+                if ui.button(t!("menu.save")).clicked() {
                     self.save_profile_dialog.open();
                 }
             });
 
-            ui.menu_button("Effect", |ui| {
-                if ui.button("Open").clicked() {
+            ui.menu_button(t!("menu.edit"), |ui| {
+                if ui.add(egui::Button::new(t!("menu.undo")).shortcut_text(ctx.format_shortcut(&super::UNDO_SHORTCUT))).clicked() {
+                    self.gui_sender.send(GuiMessage::Undo).unwrap();
+                    ui.close_menu();
+                }
+                if ui.add(egui::Button::new(t!("menu.redo")).shortcut_text(ctx.format_shortcut(&super::REDO_SHORTCUT))).clicked() {
+                    self.gui_sender.send(GuiMessage::Redo).unwrap();
+                    ui.close_menu();
+                }
+            });
+
+            ui.menu_button(t!("menu.effect"), |ui| {
+                if ui.button(t!("menu.open")).clicked() {
                     self.load_effect_dialog.open();
                 }
+                if ui.button(t!("menu.expression_editor")).clicked() {
+                    self.gui_sender.send(GuiMessage::OpenExpressionEditor).unwrap();
+                    ui.close_menu();
+                }
+                #[cfg(feature = "network")]
+                if ui.button(t!("menu.community_effects")).clicked() {
+                    self.gui_sender.send(GuiMessage::OpenCommunity).unwrap();
+                    ui.close_menu();
+                }
+                if ui.button(t!("menu.open_effect_library")).clicked() {
+                    let folder = effect_library::folder();
+                    if std::fs::create_dir_all(&folder).is_err() || open::that(&folder).is_err() {
+                        toasts.error(t!("menu.open_effect_library_failed")).duration(Some(Duration::from_millis(5000))).closable(true);
+                    }
+                    ui.close_menu();
+                }
             });
 
-            let about_modal = modals::about(ctx);
-            if ui.button("About").clicked() {
-                about_modal.open();
+            ui.menu_button(t!("menu.tools"), |ui| {
+                if ui.button(t!("menu.palettes")).clicked() {
+                    self.gui_sender.send(GuiMessage::OpenPaletteEditor).unwrap();
+                    ui.close_menu();
+                }
+                if ui.button(t!("menu.gallery")).clicked() {
+                    self.gui_sender.send(GuiMessage::OpenGallery).unwrap();
+                    ui.close_menu();
+                }
+                if ui.button(t!("menu.statistics")).clicked() {
+                    self.gui_sender.send(GuiMessage::OpenStatistics).unwrap();
+                    ui.close_menu();
+                }
+                if ui.button(t!("menu.calibration")).clicked() {
+                    self.gui_sender.send(GuiMessage::OpenCalibration).unwrap();
+                    ui.close_menu();
+                }
+                if ui.button(t!("menu.focus_timer")).clicked() {
+                    self.gui_sender.send(GuiMessage::OpenFocusTimer).unwrap();
+                    ui.close_menu();
+                }
+                if ui.button(t!("menu.controllers")).clicked() {
+                    self.gui_sender.send(GuiMessage::OpenControllers).unwrap();
+                    ui.close_menu();
+                }
+                if ui.button(t!("menu.triggers")).clicked() {
+                    self.gui_sender.send(GuiMessage::OpenTriggers).unwrap();
+                    ui.close_menu();
+                }
+            });
+
+            if ui.button(t!("menu.settings")).clicked() {
+                self.gui_sender.send(GuiMessage::OpenPreferences).unwrap();
             }
 
-            if ui.button("Donate").clicked() {
+            let about_modal = modals::about(ctx);
+            ui.menu_button(t!("menu.help"), |ui| {
+                if ui.button(t!("menu.whats_new")).clicked() {
+                    self.gui_sender.send(GuiMessage::OpenWhatsNew).unwrap();
+                    ui.close_menu();
+                }
+                #[cfg(feature = "network")]
+                if ui.button(t!("menu.check_for_updates")).clicked() {
+                    self.gui_sender.send(GuiMessage::CheckForUpdates).unwrap();
+                    ui.close_menu();
+                }
+                if ui.button(t!("menu.setup_wizard")).clicked() {
+                    self.gui_sender.send(GuiMessage::OpenOnboarding).unwrap();
+                    ui.close_menu();
+                }
+                if ui.button(t!("menu.open_log_folder")).clicked() {
+                    let folder = logging::folder();
+                    if std::fs::create_dir_all(&folder).is_err() || open::that(&folder).is_err() {
+                        toasts.error(t!("menu.open_log_folder_failed")).duration(Some(Duration::from_millis(5000))).closable(true);
+                    }
+                    ui.close_menu();
+                }
+                if ui.button(t!("menu.generate_diagnostics")).clicked() {
+                    self.save_diagnostics_dialog = FileDialog::save_file(None)
+                        .default_size(Vec2::splat(300.0))
+                        .default_filename(diagnostics::default_file_name());
+                    self.save_diagnostics_dialog.open();
+                    ui.close_menu();
+                }
+                if ui.button(t!("menu.show_fps")).clicked() {
+                    self.gui_sender.send(GuiMessage::ToggleFpsOverlay).unwrap();
+                    ui.close_menu();
+                }
+                if ui.button(t!("menu.about")).clicked() {
+                    about_modal.open();
+                    ui.close_menu();
+                }
+            });
+
+            if ui.button(t!("menu.donate")).clicked() {
                 open::that("https://www.buymeacoffee.com/4JXdev").unwrap();
             }
 
-            if !*DENY_HIDING && ui.button("Exit").clicked() {
+            if !*DENY_HIDING && ui.button(t!("menu.exit")).clicked() {
                 self.gui_sender.send(GuiMessage::Quit).unwrap();
             }
 
@@ -142,7 +259,7 @@ impl MenuBarState {
                 ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
                     if ui.button("📜").clicked() {
                         if !console::alloc_with_color_support() {
-                            toasts.error("Could not allocate debug terminal.").duration(Some(Duration::from_millis(5000))).closable(true);
+                            toasts.error(t!("menu.debug_terminal_failed")).duration(Some(Duration::from_millis(5000))).closable(true);
                         }
                         println!("Debug terminal enabled.");
                     }
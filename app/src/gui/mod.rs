@@ -1,62 +1,319 @@
-use std::{process, thread, time::Duration};
+use std::{
+    collections::BTreeMap,
+    process,
+    time::{Duration, Instant},
+};
 
 use std::sync::{
     atomic::{AtomicBool, Ordering},
-    Arc,
+    Arc, OnceLock,
 };
 
-use device_query::{DeviceQuery, Keycode};
 #[cfg(debug_assertions)]
 use eframe::egui::style::DebugOptions;
 use eframe::{
-    egui::{CentralPanel, Context, Frame, Layout, ScrollArea, Style, TopBottomPanel, ViewportCommand},
+    egui::{
+        Align2, Area, CentralPanel, Context, FontId, Frame, Id, Key, KeyboardShortcut, Layout, Modifiers, RichText, ScrollArea, Slider, Style, ThemePreference, TopBottomPanel, ViewportCommand,
+        Visuals, WidgetType,
+    },
     emath::Align,
-    epaint::{Color32, Rounding, Vec2},
+    epaint::{Color32, Rect, Rounding, Stroke, Vec2},
     CreationContext,
 };
 
 use egui_notify::Toasts;
+use legion_kb_rgb_hw::Calibration;
+use notify::RecommendedWatcher;
+use rust_i18n::t;
 use strum::IntoEnumIterator;
-use tray_icon::menu::MenuEvent;
+use tracing::{error, info};
 
 use crate::{
+    app_rules,
+    appearance::ThemeMode,
+    backlight::{self, BacklightWatcher},
+    battery::{self, BatteryState},
+    chroma::{self, ChromaEvent, ChromaPriority},
     cli::OutputType,
-    enums::Effects,
-    manager::{self, custom_effect::CustomEffect, profile::Profile, EffectManager, ManagerCreationError},
-    persist::Settings,
-    tray::{QUIT_ID, SHOW_ID},
+    controllers::{self, Control, ControlAction, ControllerListener},
+    crash,
+    effect_library::{self, LibraryEffect},
+    enums::{Brightness, Effects},
+    eyedropper::Pick,
+    foreground::{self, ForegroundWindow},
+    hotkeys::{self, Hotkey},
+    instance,
+    lock_keys::{self, LockState},
+    manager::{
+        self,
+        custom_effect::CustomEffect,
+        flash::{self, FlashPattern},
+        health::{Health, SharedHealth},
+        keystrokes,
+        palette::Palette,
+        profile::{self, EffectOptions, Profile},
+        registry::DeviceRegistry,
+        ManagerCreationError,
+    },
+    #[cfg(feature = "audio")]
+    mic::{self, MicWatcher},
+    notifications,
+    overlays::{Overlay, SystemState},
+    persist::{LoadOutcome, Preferences, Settings},
+    pomodoro::{Pomodoro, PomodoroCommand},
+    power::{self, CpuMonitor},
+    shuffle::{self, Shuffle},
+    startup::{InitialState, StartupSettings},
+    ticks::{self, WakeupMeter},
+    usage::UsageRecorder,
+    wallpaper::{self, WallpaperWatcher},
     DENY_HIDING,
 };
+#[cfg(feature = "network")]
+use crate::{
+    api::{ApiCommand, ApiServer},
+    room_sync::RoomSync,
+    triggers::TriggerPoller,
+};
+#[cfg(feature = "network")]
+use self::{
+    community::{CommunityBrowser, CommunityEvent},
+    updates::{UpdateEvent, UpdateWindow},
+};
+#[cfg(feature = "tray")]
+use crate::tray::{self, Tray, TrayEvent};
+
+use self::{
+    color_editor::ColorEditor,
+    controller_panel::ControllerPanel,
+    expression_editor::{ExpressionAction, ExpressionEditor},
+    gallery::Gallery,
+    history::ProfileHistory,
+    keyboard_preview::KeyboardLayout,
+    menu_bar::MenuBarState,
+    onboarding::{Onboarding, OnboardingAction},
+    palette_editor::{self, DecodedImage, PaletteEditor},
+    safe_mode::SafeModeAction,
+    saved_items::{self, ProfileRenamed, SavedItems},
+    style::{self, Theme},
+    swatches::SwatchPanel,
+    whats_new::WhatsNew,
+};
 
-use self::{menu_bar::MenuBarState, saved_items::SavedItems, style::Theme};
-
+mod accessibility;
+mod calibration;
+mod color_editor;
+#[cfg(feature = "network")]
+mod community;
+mod controller_panel;
+mod device_selector;
+mod effect_ui;
+mod expression_editor;
+mod favorites;
+mod focus_timer;
+mod gallery;
+mod gradient_editor;
+mod history;
+mod i18n;
+mod keyboard_preview;
 mod menu_bar;
 mod modals;
+mod onboarding;
+mod palette_editor;
+mod preferences;
+mod safe_mode;
 mod saved_items;
+mod statistics;
 pub mod style;
+mod swatches;
+mod triggers_editor;
+#[cfg(feature = "network")]
+mod updates;
+mod whats_new;
+
+/// How often the keyboard preview keeps up with software effects
+const PREVIEW_REPAINT_INTERVAL: Duration = Duration::from_millis(33);
+const UNDO_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+const REDO_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::Z);
 
 pub struct App {
+    /// Started with `--safe-mode`, the settings weren't loaded and won't be saved
+    safe_mode: bool,
     instance_not_unique: bool,
+    /// What's left to do once the keyboard is found, until then
+    pending_startup: Option<PendingStartup>,
+    /// Lets the keyboard search wake the window up when it's done
+    startup_ctx: Arc<OnceLock<Context>>,
     gui_tx: crossbeam_channel::Sender<GuiMessage>,
     gui_rx: crossbeam_channel::Receiver<GuiMessage>,
 
     has_tray: Arc<AtomicBool>,
     visible: Arc<AtomicBool>,
-
-    manager: Option<EffectManager>,
+    /// Whether the window was minimized last frame, to only act once it gets minimized
+    was_minimized: bool,
+    health: SharedHealth,
+    #[cfg(feature = "tray")]
+    tray: Option<Tray>,
+
+    manager: Option<DeviceRegistry>,
+    /// The keyboard `current_profile` belongs to
+    current_device: Option<String>,
+    /// What every other keyboard is playing, by id
+    device_profiles: BTreeMap<String, Profile>,
     state_changed: bool,
     loaded_effect: LoadedEffect,
     current_profile: Profile,
+    history: ProfileHistory,
+    /// Name of the profile last sent to the keyboard, to tell switching profiles apart from editing one
+    applied_profile_name: Option<String>,
+    /// The options last used with each effect, restored when switching back to it
+    effect_options: BTreeMap<String, EffectOptions>,
+    /// The profile that was active before an application rule forced a switch
+    rule_restore: Option<Profile>,
+    /// The name of the profile the application rules switched to last, so it's only applied when another rule matches
+    rule_profile: Option<String>,
+    /// The colors a game is broadcasting through Chroma, shown instead of the profile while they last
+    chroma_colors: Option<[[u8; 3]; 4]>,
+    /// The focus timer, shown instead of the profile while it runs
+    pomodoro: Pomodoro,
+    focus_timer_open: bool,
+    /// Counts down to switching to another saved profile
+    shuffle: Shuffle,
+    /// What the overlays last got told about the system
+    system_state: SystemState,
+    /// Turned off through the hotkey, the tray or the status bar, keeping everything else as it was to go back to it
+    lights_off: bool,
+    /// The colors last taken from the wallpaper, applied right away if following it gets turned on
+    wallpaper_palette: Option<Palette>,
+    /// When the zones started lighting up one after the other, to label the color pickers along with them
+    identify_started: Option<Instant>,
+    /// The profile shown at launch while the keyboard is left as it was, until another one is picked
+    untouched: Option<Profile>,
+    /// How many minutes the countdown of the `Clock` effect was started for and when it ends, kept while the effect
+    /// restarts for other changes
+    countdown: Option<(u16, Instant)>,
+    /// Mirrors the keyboard to the lights of the room, running for as long as the app
+    #[cfg(feature = "network")]
+    room_sync: Option<RoomSync>,
+    /// Serves the HTTP API while it's enabled
+    #[cfg(feature = "network")]
+    api_server: Option<ApiServer>,
+    /// Polls the URLs of the triggers that watch one, running for as long as the app
+    #[cfg(feature = "network")]
+    trigger_poller: Option<TriggerPoller>,
+    triggers_open: bool,
+    preferences: Preferences,
+    preferences_open: bool,
+    usage: UsageRecorder,
+    statistics_open: bool,
+    /// Color correction of each keyboard, by id
+    calibrations: BTreeMap<String, Calibration>,
+    calibration_open: bool,
+    show_fps: bool,
+    wakeups: WakeupMeter,
+    cpu_monitor: CpuMonitor,
 
     menu_bar: MenuBarState,
     saved_items: SavedItems,
+    palette_editor: PaletteEditor,
+    controller_panel: ControllerPanel,
+    /// Reads the Stream Decks and MIDI controllers while they're enabled or being learned
+    controller_listener: Option<ControllerListener>,
+    /// Watches the microphone while its overlay is enabled
+    #[cfg(feature = "audio")]
+    mic_watcher: Option<MicWatcher>,
+    /// Follows the backlight level set with Fn+Space while that's enabled
+    backlight_watcher: Option<BacklightWatcher>,
+    /// Follows the desktop wallpaper while that's enabled
+    wallpaper_watcher: Option<WallpaperWatcher>,
+    swatch_panel: SwatchPanel,
+    color_editor: ColorEditor,
+    whats_new: WhatsNew,
+    onboarding: Onboarding,
+    gallery: Gallery,
+    gallery_open: bool,
+    expression_editor: ExpressionEditor,
+    #[cfg(feature = "network")]
+    updates: UpdateWindow,
+    #[cfg(feature = "network")]
+    community: CommunityBrowser,
+    layouts: Vec<KeyboardLayout>,
+    product_id: Option<u16>,
     global_rgb: [u8; 3],
     theme: Theme,
     toasts: Toasts,
+    /// Picks up changes made to the settings file while the app is running, stops watching once dropped
+    #[allow(dead_code)]
+    settings_watcher: Option<RecommendedWatcher>,
+    /// The effects in the effect library, listed after the built-in ones
+    library: Vec<LibraryEffect>,
+    #[allow(dead_code)]
+    library_watcher: Option<RecommendedWatcher>,
 }
 
 pub enum GuiMessage {
+    /// The keyboards were looked for at launch, and found or not
+    StartupFinished(Box<error_stack::Result<DeviceRegistry, ManagerCreationError>>),
     CycleProfiles,
+    ToggleLights,
+    /// Switch to a saved profile, by name
+    LoadProfile(String),
+    /// Play a profile that isn't saved, as sent through the HTTP API
+    #[cfg(feature = "network")]
+    ApplyProfile(Profile),
+    Notification,
+    ForegroundChanged(Option<ForegroundWindow>),
+    Chroma(ChromaEvent),
+    /// A button of a Stream Deck or MIDI controller was pressed
+    ControlPressed(Control),
+    Pomodoro(PomodoroCommand),
+    /// The shuffle interval is up
+    ShuffleDue,
+    #[cfg(feature = "audio")]
+    MicMuted(bool),
+    LockKeys(LockState),
+    Battery(BatteryState),
+    /// Make the lights brighter (positive) or dimmer (negative) by that many steps
+    StepBrightness(i8),
+    /// The laptop changed its backlight level through Fn+Space
+    LaptopBacklight(u8),
+    /// A color was picked from the screen for a zone, or picking it didn't work out
+    PickedColor(usize, Pick),
+    /// A picture was decoded for the palette editor
+    DecodedImage(DecodedImage),
+    /// The colors of the desktop wallpaper, when following it started or after it changed
+    Wallpaper(Palette),
+    /// Flash the pattern of a trigger, by name
+    #[cfg(feature = "network")]
+    Trigger(String),
+    SettingsChanged,
+    /// An effect was added to the effect library, changed or removed from it, with the library as it is now
+    LibraryChanged(Vec<LibraryEffect>),
+    OpenPaletteEditor,
+    OpenPreferences,
+    OpenStatistics,
+    OpenCalibration,
+    OpenControllers,
+    OpenFocusTimer,
+    OpenTriggers,
+    OpenGallery,
+    OpenExpressionEditor,
+    OpenWhatsNew,
+    /// Go through the first launch wizard again
+    OpenOnboarding,
+    /// Look for a new release, as asked from the menu
+    #[cfg(feature = "network")]
+    CheckForUpdates,
+    #[cfg(feature = "network")]
+    Update(UpdateEvent),
+    #[cfg(feature = "network")]
+    OpenCommunity,
+    #[cfg(feature = "network")]
+    Community(CommunityEvent),
+    /// Show or hide how many frames per second the effect is actually sending
+    ToggleFpsOverlay,
+    Undo,
+    Redo,
     Quit,
 }
 
@@ -94,6 +351,14 @@ impl LoadedEffect {
     }
 }
 
+/// What's left of starting up after the keyboards were found
+struct PendingStartup {
+    /// The keyboard shown last time
+    selected_device: Option<String>,
+    /// Start from what the keyboard shows instead of the saved profile
+    show_hardware_state: bool,
+}
+
 #[derive(Default)]
 pub enum State {
     #[default]
@@ -103,103 +368,407 @@ pub enum State {
 }
 
 impl App {
-    pub fn new(output: OutputType, has_tray: Arc<AtomicBool>, visible: Arc<AtomicBool>) -> Self {
+    pub fn new(output: OutputType, has_tray: Arc<AtomicBool>, visible: Arc<AtomicBool>, health: SharedHealth) -> Self {
         let (gui_tx, gui_rx) = crossbeam_channel::unbounded::<GuiMessage>();
 
-        let manager_result = EffectManager::new(manager::OperationMode::Gui);
-
-        let instance_not_unique = if let Err(err) = &manager_result {
-            &ManagerCreationError::InstanceAlreadyRunning == err.current_context()
-        } else {
-            false
+        let safe_mode = matches!(output, OutputType::SafeMode);
+        let settings: Settings = if safe_mode { Settings::default() } else { Settings::load() };
+        let Settings {
+            current_profile,
+            profiles,
+            effects,
+            palettes,
+            swatches,
+            last_seen_version,
+            effect_options,
+            preferences,
+            usage,
+            selected_device,
+            device_profiles,
+            calibrations,
+            load_outcome,
+            ..
+        } = settings;
+
+        let profile_delay = match &output {
+            OutputType::Profile(profile) => profile.startup_delay,
+            OutputType::Custom(..) => None,
+            _ => current_profile.startup_delay,
+        };
+        // Nothing is sent until a profile is picked, so the firmware keeps whatever it was showing
+        let leave_untouched = match output {
+            OutputType::NoApply => true,
+            OutputType::NoArgs => preferences.startup.leave_untouched,
+            _ => false,
         };
+        let startup = StartupSettings {
+            leave_untouched,
+            ..preferences.startup.for_launch(profile_delay)
+        };
+
+        // Bring up the window of the running instance rather than warning about it, unless it only runs an effect from the command line
+        if visible.load(Ordering::SeqCst) && DeviceRegistry::other_instance_running() && instance::show_window() {
+            process::exit(0);
+        }
 
-        let manager = manager_result.ok();
+        // Waiting for the keyboard can take a while at boot, so it's looked for in the background while the window is up.
+        // The tray is already up on Linux too, so whoever launched the app can see it's waiting.
+        let startup_ctx = Arc::new(OnceLock::<Context>::new());
+        {
+            let gui_tx = gui_tx.clone();
+            let health = health.clone();
+            let startup_ctx = startup_ctx.clone();
+            std::thread::spawn(move || {
+                let result = DeviceRegistry::new_at_startup(manager::OperationMode::Gui, startup, |attempt| {
+                    info!("Keyboard not found yet, retrying (attempt {attempt})");
+                    health.set(Health::Waiting { attempt });
+                });
 
-        let settings: Settings = Settings::load();
-        let Settings { current_profile, profiles, effects } = settings;
+                let _ = gui_tx.send(GuiMessage::StartupFinished(Box::new(result)));
+                // Before the window is up the message gets handled on the first frame anyway
+                if let Some(ctx) = startup_ctx.get() {
+                    ctx.request_repaint();
+                }
+            });
+        }
 
         let gui_tx_c = gui_tx.clone();
+        i18n::apply(preferences.language.as_deref());
+        let theme = Theme::new(&preferences.appearance);
+        let whats_new = WhatsNew::new(gui_tx.clone(), last_seen_version.as_deref());
+        #[cfg(feature = "network")]
+        let updates = UpdateWindow::new(gui_tx.clone());
+        #[cfg(feature = "network")]
+        let community = CommunityBrowser::new(gui_tx.clone());
+        let color_editor = ColorEditor::new(gui_tx.clone());
+        let palette_editor = PaletteEditor::new(palettes, gui_tx.clone());
         // Default app state
         let mut app = Self {
-            instance_not_unique,
+            safe_mode,
+            instance_not_unique: false,
+            pending_startup: None,
+            startup_ctx,
             gui_tx,
             gui_rx,
 
             has_tray,
             visible,
-
-            manager,
+            was_minimized: false,
+            health,
+            #[cfg(feature = "tray")]
+            tray: None,
+
+            manager: None,
+            current_device: selected_device.clone(),
+            device_profiles,
             // Default to true for an instant update on launch
             state_changed: true,
             loaded_effect: LoadedEffect::default(),
+            history: ProfileHistory::new(&current_profile),
+            applied_profile_name: None,
             current_profile,
+            effect_options,
+            rule_restore: None,
+            rule_profile: None,
+            chroma_colors: None,
+            #[cfg(feature = "network")]
+            room_sync: None,
+            #[cfg(feature = "network")]
+            api_server: None,
+            #[cfg(feature = "network")]
+            trigger_poller: None,
+            triggers_open: false,
+            preferences,
+            preferences_open: false,
+            usage: UsageRecorder::new(usage),
+            statistics_open: false,
+            calibrations,
+            calibration_open: false,
+            show_fps: false,
+            wakeups: WakeupMeter::new(),
+            cpu_monitor: CpuMonitor::new(),
 
             menu_bar: MenuBarState::new(gui_tx_c),
             saved_items: SavedItems::new(profiles, effects),
+            palette_editor,
+            controller_panel: ControllerPanel::default(),
+            controller_listener: None,
+            #[cfg(feature = "audio")]
+            mic_watcher: None,
+            backlight_watcher: None,
+            wallpaper_watcher: None,
+            pomodoro: Pomodoro::default(),
+            focus_timer_open: false,
+            shuffle: Shuffle::default(),
+            system_state: SystemState::default(),
+            lights_off: false,
+            wallpaper_palette: None,
+            identify_started: None,
+            untouched: None,
+            countdown: None,
+            swatch_panel: SwatchPanel::new(swatches),
+            color_editor,
+            whats_new,
+            onboarding: Onboarding::default(),
+            gallery: Gallery::default(),
+            gallery_open: false,
+            expression_editor: ExpressionEditor::default(),
+            #[cfg(feature = "network")]
+            updates,
+            #[cfg(feature = "network")]
+            community,
+            layouts: keyboard_preview::bundled_layouts(),
+            product_id: legion_kb_rgb_hw::find_keyboard_product_id().ok(),
             global_rgb: [0; 3],
-            theme: Theme::default(),
+            theme,
             toasts: Toasts::default(),
+            settings_watcher: None,
+            library: Vec::new(),
+            library_watcher: None,
         };
 
+        // Nothing is new on a fresh install
+        app.whats_new.open = !safe_mode && load_outcome != LoadOutcome::Created && app.whats_new.has_news();
+        // Walk through setting things up on a fresh install instead
+        app.onboarding.open = !safe_mode && load_outcome == LoadOutcome::Created;
+
+        match load_outcome {
+            LoadOutcome::Loaded | LoadOutcome::Created => {}
+            LoadOutcome::RecoveredFromBackup => {
+                app.toasts
+                    .warning(t!("app.recovered_from_backup"))
+                    .duration(Some(Duration::from_millis(8000)))
+                    .closable(true);
+            }
+            LoadOutcome::Reset => {
+                app.toasts
+                    .error(t!("app.settings_reset"))
+                    .duration(Some(Duration::from_millis(8000)))
+                    .closable(true);
+            }
+        }
+
+        let show_hardware_state = matches!(output, OutputType::NoArgs | OutputType::NoApply) && app.preferences.startup.initial_state == InitialState::Keyboard;
+
         // Update the state according to the option chosen by the user
         match output {
             OutputType::Profile(profile) => app.current_profile = profile,
             OutputType::Custom(effect) => app.loaded_effect = LoadedEffect::queued(effect),
-            OutputType::NoArgs => {}
+            OutputType::SafeMode | OutputType::NoArgs | OutputType::NoApply => {}
+            OutputType::Timed { .. } => unreachable!("Timed effects are only played through the CLI"),
+            OutputType::Oneshot(..) => unreachable!("Oneshot effects are only set through the CLI"),
             OutputType::Exit => unreachable!("Exiting the app supersedes starting the GUI"),
+            #[cfg(feature = "network")]
+            OutputType::Mqtt(..) => unreachable!("MQTT is only served through the CLI"),
+        }
+
+        app.apply_offline();
+        app.apply_keystroke_counting();
+        app.apply_hotkeys();
+        if leave_untouched {
+            app.untouched = Some(app.current_profile.clone());
         }
+        app.pending_startup = Some(PendingStartup {
+            selected_device,
+            show_hardware_state,
+        });
 
         app
     }
 
-    pub fn init(self, cc: &CreationContext<'_>) -> Self {
-        if !*DENY_HIDING {
-            cc.egui_ctx.send_viewport_cmd(ViewportCommand::Visible(self.visible.load(Ordering::SeqCst)));
+    /// Take over the keyboards once the search started at launch is over, or show why it failed
+    fn finish_startup(&mut self, ctx: &Context, result: error_stack::Result<DeviceRegistry, ManagerCreationError>) {
+        let Some(startup) = self.pending_startup.take() else {
+            return;
+        };
+
+        self.health.set(match &result {
+            Ok(manager) => manager.health(),
+            Err(err) if *err.current_context() == ManagerCreationError::InstanceAlreadyRunning => Health::Normal,
+            Err(_) => Health::Disconnected,
+        });
+
+        let manager = match result {
+            Ok(manager) => manager,
+            Err(err) => {
+                self.instance_not_unique = *err.current_context() == ManagerCreationError::InstanceAlreadyRunning;
+                return;
+            }
+        };
+        self.manager = Some(manager);
+
+        self.apply_reduced_motion();
+        self.apply_brightness_scale();
+        self.apply_effect_speed();
+        self.apply_frame_rate();
+        self.apply_crossfade();
+        self.apply_calibrations();
+        self.apply_synced_lights();
+        self.apply_weather();
+        self.apply_exit_settings();
+        self.restore_devices(startup.selected_device);
+        if startup.show_hardware_state {
+            self.show_hardware_state();
+        }
+        // Anything but the profile shown now counts as picking one
+        if let Some(untouched) = &mut self.untouched {
+            untouched.clone_from(&self.current_profile);
+        }
+        self.state_changed = true;
+
+        self.start_services(ctx);
+    }
+
+    /// Start from what the keyboard was already showing instead of the saved profile, if it could tell
+    fn show_hardware_state(&mut self) {
+        let Some(state) = self.manager.as_ref().and_then(DeviceRegistry::hardware_state) else {
+            return;
+        };
+
+        self.current_profile = Profile::from_hardware(state);
+        self.history = ProfileHistory::new(&self.current_profile);
+    }
+
+    /// Pick up where every keyboard was left off, the one picked last time being the one shown
+    fn restore_devices(&mut self, selected_device: Option<String>) {
+        let Some(manager) = &mut self.manager else {
+            return;
+        };
+
+        // A device picked from the command line wins
+        if let Some(id) = selected_device.filter(|_| !DeviceRegistry::has_selector()) {
+            manager.select(&id);
+        }
+        if let Some(id) = manager.selected().map(legion_kb_rgb_hw::KeyboardInfo::id) {
+            self.switch_device(id);
+        }
+
+        let Some(manager) = &mut self.manager else {
+            return;
+        };
+        self.product_id = manager.selected().map(|device| device.product_id).or(self.product_id);
+
+        // The others get their profile once picked, like the one shown
+        if self.untouched.is_some() {
+            return;
+        }
+
+        let others: Vec<String> = manager.devices().map(legion_kb_rgb_hw::KeyboardInfo::id).filter(|id| Some(id) != self.current_device.as_ref()).collect();
+        for id in others {
+            let profile = self.device_profiles.get(&id).cloned().unwrap_or_else(|| self.current_profile.clone());
+            manager.set_profile_of(&id, profile);
+        }
+    }
+
+    /// Show the profile of another keyboard, keeping that of the current one around
+    fn switch_device(&mut self, id: String) {
+        if self.current_device.as_ref() == Some(&id) {
+            return;
+        }
+
+        if let Some(previous) = self.current_device.replace(id.clone()) {
+            self.device_profiles.insert(previous, self.current_profile.clone());
+        }
+        if let Some(profile) = self.device_profiles.remove(&id) {
+            self.current_profile = profile;
+            self.history = ProfileHistory::new(&self.current_profile);
+        }
+    }
+
+    fn select_device(&mut self, id: String) {
+        let Some(manager) = &mut self.manager else {
+            return;
+        };
+
+        if manager.select(&id) {
+            self.product_id = manager.selected().map(|device| device.product_id);
+            // A custom effect keeps playing on the keyboard it was started on
+            self.loaded_effect = LoadedEffect::none();
+            self.switch_device(id);
         }
+    }
+
+    /// The tray doesn't know about the app's state, so the app has to keep it updated itself
+    #[cfg(feature = "tray")]
+    pub fn with_tray(mut self, tray: Option<Tray>) -> Self {
+        self.tray = tray;
+        self
+    }
 
-        let egui_ctx = cc.egui_ctx.clone();
+    #[cfg(feature = "tray")]
+    fn listen_to_tray(&self, egui_ctx: Context) {
         let gui_tx = self.gui_tx.clone();
         let has_tray = self.has_tray.clone();
+        let visible = self.visible.clone();
 
-        std::thread::spawn(move || loop {
-            if let Ok(event) = MenuEvent::receiver().recv() {
-                if event.id == SHOW_ID {
-                    egui_ctx.request_repaint();
+        std::thread::spawn(move || {
+            for event in tray::events() {
+                egui_ctx.request_repaint();
 
-                    egui_ctx.send_viewport_cmd(ViewportCommand::Visible(true));
-                    egui_ctx.send_viewport_cmd(ViewportCommand::Focus);
-                } else if event.id == QUIT_ID {
-                    egui_ctx.request_repaint();
-
-                    let _ = gui_tx.send(GuiMessage::Quit);
-                    has_tray.store(false, Ordering::SeqCst);
+                match event {
+                    TrayEvent::Show => {
+                        visible.store(true, Ordering::SeqCst);
+                        egui_ctx.send_viewport_cmd(ViewportCommand::Visible(true));
+                        egui_ctx.send_viewport_cmd(ViewportCommand::Focus);
+                    }
+                    TrayEvent::LoadProfile(name) => {
+                        let _ = gui_tx.send(GuiMessage::LoadProfile(name));
+                    }
+                    TrayEvent::ToggleLights => {
+                        let _ = gui_tx.send(GuiMessage::ToggleLights);
+                    }
+                    TrayEvent::Pomodoro(command) => {
+                        let _ = gui_tx.send(GuiMessage::Pomodoro(command));
+                    }
+                    TrayEvent::Quit => {
+                        let _ = gui_tx.send(GuiMessage::Quit);
+                        has_tray.store(false, Ordering::SeqCst);
+                    }
                 }
             }
         });
+    }
 
-        let ctx = cc.egui_ctx.clone();
-        let gui_tx_c = self.gui_tx.clone();
-        if self.manager.is_some() {
-            thread::spawn(move || {
-                let state = device_query::DeviceState::new();
-                let mut lock_switching = false;
-
-                loop {
-                    let keys = state.get_keys();
-
-                    if keys.contains(&Keycode::LMeta) && keys.contains(&Keycode::RAlt) {
-                        if !lock_switching {
-                            let _ = gui_tx_c.send(GuiMessage::CycleProfiles);
-                            ctx.request_repaint();
-                            lock_switching = true;
-                        }
-                    } else {
-                        lock_switching = false;
-                    }
+    pub fn init(mut self, cc: &CreationContext<'_>) -> Self {
+        if !*DENY_HIDING {
+            cc.egui_ctx.send_viewport_cmd(ViewportCommand::Visible(self.visible.load(Ordering::SeqCst)));
+        }
 
-                    thread::sleep(Duration::from_millis(50));
-                }
+        #[cfg(feature = "tray")]
+        self.listen_to_tray(cc.egui_ctx.clone());
+
+        #[cfg(feature = "network")]
+        if !self.preferences.offline && !self.safe_mode {
+            self.updates.check(&cc.egui_ctx, false);
+        }
+
+        // The keyboard search can only wake the window up from now on
+        let _ = self.startup_ctx.set(cc.egui_ctx.clone());
+
+        // Go through the usual exit when terminated, so the settings get saved too
+        {
+            let ctx = cc.egui_ctx.clone();
+            let gui_tx = self.gui_tx.clone();
+            crash::set_on_terminate(move || {
+                let _ = gui_tx.send(GuiMessage::Quit);
+                ctx.request_repaint();
+            });
+        }
+
+        if !self.safe_mode {
+            let ctx = cc.egui_ctx.clone();
+            let gui_tx = self.gui_tx.clone();
+            self.settings_watcher = Settings::watch(move || {
+                let _ = gui_tx.send(GuiMessage::SettingsChanged);
+                ctx.request_repaint();
+            });
+
+            let ctx = cc.egui_ctx.clone();
+            let gui_tx = self.gui_tx.clone();
+            self.library = effect_library::scan();
+            self.library_watcher = effect_library::watch(move |library| {
+                let _ = gui_tx.send(GuiMessage::LibraryChanged(library));
+                ctx.request_repaint();
             });
         }
 
@@ -207,14 +776,112 @@ impl App {
 
         self
     }
+
+    /// Start everything that reacts to the system on behalf of the keyboard, once it was found
+    fn start_services(&mut self, ctx: &Context) {
+        if self.manager.is_some() && !self.safe_mode {
+            let ctx = ctx.clone();
+            let gui_tx = self.gui_tx.clone();
+
+            hotkeys::spawn_listener(move |hotkey| {
+                let message = match hotkey {
+                    Hotkey::CycleProfiles => GuiMessage::CycleProfiles,
+                    Hotkey::ToggleLights => GuiMessage::ToggleLights,
+                    Hotkey::BrightnessUp => GuiMessage::StepBrightness(1),
+                    Hotkey::BrightnessDown => GuiMessage::StepBrightness(-1),
+                };
+                let _ = gui_tx.send(message);
+                ctx.request_repaint();
+            });
+        }
+
+        self.start_notification_listener(ctx);
+        if self.preferences.chroma.enabled {
+            self.start_chroma(ctx);
+        }
+
+        self.apply_controllers(ctx);
+
+        self.start_overlay_watchers(ctx);
+        self.start_backlight_watcher(ctx);
+        self.start_wallpaper_watcher(ctx);
+        self.apply_shuffle(ctx);
+
+        #[cfg(feature = "network")]
+        if let (Some(manager), false) = (&self.manager, self.safe_mode) {
+            self.room_sync = Some(RoomSync::spawn(manager.preview().clone(), self.preferences.room_sync.clone()));
+
+            let ctx = ctx.clone();
+            let gui_tx = self.gui_tx.clone();
+            self.trigger_poller = Some(TriggerPoller::spawn(self.preferences.triggers.clone(), move |name| {
+                let _ = gui_tx.send(GuiMessage::Trigger(name));
+                ctx.request_repaint();
+            }));
+        }
+
+        self.apply_api(ctx);
+
+        if self.manager.is_some() && !self.safe_mode {
+            let ctx = ctx.clone();
+            let gui_tx = self.gui_tx.clone();
+
+            foreground::spawn_watcher(move |window| {
+                let _ = gui_tx.send(GuiMessage::ForegroundChanged(window));
+                ctx.request_repaint();
+            });
+        }
+
+        if self.manager.is_some() {
+            let ctx = ctx.clone();
+            let gui_tx = self.gui_tx.clone();
+            let visible = self.visible.clone();
+
+            instance::serve(true, move |request| match request {
+                instance::Request::Shutdown => {
+                    let _ = gui_tx.send(GuiMessage::Quit);
+                    ctx.request_repaint();
+                }
+                instance::Request::ShowWindow => {
+                    visible.store(true, Ordering::SeqCst);
+                    ctx.send_viewport_cmd(ViewportCommand::Visible(true));
+                    ctx.send_viewport_cmd(ViewportCommand::Minimized(false));
+                    ctx.send_viewport_cmd(ViewportCommand::Focus);
+                    ctx.request_repaint();
+                }
+            });
+        }
+    }
 }
 
 impl eframe::App for App {
     fn update(&mut self, ctx: &eframe::egui::Context, _frame: &mut eframe::Frame) {
-        if let Ok(message) = self.gui_rx.try_recv() {
-            match message {
-                GuiMessage::CycleProfiles => self.cycle_profiles(),
-                GuiMessage::Quit => self.exit_app(),
+        ticks::record_wakeup();
+        self.update_health();
+        #[cfg(feature = "tray")]
+        if let Some(tray) = &mut self.tray {
+            tray.show_profiles(
+                self.saved_items.profiles.iter().filter_map(|profile| profile.name.clone()).collect(),
+                favorites::favorites(&self.saved_items.profiles).filter_map(|profile| profile.name.clone()).collect(),
+            );
+            tray.show_lights_off(self.lights_off);
+        }
+
+        // Every sender requests a repaint along with its message, so handle all of them now rather than one per
+        // frame, as nothing else might wake the GUI up for the rest
+        let gui_rx = self.gui_rx.clone();
+        for message in gui_rx.try_iter() {
+            self.handle_message(ctx, message);
+        }
+
+        // Text fields have their own undo
+        if !ctx.wants_keyboard_input() {
+            // Checked first as the undo shortcut would also match it
+            if ctx.input_mut(|input| input.consume_shortcut(&REDO_SHORTCUT)) {
+                self.redo();
+            } else if ctx.input_mut(|input| input.consume_shortcut(&UNDO_SHORTCUT)) {
+                self.undo();
+            } else if let Some(name) = favorites::shortcut_pressed(ctx, &self.saved_items.profiles) {
+                self.load_profile(&name);
             }
         }
 
@@ -224,7 +891,7 @@ impl eframe::App for App {
         if *DENY_HIDING && !self.visible.load(Ordering::SeqCst) {
             self.visible.store(true, Ordering::SeqCst);
             self.toasts
-                .warning("Window hiding is currently not supported.\nSee https://github.com/4JX/L5P-Keyboard-RGB/issues/181")
+                .warning(t!("app.hiding_unsupported"))
                 .duration(None);
         }
 
@@ -232,49 +899,381 @@ impl eframe::App for App {
             self.exit_app();
         }
 
-        if !self.instance_not_unique && self.manager.is_none() && modals::manager_error(ctx) {
+        if !self.instance_not_unique && self.manager.is_none() && self.pending_startup.is_none() && modals::manager_error(ctx) {
             self.exit_app();
         }
 
-        TopBottomPanel::top("top-panel").show(ctx, |ui| {
-            self.menu_bar
-                .show(ctx, ui, &mut self.current_profile, &mut self.loaded_effect, &mut self.state_changed, &mut self.toasts);
+        let picked_device = TopBottomPanel::top("top-panel")
+            .show(ctx, |ui| {
+                self.menu_bar
+                    .show(ctx, ui, &mut self.current_profile, &mut self.loaded_effect, &mut self.state_changed, &mut self.toasts);
+                self.manager.as_ref().and_then(|manager| device_selector::show(ui, manager))
+            })
+            .inner;
+        if let Some(id) = picked_device {
+            self.select_device(id);
+        }
+
+        if favorites::favorites(&self.saved_items.profiles).next().is_some() {
+            let picked = TopBottomPanel::top("favorites-bar")
+                .show(ctx, |ui| favorites::show(ctx, ui, &self.saved_items.profiles, &self.current_profile))
+                .inner;
+            if let Some(name) = picked {
+                self.load_profile(&name);
+            }
+        }
+
+        if self.safe_mode {
+            if let Some(action) = safe_mode::show_banner(ctx) {
+                self.leave_safe_mode(action);
+            }
+        }
+
+        TopBottomPanel::bottom("status-bar").show(ctx, |ui| {
+            // CPU usage is only measured while a built-in effect is running, custom effects aren't part of the profile
+            let effect_thread = self.manager.as_ref().and_then(|manager| manager.effect_thread_id()).filter(|_| self.loaded_effect.is_none());
+            let cpu_usage = self.cpu_monitor.usage(effect_thread);
+            let estimate = power::estimate(&self.current_profile, cpu_usage);
+
+            ui.horizontal(|ui| {
+                if ui.selectable_label(self.lights_off, t!("app.lights_off")).on_hover_text(t!("app.lights_off_hint")).clicked() {
+                    self.toggle_lights();
+                }
+                let response = ui
+                    .add(Slider::new(&mut self.preferences.backlight.scale, backlight::MIN_SCALE..=100).suffix("%").text(t!("app.brightness_scale")))
+                    .on_hover_text(t!("app.brightness_scale_hint"));
+                if response.changed() {
+                    self.apply_brightness_scale();
+                }
+                ui.label(t!("app.power_impact", estimate = i18n::power_estimate(&estimate))).on_hover_text(t!(
+                    "app.power_breakdown",
+                    led = format!("{:.1}", estimate.led_watts),
+                    cpu = format!("{:.1}", estimate.cpu_watts)
+                ));
+            });
+
+            // Nothing to keep up with unless there's a thread being measured
+            if self.cpu_monitor.is_measuring() && self.is_on_screen(ctx) {
+                ctx.request_repaint_after(ticks::until_next(power::CPU_REFRESH_INTERVAL));
+            }
         });
 
+        TopBottomPanel::bottom("preview-panel")
+            // Kept dark in every theme so the lit keys stand out
+            .frame(Frame::none().inner_margin(self.theme.spacing.medium).fill(Color32::from_gray(20)))
+            .show(ctx, |ui| {
+                // Show what the effect is actually outputting, the hardware animates some effects by itself though so those show the profile instead
+                let zone_colors = self
+                    .manager
+                    .as_ref()
+                    .and_then(|manager| manager.preview().zone_colors())
+                    .unwrap_or_else(|| self.current_profile.rgb_zones.map(|zone| if zone.enabled { zone.rgb } else { [0; 3] }));
+                self.preview_layout().show(ui, zone_colors);
+
+                // Software effects change the colors on their own, keep up with them
+                if (!self.current_profile.effect.is_built_in() || self.loaded_effect.is_playing()) && self.is_on_screen(ctx) {
+                    ctx.request_repaint_after(ticks::until_next(PREVIEW_REPAINT_INTERVAL));
+                }
+            });
+
         CentralPanel::default()
-            .frame(Frame::none().inner_margin(self.theme.spacing.large).fill(Color32::from_gray(26)))
+            .frame(Frame::none().inner_margin(self.theme.spacing.large).fill(style::panel_fill(&ctx.style().visuals)))
             .show(ctx, |ui| {
                 ui.style_mut().spacing.item_spacing = Vec2::splat(self.theme.spacing.large);
                 self.show_ui_elements(ctx, ui);
             });
 
+        self.palette_editor.show(ctx, &mut self.current_profile, &mut self.state_changed, &mut self.toasts);
+        let reduced_motion = self.preferences.reduced_motion;
+        let effect_speed = self.preferences.effect_speed;
+        let frame_rate = self.preferences.frame_rate;
+        let crossfade_ms = self.preferences.crossfade_ms;
+        let synced_lights = self.preferences.synced_lights.clone();
+        let hotkeys = self.preferences.hotkeys.clone();
+        let backlight = self.preferences.backlight;
+        let notifications = self.preferences.notifications;
+        let weather = self.preferences.weather.clone();
+        let wallpaper = self.preferences.wallpaper;
+        let exit = self.preferences.exit;
+        let overlays = self.preferences.overlays;
+        let chroma = self.preferences.chroma;
+        let room_sync = self.preferences.room_sync.clone();
+        let api = self.preferences.api.clone();
+        let offline = self.preferences.offline;
+        let shuffle = self.preferences.shuffle;
+        let appearance = self.preferences.appearance;
+        let language = self.preferences.language.clone();
+        preferences::show(ctx, &mut self.preferences_open, &mut self.preferences, &self.saved_items.profiles, &self.layouts);
+        if self.preferences.reduced_motion != reduced_motion {
+            self.apply_reduced_motion();
+            self.state_changed = true;
+        }
+        if self.preferences.effect_speed != effect_speed {
+            self.apply_effect_speed();
+        }
+        if self.preferences.frame_rate != frame_rate {
+            self.apply_frame_rate();
+            self.state_changed = true;
+        }
+        if self.preferences.crossfade_ms != crossfade_ms {
+            self.apply_crossfade();
+        }
+        if self.preferences.synced_lights != synced_lights {
+            self.apply_synced_lights();
+            self.state_changed = true;
+        }
+        if self.preferences.hotkeys != hotkeys {
+            self.apply_hotkeys();
+        }
+        if self.preferences.backlight != backlight {
+            self.start_backlight_watcher(ctx);
+        }
+        if self.preferences.notifications != notifications {
+            self.start_notification_listener(ctx);
+        }
+        if self.preferences.weather != weather {
+            self.apply_weather();
+            self.state_changed = true;
+        }
+        if self.preferences.wallpaper != wallpaper {
+            self.start_wallpaper_watcher(ctx);
+            if let Some(palette) = self.wallpaper_palette.take() {
+                self.follow_wallpaper(palette);
+            }
+        }
+        if self.preferences.exit != exit {
+            self.apply_exit_settings();
+        }
+        if self.preferences.overlays != overlays {
+            self.start_overlay_watchers(ctx);
+            self.apply_overlay();
+        }
+        if self.preferences.chroma != chroma {
+            self.apply_chroma(ctx);
+        }
+        if self.preferences.room_sync != room_sync {
+            self.apply_room_sync();
+        }
+        if self.preferences.api != api {
+            self.apply_api(ctx);
+        }
+        if self.preferences.offline != offline {
+            self.apply_offline();
+        }
+        if self.preferences.shuffle != shuffle {
+            self.apply_shuffle(ctx);
+        }
+        if self.preferences.appearance != appearance {
+            self.apply_appearance(ctx);
+        }
+        if self.preferences.language != language {
+            i18n::apply(self.preferences.language.as_deref());
+        }
+        let count_keystrokes = self.preferences.count_keystrokes;
+        statistics::show(ctx, &mut self.statistics_open, &mut self.usage, &mut self.preferences.count_keystrokes);
+        if self.preferences.count_keystrokes != count_keystrokes {
+            self.apply_keystroke_counting();
+        }
+        self.show_calibration(ctx);
+        let controllers_open = self.controller_panel.open;
+        if self.controller_panel.show(ctx, &mut self.preferences.controllers, &self.saved_items.profiles) || self.controller_panel.open != controllers_open {
+            self.apply_controllers(ctx);
+        }
+        self.show_focus_timer(ctx);
+        self.show_triggers(ctx);
+        self.show_gallery(ctx);
+        self.show_expression_editor(ctx);
+        if self.show_fps {
+            self.show_fps_overlay(ctx);
+        }
+        self.whats_new.show(ctx);
+        self.show_onboarding(ctx);
+        #[cfg(feature = "network")]
+        self.updates.show(ctx, &mut self.toasts);
+        #[cfg(feature = "network")]
+        self.show_community(ctx);
+
+        self.history.track(&self.current_profile);
+
         if self.state_changed {
             self.update_state();
         }
 
+        self.handle_minimize(ctx);
         self.handle_close_request(ctx);
     }
 
     fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
-        let SavedItems { profiles, custom_effects, .. } = self.saved_items.clone();
-
-        let mut settings = Settings::new(profiles, custom_effects, self.current_profile.clone());
-
-        settings.save();
+        if !self.safe_mode {
+            self.save_settings();
+        }
 
         self.visible.store(false, Ordering::SeqCst);
 
         if let Some(manager) = self.manager.take() {
-            manager.shutdown();
+            // Leave the keyboard alone if it was never touched
+            if self.untouched.is_some() {
+                manager.shutdown();
+            } else {
+                manager.exit();
+            }
         }
     }
 }
 
 impl App {
+    fn handle_message(&mut self, ctx: &Context, message: GuiMessage) {
+        match message {
+            GuiMessage::StartupFinished(result) => self.finish_startup(ctx, *result),
+            GuiMessage::CycleProfiles => {
+                self.cycle_profiles();
+                self.history.settle(&self.current_profile);
+            }
+            GuiMessage::ToggleLights => self.toggle_lights(),
+            GuiMessage::LoadProfile(name) => {
+                self.load_profile(&name);
+                self.history.settle(&self.current_profile);
+            }
+            #[cfg(feature = "network")]
+            GuiMessage::ApplyProfile(profile) => {
+                self.current_profile = profile;
+                self.loaded_effect.state = State::None;
+                self.state_changed = true;
+                self.history.settle(&self.current_profile);
+            }
+            GuiMessage::Notification => {
+                if self.preferences.notifications.enabled {
+                    if let Some(manager) = &self.manager {
+                        manager.flash(self.preferences.notifications.flash);
+                    }
+                }
+            }
+            GuiMessage::ForegroundChanged(window) => self.handle_foreground_change(window.as_ref()),
+            GuiMessage::Chroma(event) => self.handle_chroma(event),
+            GuiMessage::ControlPressed(control) => self.handle_control(control),
+            GuiMessage::Pomodoro(command) => self.handle_pomodoro(command),
+            GuiMessage::ShuffleDue => self.shuffle_profile(),
+            #[cfg(feature = "audio")]
+            GuiMessage::MicMuted(muted) => self.update_system_state(|state| state.mic_muted = Some(muted)),
+            GuiMessage::LockKeys(lock_keys) => self.update_system_state(|state| state.lock_keys = lock_keys),
+            GuiMessage::Battery(battery) => self.update_system_state(|state| state.battery = Some(battery)),
+            GuiMessage::StepBrightness(steps) => {
+                self.preferences.backlight.step_scale(steps);
+                self.apply_brightness_scale();
+            }
+            GuiMessage::LaptopBacklight(level) => self.follow_laptop_backlight(level),
+            GuiMessage::Wallpaper(palette) => self.follow_wallpaper(palette),
+            GuiMessage::PickedColor(zone, pick) => self.apply_picked_color(zone, pick),
+            GuiMessage::DecodedImage(decoded) => {
+                self.palette_editor.handle_decoded(decoded, &mut self.current_profile, &mut self.state_changed, &mut self.toasts);
+            }
+            #[cfg(feature = "network")]
+            GuiMessage::Trigger(name) => self.fire_trigger(&name),
+            GuiMessage::SettingsChanged => self.reload_settings(ctx),
+            GuiMessage::LibraryChanged(library) => self.library = library,
+            GuiMessage::OpenPaletteEditor => self.palette_editor.open = true,
+            GuiMessage::OpenPreferences => self.preferences_open = true,
+            GuiMessage::OpenStatistics => self.statistics_open = true,
+            GuiMessage::OpenCalibration => self.calibration_open = true,
+            GuiMessage::OpenFocusTimer => self.focus_timer_open = true,
+            GuiMessage::OpenTriggers => self.triggers_open = true,
+            GuiMessage::OpenExpressionEditor => self.expression_editor.open = true,
+            GuiMessage::OpenGallery => {
+                // Recorded again every time, to show the colors the profile has now
+                self.gallery.record(&self.current_profile);
+                self.gallery_open = true;
+            }
+            GuiMessage::OpenControllers => {
+                // Controls can only be learned while they're being read
+                self.controller_panel.open = true;
+                self.apply_controllers(ctx);
+            }
+            GuiMessage::OpenWhatsNew => self.whats_new.open = true,
+            GuiMessage::OpenOnboarding => self.onboarding.restart(),
+            #[cfg(feature = "network")]
+            GuiMessage::CheckForUpdates => {
+                if !self.preferences.offline {
+                    self.updates.check(ctx, true);
+                } else {
+                    self.toasts.info(t!("updates.disabled")).duration(Some(Duration::from_millis(5000))).closable(true);
+                }
+            }
+            #[cfg(feature = "network")]
+            GuiMessage::Update(event) => self.updates.handle(event, &mut self.toasts),
+            #[cfg(feature = "network")]
+            GuiMessage::OpenCommunity => {
+                if self.preferences.offline {
+                    self.toasts.info(t!("community.offline")).duration(Some(Duration::from_millis(5000))).closable(true);
+                } else if !self.preferences.community.has_index() {
+                    self.toasts.info(t!("community.no_index")).duration(Some(Duration::from_millis(5000))).closable(true);
+                } else {
+                    self.community.open(ctx, &self.preferences.community.index_url);
+                }
+            }
+            #[cfg(feature = "network")]
+            GuiMessage::Community(event) => self.community.handle(ctx, event, &mut self.toasts),
+            GuiMessage::ToggleFpsOverlay => self.show_fps = !self.show_fps,
+            GuiMessage::Undo => self.undo(),
+            GuiMessage::Redo => self.redo(),
+            GuiMessage::Quit => self.exit_app(),
+        }
+    }
+
+    /// Whether there's anyone to keep the window up to date for, periodic repaints are skipped otherwise and the
+    /// background threads wake the GUI up when they need it
+    fn is_on_screen(&self, ctx: &Context) -> bool {
+        let minimized = ctx.input(|i| i.viewport().minimized).unwrap_or(false);
+
+        !minimized && self.visible.load(Ordering::SeqCst)
+    }
+
+    fn save_settings(&self) {
+        let SavedItems { profiles, custom_effects, .. } = self.saved_items.clone();
+
+        // Don't persist the temporary profile set by a rule as the current one
+        let current_profile = self.rule_restore.clone().unwrap_or_else(|| self.current_profile.clone());
+
+        let mut settings = Settings::new(
+            profiles,
+            custom_effects,
+            current_profile,
+            self.palette_editor.palettes.clone(),
+            self.effect_options.clone(),
+            self.preferences.clone(),
+            self.usage.snapshot(),
+        );
+        settings.swatches = self.swatch_panel.swatches.clone();
+        settings.selected_device.clone_from(&self.current_device);
+        settings.device_profiles.clone_from(&self.device_profiles);
+        settings.calibrations.clone_from(&self.calibrations);
+
+        if let Err(err) = settings.save() {
+            error!("{err:?}");
+        }
+    }
+
     fn configure_style(&self, ctx: &Context) {
-        let style = Style {
+        for (theme, visuals) in [(eframe::egui::Theme::Dark, &self.theme.dark), (eframe::egui::Theme::Light, &self.theme.light)] {
+            ctx.set_style_of(theme, Self::style_with(visuals.clone()));
+        }
+
+        ctx.set_theme(match self.preferences.appearance.theme {
+            ThemeMode::Dark => ThemePreference::Dark,
+            ThemeMode::Light => ThemePreference::Light,
+            ThemeMode::System => ThemePreference::System,
+        });
+    }
+
+    /// Rebuild the styles after the appearance settings changed
+    fn apply_appearance(&mut self, ctx: &Context) {
+        self.theme = Theme::new(&self.preferences.appearance);
+        self.configure_style(ctx);
+    }
+
+    fn style_with(visuals: Visuals) -> Style {
+        Style {
             // text_styles: text_utils::default_text_styles(),
-            visuals: self.theme.visuals.clone(),
+            visuals,
             #[cfg(debug_assertions)]
             debug: DebugOptions {
                 debug_on_hover: false,
@@ -288,10 +1287,7 @@ impl App {
                 show_unaligned: false,
             },
             ..Style::default()
-        };
-
-        // ctx.set_fonts(text_utils::get_font_def());
-        ctx.set_style(style);
+        }
     }
 
     fn exit_app(&mut self) {
@@ -302,64 +1298,882 @@ impl App {
         process::exit(0);
     }
 
-    fn cycle_profiles(&mut self) {
-        let len = self.saved_items.profiles.len();
+    /// Fix the settings as asked and start again outside of safe mode
+    fn leave_safe_mode(&mut self, action: SafeModeAction) {
+        match action {
+            SafeModeAction::Repair => match Settings::repair() {
+                Ok(dropped) => info!("Repaired the settings, {dropped} values could not be read and were removed"),
+                Err(err) => {
+                    error!("{err:?}");
+                    self.toasts
+                        .error(t!("safe_mode.repair_failed"))
+                        .duration(Some(Duration::from_millis(5000)))
+                        .closable(true);
+                    return;
+                }
+            },
+            SafeModeAction::Reset => {
+                if let Err(err) = Settings::reset() {
+                    error!("{err:?}");
+                    self.toasts.error(t!("safe_mode.reset_failed")).duration(Some(Duration::from_millis(5000))).closable(true);
+                    return;
+                }
+            }
+            SafeModeAction::Restart => {}
+        }
 
-        let current_profile_name = &self.current_profile.name;
+        if let Err(err) = safe_mode::restart() {
+            error!("{err:?}");
+            self.toasts
+                .error(t!("safe_mode.restart_failed"))
+                .duration(Some(Duration::from_millis(5000)))
+                .closable(true);
+            return;
+        }
 
-        if let Some((i, _)) = self.saved_items.profiles.iter().enumerate().find(|(_, profile)| &profile.name == current_profile_name) {
-            if i == len - 1 && len > 0 {
-                self.current_profile = self.saved_items.profiles[0].clone();
-            } else {
-                self.current_profile = self.saved_items.profiles[i + 1].clone();
+        self.exit_app();
+    }
+
+    /// Pick up the saved items and preferences from the settings file, leaving what's currently playing alone
+    fn reload_settings(&mut self, ctx: &Context) {
+        match Settings::read() {
+            Ok(settings) => {
+                self.saved_items.profiles = settings.profiles;
+                self.saved_items.custom_effects = settings.effects;
+                self.palette_editor.palettes = settings.palettes;
+                self.swatch_panel.swatches = settings.swatches;
+                self.effect_options = settings.effect_options;
+                self.preferences = settings.preferences;
+                self.apply_reduced_motion();
+                self.apply_offline();
+                self.apply_keystroke_counting();
+                self.apply_hotkeys();
+                self.apply_brightness_scale();
+                self.start_backlight_watcher(ctx);
+                self.start_notification_listener(ctx);
+                self.start_wallpaper_watcher(ctx);
+                self.apply_shuffle(ctx);
+                self.apply_effect_speed();
+                self.apply_frame_rate();
+                self.apply_crossfade();
+                self.apply_synced_lights();
+                self.apply_weather();
+                self.apply_exit_settings();
+                self.apply_chroma(ctx);
+                self.apply_room_sync();
+                self.apply_api(ctx);
+                self.apply_triggers();
+                self.start_overlay_watchers(ctx);
+                self.apply_controllers(ctx);
+                self.calibrations = settings.calibrations;
+                self.apply_calibrations();
+                self.state_changed = true;
+                self.apply_appearance(ctx);
+                i18n::apply(self.preferences.language.as_deref());
+
+                self.toasts.info(t!("app.settings_reloaded")).duration(Some(Duration::from_millis(3000)));
             }
+            Err(_) => {
+                self.toasts
+                    .error(t!("app.settings_unreadable"))
+                    .duration(Some(Duration::from_millis(5000)))
+                    .closable(true);
+            }
+        }
+    }
 
-            self.state_changed = true;
+    fn apply_reduced_motion(&self) {
+        if let Some(manager) = &self.manager {
+            manager.set_reduced_motion(self.preferences.reduced_motion.is_active());
         }
     }
 
-    fn show_ui_elements(&mut self, ctx: &Context, ui: &mut eframe::egui::Ui) {
-        ui.with_layout(Layout::left_to_right(Align::Center).with_cross_justify(true), |ui| {
-            ui.vertical(|ui| {
-                let can_tweak_colors = self.current_profile.effect.takes_color_array() && self.loaded_effect.is_none();
+    fn apply_brightness_scale(&self) {
+        if let Some(manager) = &self.manager {
+            manager.set_brightness_scale(self.preferences.backlight.scale);
+        }
+    }
 
-                let res = ui.add_enabled_ui(can_tweak_colors, |ui| {
-                    ui.style_mut().spacing.item_spacing.y = self.theme.spacing.medium;
-                    let response = ui.horizontal(|ui| {
-                        ui.style_mut().spacing.interact_size = Vec2::splat(60.0);
-                        for i in 0..4 {
-                            self.state_changed |= ui.color_edit_button_srgb(&mut self.current_profile.rgb_zones[i].rgb).changed();
-                        }
-                    });
+    fn apply_hotkeys(&self) {
+        hotkeys::set_bindings(&self.preferences.hotkeys);
+    }
 
-                    ui.style_mut().spacing.interact_size = Vec2::new(response.response.rect.width(), 30.0);
-                    if ui.color_edit_button_srgb(&mut self.global_rgb).changed() {
-                        for i in 0..4 {
-                            self.current_profile.rgb_zones[i].rgb = self.global_rgb;
-                        }
-                        self.state_changed = true;
-                    }
+    fn apply_keystroke_counting(&self) {
+        keystrokes::set_counting(self.preferences.count_keystrokes);
+    }
+
+    fn apply_effect_speed(&self) {
+        if let Some(manager) = &self.manager {
+            manager.set_speed_multiplier(self.preferences.effect_speed);
+        }
+    }
+
+    fn apply_frame_rate(&self) {
+        if let Some(manager) = &self.manager {
+            manager.set_frame_rate(self.preferences.frame_rate);
+        }
+    }
+
+    fn apply_synced_lights(&self) {
+        if let Some(manager) = &self.manager {
+            manager.set_synced_lights(&self.preferences.synced_lights);
+        }
+    }
+
+    fn apply_offline(&self) {
+        #[cfg(feature = "network")]
+        crate::util::set_online(!self.preferences.offline);
+    }
+
+    fn apply_weather(&self) {
+        if let Some(manager) = &self.manager {
+            manager.set_weather(&self.preferences.weather);
+        }
+    }
+
+    fn apply_exit_settings(&self) {
+        if let Some(manager) = &self.manager {
+            manager.set_exit_settings(self.preferences.exit);
+        }
+    }
+
+    /// Take in what a watcher reported, showing what the overlays make of it
+    fn update_system_state(&mut self, update: impl FnOnce(&mut SystemState)) {
+        update(&mut self.system_state);
+        self.apply_overlay();
+    }
+
+    /// What the overlays show over the current profile
+    fn overlay(&self) -> Overlay {
+        if self.lights_off {
+            Overlay::default()
+        } else {
+            self.preferences.overlays.overlay(&self.system_state, &self.current_profile)
+        }
+    }
+
+    /// Show what the overlays make of the system over the effect playing, without restarting it unless a zone starts or
+    /// stops pulsing
+    fn apply_overlay(&mut self) {
+        // The overlays wait along with the profile while the keyboard is left as it was
+        if self.untouched.is_some() {
+            return;
+        }
+
+        let overlay = self.overlay();
+        if self.manager.as_ref().is_some_and(|manager| manager.set_overlay(overlay)) {
+            self.state_changed = true;
+        }
+    }
+
+    /// Watch what the enabled overlays show, the microphone only while its overlay is enabled and the rest once started
+    fn start_overlay_watchers(&mut self, ctx: &Context) {
+        if self.manager.is_none() || self.safe_mode {
+            return;
+        }
+
+        #[cfg(feature = "audio")]
+        if !self.preferences.overlays.mic.enabled {
+            self.mic_watcher = None;
+        } else if self.mic_watcher.is_none() {
+            let ctx = ctx.clone();
+            let gui_tx = self.gui_tx.clone();
+            self.mic_watcher = Some(mic::spawn_watcher(move |muted| {
+                let _ = gui_tx.send(GuiMessage::MicMuted(muted));
+                ctx.request_repaint();
+            }));
+        }
+
+        if self.preferences.overlays.any_lock_enabled() {
+            let ctx = ctx.clone();
+            let gui_tx = self.gui_tx.clone();
+            lock_keys::spawn_watcher(move |state| {
+                let _ = gui_tx.send(GuiMessage::LockKeys(state));
+                ctx.request_repaint();
+            });
+        }
+
+        if self.preferences.overlays.battery.enabled {
+            let ctx = ctx.clone();
+            let gui_tx = self.gui_tx.clone();
+            battery::spawn_watcher(move |state| {
+                let _ = gui_tx.send(GuiMessage::Battery(state));
+                ctx.request_repaint();
+            });
+        }
+    }
+
+    fn start_backlight_watcher(&mut self, ctx: &Context) {
+        if self.manager.is_none() || self.safe_mode || !self.preferences.backlight.follow_laptop_key {
+            self.backlight_watcher = None;
+            return;
+        }
+        if self.backlight_watcher.is_some() {
+            return;
+        }
+
+        let ctx = ctx.clone();
+        let gui_tx = self.gui_tx.clone();
+        self.backlight_watcher = Some(backlight::spawn_watcher(move |level| {
+            let _ = gui_tx.send(GuiMessage::LaptopBacklight(level));
+            ctx.request_repaint();
+        }));
+    }
+
+    fn start_notification_listener(&self, ctx: &Context) {
+        if self.manager.is_none() || self.safe_mode || !self.preferences.notifications.enabled {
+            return;
+        }
+
+        let ctx = ctx.clone();
+        let gui_tx = self.gui_tx.clone();
+        notifications::spawn_listener(move || {
+            let _ = gui_tx.send(GuiMessage::Notification);
+            ctx.request_repaint();
+        });
+    }
+
+    fn start_wallpaper_watcher(&mut self, ctx: &Context) {
+        if self.safe_mode || !self.preferences.wallpaper.follow {
+            self.wallpaper_watcher = None;
+            return;
+        }
+        if self.wallpaper_watcher.is_some() {
+            return;
+        }
+
+        let ctx = ctx.clone();
+        let gui_tx = self.gui_tx.clone();
+        // Decoded on the thread of the watcher, the GUI only gets the colors
+        self.wallpaper_watcher = Some(wallpaper::spawn_watcher(move |path| match Palette::from_image(String::new(), &path, 4) {
+            Ok(palette) => {
+                let _ = gui_tx.send(GuiMessage::Wallpaper(palette));
+                ctx.request_repaint();
+            }
+            Err(err) => tracing::warn!("Could not take the colors of the wallpaper at {}: {err:?}", path.display()),
+        }));
+    }
+
+    fn apply_picked_color(&mut self, zone: usize, pick: Pick) {
+        self.color_editor.finish_pick();
+
+        match pick {
+            Pick::Color(rgb) => {
+                self.current_profile.rgb_zones[zone].rgb = rgb;
+                self.untouched = None;
+                self.state_changed = true;
+            }
+            Pick::Cancelled => {}
+            Pick::Failed => {
+                self.toasts.error(t!("color_editor.pick_failed")).duration(Some(Duration::from_millis(5000))).closable(true);
+            }
+        }
+    }
+
+    /// Color the zones like the wallpaper, remembering the colors for when following it gets turned on
+    fn follow_wallpaper(&mut self, palette: Palette) {
+        if self.preferences.wallpaper.follow {
+            palette_editor::apply(&palette, &mut self.current_profile);
+            self.untouched = None;
+            self.state_changed = true;
+        }
+        self.wallpaper_palette = Some(palette);
+    }
+
+    /// Go along with the level picked through Fn+Space, which the keyboard is already showing by now
+    fn follow_laptop_backlight(&mut self, level: u8) {
+        if !self.preferences.backlight.follow_laptop_key {
+            return;
+        }
+
+        match level {
+            0 => self.set_lights_off(true),
+            level => {
+                self.set_lights_off(false);
+                self.current_profile.brightness = if level == 1 { Brightness::Low } else { Brightness::High };
+            }
+        }
+        self.state_changed = true;
+    }
+
+    fn apply_room_sync(&self) {
+        #[cfg(feature = "network")]
+        if let Some(room_sync) = &self.room_sync {
+            room_sync.set_settings(self.preferences.room_sync.clone());
+        }
+    }
+
+    fn apply_triggers(&self) {
+        #[cfg(feature = "network")]
+        if let Some(trigger_poller) = &self.trigger_poller {
+            trigger_poller.set_triggers(self.preferences.triggers.clone());
+        }
+    }
+
+    /// Start or stop serving the HTTP API, restarting it for the new address and token
+    fn apply_api(&mut self, ctx: &Context) {
+        #[cfg(feature = "network")]
+        {
+            // The old server has to let go of the port before it can be bound again
+            self.api_server = None;
+
+            let settings = &self.preferences.api;
+            let Some(manager) = self.manager.as_ref().filter(|_| settings.enabled && !self.safe_mode) else {
+                return;
+            };
+
+            let ctx = ctx.clone();
+            let gui_tx = self.gui_tx.clone();
+            let server = ApiServer::start(settings, manager.preview().clone(), move |command| {
+                let _ = gui_tx.send(match command {
+                    ApiCommand::LoadProfile(name) => GuiMessage::LoadProfile(name),
+                    ApiCommand::Apply(profile) => GuiMessage::ApplyProfile(profile),
+                    ApiCommand::Trigger(name) => GuiMessage::Trigger(name),
+                });
+                ctx.request_repaint();
+            });
+
+            match server {
+                Ok(server) => self.api_server = Some(server),
+                Err(err) => {
+                    tracing::warn!("{err:?}");
+                    self.toasts.error(t!("preferences.api_failed")).duration(Some(Duration::from_millis(5000))).closable(true);
+                }
+            }
+        }
+        #[cfg(not(feature = "network"))]
+        let _ = ctx;
+    }
+
+    fn apply_calibrations(&self) {
+        if let Some(manager) = &self.manager {
+            manager.set_calibrations(&self.calibrations);
+        }
+    }
+
+    /// Which step of identifying the zones is being played, from 0 to 3 for each zone and 4 for all of them
+    fn identifying_step(&self) -> Option<usize> {
+        let step = self.identify_started?.elapsed().as_millis() / flash::IDENTIFY_STEP.as_millis();
+        (step < u128::from(flash::IDENTIFY_STEPS)).then_some(step as usize)
+    }
+
+    fn show_onboarding(&mut self, ctx: &Context) {
+        if !self.onboarding.open {
+            return;
+        }
+
+        let keyboard = self.manager.as_ref().and_then(DeviceRegistry::selected).map(|device| device.model.name);
+        match self.onboarding.show(ctx, keyboard, &mut self.preferences, &self.saved_items.profiles) {
+            Some(OnboardingAction::FlashZone(zone)) => {
+                if let Some(manager) = &mut self.manager {
+                    let pattern = FlashPattern {
+                        rgb: flash::ZONE_COLORS[zone],
+                        count: 3,
+                        interval: 250,
+                    };
+                    manager.flash_zone(zone, pattern);
+                }
+            }
+            Some(OnboardingAction::CreateProfile(profile)) => {
+                self.saved_items.profiles.push(profile.clone());
+                self.current_profile = profile;
+                self.loaded_effect.state = State::None;
+                self.state_changed = true;
+            }
+            None => {}
+        }
+    }
+
+    fn show_gallery(&mut self, ctx: &Context) {
+        if !self.gallery_open {
+            return;
+        }
+
+        let mut open = self.gallery_open;
+        let picked = self.gallery.show(ctx, &mut open, self.preview_layout());
+        self.gallery_open = open;
+
+        if let Some(effect) = picked {
+            if self.current_profile.effect != effect {
+                self.switch_effect(effect);
+            }
+            self.loaded_effect.state = State::None;
+            self.state_changed = true;
+        }
+    }
+
+    #[cfg(feature = "network")]
+    fn show_community(&mut self, ctx: &Context) {
+        if !self.community.open {
+            return;
+        }
+
+        let layout = self.preview_layout_index();
+        let Some(effect) = self.community.show(ctx, &self.layouts[layout], &self.preferences.community.index_url) else {
+            return;
+        };
+
+        self.install_effect(&effect);
+    }
+
+    fn show_expression_editor(&mut self, ctx: &Context) {
+        if !self.expression_editor.open {
+            return;
+        }
+
+        let layout = self.preview_layout_index();
+        match self.expression_editor.show(ctx, &self.layouts[layout]) {
+            Some(ExpressionAction::Play(effect)) => {
+                self.loaded_effect = LoadedEffect::queued(effect);
+                self.state_changed = true;
+            }
+            Some(ExpressionAction::Save(effect)) => self.install_effect(&effect),
+            None => {}
+        }
+    }
+
+    /// Add an effect to the effect library, telling how that went
+    fn install_effect(&mut self, effect: &CustomEffect) {
+        let name = effect.name.clone().unwrap_or_default();
+        match effect_library::install(effect) {
+            Ok(installed) => {
+                // Shown right away, even when the folder isn't being watched
+                self.library = effect_library::scan();
+                self.toasts.success(t!("app.effect_installed", name = installed.name())).duration(Some(Duration::from_millis(5000))).closable(true);
+            }
+            Err(err) => {
+                error!("Could not install {name}: {err:?}");
+                self.toasts.error(t!("app.effect_install_failed", name = name)).duration(Some(Duration::from_millis(5000))).closable(true);
+            }
+        }
+    }
+
+    /// Calibrate the keyboard being shown, replaying the profile so the change can be seen right away
+    fn show_calibration(&mut self, ctx: &Context) {
+        if !self.calibration_open {
+            return;
+        }
+        let Some(device) = self.manager.as_ref().and_then(DeviceRegistry::selected).cloned() else {
+            return;
+        };
+
+        let mut calibration = self.calibrations.get(&device.id()).copied().unwrap_or_default();
+        if calibration::show(ctx, &mut self.calibration_open, Some(device.model.name), &mut calibration) {
+            if calibration.is_identity() {
+                self.calibrations.remove(&device.id());
+            } else {
+                self.calibrations.insert(device.id(), calibration);
+            }
+            self.apply_calibrations();
+            self.state_changed = true;
+        }
+    }
+
+    fn apply_crossfade(&self) {
+        if let Some(manager) = &self.manager {
+            manager.set_crossfade(Duration::from_millis(self.preferences.crossfade_ms.into()));
+        }
+    }
+
+    /// Measured rate at which the shown keyboard is being updated, for checking the frame rate limit, along with how often the
+    /// process wakes up
+    fn show_fps_overlay(&mut self, ctx: &Context) {
+        let fps = self.manager.as_ref().map_or(0, |manager| manager.preview().fps());
+        let wakeups = self.wakeups.rate();
+
+        Area::new(Id::new("fps-overlay"))
+            .anchor(Align2::RIGHT_BOTTOM, Vec2::new(-self.theme.spacing.large, -self.theme.spacing.large))
+            .interactable(false)
+            .show(ctx, |ui| {
+                Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.monospace(t!("debug.fps", fps = fps));
+                    ui.monospace(t!("debug.wakeups", wakeups = format!("{wakeups:.1}")));
+                });
+            });
+
+        // Keep the numbers current only while they can be seen
+        if self.is_on_screen(ctx) {
+            ctx.request_repaint_after(ticks::until_next(Duration::from_millis(500)));
+        }
+    }
+
+    /// Publish the health of the effect engine for the tray
+    fn update_health(&mut self) {
+        self.health.update(|health| {
+            // A disconnected keyboard can come back, other problems don't fix themselves
+            if matches!(*health, Health::Normal | Health::Disconnected) {
+                *health = match &self.manager {
+                    Some(manager) => manager.health(),
+                    None if self.instance_not_unique || self.pending_startup.is_some() => Health::Normal,
+                    None => Health::Disconnected,
+                };
+            }
+        });
+
+        // The Linux tray gets told about changes by itself
+        #[cfg(all(not(target_os = "linux"), feature = "tray"))]
+        if let Some(tray) = &mut self.tray {
+            tray.show_health(&self.health.get());
+        }
+    }
+
+    /// Keep the rules and stats that refer to a profile by name pointing at it after it was renamed
+    fn rename_profile_references(&mut self, renamed: &ProfileRenamed) {
+        self.preferences.rename_profile(&renamed.old, &renamed.new);
+        self.usage.rename_profile(&renamed.old, &renamed.new);
+
+        if let Some(profile) = &mut self.rule_restore {
+            if profile.name.as_deref() == Some(renamed.old.as_str()) {
+                profile.name = Some(renamed.new.clone());
+            }
+        }
+        if self.rule_profile.as_deref() == Some(renamed.old.as_str()) {
+            self.rule_profile = Some(renamed.new.clone());
+        }
+    }
+
+    fn undo(&mut self) {
+        if self.history.undo(&mut self.current_profile) {
+            self.state_changed = true;
+        }
+    }
+
+    fn redo(&mut self) {
+        if self.history.redo(&mut self.current_profile) {
+            self.state_changed = true;
+        }
+    }
+
+    fn toggle_lights(&mut self) {
+        self.set_lights_off(!self.lights_off);
+        self.state_changed = true;
+    }
+
+    /// Turn the lights off, or back on to whatever should be playing
+    fn set_lights_off(&mut self, off: bool) {
+        if self.lights_off && !off && self.loaded_effect.is_playing() {
+            self.loaded_effect.state = State::Queued;
+        }
+        self.lights_off = off;
+    }
+
+    fn cycle_profiles(&mut self) {
+        let len = self.saved_items.profiles.len();
+
+        let current_profile_name = &self.current_profile.name;
+
+        if let Some((i, _)) = self.saved_items.profiles.iter().enumerate().find(|(_, profile)| &profile.name == current_profile_name) {
+            if i == len - 1 && len > 0 {
+                self.current_profile = self.saved_items.profiles[0].clone();
+            } else {
+                self.current_profile = self.saved_items.profiles[i + 1].clone();
+            }
+
+            self.state_changed = true;
+        }
+    }
+
+    fn load_profile(&mut self, name: &str) {
+        if let Some(profile) = self.saved_items.profiles.iter().find(|profile| profile.name.as_deref() == Some(name)) {
+            self.current_profile = profile.clone();
+            self.loaded_effect.state = State::None;
+            self.untouched = None;
+            self.state_changed = true;
+        }
+    }
+
+    /// The layout chosen by the user, otherwise the one matching the detected keyboard
+    fn preview_layout(&self) -> &KeyboardLayout {
+        &self.layouts[self.preview_layout_index()]
+    }
+
+    /// Where [`Self::preview_layout`] is in the list, for when the layouts are borrowed apart from the rest of the app
+    fn preview_layout_index(&self) -> usize {
+        let chosen = self.preferences.preview_layout.as_ref().and_then(|name| self.layouts.iter().position(|layout| &layout.name == name));
+        let detected = || self.product_id.and_then(|id| keyboard_preview::detect(&self.layouts, id));
+
+        chosen.or_else(detected).unwrap_or(0)
+    }
+
+    /// Listen for the lighting games broadcast through Chroma, which then gets shown through [`Self::chroma_profile`]
+    fn start_chroma(&self, ctx: &Context) {
+        if self.manager.is_none() || self.safe_mode {
+            return;
+        }
+
+        let ctx = ctx.clone();
+        let gui_tx = self.gui_tx.clone();
+        chroma::start_receiver(move |event| {
+            let _ = gui_tx.send(GuiMessage::Chroma(event));
+            ctx.request_repaint();
+        });
+    }
+
+    fn apply_chroma(&mut self, ctx: &Context) {
+        if self.preferences.chroma.enabled {
+            self.start_chroma(ctx);
+        } else {
+            self.set_chroma_colors(None);
+        }
+        self.state_changed = true;
+    }
+
+    fn handle_chroma(&mut self, event: ChromaEvent) {
+        if !self.preferences.chroma.enabled {
+            return;
+        }
+
+        self.set_chroma_colors(match event {
+            ChromaEvent::Colors(colors) => Some(colors),
+            ChromaEvent::Ended => None,
+        });
+    }
+
+    fn set_chroma_colors(&mut self, colors: Option<[[u8; 3]; 4]>) {
+        if colors != self.chroma_colors {
+            // A custom effect the game took over from has to be played again once it stops
+            if colors.is_none() && matches!(self.loaded_effect.state, State::Playing) {
+                self.loaded_effect.state = State::Queued;
+            }
+            self.chroma_colors = colors;
+            self.state_changed = true;
+        }
+    }
+
+    /// What a game is broadcasting as a static profile, unless the profile of an app rule takes priority over it
+    fn chroma_profile(&self) -> Option<Profile> {
+        let colors = self.chroma_colors?;
+        if self.preferences.chroma.priority == ChromaPriority::AppRules && self.rule_restore.is_some() {
+            return None;
+        }
+
+        Some(Profile {
+            rgb_zones: profile::arr_to_zones(colors.concat().try_into().unwrap()),
+            brightness: self.current_profile.brightness,
+            ..Profile::default()
+        })
+    }
+
+    fn show_focus_timer(&mut self, ctx: &Context) {
+        let settings = self.preferences.pomodoro;
+        if let Some(command) = focus_timer::show(ctx, &mut self.focus_timer_open, &self.pomodoro, &mut self.preferences.pomodoro) {
+            self.handle_pomodoro(command);
+        }
+        if self.preferences.pomodoro != settings && self.pomodoro.is_active() {
+            self.state_changed = true;
+        }
+
+        if self.pomodoro.tick(&self.preferences.pomodoro) {
+            self.state_changed = true;
+        }
+        // Keep counting down while nothing else happens, even with the window hidden
+        if self.pomodoro.is_active() {
+            ctx.request_repaint_after(ticks::until_next(Duration::from_secs(1)));
+        }
+    }
+
+    fn apply_shuffle(&mut self, ctx: &Context) {
+        let ctx = ctx.clone();
+        let gui_tx = self.gui_tx.clone();
+        self.shuffle.apply(&self.preferences.shuffle, move || {
+            let _ = gui_tx.send(GuiMessage::ShuffleDue);
+            ctx.request_repaint();
+        });
+    }
+
+    /// Switch to a random saved profile now that the shuffle interval is up, unless an application rule is holding one
+    fn shuffle_profile(&mut self) {
+        if self.rule_restore.is_some() || self.lights_off {
+            return;
+        }
+
+        let picked = shuffle::pick(&self.saved_items.profiles, &self.current_profile, &self.preferences.shuffle, &mut rand::thread_rng());
+        if let Some(name) = picked.and_then(|profile| profile.name.clone()) {
+            self.load_profile(&name);
+            self.history.settle(&self.current_profile);
+        }
+    }
+
+    fn show_triggers(&mut self, ctx: &Context) {
+        let triggers = self.preferences.triggers.clone();
+        if let Some(name) = triggers_editor::show(ctx, &mut self.triggers_open, &mut self.preferences.triggers) {
+            self.fire_trigger(&name);
+        }
+        if self.preferences.triggers != triggers {
+            self.apply_triggers();
+        }
+    }
+
+    /// Flash the pattern of the trigger, the keyboard going back to what it was playing afterwards
+    fn fire_trigger(&self, name: &str) {
+        let Some(trigger) = self.preferences.triggers.iter().find(|trigger| trigger.name == name) else {
+            tracing::warn!("No trigger called {name}");
+            return;
+        };
+
+        if let Some(manager) = &self.manager {
+            manager.flash(trigger.pattern);
+        }
+    }
+
+    fn handle_pomodoro(&mut self, command: PomodoroCommand) {
+        self.pomodoro.handle(command, &self.preferences.pomodoro);
+
+        // A custom effect the timer took over from has to be played again once it stops
+        if !self.pomodoro.is_active() && matches!(self.loaded_effect.state, State::Playing) {
+            self.loaded_effect.state = State::Queued;
+        }
+        self.state_changed = true;
+    }
+
+    /// Read the Stream Decks and MIDI controllers while they're enabled or the panel is open to learn them, their presses
+    /// then go through [`Self::handle_control`]
+    fn apply_controllers(&mut self, ctx: &Context) {
+        // The old listener has to let go of the MIDI devices before they can be opened again
+        self.controller_listener = None;
+
+        let settings = &self.preferences.controllers;
+        if !(settings.enabled || self.controller_panel.open) || self.manager.is_none() || self.safe_mode {
+            return;
+        }
+
+        let ctx = ctx.clone();
+        let gui_tx = self.gui_tx.clone();
+        self.controller_listener = Some(controllers::spawn_listener(settings.midi_devices.clone(), move |control| {
+            let _ = gui_tx.send(GuiMessage::ControlPressed(control));
+            ctx.request_repaint();
+        }));
+    }
+
+    fn handle_control(&mut self, control: Control) {
+        if self.controller_panel.learn(control, &mut self.preferences.controllers) || !self.preferences.controllers.enabled {
+            return;
+        }
+
+        for action in self.preferences.controllers.actions_for(control) {
+            match action {
+                ControlAction::CycleProfiles => self.cycle_profiles(),
+                ControlAction::Profile(name) => self.load_profile(&name),
+                ControlAction::Brightness(brightness) => {
+                    self.current_profile.brightness = brightness;
+                    self.state_changed = true;
+                }
+                ControlAction::ToggleBrightness => {
+                    self.current_profile.brightness = match self.current_profile.brightness {
+                        Brightness::Low => Brightness::High,
+                        Brightness::High => Brightness::Low,
+                    };
+                    self.state_changed = true;
+                }
+            }
+        }
+        self.history.settle(&self.current_profile);
+    }
+
+    fn handle_foreground_change(&mut self, window: Option<&ForegroundWindow>) {
+        let name = app_rules::find_profile(&self.preferences.app_rules, &self.preferences.fullscreen, window);
+        // Titles change all the time while the same rule keeps matching
+        if name == self.rule_profile.as_deref() {
+            return;
+        }
+        self.rule_profile = name.map(ToOwned::to_owned);
+
+        let target = name.and_then(|name| self.saved_items.profiles.iter().find(|profile| profile.name.as_deref() == Some(name))).cloned();
+
+        if let Some(target) = target {
+            let previous = std::mem::replace(&mut self.current_profile, target);
+            // Keep the profile the user had before the first switch
+            self.rule_restore.get_or_insert(previous);
+        } else if let Some(profile) = self.rule_restore.take() {
+            self.current_profile = profile;
+        } else {
+            return;
+        }
+
+        self.loaded_effect.state = State::None;
+        self.state_changed = true;
+        self.history.settle(&self.current_profile);
+    }
+
+    fn show_ui_elements(&mut self, ctx: &Context, ui: &mut eframe::egui::Ui) {
+        ui.with_layout(Layout::left_to_right(Align::Center).with_cross_justify(true), |ui| {
+            ui.vertical(|ui| {
+                let can_tweak_colors = self.current_profile.effect.takes_color_array() && self.loaded_effect.is_none();
+                let identifying = self.identifying_step();
+
+                let res = ui.add_enabled_ui(can_tweak_colors, |ui| {
+                    ui.style_mut().spacing.item_spacing.y = self.theme.spacing.medium;
+                    let response = ui.horizontal(|ui| {
+                        ui.style_mut().spacing.interact_size = Vec2::splat(60.0);
+                        for i in 0..4 {
+                            let response = ui.color_edit_button_srgb(&mut self.current_profile.rgb_zones[i].rgb);
+                            accessibility::describe(&response, WidgetType::ColorButton, t!("accessibility.zone_color", zone = i + 1));
+                            self.state_changed |= response.changed();
+                            if let Some(step) = identifying {
+                                label_zone(ui, response.rect, i, step == i || step == 4);
+                            }
+                        }
+                    });
+
+                    ui.style_mut().spacing.interact_size = Vec2::new(response.response.rect.width(), 30.0);
+                    let response = ui.color_edit_button_srgb(&mut self.global_rgb);
+                    accessibility::describe(&response, WidgetType::ColorButton, t!("accessibility.all_zones_color"));
+                    if response.changed() {
+                        for i in 0..4 {
+                            self.current_profile.rgb_zones[i].rgb = self.global_rgb;
+                        }
+                        self.state_changed = true;
+                    }
+
+                    self.swatch_panel.show(ctx, ui, &mut self.current_profile, self.global_rgb, &mut self.state_changed, &mut self.toasts);
+                    self.color_editor.show(ui, &mut self.current_profile, &mut self.state_changed);
+                    gradient_editor::show(ui, &mut self.current_profile, &mut self.state_changed);
+                    self.palette_editor.show_presets(ui, &mut self.current_profile, &mut self.state_changed, &mut self.toasts);
 
                     response.response
                 });
 
                 ui.set_width(res.inner.rect.width());
 
+                ui.horizontal(|ui| {
+                    ui.add_enabled_ui(self.manager.is_some() && identifying.is_none(), |ui| {
+                        if ui.button(t!("app.identify_zones")).on_hover_text(t!("app.identify_zones_hint")).clicked() {
+                            if let Some(manager) = &mut self.manager {
+                                manager.identify_zones();
+                                self.identify_started = Some(Instant::now());
+                            }
+                        }
+                    });
+                    if ui.button(t!("app.surprise_me")).on_hover_text(t!("app.surprise_me_hint")).clicked() {
+                        self.current_profile = shuffle::surprise(&mut rand::thread_rng());
+                        self.loaded_effect.state = State::None;
+                        self.untouched = None;
+                        self.state_changed = true;
+                    }
+                });
+                if identifying.is_some() {
+                    ctx.request_repaint_after(ticks::until_next(Duration::from_millis(50)));
+                }
+
                 self.show_effect_ui(ui);
 
-                self.saved_items
+                let renamed = self
+                    .saved_items
                     .show(ctx, ui, &mut self.current_profile, &mut self.loaded_effect, &self.theme.spacing, &mut self.state_changed);
+                if let Some(renamed) = renamed {
+                    self.rename_profile_references(&renamed);
+                }
             });
 
             ui.vertical_centered_justified(|ui| {
-                if self.loaded_effect.is_playing() && ui.button("Stop custom effect").clicked() {
+                if self.loaded_effect.is_playing() && ui.button(t!("app.stop_custom_effect")).clicked() {
                     self.loaded_effect.state = State::None;
                     self.state_changed = true;
                 }
 
                 Frame {
                     rounding: Rounding::same(6.0),
-                    fill: Color32::from_gray(20),
+                    fill: style::inset_fill(ui.visuals()),
                     ..Frame::default()
                 }
                 .show(ui, |ui| {
@@ -367,12 +2181,37 @@ impl App {
                     ScrollArea::vertical().show(ui, |ui| {
                         ui.with_layout(Layout::top_down_justified(Align::Min), |ui| {
                             for val in Effects::iter() {
-                                let text: &'static str = val.into();
-                                if ui.selectable_value(&mut self.current_profile.effect, val, text).clicked() {
+                                let selected = self.current_profile.effect == val;
+                                let response = ui.selectable_label(selected, i18n::name_of("effects", val));
+                                if response.gained_focus() {
+                                    response.scroll_to_me(None);
+                                }
+                                if response.clicked() {
+                                    if selected {
+                                        self.current_profile.effect = val;
+                                    } else {
+                                        self.switch_effect(val);
+                                    }
                                     self.state_changed = true;
                                     self.loaded_effect.state = State::None;
                                 }
                             }
+
+                            if !self.library.is_empty() {
+                                ui.separator();
+                                ui.label(RichText::new(t!("app.effect_library")).small());
+                            }
+                            for entry in &self.library {
+                                let selected = !self.loaded_effect.is_none() && self.loaded_effect.effect == entry.effect;
+                                let mut response = ui.selectable_label(selected, entry.name());
+                                if entry.effect.author.is_some() || entry.effect.description.is_some() || entry.effect.thumbnail.is_some() {
+                                    response = response.on_hover_ui(|ui| saved_items::show_effect_details(ui, &entry.effect));
+                                }
+                                if response.clicked() {
+                                    self.loaded_effect = LoadedEffect::queued(entry.effect.clone());
+                                    self.state_changed = true;
+                                }
+                            }
                         });
                     });
                 });
@@ -380,22 +2219,84 @@ impl App {
         });
     }
 
+    /// Change the effect, remembering the options used with the current one and restoring those last used with the new one
+    fn switch_effect(&mut self, effect: Effects) {
+        let previous: &'static str = self.current_profile.effect.into();
+        self.effect_options.insert(previous.to_string(), self.current_profile.effect_options());
+
+        let name: &'static str = effect.into();
+        match self.effect_options.get(name) {
+            Some(options) => self.current_profile.apply_effect_options(options),
+            None => self.current_profile.effect = effect,
+        }
+    }
+
     fn show_effect_ui(&mut self, ui: &mut eframe::egui::Ui) {
         ui.add_enabled_ui(self.loaded_effect.is_none(), |ui| {
             let mut effect = self.current_profile.effect;
             effect.show_ui(ui, &mut self.current_profile, &mut self.state_changed, &self.theme);
+            // Write back any changes made to the effect's own parameters
+            self.current_profile.effect = effect;
+
+            effect_ui::overlay_ui::show(ui, &mut self.current_profile, &mut self.state_changed, &self.theme.spacing);
+            effect_ui::modulation_ui::show(ui, &mut self.current_profile, &mut self.state_changed, &self.theme.spacing);
         });
     }
 
     fn update_state(&mut self) {
+        // A plain black profile keeps the keyboard dark, whatever the effect or the overlays
+        let override_profile = if self.lights_off {
+            Some(Profile::default())
+        } else {
+            self.pomodoro.profile(&self.preferences.pomodoro, self.current_profile.brightness).or_else(|| self.chroma_profile())
+        };
+
+        if let Some(untouched) = &self.untouched {
+            let picked = override_profile.is_some() || self.loaded_effect.is_queued() || *untouched != self.current_profile;
+            if !picked {
+                self.state_changed = false;
+                return;
+            }
+            self.untouched = None;
+        }
+
+        let overlay = self.overlay();
         if let Some(manager) = self.manager.as_mut() {
-            if self.loaded_effect.is_none() {
-                manager.set_profile(self.current_profile.clone());
+            manager.set_overlay(overlay);
+
+            self.countdown = match self.current_profile.effect {
+                Effects::Clock { countdown_minutes } if countdown_minutes > 0 => self
+                    .countdown
+                    .filter(|(minutes, _)| *minutes == countdown_minutes)
+                    .or_else(|| Some((countdown_minutes, Instant::now() + Duration::from_secs(u64::from(countdown_minutes) * 60)))),
+                _ => None,
+            };
+            manager.set_countdown_deadline(self.countdown.map(|(_, deadline)| deadline));
+
+            if let Some(profile) = override_profile {
+                manager.show_profile(profile);
+                // Go back to the profile with a fade once the timer or the game stops
+                self.applied_profile_name = None;
+            } else if self.loaded_effect.is_none() {
+                // Only fade when going to another profile, not while the current one is being tweaked
+                let switched = self.current_profile.name.is_some() && self.current_profile.name != self.applied_profile_name;
+                if switched {
+                    manager.switch_profile(self.current_profile.clone());
+                } else {
+                    manager.set_profile(self.current_profile.clone());
+                }
+                self.applied_profile_name = self.current_profile.name.clone();
+
+                let effect: &'static str = self.current_profile.effect.into();
+                self.usage.record(self.current_profile.name.clone(), effect.to_string());
             } else if self.loaded_effect.is_queued() {
                 self.loaded_effect.state = State::Playing;
 
                 let effect = self.loaded_effect.effect.clone();
+                let name = effect.name.clone().unwrap_or_else(|| "Custom effect".to_string());
                 manager.custom_effect(effect);
+
+                self.usage.record(None, name);
             }
         }
 
@@ -409,12 +2310,39 @@ impl App {
                 return;
             }
 
-            if self.has_tray.load(Ordering::Relaxed) {
+            if self.has_tray.load(Ordering::Relaxed) && self.preferences.window.close_to_tray {
                 ctx.send_viewport_cmd(ViewportCommand::CancelClose);
                 ctx.send_viewport_cmd(ViewportCommand::Visible(false));
+                self.visible.store(false, Ordering::SeqCst);
             } else {
                 // Close normally
             }
         }
     }
+
+    fn handle_minimize(&mut self, ctx: &Context) {
+        let minimized = ctx.input(|i| i.viewport().minimized).unwrap_or(false);
+        let just_minimized = minimized && !self.was_minimized;
+        self.was_minimized = minimized;
+
+        if just_minimized && !*DENY_HIDING && self.has_tray.load(Ordering::Relaxed) && self.preferences.window.minimize_to_tray {
+            // Restore it while hidden, so showing it from the tray doesn't bring back a minimized window
+            ctx.send_viewport_cmd(ViewportCommand::Visible(false));
+            ctx.send_viewport_cmd(ViewportCommand::Minimized(false));
+            self.visible.store(false, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Outline a zone color picker in the color the zone lights up in while identifying them, with its number on top
+fn label_zone(ui: &eframe::egui::Ui, rect: Rect, zone: usize, lit: bool) {
+    let [r, g, b] = flash::ZONE_COLORS[zone];
+    let color = Color32::from_rgb(r, g, b);
+    let painter = ui.painter();
+
+    painter.rect_stroke(rect.expand(3.0), Rounding::same(4.0), Stroke::new(if lit { 4.0 } else { 1.0 }, color));
+    if lit {
+        painter.text(rect.center() + Vec2::splat(1.0), Align2::CENTER_CENTER, (zone + 1).to_string(), FontId::proportional(28.0), Color32::BLACK);
+        painter.text(rect.center(), Align2::CENTER_CENTER, (zone + 1).to_string(), FontId::proportional(28.0), Color32::WHITE);
+    }
 }
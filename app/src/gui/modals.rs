@@ -1,7 +1,8 @@
-use eframe::egui::{Color32, Context, Frame, ScrollArea};
+use eframe::egui::{Context, Frame, ScrollArea, Ui};
 use egui_modal::Modal;
+use rust_i18n::t;
 
-use crate::util;
+use super::style;
 
 pub fn unique_instance(ctx: &Context) -> bool {
     let mut exit_app = false;
@@ -9,13 +10,13 @@ pub fn unique_instance(ctx: &Context) -> bool {
     let modal = Modal::new(ctx, "unique_instance_error_modal");
 
     modal.show(|ui| {
-        modal.title(ui, "Warning");
+        modal.title(ui, t!("modals.warning"));
         modal.frame(ui, |ui| {
-            modal.body(ui, "Another instance is already running, please close it and try again.");
+            modal.body(ui, t!("modals.instance_running"));
         });
 
         modal.buttons(ui, |ui| {
-            exit_app = modal.caution_button(ui, "Exit").clicked();
+            exit_app = modal.caution_button(ui, t!("modals.exit")).clicked();
         });
     });
 
@@ -30,24 +31,24 @@ pub fn manager_error(ctx: &Context) -> bool {
     let modal = Modal::new(ctx, "manager_error_modal");
 
     modal.show(|ui| {
-        modal.title(ui, "Warning");
+        modal.title(ui, t!("modals.warning"));
         modal.frame(ui, |ui| {
-            modal.body(ui, "Failed to find a valid keyboard.");
-            modal.body(ui, "Ensure that you have a supported model and that the application has access to it.");
+            modal.body(ui, t!("modals.no_keyboard"));
+            modal.body(ui, t!("modals.check_access"));
             ui.horizontal(|ui| {
                 ui.spacing_mut().item_spacing.x = -2.0;
 
-                modal.body(ui, "On Linux, see");
-                util::clickable_link(ui, "https://github.com/4JX/L5P-Keyboard-RGB#usage");
+                modal.body(ui, t!("modals.linux_see"));
+                clickable_link(ui, "https://github.com/4JX/L5P-Keyboard-RGB#usage");
             });
-            modal.body(ui, "In certain cases, this may be due to a hardware error.");
+            modal.body(ui, t!("modals.hardware_error"));
 
-            if let Ok(list) = legion_rgb_driver::find_possible_keyboards() {
-                modal.body(ui, "Please attach the following list of identifiers when making an issue:");
-                Frame::none().fill(Color32::from_gray(20)).inner_margin(5.0).rounding(6.0).show(ui, |ui| {
+            if let Ok(list) = legion_kb_rgb_hw::find_possible_keyboards() {
+                modal.body(ui, t!("modals.attach_identifiers"));
+                Frame::none().fill(style::inset_fill(ui.visuals())).inner_margin(5.0).rounding(6.0).show(ui, |ui| {
                     ScrollArea::vertical().show(ui, |ui| {
                         if list.is_empty() {
-                            ui.label("No candidates found");
+                            ui.label(t!("modals.no_candidates"));
                         } else {
                             for d in list {
                                 ui.label(d);
@@ -59,7 +60,7 @@ pub fn manager_error(ctx: &Context) -> bool {
         });
 
         modal.buttons(ui, |ui| {
-            exit_app = modal.caution_button(ui, "Exit").clicked();
+            exit_app = modal.caution_button(ui, t!("modals.exit")).clicked();
         });
     });
 
@@ -72,21 +73,27 @@ pub fn about(ctx: &Context) -> Modal {
     let modal = Modal::new(ctx, "about_modal");
 
     modal.show(|ui| {
-        modal.title(ui, "About");
+        modal.title(ui, t!("modals.about"));
         modal.frame(ui, |ui| {
-            modal.body(ui, "A program made by 4JX.");
+            modal.body(ui, t!("modals.made_by"));
             ui.horizontal(|ui| {
                 ui.spacing_mut().item_spacing.x = -2.0;
 
-                modal.body(ui, "Something's not working?:");
-                util::clickable_link(ui, "https://github.com/4JX/L5P-Keyboard-RGB");
+                modal.body(ui, t!("modals.not_working"));
+                clickable_link(ui, "https://github.com/4JX/L5P-Keyboard-RGB");
             });
 
-            modal.body(ui, format!("Current version: {}", env!("CARGO_PKG_VERSION")));
+            modal.body(ui, t!("modals.version", version = env!("CARGO_PKG_VERSION")));
         });
 
-        modal.buttons(ui, |ui| modal.button(ui, "Close"));
+        modal.buttons(ui, |ui| modal.button(ui, t!("modals.close")));
     });
 
     modal
 }
+
+fn clickable_link(ui: &mut Ui, url: &str) {
+    if ui.link(url).clicked() {
+        open::that(url).unwrap();
+    }
+}
@@ -0,0 +1,215 @@
+//! Walks through the first launch: finding the keyboard, telling its zones apart, picking how the program starts and
+//! closes, and making a first profile.
+
+use eframe::{
+    egui::{Align, Button, ComboBox, Context, Layout, RichText, TextEdit, Ui, Window},
+    epaint::Color32,
+};
+use rust_i18n::t;
+use strum::IntoEnumIterator;
+
+use crate::{
+    enums::Effects,
+    manager::{
+        flash::ZONE_COLORS,
+        profile::{self, Profile},
+    },
+    persist::Preferences,
+    startup::InitialState,
+    DENY_HIDING,
+};
+
+use super::i18n;
+
+/// The effects offered for the first profile, the ones the keyboard plays by itself
+const FIRST_EFFECTS: [Effects; 4] = [Effects::Static, Effects::Breath, Effects::Smooth, Effects::Wave];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Keyboard,
+    Zones,
+    Startup,
+    Profile,
+}
+
+impl Step {
+    const ALL: [Self; 4] = [Self::Keyboard, Self::Zones, Self::Startup, Self::Profile];
+
+    fn index(self) -> usize {
+        Self::ALL.iter().position(|step| *step == self).unwrap()
+    }
+}
+
+/// Something the wizard needs the rest of the app to do
+pub enum OnboardingAction {
+    /// Blink a zone, from 0 to 3
+    FlashZone(usize),
+    /// Save the profile made in the last step and switch to it
+    CreateProfile(Profile),
+}
+
+pub struct Onboarding {
+    pub open: bool,
+    step: Step,
+    profile_name: String,
+    effect: Effects,
+    colors: [[u8; 3]; 4],
+}
+
+impl Default for Onboarding {
+    fn default() -> Self {
+        Self {
+            open: false,
+            step: Step::Keyboard,
+            profile_name: t!("onboarding.default_profile_name"),
+            effect: Effects::Static,
+            colors: ZONE_COLORS,
+        }
+    }
+}
+
+impl Onboarding {
+    /// Start over from the first step
+    pub fn restart(&mut self) {
+        *self = Self { open: true, ..Self::default() };
+    }
+
+    /// Show the current step, `keyboard` being the name of the model found if any
+    pub fn show(&mut self, ctx: &Context, keyboard: Option<&str>, preferences: &mut Preferences, profiles: &[Profile]) -> Option<OnboardingAction> {
+        let mut action = None;
+        let mut open = self.open;
+
+        Window::new(t!("onboarding.title")).open(&mut open).collapsible(false).resizable(false).default_width(380.0).show(ctx, |ui| {
+            ui.label(t!("onboarding.step", current = self.step.index() + 1, total = Step::ALL.len()));
+            ui.separator();
+
+            match self.step {
+                Step::Keyboard => show_keyboard(ui, keyboard),
+                Step::Zones => action = show_zones(ui, keyboard.is_some()),
+                Step::Startup => show_startup(ui, preferences),
+                Step::Profile => self.show_profile(ui, profiles),
+            }
+
+            ui.separator();
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                let index = self.step.index();
+                match Step::ALL.get(index + 1) {
+                    Some(next) => {
+                        if ui.button(t!("onboarding.next")).clicked() {
+                            self.step = *next;
+                        }
+                    }
+                    None => {
+                        let name_taken = profiles.iter().any(|profile| profile.name.as_deref() == Some(self.profile_name.trim()));
+                        if ui.add_enabled(!self.profile_name.trim().is_empty() && !name_taken, Button::new(t!("onboarding.finish"))).clicked() {
+                            action = Some(OnboardingAction::CreateProfile(self.profile()));
+                            self.open = false;
+                        }
+                    }
+                }
+                if index > 0 && ui.button(t!("onboarding.back")).clicked() {
+                    self.step = Step::ALL[index - 1];
+                }
+                if ui.button(t!("onboarding.skip")).clicked() {
+                    self.open = false;
+                }
+            });
+        });
+
+        self.open &= open;
+        action
+    }
+
+    fn show_profile(&mut self, ui: &mut Ui, profiles: &[Profile]) {
+        ui.label(t!("onboarding.profile_hint"));
+
+        ui.horizontal(|ui| {
+            ui.label(t!("onboarding.profile_name"));
+            ui.add(TextEdit::singleline(&mut self.profile_name).desired_width(160.0));
+        });
+        if profiles.iter().any(|profile| profile.name.as_deref() == Some(self.profile_name.trim())) {
+            ui.label(RichText::new(t!("saved_items.name_taken")).small());
+        }
+
+        ComboBox::from_label(t!("onboarding.profile_effect"))
+            .selected_text(i18n::name_of("effects", self.effect))
+            .show_ui(ui, |ui| {
+                for effect in FIRST_EFFECTS {
+                    ui.selectable_value(&mut self.effect, effect, i18n::name_of("effects", effect));
+                }
+            });
+
+        if self.effect.takes_color_array() {
+            ui.horizontal(|ui| {
+                for color in &mut self.colors {
+                    ui.color_edit_button_srgb(color);
+                }
+            });
+        }
+    }
+
+    fn profile(&self) -> Profile {
+        Profile {
+            name: Some(self.profile_name.trim().to_string()),
+            rgb_zones: profile::arr_to_zones(self.colors.concat().try_into().unwrap()),
+            effect: self.effect,
+            ..Profile::default()
+        }
+    }
+}
+
+fn show_keyboard(ui: &mut Ui, keyboard: Option<&str>) {
+    ui.label(t!("onboarding.welcome"));
+    ui.add_space(4.0);
+
+    match keyboard {
+        Some(model) => {
+            ui.label(RichText::new(t!("onboarding.keyboard_found", model = model)).strong());
+        }
+        None => {
+            ui.label(RichText::new(t!("onboarding.keyboard_missing")).strong());
+            ui.label(t!("onboarding.keyboard_missing_hint"));
+        }
+    }
+}
+
+fn show_zones(ui: &mut Ui, has_keyboard: bool) -> Option<OnboardingAction> {
+    let mut action = None;
+
+    ui.label(t!("onboarding.zones_hint"));
+    ui.add_enabled_ui(has_keyboard, |ui| {
+        ui.horizontal(|ui| {
+            for (zone, [r, g, b]) in ZONE_COLORS.into_iter().enumerate() {
+                let text = RichText::new(t!("onboarding.zone", zone = zone + 1)).color(Color32::from_rgb(r, g, b));
+                if ui.button(text).clicked() {
+                    action = Some(OnboardingAction::FlashZone(zone));
+                }
+            }
+        });
+    });
+
+    action
+}
+
+fn show_startup(ui: &mut Ui, preferences: &mut Preferences) {
+    ui.label(t!("onboarding.startup_hint"));
+
+    ComboBox::from_label(t!("preferences.initial_state"))
+        .selected_text(i18n::name_of("initial_state", preferences.startup.initial_state))
+        .show_ui(ui, |ui| {
+            for option in InitialState::iter() {
+                ui.selectable_value(&mut preferences.startup.initial_state, option, i18n::name_of("initial_state", option));
+            }
+        })
+        .response
+        .on_hover_text(t!("preferences.initial_state_hint"));
+    ui.checkbox(&mut preferences.startup.leave_untouched, t!("preferences.leave_untouched"))
+        .on_hover_text(t!("preferences.leave_untouched_hint"));
+
+    ui.add_enabled_ui(!*DENY_HIDING, |ui| {
+        ui.checkbox(&mut preferences.window.minimize_to_tray, t!("preferences.minimize_to_tray"));
+        ui.checkbox(&mut preferences.window.close_to_tray, t!("preferences.close_to_tray"));
+    })
+    .response
+    .on_disabled_hover_text(t!("preferences.tray_unsupported"));
+}
@@ -0,0 +1,244 @@
+use std::{path::PathBuf, thread, time::Duration};
+
+use crossbeam_channel::Sender;
+use eframe::{
+    egui::{Button, ComboBox, Context, ScrollArea, Slider, TextEdit, Ui, Window},
+    epaint::Vec2,
+};
+use egui_file::FileDialog;
+use egui_notify::Toasts;
+use rust_i18n::t;
+
+use crate::{
+    manager::{palette::Palette, profile::Profile},
+    wallpaper,
+};
+
+use super::GuiMessage;
+
+/// A picture decoded off the GUI thread, `None` if it couldn't be read
+pub enum DecodedImage {
+    /// For the zone colors, without saving them as a palette
+    Zones(Option<Palette>),
+    /// Saved as a new palette
+    Palette(Option<Palette>),
+}
+
+pub struct PaletteEditor {
+    pub palettes: Vec<Palette>,
+    pub open: bool,
+
+    gui_sender: Sender<GuiMessage>,
+    built_in: Vec<Palette>,
+    selected: Option<usize>,
+    import_text: String,
+    color_count: usize,
+    image_dialog: FileDialog,
+    /// Picks a picture to take the zone colors from, without saving them as a palette
+    zones_image_dialog: FileDialog,
+}
+
+impl PaletteEditor {
+    pub fn new(palettes: Vec<Palette>, gui_sender: Sender<GuiMessage>) -> Self {
+        Self {
+            palettes,
+            open: false,
+            gui_sender,
+            built_in: Palette::built_in(),
+            selected: None,
+            import_text: String::new(),
+            color_count: 4,
+            image_dialog: FileDialog::open_file(None).default_size(Vec2::splat(300.0)),
+            zones_image_dialog: FileDialog::open_file(None).default_size(Vec2::splat(300.0)),
+        }
+    }
+
+    pub fn show(&mut self, ctx: &Context, current_profile: &mut Profile, changed: &mut bool, toasts: &mut Toasts) {
+        let mut open = self.open;
+
+        Window::new(t!("palettes.title")).open(&mut open).resizable(false).collapsible(false).show(ctx, |ui| {
+            self.show_list(ui);
+            ui.separator();
+            self.show_selected(ui, current_profile, changed);
+            ui.separator();
+            self.show_import(ui, toasts);
+        });
+
+        self.open = open;
+        self.handle_image_import(ctx);
+        self.handle_zones_image(ctx);
+    }
+
+    /// A dropdown of the built-in and saved palettes, filling the zones with the chosen one
+    pub fn show_presets(&mut self, ui: &mut Ui, current_profile: &mut Profile, changed: &mut bool, toasts: &mut Toasts) {
+        ComboBox::from_label(t!("palettes.presets")).selected_text(t!("palettes.choose")).show_ui(ui, |ui| {
+            for palette in self.built_in.iter().chain(&self.palettes).filter(|palette| !palette.colors.is_empty()) {
+                if ui.selectable_label(false, &palette.name).clicked() {
+                    apply(palette, current_profile);
+                    *changed = true;
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            if ui.button(t!("palettes.zones_from_image")).on_hover_text(t!("palettes.zones_from_image_hint")).clicked() {
+                self.zones_image_dialog.open();
+            }
+            if ui.button(t!("palettes.zones_from_wallpaper")).clicked() {
+                match wallpaper::current() {
+                    Some(path) => self.decode(ui.ctx(), path, String::new(), 4, DecodedImage::Zones),
+                    None => {
+                        toasts.error(t!("palettes.no_wallpaper")).duration(Some(Duration::from_millis(5000))).closable(true);
+                    }
+                }
+            }
+        });
+    }
+
+    fn show_list(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            if ui.button("+").clicked() {
+                let palette = Palette::new(self.next_name(), vec![[255, 255, 255]]);
+                self.push(palette);
+            }
+            if ui.add_enabled(self.selected.is_some(), Button::new("-")).clicked() {
+                if let Some(i) = self.selected.take() {
+                    self.palettes.remove(i);
+                }
+            }
+        });
+
+        ScrollArea::vertical().max_height(80.0).id_salt("palette_list").show(ui, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                for (i, palette) in self.palettes.iter().enumerate() {
+                    if ui.selectable_label(self.selected == Some(i), &palette.name).clicked() {
+                        self.selected = Some(i);
+                    }
+                }
+            });
+        });
+    }
+
+    fn show_selected(&mut self, ui: &mut Ui, current_profile: &mut Profile, changed: &mut bool) {
+        let Some(palette) = self.selected.and_then(|i| self.palettes.get_mut(i)) else {
+            ui.label(t!("palettes.select_hint"));
+            return;
+        };
+
+        ui.add(TextEdit::singleline(&mut palette.name).hint_text(t!("palettes.name")));
+
+        ui.horizontal_wrapped(|ui| {
+            let mut remove = None;
+
+            for (i, color) in palette.colors.iter_mut().enumerate() {
+                let response = ui.color_edit_button_srgb(color);
+                response.context_menu(|ui| {
+                    if ui.button(t!("palettes.remove")).clicked() {
+                        remove = Some(i);
+                        ui.close_menu();
+                    }
+                });
+            }
+
+            if let Some(i) = remove {
+                palette.colors.remove(i);
+            }
+
+            if ui.button("+").clicked() {
+                palette.colors.push(palette.colors.last().copied().unwrap_or([255; 3]));
+            }
+        });
+
+        if ui.add_enabled(!palette.colors.is_empty(), Button::new(t!("palettes.apply"))).clicked() {
+            apply(palette, current_profile);
+            *changed = true;
+        }
+    }
+
+    fn show_import(&mut self, ui: &mut Ui, toasts: &mut Toasts) {
+        ui.label(t!("palettes.import_title"));
+
+        ui.horizontal(|ui| {
+            ui.add(TextEdit::singleline(&mut self.import_text).hint_text(t!("palettes.import_hint")));
+
+            if ui.button(t!("palettes.import")).clicked() {
+                match Palette::from_import(self.next_name(), &self.import_text) {
+                    Ok(palette) => {
+                        self.import_text.clear();
+                        self.push(palette);
+                    }
+                    Err(_) => {
+                        toasts.error(t!("palettes.no_valid_colors")).duration(Some(Duration::from_millis(5000))).closable(true);
+                    }
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            if ui.button(t!("palettes.from_image")).clicked() {
+                self.image_dialog.open();
+            }
+            ui.add(Slider::new(&mut self.color_count, 2..=8));
+            ui.label(t!("palettes.colors"));
+        });
+    }
+
+    fn handle_image_import(&mut self, ctx: &Context) {
+        if self.image_dialog.show(ctx).selected() {
+            if let Some(path) = self.image_dialog.path().map(PathBuf::from) {
+                self.decode(ctx, path, self.next_name(), self.color_count, DecodedImage::Palette);
+            }
+        }
+    }
+
+    fn handle_zones_image(&mut self, ctx: &Context) {
+        if self.zones_image_dialog.show(ctx).selected() {
+            if let Some(path) = self.zones_image_dialog.path().map(PathBuf::from) {
+                self.decode(ctx, path, String::new(), 4, DecodedImage::Zones);
+            }
+        }
+    }
+
+    /// Take the `count` most dominant colors of a picture on another thread, as decoding a large one takes a while
+    fn decode(&self, ctx: &Context, path: PathBuf, name: String, count: usize, decoded: fn(Option<Palette>) -> DecodedImage) {
+        let ctx = ctx.clone();
+        let gui_sender = self.gui_sender.clone();
+        thread::spawn(move || {
+            let palette = Palette::from_image(name, &path, count)
+                .map_err(|err| tracing::warn!("Could not take the colors of {}: {err:?}", path.display()))
+                .ok();
+            let _ = gui_sender.send(GuiMessage::DecodedImage(decoded(palette)));
+            ctx.request_repaint();
+        });
+    }
+
+    /// Use a picture decoded for the zone colors or for a new palette
+    pub fn handle_decoded(&mut self, decoded: DecodedImage, current_profile: &mut Profile, changed: &mut bool, toasts: &mut Toasts) {
+        match decoded {
+            DecodedImage::Zones(Some(palette)) => {
+                apply(&palette, current_profile);
+                *changed = true;
+            }
+            DecodedImage::Palette(Some(palette)) => self.push(palette),
+            DecodedImage::Zones(None) | DecodedImage::Palette(None) => {
+                toasts.error(t!("palettes.image_failed")).duration(Some(Duration::from_millis(5000))).closable(true);
+            }
+        }
+    }
+
+    fn push(&mut self, palette: Palette) {
+        self.palettes.push(palette);
+        self.selected = Some(self.palettes.len() - 1);
+    }
+
+    fn next_name(&self) -> String {
+        t!("palettes.default_name", n = self.palettes.len() + 1)
+    }
+}
+
+pub fn apply(palette: &Palette, profile: &mut Profile) {
+    for (zone, rgb) in profile.rgb_zones.iter_mut().zip(palette.to_zone_colors()) {
+        zone.rgb = rgb;
+    }
+    profile.palette = Some(palette.clone());
+}
@@ -0,0 +1,548 @@
+use eframe::egui::{CollapsingHeader, ComboBox, Context, DragValue, Grid, Slider, TextEdit, Ui, Window};
+use legion_kb_rgb_hw::lights::{self, Section};
+use rand::{distributions::Alphanumeric, Rng};
+use rust_i18n::t;
+use strum::IntoEnumIterator;
+
+use crate::{
+    api,
+    app_rules::{AppRule, RuleTarget},
+    chroma::{self, ChromaPriority},
+    exit::ExitBehavior,
+    hotkeys,
+    manager::{clock::SpeedMultiplier, frame_rate::FrameRate, profile::Profile},
+    overlays::OverlaySettings,
+    persist::Preferences,
+    appearance::ThemeMode,
+    reduced_motion::ReducedMotion,
+    room_sync::{LightMapping, RoomService, ZoneSource},
+    startup::InitialState,
+    weather::WeatherProvider,
+    DENY_HIDING,
+};
+
+use super::{i18n, keyboard_preview::KeyboardLayout};
+
+pub fn show(ctx: &Context, open: &mut bool, preferences: &mut Preferences, profiles: &[Profile], layouts: &[KeyboardLayout]) {
+    Window::new(t!("preferences.title")).open(open).resizable(false).collapsible(false).show(ctx, |ui| {
+        show_notifications(ui, preferences);
+        ui.separator();
+        show_fullscreen(ui, preferences, profiles);
+        show_app_rules(ui, preferences, profiles);
+        show_shuffle(ui, preferences);
+        ui.separator();
+        show_language(ui, preferences);
+        show_appearance(ui, preferences);
+        show_preview_layout(ui, preferences, layouts);
+        show_reduced_motion(ui, preferences);
+        show_effect_speed(ui, preferences);
+        show_frame_rate(ui, preferences);
+        show_crossfade(ui, preferences);
+        show_synced_lights(ui, preferences);
+        show_hotkeys(ui, preferences);
+        show_overlays(ui, &mut preferences.overlays);
+        show_chroma(ui, preferences);
+        show_room_sync(ui, preferences);
+        show_api(ui, preferences);
+        show_weather(ui, preferences);
+        show_wallpaper(ui, preferences);
+        ui.separator();
+        show_window(ui, preferences);
+        ui.separator();
+        show_startup(ui, preferences);
+        show_exit(ui, preferences);
+        ui.separator();
+        show_network(ui, preferences);
+        show_community(ui, preferences);
+    });
+}
+
+fn show_notifications(ui: &mut Ui, preferences: &mut Preferences) {
+    let settings = &mut preferences.notifications;
+
+    ui.checkbox(&mut settings.enabled, t!("preferences.flash_on_notifications"));
+    ui.add_enabled_ui(settings.enabled, |ui| {
+        ui.horizontal(|ui| {
+            ui.color_edit_button_srgb(&mut settings.flash.rgb);
+            ui.label(t!("preferences.flash_color"));
+        });
+        ui.horizontal(|ui| {
+            ui.add(Slider::new(&mut settings.flash.count, 1..=5));
+            ui.label(t!("preferences.flashes"));
+        });
+    });
+}
+
+fn show_fullscreen(ui: &mut Ui, preferences: &mut Preferences, profiles: &[Profile]) {
+    let settings = &mut preferences.fullscreen;
+
+    ui.checkbox(&mut settings.enabled, t!("preferences.fullscreen_switch"));
+    ui.add_enabled_ui(settings.enabled, |ui| {
+        ComboBox::from_label(t!("preferences.profile"))
+            .selected_text(settings.profile.clone().unwrap_or_else(|| t!("preferences.none")))
+            .show_ui(ui, |ui| {
+                for name in profiles.iter().filter_map(|profile| profile.name.as_ref()) {
+                    ui.selectable_value(&mut settings.profile, Some(name.clone()), name);
+                }
+            });
+    });
+}
+
+fn show_shuffle(ui: &mut Ui, preferences: &mut Preferences) {
+    let settings = &mut preferences.shuffle;
+
+    ui.checkbox(&mut settings.enabled, t!("preferences.shuffle")).on_hover_text(t!("preferences.shuffle_hint"));
+    ui.add_enabled_ui(settings.enabled, |ui| {
+        ui.horizontal(|ui| {
+            ui.add(DragValue::new(&mut settings.minutes).range(1..=1440).suffix(" min"));
+            ui.label(t!("preferences.shuffle_interval"));
+        });
+        ui.checkbox(&mut settings.pinned_only, t!("preferences.shuffle_pinned_only"));
+    });
+}
+
+fn show_app_rules(ui: &mut Ui, preferences: &mut Preferences, profiles: &[Profile]) {
+    CollapsingHeader::new(t!("preferences.app_rules")).id_salt("app_rules").show(ui, |ui| {
+        let mut remove = None;
+
+        Grid::new("app_rules_grid").num_columns(4).show(ui, |ui| {
+            for (i, rule) in preferences.app_rules.iter_mut().enumerate() {
+                ui.add(TextEdit::singleline(&mut rule.pattern).hint_text(t!("preferences.rule_pattern_hint")).desired_width(100.0));
+
+                ComboBox::from_id_salt(("rule_target", i))
+                    .selected_text(i18n::name_of("rule_target", rule.target))
+                    .show_ui(ui, |ui| {
+                        for target in RuleTarget::iter() {
+                            ui.selectable_value(&mut rule.target, target, i18n::name_of("rule_target", target));
+                        }
+                    });
+
+                ComboBox::from_id_salt(("rule_profile", i)).selected_text(rule.profile.as_str()).show_ui(ui, |ui| {
+                    for name in profiles.iter().filter_map(|profile| profile.name.as_ref()) {
+                        ui.selectable_value(&mut rule.profile, name.clone(), name);
+                    }
+                });
+
+                if ui.button("-").clicked() {
+                    remove = Some(i);
+                }
+                ui.end_row();
+            }
+        });
+
+        if let Some(i) = remove {
+            preferences.app_rules.remove(i);
+        }
+
+        if ui.button(t!("preferences.add_rule")).clicked() {
+            preferences.app_rules.push(AppRule::default());
+        }
+    });
+}
+
+fn show_preview_layout(ui: &mut Ui, preferences: &mut Preferences, layouts: &[KeyboardLayout]) {
+    ComboBox::from_label(t!("preferences.preview_layout"))
+        .selected_text(preferences.preview_layout.clone().unwrap_or_else(|| t!("preferences.automatic")))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut preferences.preview_layout, None, t!("preferences.automatic"));
+            for layout in layouts {
+                ui.selectable_value(&mut preferences.preview_layout, Some(layout.name.clone()), &layout.name);
+            }
+        });
+}
+
+fn show_language(ui: &mut Ui, preferences: &mut Preferences) {
+    ComboBox::from_label(t!("preferences.language"))
+        .selected_text(preferences.language.as_deref().map_or_else(|| t!("preferences.follow_system"), i18n::native_name))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut preferences.language, None, t!("preferences.follow_system"));
+            for language in i18n::languages() {
+                ui.selectable_value(&mut preferences.language, Some(language.to_string()), i18n::native_name(language));
+            }
+        });
+}
+
+fn show_appearance(ui: &mut Ui, preferences: &mut Preferences) {
+    let settings = &mut preferences.appearance;
+
+    ComboBox::from_label(t!("preferences.theme"))
+        .selected_text(i18n::name_of("theme", settings.theme))
+        .show_ui(ui, |ui| {
+            for option in ThemeMode::iter() {
+                ui.selectable_value(&mut settings.theme, option, i18n::name_of("theme", option));
+            }
+        });
+    ui.horizontal(|ui| {
+        ui.color_edit_button_srgb(&mut settings.accent);
+        ui.label(t!("preferences.accent_color"));
+    });
+}
+
+fn show_reduced_motion(ui: &mut Ui, preferences: &mut Preferences) {
+    ComboBox::from_label(t!("preferences.reduced_motion"))
+        .selected_text(i18n::name_of("reduced_motion", preferences.reduced_motion))
+        .show_ui(ui, |ui| {
+            for option in ReducedMotion::iter() {
+                ui.selectable_value(&mut preferences.reduced_motion, option, i18n::name_of("reduced_motion", option));
+            }
+        })
+        .response
+        .on_hover_text(t!("preferences.reduced_motion_hint"));
+}
+
+fn show_effect_speed(ui: &mut Ui, preferences: &mut Preferences) {
+    ui.horizontal(|ui| {
+        ui.add(Slider::new(&mut preferences.effect_speed.0, SpeedMultiplier::RANGE).logarithmic(true).suffix("×"));
+        ui.label(t!("preferences.effect_speed"));
+    })
+    .response
+    .on_hover_text(t!("preferences.effect_speed_hint"));
+}
+
+fn show_frame_rate(ui: &mut Ui, preferences: &mut Preferences) {
+    ComboBox::from_label(t!("preferences.frame_rate"))
+        .selected_text(i18n::name_of("frame_rate", preferences.frame_rate))
+        .show_ui(ui, |ui| {
+            for option in FrameRate::iter() {
+                ui.selectable_value(&mut preferences.frame_rate, option, i18n::name_of("frame_rate", option));
+            }
+        })
+        .response
+        .on_hover_text(t!("preferences.frame_rate_hint"));
+}
+
+fn show_crossfade(ui: &mut Ui, preferences: &mut Preferences) {
+    ui.horizontal(|ui| {
+        ui.add(DragValue::new(&mut preferences.crossfade_ms).range(0..=5000).speed(10).suffix(" ms"));
+        ui.label(t!("preferences.crossfade"));
+    })
+    .response
+    .on_hover_text(t!("preferences.crossfade_hint"));
+}
+
+fn show_synced_lights(ui: &mut Ui, preferences: &mut Preferences) {
+    let available = lights::available();
+    if available.is_empty() {
+        return;
+    }
+
+    ui.label(t!("preferences.synced_lights")).on_hover_text(t!("preferences.synced_lights_hint"));
+    ui.horizontal(|ui| {
+        for section in available {
+            let mut synced = preferences.synced_lights.contains(&section);
+            if ui.checkbox(&mut synced, light_name(section)).changed() {
+                if synced {
+                    preferences.synced_lights.push(section);
+                } else {
+                    preferences.synced_lights.retain(|synced| *synced != section);
+                }
+            }
+        }
+    });
+}
+
+fn show_hotkeys(ui: &mut Ui, preferences: &mut Preferences) {
+    let settings = &mut preferences.hotkeys;
+
+    CollapsingHeader::new(t!("preferences.hotkeys")).id_salt("hotkeys").show(ui, |ui| {
+        let bindings = [
+            (&mut settings.toggle_lights, t!("preferences.toggle_lights_hotkey")),
+            (&mut settings.brightness_up, t!("preferences.brightness_up_hotkey")),
+            (&mut settings.brightness_down, t!("preferences.brightness_down_hotkey")),
+        ];
+        let mut any_unknown = false;
+        Grid::new("hotkeys_grid").num_columns(2).show(ui, |ui| {
+            for (keys, label) in bindings {
+                ui.label(label);
+                ui.add(TextEdit::singleline(keys).hint_text(t!("preferences.hotkey_disabled")).desired_width(140.0));
+                ui.end_row();
+                any_unknown |= hotkeys::parse(keys).is_none();
+            }
+        });
+        if any_unknown {
+            ui.colored_label(ui.visuals().error_fg_color, t!("preferences.hotkey_unknown_key"));
+        }
+        ui.label(t!("preferences.hotkeys_hint"));
+
+        ui.add_enabled_ui(cfg!(target_os = "linux"), |ui| {
+            ui.checkbox(&mut preferences.backlight.follow_laptop_key, t!("preferences.follow_laptop_key"))
+                .on_hover_text(t!("preferences.follow_laptop_key_hint"));
+        })
+        .response
+        .on_disabled_hover_text(t!("preferences.linux_only"));
+    });
+}
+
+fn show_overlays(ui: &mut Ui, settings: &mut OverlaySettings) {
+    CollapsingHeader::new(t!("preferences.overlays")).id_salt("overlays").show(ui, |ui| {
+        ui.label(t!("preferences.overlays_hint"));
+
+        #[cfg(feature = "audio")]
+        {
+            let mic = &mut settings.mic;
+            ui.checkbox(&mut mic.enabled, t!("preferences.mic_indicator"));
+            ui.add_enabled_ui(mic.enabled, |ui| {
+                ui.horizontal(|ui| {
+                    zone_picker(ui, "mic_zone", &mut mic.zone);
+                    ui.color_edit_button_srgb(&mut mic.muted);
+                    ui.label(t!("preferences.mic_muted"));
+                    ui.color_edit_button_srgb(&mut mic.live);
+                    ui.label(t!("preferences.mic_live"));
+                });
+            });
+        }
+
+        let locks = [
+            (&mut settings.caps_lock, t!("preferences.caps_lock_indicator"), "caps_lock_zone"),
+            (&mut settings.num_lock, t!("preferences.num_lock_indicator"), "num_lock_zone"),
+        ];
+        for (indicator, label, id) in locks {
+            ui.checkbox(&mut indicator.enabled, label);
+            ui.add_enabled_ui(indicator.enabled, |ui| {
+                ui.horizontal(|ui| {
+                    zone_picker(ui, id, &mut indicator.zone);
+                    ui.color_edit_button_srgb(&mut indicator.color);
+                });
+            });
+        }
+
+        let battery = &mut settings.battery;
+        ui.checkbox(&mut battery.enabled, t!("preferences.battery_indicator")).on_hover_text(t!("preferences.battery_indicator_hint"));
+        ui.add_enabled_ui(battery.enabled, |ui| {
+            ui.horizontal(|ui| {
+                zone_picker(ui, "battery_zone", &mut battery.zone);
+                ui.color_edit_button_srgb(&mut battery.low_color);
+                ui.label(t!("preferences.battery_low"));
+                ui.add(DragValue::new(&mut battery.low_below).range(5..=50).suffix("%"));
+                ui.color_edit_button_srgb(&mut battery.charging_color);
+                ui.label(t!("preferences.battery_charging"));
+            });
+        });
+    });
+}
+
+fn zone_picker(ui: &mut Ui, id: &str, zone: &mut usize) {
+    ComboBox::from_id_salt(id).selected_text(t!("preferences.zone", zone = *zone + 1)).show_ui(ui, |ui| {
+        for option in 0..4 {
+            ui.selectable_value(zone, option, t!("preferences.zone", zone = option + 1));
+        }
+    });
+}
+
+fn show_chroma(ui: &mut Ui, preferences: &mut Preferences) {
+    let settings = &mut preferences.chroma;
+
+    ui.add_enabled_ui(chroma::is_supported(), |ui| {
+        ui.checkbox(&mut settings.enabled, t!("preferences.chroma")).on_hover_text(t!("preferences.chroma_hint"));
+        ui.add_enabled_ui(settings.enabled, |ui| {
+            ComboBox::from_label(t!("preferences.chroma_priority"))
+                .selected_text(i18n::name_of("chroma_priority", settings.priority))
+                .show_ui(ui, |ui| {
+                    for option in ChromaPriority::iter() {
+                        ui.selectable_value(&mut settings.priority, option, i18n::name_of("chroma_priority", option));
+                    }
+                });
+        });
+    })
+    .response
+    .on_disabled_hover_text(t!("preferences.chroma_unsupported"));
+}
+
+fn show_room_sync(ui: &mut Ui, preferences: &mut Preferences) {
+    let settings = &mut preferences.room_sync;
+
+    CollapsingHeader::new(t!("preferences.room_sync")).id_salt("room_sync").show(ui, |ui| {
+        ui.add_enabled_ui(cfg!(feature = "network"), |ui| {
+            ui.checkbox(&mut settings.enabled, t!("preferences.room_sync_enabled"));
+        })
+        .response
+        .on_disabled_hover_text(t!("preferences.room_sync_unavailable"));
+
+        ComboBox::from_label(t!("preferences.room_sync_service"))
+            .selected_text(i18n::name_of("room_service", settings.service))
+            .show_ui(ui, |ui| {
+                for service in RoomService::iter() {
+                    ui.selectable_value(&mut settings.service, service, i18n::name_of("room_service", service));
+                }
+            });
+        ui.add(TextEdit::singleline(&mut settings.endpoint).hint_text(t!("preferences.room_sync_endpoint_hint")));
+        let token_hint = match settings.service {
+            RoomService::HomeAssistant => t!("preferences.room_sync_token_hint"),
+            RoomService::Hue => t!("preferences.room_sync_username_hint"),
+        };
+        ui.add(TextEdit::singleline(&mut settings.token).password(true).hint_text(token_hint));
+
+        let light_hint = match settings.service {
+            RoomService::HomeAssistant => t!("preferences.room_sync_entity_hint"),
+            RoomService::Hue => t!("preferences.room_sync_light_hint"),
+        };
+        let mut remove = None;
+        Grid::new("room_sync_grid").num_columns(3).show(ui, |ui| {
+            for (i, mapping) in settings.lights.iter_mut().enumerate() {
+                ui.add(TextEdit::singleline(&mut mapping.light).hint_text(light_hint.as_str()).desired_width(120.0));
+
+                ComboBox::from_id_salt(("room_sync_zone", i))
+                    .selected_text(i18n::name_of("zone_source", mapping.zone))
+                    .show_ui(ui, |ui| {
+                        for zone in ZoneSource::iter() {
+                            ui.selectable_value(&mut mapping.zone, zone, i18n::name_of("zone_source", zone));
+                        }
+                    });
+
+                if ui.button("-").clicked() {
+                    remove = Some(i);
+                }
+                ui.end_row();
+            }
+        });
+
+        if let Some(i) = remove {
+            settings.lights.remove(i);
+        }
+
+        if ui.button(t!("preferences.room_sync_add_light")).clicked() {
+            settings.lights.push(LightMapping::default());
+        }
+    });
+}
+
+fn show_api(ui: &mut Ui, preferences: &mut Preferences) {
+    let settings = &mut preferences.api;
+
+    CollapsingHeader::new(t!("preferences.api")).id_salt("api").show(ui, |ui| {
+        ui.add_enabled_ui(cfg!(feature = "network") && !settings.token.is_empty(), |ui| {
+            ui.checkbox(&mut settings.enabled, t!("preferences.api_enabled"));
+        })
+        .response
+        .on_disabled_hover_text(if cfg!(feature = "network") { t!("preferences.api_token_required") } else { t!("preferences.room_sync_unavailable") });
+
+        ui.horizontal(|ui| {
+            ui.label(t!("preferences.api_bind"));
+            ui.add(TextEdit::singleline(&mut settings.bind).hint_text(api::DEFAULT_BIND).desired_width(140.0));
+        });
+        ui.horizontal(|ui| {
+            ui.label(t!("preferences.api_token"));
+            ui.add(TextEdit::singleline(&mut settings.token).password(true).desired_width(140.0));
+            if ui.button(t!("preferences.api_generate_token")).clicked() {
+                settings.token = rand::thread_rng().sample_iter(&Alphanumeric).take(32).map(char::from).collect();
+            }
+        });
+        if settings.token.is_empty() {
+            settings.enabled = false;
+        }
+        ui.label(t!("preferences.api_hint"));
+    });
+}
+
+fn show_wallpaper(ui: &mut Ui, preferences: &mut Preferences) {
+    ui.checkbox(&mut preferences.wallpaper.follow, t!("preferences.follow_wallpaper"))
+        .on_hover_text(t!("preferences.follow_wallpaper_hint"));
+}
+
+fn show_weather(ui: &mut Ui, preferences: &mut Preferences) {
+    let settings = &mut preferences.weather;
+
+    ui.horizontal(|ui| {
+        ComboBox::from_label(t!("preferences.weather_provider"))
+            .selected_text(i18n::name_of("weather_provider", settings.provider))
+            .show_ui(ui, |ui| {
+                for provider in WeatherProvider::iter() {
+                    ui.selectable_value(&mut settings.provider, provider, i18n::name_of("weather_provider", provider));
+                }
+            });
+
+        if settings.provider == WeatherProvider::OpenWeatherMap {
+            ui.add(TextEdit::singleline(&mut settings.api_key).password(true).hint_text(t!("preferences.weather_api_key")).desired_width(140.0));
+        }
+    });
+}
+
+fn light_name(section: Section) -> String {
+    match section {
+        Section::Logo => t!("preferences.light_logo"),
+        Section::Lightbar => t!("preferences.light_lightbar"),
+    }
+}
+
+fn show_window(ui: &mut Ui, preferences: &mut Preferences) {
+    let settings = &mut preferences.window;
+
+    // The window can't be brought back once hidden there
+    ui.add_enabled_ui(!*DENY_HIDING, |ui| {
+        ui.checkbox(&mut settings.minimize_to_tray, t!("preferences.minimize_to_tray"));
+        ui.checkbox(&mut settings.close_to_tray, t!("preferences.close_to_tray"));
+    })
+    .response
+    .on_disabled_hover_text(t!("preferences.tray_unsupported"));
+}
+
+fn show_startup(ui: &mut Ui, preferences: &mut Preferences) {
+    let settings = &mut preferences.startup;
+
+    ui.label(t!("preferences.at_boot")).on_hover_text(t!("preferences.at_boot_hint"));
+    ui.horizontal(|ui| {
+        ui.add(DragValue::new(&mut settings.delay).range(0..=300).suffix(" s"));
+        ui.label(t!("preferences.startup_delay"));
+    });
+    ui.checkbox(&mut settings.retry, t!("preferences.retry"));
+    ui.add_enabled_ui(settings.retry, |ui| {
+        ui.horizontal(|ui| {
+            ui.add(DragValue::new(&mut settings.retry_timeout).range(1..=600).suffix(" s"));
+            ui.label(t!("preferences.retry_timeout"));
+        });
+    });
+
+    ComboBox::from_label(t!("preferences.initial_state"))
+        .selected_text(i18n::name_of("initial_state", settings.initial_state))
+        .show_ui(ui, |ui| {
+            for option in InitialState::iter() {
+                ui.selectable_value(&mut settings.initial_state, option, i18n::name_of("initial_state", option));
+            }
+        })
+        .response
+        .on_hover_text(t!("preferences.initial_state_hint"));
+    ui.checkbox(&mut settings.leave_untouched, t!("preferences.leave_untouched"))
+        .on_hover_text(t!("preferences.leave_untouched_hint"));
+}
+
+fn show_exit(ui: &mut Ui, preferences: &mut Preferences) {
+    let settings = &mut preferences.exit;
+
+    ui.horizontal(|ui| {
+        ComboBox::from_label(t!("preferences.on_exit"))
+            .selected_text(i18n::name_of("exit_behavior", settings.behavior))
+            .show_ui(ui, |ui| {
+                for option in ExitBehavior::iter() {
+                    ui.selectable_value(&mut settings.behavior, option, i18n::name_of("exit_behavior", option));
+                }
+            })
+            .response
+            .on_hover_text(t!("preferences.on_exit_hint"));
+        if settings.behavior == ExitBehavior::Fallback {
+            ui.color_edit_button_srgb(&mut settings.fallback_color);
+        }
+    });
+}
+
+fn show_community(ui: &mut Ui, preferences: &mut Preferences) {
+    let settings = &mut preferences.community;
+
+    ui.add_enabled_ui(cfg!(feature = "network"), |ui| {
+        ui.horizontal(|ui| {
+            ui.label(t!("preferences.community_index"));
+            ui.add(TextEdit::singleline(&mut settings.index_url).hint_text(t!("preferences.community_index_none")).desired_width(220.0));
+        })
+        .response
+        .on_hover_text(t!("preferences.community_index_hint"));
+    })
+    .response
+    .on_disabled_hover_text(t!("preferences.community_unavailable"));
+}
+
+fn show_network(ui: &mut Ui, preferences: &mut Preferences) {
+    ui.add_enabled_ui(cfg!(feature = "network"), |ui| {
+        ui.checkbox(&mut preferences.offline, t!("preferences.offline")).on_hover_text(t!("preferences.offline_hint"));
+    })
+    .response
+    .on_disabled_hover_text(t!("preferences.offline_unavailable"));
+}
@@ -0,0 +1,54 @@
+use std::{env, process::Command};
+
+use eframe::{
+    egui::{Context, RichText, TopBottomPanel},
+    epaint::Color32,
+};
+use rust_i18n::t;
+
+use crate::persist::Settings;
+
+pub enum SafeModeAction {
+    /// Drop the parts of the settings that can't be read and start normally
+    Repair,
+    /// Start over with the default settings
+    Reset,
+    /// Start normally, leaving the settings as they are
+    Restart,
+}
+
+/// Explain why nothing was loaded and offer ways out of safe mode
+pub fn show_banner(ctx: &Context) -> Option<SafeModeAction> {
+    let mut action = None;
+
+    TopBottomPanel::top("safe-mode-banner").show(ctx, |ui| {
+        ui.label(RichText::new(t!("safe_mode.title")).strong().color(Color32::from_rgb(255, 170, 0)));
+        ui.label(t!("safe_mode.explanation"));
+
+        ui.horizontal(|ui| {
+            if ui.button(t!("safe_mode.repair")).on_hover_text(t!("safe_mode.repair_hint")).clicked() {
+                action = Some(SafeModeAction::Repair);
+            }
+            if ui.button(t!("safe_mode.reset")).on_hover_text(t!("safe_mode.reset_hint")).clicked() {
+                action = Some(SafeModeAction::Reset);
+            }
+            if ui.button(t!("safe_mode.restart")).clicked() {
+                action = Some(SafeModeAction::Restart);
+            }
+        });
+    });
+
+    action
+}
+
+/// Start a new instance outside of safe mode, which takes over once this one exits
+pub fn restart() -> std::io::Result<()> {
+    Command::new(env::current_exe()?)
+        .arg("--gui")
+        .arg("--takeover")
+        .arg("--config")
+        .arg(Settings::get_location())
+        .spawn()?;
+
+    Ok(())
+}
@@ -1,12 +1,22 @@
+use std::collections::BTreeMap;
+
 use eframe::{
-    egui::{Context, Frame, RichText, ScrollArea, Ui},
-    epaint::{Color32, Rounding},
+    egui::{CollapsingHeader, Context, DragValue, Frame, Id, Key, Modifiers, Rect, RichText, ScrollArea, Sense, TextEdit, Ui},
+    epaint::{Color32, Rounding, Vec2},
 };
 use egui_modal::Modal;
+use rust_i18n::t;
 
-use crate::manager::{custom_effect::CustomEffect, profile::Profile};
+use crate::{
+    manager::{custom_effect::CustomEffect, profile::Profile},
+    power,
+};
 
-use super::{style::SpacingStyle, LoadedEffect, State};
+use super::{
+    i18n,
+    style::{self, SpacingStyle},
+    LoadedEffect, State,
+};
 
 #[derive(Clone)]
 pub struct SavedItems {
@@ -15,6 +25,27 @@ pub struct SavedItems {
 
     tab: Tab,
     new_item_name: String,
+    search: String,
+    /// The profile being renamed and the name typed so far
+    renaming: Option<(usize, String)>,
+    /// The profile that should take the keyboard focus once shown, after being moved with the keyboard
+    refocus: Option<usize>,
+}
+
+/// Something done to a profile through its button
+enum ProfileAction {
+    /// Another profile was dropped on it, by index
+    Dropped(usize),
+    /// Moved with the keyboard to the previous (-1) or next (1) profile shown
+    Shift(isize),
+    Rename,
+    Duplicate,
+}
+
+/// A profile got renamed, anything referring to it by name has to follow along
+pub struct ProfileRenamed {
+    pub old: String,
+    pub new: String,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq)]
@@ -30,6 +61,9 @@ impl SavedItems {
             custom_effects,
             tab: Tab::Profiles,
             new_item_name: String::default(),
+            search: String::default(),
+            renaming: None,
+            refocus: None,
         }
     }
 
@@ -49,19 +83,19 @@ impl SavedItems {
                 let is_empty = new_item_name.is_empty();
                 let name_not_unique = items.iter().any(|item| item_name_extractor(item) == Some(new_item_name.clone()));
 
-                modal.button(ui, "Cancel");
+                modal.button(ui, t!("saved_items.cancel"));
 
                 ui.add_enabled_ui(!is_empty && !name_not_unique, |ui| {
-                    if modal.button(ui, "Save").clicked() {
+                    if modal.button(ui, t!("saved_items.save")).clicked() {
                         item_name_setter(current_item, new_item_name.clone());
                         items.push(current_item.clone());
                     };
                 });
 
                 if is_empty {
-                    ui.label("You must enter a name");
+                    ui.label(t!("saved_items.name_required"));
                 } else if name_not_unique {
-                    ui.label("Name already in use");
+                    ui.label(t!("saved_items.name_taken"));
                 }
             });
         });
@@ -73,7 +107,7 @@ impl SavedItems {
         Self::setup_modal(
             ctx,
             "profile_modal",
-            "Enter the name of the profile",
+            &t!("saved_items.profile_name_prompt"),
             &mut self.new_item_name,
             &mut self.profiles,
             current_profile,
@@ -86,7 +120,7 @@ impl SavedItems {
         Self::setup_modal(
             ctx,
             "effect_modal",
-            "Enter the name of the custom effect",
+            &t!("saved_items.effect_name_prompt"),
             &mut self.new_item_name,
             &mut self.custom_effects,
             &mut loaded_effect.effect,
@@ -96,8 +130,8 @@ impl SavedItems {
     }
 
     pub fn show_header(&mut self, ctx: &Context, ui: &mut Ui, current_profile: &mut Profile, loaded_effect: &mut LoadedEffect) {
-        ui.selectable_value(&mut self.tab, Tab::Profiles, RichText::new("Profiles").heading());
-        ui.selectable_value(&mut self.tab, Tab::CustomEffects, RichText::new("Custom Effects").heading());
+        ui.selectable_value(&mut self.tab, Tab::Profiles, RichText::new(t!("saved_items.profiles")).heading());
+        ui.selectable_value(&mut self.tab, Tab::CustomEffects, RichText::new(t!("saved_items.custom_effects")).heading());
 
         let profile_modal = self.setup_profile_modal(ctx, current_profile);
         let effect_modal = self.setup_effect_modal(ctx, loaded_effect);
@@ -127,7 +161,10 @@ impl SavedItems {
         }
     }
 
-    pub fn show(&mut self, ctx: &Context, ui: &mut Ui, current_profile: &mut Profile, loaded_effect: &mut LoadedEffect, spacing: &SpacingStyle, changed: &mut bool) {
+    /// Show the saved items, returning the profile that was renamed this frame if any
+    pub fn show(&mut self, ctx: &Context, ui: &mut Ui, current_profile: &mut Profile, loaded_effect: &mut LoadedEffect, spacing: &SpacingStyle, changed: &mut bool) -> Option<ProfileRenamed> {
+        let mut renamed = None;
+
         ui.scope(|ui: &mut Ui| {
             ui.style_mut().spacing.item_spacing = spacing.default;
 
@@ -137,40 +174,322 @@ impl SavedItems {
 
             Frame {
                 rounding: Rounding::same(6.0),
-                fill: Color32::from_gray(20),
+                fill: style::inset_fill(ui.visuals()),
                 ..Frame::default()
             }
             .show(ui, |ui| {
                 ui.set_height(ui.available_height());
 
                 if self.profiles.is_empty() {
-                    ui.centered_and_justified(|ui| ui.label("No profiles added"));
+                    ui.centered_and_justified(|ui| ui.label(t!("saved_items.no_profiles")));
                 } else {
-                    ScrollArea::vertical().auto_shrink([false; 2]).show(ui, |ui| {
-                        ui.horizontal_wrapped(|ui| match self.tab {
-                            Tab::Profiles => {
-                                for prof in self.profiles.iter() {
-                                    let name = prof.name.as_deref().unwrap_or("Unnamed");
-                                    if ui.selectable_value(current_profile, prof.clone(), name).clicked() {
-                                        *changed = true;
-                                        loaded_effect.state = State::None;
-                                    };
-                                }
-                            }
-                            Tab::CustomEffects => {
+                    ui.add(TextEdit::singleline(&mut self.search).hint_text(t!("saved_items.search_hint")).desired_width(f32::INFINITY));
+
+                    ScrollArea::vertical().auto_shrink([false; 2]).show(ui, |ui| match self.tab {
+                        Tab::Profiles => renamed = self.show_profiles(ui, current_profile, loaded_effect, changed),
+                        Tab::CustomEffects => {
+                            let query = self.search.trim().to_lowercase();
+                            ui.horizontal_wrapped(|ui| {
                                 for effect in self.custom_effects.iter() {
-                                    let name = effect.name.as_deref().unwrap_or("Unnamed");
-                                    if ui.selectable_value(&mut loaded_effect.effect, effect.clone(), name).clicked() {
+                                    let name = effect.name.clone().unwrap_or_else(|| t!("saved_items.unnamed"));
+                                    if !name.to_lowercase().contains(&query) {
+                                        continue;
+                                    }
+                                    let response = ui.selectable_value(&mut loaded_effect.effect, effect.clone(), name);
+                                    if response.clicked() {
                                         *changed = true;
                                         loaded_effect.effect = effect.clone();
                                         loaded_effect.state = State::Queued;
                                     };
+                                    if effect.author.is_some() || effect.description.is_some() || effect.thumbnail.is_some() {
+                                        response.on_hover_ui(|ui| show_effect_details(ui, effect));
+                                    }
                                 }
-                            }
-                        });
+                            });
+                        }
                     });
                 }
             });
         });
+
+        renamed
+    }
+
+    /// Show the profiles matching the search, the ones in a group under a collapsible header
+    fn show_profiles(&mut self, ui: &mut Ui, current_profile: &mut Profile, loaded_effect: &mut LoadedEffect, changed: &mut bool) -> Option<ProfileRenamed> {
+        let query = self.search.trim().to_lowercase();
+
+        let mut groups: BTreeMap<Option<String>, Vec<usize>> = BTreeMap::new();
+        for (i, profile) in self.profiles.iter().enumerate().filter(|(_, profile)| profile.matches_search(&query)) {
+            let group = profile.group.as_deref().map(str::trim).filter(|group| !group.is_empty());
+            groups.entry(group.map(str::to_string)).or_default().push(i);
+        }
+
+        if groups.is_empty() {
+            ui.label(t!("saved_items.no_matches"));
+            return None;
+        }
+
+        // Which profile was dropped on which
+        let mut dropped = None;
+        let mut duplicated = None;
+        let mut renamed = None;
+        let refocus = self.refocus.take();
+
+        // Ungrouped profiles sort first
+        for (group, indices) in groups {
+            let show_buttons = |ui: &mut Ui| {
+                ui.horizontal_wrapped(|ui| {
+                    for (position, &i) in indices.iter().enumerate() {
+                        if self.renaming.as_ref().is_some_and(|(renaming, _)| *renaming == i) {
+                            renamed = Self::show_rename_edit(ui, i, &mut self.renaming, &mut self.profiles, current_profile);
+                            continue;
+                        }
+
+                        match Self::show_profile_button(ui, i, &mut self.profiles[i], current_profile, loaded_effect, refocus == Some(i), changed) {
+                            Some(ProfileAction::Dropped(from)) => dropped = Some((from, i)),
+                            Some(ProfileAction::Shift(offset)) => {
+                                if let Some(&to) = position.checked_add_signed(offset).and_then(|position| indices.get(position)) {
+                                    dropped = Some((i, to));
+                                    self.refocus = Some(to);
+                                }
+                            }
+                            Some(ProfileAction::Rename) => self.renaming = Some((i, self.profiles[i].name.clone().unwrap_or_default())),
+                            Some(ProfileAction::Duplicate) => duplicated = Some(i),
+                            None => {}
+                        }
+                    }
+                });
+            };
+
+            match group {
+                None => show_buttons(ui),
+                Some(group) => {
+                    // Expand everything while searching so no match is hidden
+                    let open = (!query.is_empty()).then_some(true);
+                    CollapsingHeader::new(group).default_open(true).open(open).show(ui, show_buttons);
+                }
+            }
+        }
+
+        if let Some((from, to)) = dropped {
+            self.move_profile(from, to);
+        }
+
+        if let Some(i) = duplicated {
+            self.duplicate_profile(i);
+        }
+
+        renamed
+    }
+
+    /// Edit the name of a profile in place of its button, only accepting names that aren't empty or taken
+    fn show_rename_edit(ui: &mut Ui, i: usize, renaming: &mut Option<(usize, String)>, profiles: &mut [Profile], current_profile: &mut Profile) -> Option<ProfileRenamed> {
+        let (_, name) = renaming.as_mut()?;
+
+        let new_name = name.trim().to_string();
+        let error = if new_name.is_empty() {
+            Some(t!("saved_items.name_required"))
+        } else if profiles.iter().enumerate().any(|(j, profile)| j != i && profile.name.as_deref() == Some(new_name.as_str())) {
+            Some(t!("saved_items.name_taken"))
+        } else {
+            None
+        };
+
+        let response = ui.add(TextEdit::singleline(name).desired_width(120.0));
+        if let Some(error) = error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+
+        if !response.lost_focus() {
+            response.request_focus();
+            return None;
+        }
+
+        // Clicking elsewhere or pressing escape cancels, pressing enter on an invalid name keeps editing
+        if !ui.input(|input| input.key_pressed(Key::Enter)) {
+            *renaming = None;
+            return None;
+        }
+        if error.is_some() {
+            response.request_focus();
+            return None;
+        }
+
+        *renaming = None;
+
+        let profile = &mut profiles[i];
+        if *current_profile == *profile {
+            current_profile.name = Some(new_name.clone());
+        }
+        let old_name = profile.name.replace(new_name.clone());
+
+        old_name.filter(|old_name| *old_name != new_name).map(|old| ProfileRenamed { old, new: new_name })
+    }
+
+    /// Insert a copy of a profile right after it, named after the original
+    fn duplicate_profile(&mut self, i: usize) {
+        let mut copy = self.profiles[i].clone();
+        let base = copy.name.clone().unwrap_or_else(|| t!("saved_items.unnamed"));
+
+        let is_taken = |name: &str| self.profiles.iter().any(|profile| profile.name.as_deref() == Some(name));
+        let mut name = t!("saved_items.copy_name", name = base);
+        let mut n = 2;
+        while is_taken(&name) {
+            name = t!("saved_items.numbered_copy_name", name = base, n = n);
+            n += 1;
+        }
+
+        copy.name = Some(name);
+        self.profiles.insert(i + 1, copy);
+    }
+
+    /// Move a profile to the position of another one, also moving it to the same group
+    fn move_profile(&mut self, from: usize, to: usize) {
+        if from == to || from >= self.profiles.len() || to >= self.profiles.len() {
+            return;
+        }
+
+        let group = self.profiles[to].group.clone();
+        let mut profile = self.profiles.remove(from);
+        profile.group = group;
+        self.profiles.insert(to, profile);
+    }
+
+    /// Show the button for a profile that can be dragged onto others to reorder them, returning what was done to it if anything
+    fn show_profile_button(
+        ui: &mut Ui, i: usize, profile: &mut Profile, current_profile: &mut Profile, loaded_effect: &mut LoadedEffect, request_focus: bool, changed: &mut bool,
+    ) -> Option<ProfileAction> {
+        let name = profile.name.clone().unwrap_or_else(|| t!("saved_items.unnamed"));
+        let estimate = power::estimate(profile, None);
+
+        let mut hover_text = format!(
+            "{}\n{}",
+            i18n::name_of("effects", profile.effect),
+            t!("saved_items.power_impact", estimate = i18n::power_estimate(&estimate))
+        );
+        if !profile.tags.is_empty() {
+            hover_text.push('\n');
+            hover_text.push_str(&t!("saved_items.tags_list", tags = profile.tags.join(", ")));
+        }
+        hover_text.push_str("\n\n");
+        hover_text.push_str(&t!("saved_items.drag_hint"));
+        hover_text.push('\n');
+        hover_text.push_str(&t!("saved_items.keyboard_hint"));
+
+        let drag = ui.dnd_drag_source(Id::new(("profile_drag", i)), i, |ui| ui.selectable_value(current_profile, profile.clone(), name).on_hover_text(hover_text));
+        let response = drag.inner;
+
+        if drag.response.dnd_hover_payload::<usize>().is_some_and(|from| *from != i) {
+            ui.painter().rect_stroke(drag.response.rect, Rounding::same(2.0), ui.visuals().selection.stroke);
+        }
+
+        if response.clicked() {
+            *changed = true;
+            loaded_effect.state = State::None;
+        };
+
+        let mut action = None;
+
+        if request_focus {
+            response.request_focus();
+        }
+        if response.gained_focus() {
+            response.scroll_to_me(None);
+        }
+        if response.has_focus() {
+            action = ui.input_mut(|input| {
+                if input.consume_key(Modifiers::NONE, Key::F2) {
+                    Some(ProfileAction::Rename)
+                } else if input.consume_key(Modifiers::COMMAND, Key::D) {
+                    Some(ProfileAction::Duplicate)
+                } else if input.consume_key(Modifiers::ALT, Key::ArrowLeft) || input.consume_key(Modifiers::ALT, Key::ArrowUp) {
+                    Some(ProfileAction::Shift(-1))
+                } else if input.consume_key(Modifiers::ALT, Key::ArrowRight) || input.consume_key(Modifiers::ALT, Key::ArrowDown) {
+                    Some(ProfileAction::Shift(1))
+                } else {
+                    None
+                }
+            });
+        }
+
+        response.context_menu(|ui| {
+            let was_current = *current_profile == *profile;
+
+            if ui.button(t!("saved_items.rename")).clicked() {
+                action = Some(ProfileAction::Rename);
+                ui.close_menu();
+            }
+            if ui.button(t!("saved_items.duplicate")).clicked() {
+                action = Some(ProfileAction::Duplicate);
+                ui.close_menu();
+            }
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.label(t!("saved_items.group"));
+                let mut group = profile.group.clone().unwrap_or_default();
+                if ui.text_edit_singleline(&mut group).changed() {
+                    profile.group = (!group.trim().is_empty()).then_some(group);
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label(t!("saved_items.tags"));
+                // Keep what was typed as is, so separators don't disappear while typing
+                let id = ui.id().with(("profile_tags", i));
+                let mut tags = ui.data_mut(|data| data.get_temp_mut_or_insert_with(id, || profile.tags.join(", ")).clone());
+                if ui.add(TextEdit::singleline(&mut tags).hint_text(t!("saved_items.comma_separated"))).changed() {
+                    profile.tags = tags.split(',').map(str::trim).filter(|tag| !tag.is_empty()).map(str::to_string).collect();
+                    ui.data_mut(|data| data.insert_temp(id, tags));
+                }
+            });
+
+            ui.checkbox(&mut profile.pinned, t!("saved_items.pinned")).on_hover_text(t!("saved_items.pinned_hint"));
+
+            ui.horizontal(|ui| {
+                let mut custom_delay = profile.startup_delay.is_some();
+                if ui.checkbox(&mut custom_delay, t!("saved_items.startup_delay")).on_hover_text(t!("saved_items.startup_delay_hint")).changed() {
+                    profile.startup_delay = custom_delay.then_some(0);
+                }
+                if let Some(delay) = &mut profile.startup_delay {
+                    ui.add(DragValue::new(delay).range(0..=300).suffix(" s"));
+                }
+            });
+
+            if was_current {
+                current_profile.group.clone_from(&profile.group);
+                current_profile.tags.clone_from(&profile.tags);
+                current_profile.startup_delay = profile.startup_delay;
+                current_profile.pinned = profile.pinned;
+            }
+        });
+
+        if let Some(from) = drag.response.dnd_release_payload::<usize>() {
+            action = Some(ProfileAction::Dropped(*from));
+        }
+
+        action
+    }
+}
+
+/// The description and author of a custom effect, above its thumbnail scaled up
+pub(super) fn show_effect_details(ui: &mut Ui, effect: &CustomEffect) {
+    if let Some(description) = &effect.description {
+        ui.label(description);
+    }
+    if let Some(author) = &effect.author {
+        ui.label(RichText::new(t!("saved_items.effect_author", author = author)).small());
+    }
+
+    if let Some(thumbnail) = effect.thumbnail_image() {
+        let (rect, _) = ui.allocate_exact_size(Vec2::new(120.0, 72.0), Sense::hover());
+        let cell = Vec2::new(rect.width() / thumbnail.width() as f32, rect.height() / thumbnail.height() as f32);
+
+        for (x, y, pixel) in thumbnail.enumerate_pixels() {
+            let [r, g, b] = pixel.0;
+            let min = rect.min + Vec2::new(x as f32 * cell.x, y as f32 * cell.y);
+            ui.painter().rect_filled(Rect::from_min_size(min, cell), Rounding::ZERO, Color32::from_rgb(r, g, b));
+        }
     }
 }
@@ -0,0 +1,60 @@
+use eframe::egui::{Context, Grid, Window};
+use rust_i18n::t;
+
+use crate::{manager::keystrokes, usage::UsageRecorder};
+
+pub fn show(ctx: &Context, open: &mut bool, recorder: &mut UsageRecorder, count_keystrokes: &mut bool) {
+    Window::new(t!("statistics.title")).open(open).resizable(false).collapsible(false).show(ctx, |ui| {
+        let stats = recorder.snapshot();
+
+        match stats.most_used_profile() {
+            Some((name, secs)) => ui.label(t!("statistics.most_used_profile", name = name, duration = format_duration(secs))),
+            None => ui.label(t!("statistics.no_profile_used")),
+        };
+
+        ui.separator();
+
+        Grid::new("effect_usage_grid").num_columns(2).striped(true).show(ui, |ui| {
+            for (effect, secs) in stats.effects_by_usage() {
+                ui.label(effect);
+                ui.label(format_duration(secs));
+                ui.end_row();
+            }
+        });
+
+        ui.separator();
+
+        ui.checkbox(count_keystrokes, t!("statistics.count_keystrokes"));
+        if *count_keystrokes || stats.keystrokes.iter().any(|count| *count > 0) {
+            ui.label(t!("statistics.keystrokes"));
+            Grid::new("keystroke_grid").num_columns(2).striped(true).show(ui, |ui| {
+                for (zone, count) in stats.keystrokes.iter().enumerate() {
+                    ui.label(t!("preferences.zone", zone = zone + 1));
+                    ui.label(count.to_string());
+                    ui.end_row();
+                }
+            });
+            if ui.button(t!("statistics.reset_keystrokes")).clicked() {
+                keystrokes::reset();
+            }
+        }
+
+        ui.separator();
+
+        ui.label(t!("statistics.private"));
+        if ui.button(t!("statistics.clear")).clicked() {
+            recorder.clear();
+        }
+    });
+}
+
+fn format_duration(secs: u64) -> String {
+    let hours = secs / 3600;
+    let minutes = (secs % 3600) / 60;
+
+    if hours > 0 {
+        t!("statistics.hours_minutes", hours = hours, minutes = minutes)
+    } else {
+        t!("statistics.minutes", minutes = minutes)
+    }
+}
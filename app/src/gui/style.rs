@@ -3,26 +3,63 @@ use eframe::{
     epaint::{Color32, Stroke, Vec2},
 };
 
+use crate::appearance::Appearance;
+
 pub struct Theme {
     pub spacing: SpacingStyle,
-    pub visuals: Visuals,
+    pub dark: Visuals,
+    pub light: Visuals,
 }
 
-impl Default for Theme {
-    fn default() -> Self {
+impl Theme {
+    pub fn new(appearance: &Appearance) -> Self {
+        let [r, g, b] = appearance.accent;
+        let accent = Color32::from_rgb(r, g, b);
+
         Self {
             spacing: SpacingStyle::default(),
-            visuals: Visuals {
+            dark: Visuals {
                 selection: Selection {
-                    bg_fill: Color32::from_rgb(40, 100, 160),
-                    stroke: Stroke::new(1.0, Color32::from_rgb(192, 222, 255)),
+                    bg_fill: accent,
+                    stroke: Stroke::new(1.0, accent.lerp_to_gamma(Color32::WHITE, 0.75)),
                 },
-                ..Visuals::default()
+                ..Visuals::dark()
+            },
+            light: Visuals {
+                selection: Selection {
+                    bg_fill: accent.lerp_to_gamma(Color32::WHITE, 0.6),
+                    stroke: Stroke::new(1.0, accent.lerp_to_gamma(Color32::BLACK, 0.5)),
+                },
+                ..Visuals::light()
             },
         }
     }
 }
 
+impl Default for Theme {
+    fn default() -> Self {
+        Self::new(&Appearance::default())
+    }
+}
+
+/// Background of the main panel
+pub fn panel_fill(visuals: &Visuals) -> Color32 {
+    if visuals.dark_mode {
+        Color32::from_gray(26)
+    } else {
+        Color32::from_gray(240)
+    }
+}
+
+/// Background of the lists and previews set into the main panel
+pub fn inset_fill(visuals: &Visuals) -> Color32 {
+    if visuals.dark_mode {
+        Color32::from_gray(20)
+    } else {
+        Color32::from_gray(228)
+    }
+}
+
 pub struct SpacingStyle {
     pub default: Vec2,
     pub large: f32,
@@ -0,0 +1,129 @@
+use std::{path::PathBuf, time::Duration};
+
+use eframe::{
+    egui::{Button, Context, Key, Modifiers, Sense, TextEdit, Ui, WidgetType},
+    epaint::{Color32, Rounding, Stroke, Vec2},
+};
+use egui_file::FileDialog;
+use egui_notify::Toasts;
+use rust_i18n::t;
+
+use super::accessibility;
+use crate::manager::{
+    profile::Profile,
+    swatch::{self, Swatch},
+};
+
+const SWATCH_SIZE: f32 = 20.0;
+
+/// Favorite colors shared across profiles
+pub struct SwatchPanel {
+    pub swatches: Vec<Swatch>,
+
+    import_dialog: FileDialog,
+    export_dialog: FileDialog,
+}
+
+impl SwatchPanel {
+    pub fn new(swatches: Vec<Swatch>) -> Self {
+        Self {
+            swatches,
+            import_dialog: FileDialog::open_file(None).default_size(Vec2::splat(300.0)),
+            export_dialog: FileDialog::save_file(None).default_size(Vec2::splat(300.0)),
+        }
+    }
+
+    /// Show the swatches, `new_color` being what gets saved when adding one
+    pub fn show(&mut self, ctx: &Context, ui: &mut Ui, current_profile: &mut Profile, new_color: [u8; 3], changed: &mut bool, toasts: &mut Toasts) {
+        let mut remove = None;
+
+        ui.horizontal_wrapped(|ui| {
+            for (i, swatch) in self.swatches.iter_mut().enumerate() {
+                let response = show_swatch(ui, swatch.rgb).on_hover_text(t!("swatches.hover", name = swatch.name, hex = swatch.hex()));
+                accessibility::describe(&response, WidgetType::Button, t!("accessibility.swatch", name = swatch.name, hex = swatch.hex()));
+
+                if response.clicked() {
+                    for zone in &mut current_profile.rgb_zones {
+                        zone.rgb = swatch.rgb;
+                    }
+                    *changed = true;
+                }
+                if response.has_focus() && ui.input_mut(|input| input.consume_key(Modifiers::NONE, Key::Delete)) {
+                    remove = Some(i);
+                }
+
+                response.context_menu(|ui| {
+                    ui.add(TextEdit::singleline(&mut swatch.name).hint_text(t!("swatches.name")).desired_width(120.0));
+                    for (zone_index, zone) in current_profile.rgb_zones.iter_mut().enumerate() {
+                        if ui.button(t!("swatches.apply_to_zone", zone = zone_index + 1)).clicked() {
+                            zone.rgb = swatch.rgb;
+                            *changed = true;
+                            ui.close_menu();
+                        }
+                    }
+                    ui.separator();
+                    if ui.button(t!("swatches.remove")).clicked() {
+                        remove = Some(i);
+                        ui.close_menu();
+                    }
+                });
+            }
+
+            if ui.add(Button::new("+").min_size(Vec2::splat(SWATCH_SIZE))).on_hover_text(t!("swatches.add_hint")).clicked() {
+                self.swatches.push(Swatch::unnamed(new_color));
+            }
+
+            ui.menu_button("...", |ui| {
+                if ui.button(t!("swatches.import")).clicked() {
+                    self.import_dialog.open();
+                    ui.close_menu();
+                }
+                if ui.add_enabled(!self.swatches.is_empty(), Button::new(t!("swatches.export"))).clicked() {
+                    self.export_dialog.open();
+                    ui.close_menu();
+                }
+            })
+            .response
+            .on_hover_text(t!("swatches.share_hint"));
+        });
+
+        if let Some(i) = remove {
+            self.swatches.remove(i);
+        }
+
+        self.handle_dialogs(ctx, toasts);
+    }
+
+    fn handle_dialogs(&mut self, ctx: &Context, toasts: &mut Toasts) {
+        if self.import_dialog.show(ctx).selected() {
+            if let Some(path) = self.import_dialog.path().map(PathBuf::from) {
+                match swatch::import(&path) {
+                    Ok(swatches) => {
+                        toasts.info(t!("swatches.imported", count = swatches.len())).duration(Some(Duration::from_millis(3000)));
+                        self.swatches.extend(swatches);
+                    }
+                    Err(_) => {
+                        toasts.error(t!("swatches.import_failed")).duration(Some(Duration::from_millis(5000))).closable(true);
+                    }
+                }
+            }
+        }
+
+        if self.export_dialog.show(ctx).selected() {
+            if let Some(path) = self.export_dialog.path().map(PathBuf::from) {
+                if swatch::export(&self.swatches, &path).is_err() {
+                    toasts.error(t!("swatches.export_failed")).duration(Some(Duration::from_millis(5000))).closable(true);
+                }
+            }
+        }
+    }
+}
+
+fn show_swatch(ui: &mut Ui, [r, g, b]: [u8; 3]) -> eframe::egui::Response {
+    let (rect, response) = ui.allocate_exact_size(Vec2::splat(SWATCH_SIZE), Sense::click());
+
+    let stroke = if response.hovered() || response.has_focus() { ui.visuals().selection.stroke } else { Stroke::new(1.0, Color32::from_gray(60)) };
+    ui.painter().rect(rect, Rounding::same(4.0), Color32::from_rgb(r, g, b), stroke);
+
+    response
+}
@@ -0,0 +1,66 @@
+use eframe::egui::{ComboBox, Context, DragValue, Grid, TextEdit, Window};
+use rust_i18n::t;
+use strum::IntoEnumIterator;
+
+use super::i18n;
+use crate::triggers::{Trigger, TriggerKind};
+
+/// Names, sources and flash patterns of the triggers, returns the name of the one to test-fire, if asked to
+pub fn show(ctx: &Context, open: &mut bool, triggers: &mut Vec<Trigger>) -> Option<String> {
+    let mut test = None;
+
+    Window::new(t!("triggers.title")).open(open).resizable(false).collapsible(false).show(ctx, |ui| {
+        ui.label(t!("triggers.hint"));
+
+        let mut remove = None;
+        Grid::new("triggers_grid").num_columns(8).show(ui, |ui| {
+            for (i, trigger) in triggers.iter_mut().enumerate() {
+                ui.add(TextEdit::singleline(&mut trigger.name).hint_text(t!("triggers.name")).desired_width(100.0));
+
+                ComboBox::from_id_salt(("trigger_kind", i))
+                    .selected_text(i18n::name_of("trigger_kind", trigger.kind))
+                    .show_ui(ui, |ui| {
+                        for kind in TriggerKind::iter() {
+                            ui.selectable_value(&mut trigger.kind, kind, i18n::name_of("trigger_kind", kind));
+                        }
+                    });
+
+                if trigger.kind == TriggerKind::PolledUrl {
+                    ui.add(TextEdit::singleline(&mut trigger.url).hint_text("http://").desired_width(160.0));
+                    ui.add(TextEdit::singleline(&mut trigger.contains).hint_text(t!("triggers.contains")).desired_width(80.0));
+                    ui.add(DragValue::new(&mut trigger.interval_secs).range(5..=86400).suffix(" s"));
+                } else {
+                    ui.label(format!("POST /trigger/{}", trigger.name));
+                    ui.label("");
+                    ui.label("");
+                }
+
+                ui.horizontal(|ui| {
+                    ui.color_edit_button_srgb(&mut trigger.pattern.rgb);
+                    ui.add(DragValue::new(&mut trigger.pattern.count).range(1..=20).suffix("×"));
+                });
+
+                if ui.button(t!("triggers.test")).clicked() {
+                    test = Some(trigger.name.clone());
+                }
+                if ui.button("-").clicked() {
+                    remove = Some(i);
+                }
+                ui.end_row();
+            }
+        });
+
+        if let Some(i) = remove {
+            triggers.remove(i);
+        }
+
+        if ui.button(t!("triggers.add")).clicked() {
+            triggers.push(Trigger {
+                name: t!("triggers.default_name", number = triggers.len() + 1),
+                ..Trigger::default()
+            });
+        }
+    });
+
+    test
+}
@@ -0,0 +1,175 @@
+//! Window showing the release notes of a newer version, with a way to download it or, for the portable Windows build,
+//! to update in place.
+
+use std::{thread, time::Duration};
+
+use crossbeam_channel::Sender;
+use eframe::egui::{Context, RichText, ScrollArea, Ui, Window};
+use egui_notify::Toasts;
+use rust_i18n::t;
+use tracing::error;
+
+use crate::updates::{self, Release};
+
+use super::GuiMessage;
+
+/// What the background checks and downloads report back
+pub enum UpdateEvent {
+    Found(Release),
+    UpToDate,
+    CheckFailed,
+    Installed,
+    InstallFailed,
+}
+
+pub struct UpdateWindow {
+    pub open: bool,
+    gui_sender: Sender<GuiMessage>,
+    release: Option<Release>,
+    checking: bool,
+    /// Asked for from the menu, so finding nothing is worth telling about
+    manual: bool,
+    installing: bool,
+    installed: bool,
+}
+
+impl UpdateWindow {
+    pub fn new(gui_sender: Sender<GuiMessage>) -> Self {
+        Self {
+            open: false,
+            gui_sender,
+            release: None,
+            checking: false,
+            manual: false,
+            installing: false,
+            installed: false,
+        }
+    }
+
+    /// Look for a new release in the background, opening the window if there's one
+    pub fn check(&mut self, ctx: &Context, manual: bool) {
+        self.manual |= manual;
+        if self.checking {
+            return;
+        }
+        self.checking = true;
+
+        let ctx = ctx.clone();
+        let gui_sender = self.gui_sender.clone();
+        thread::spawn(move || {
+            let event = match updates::check() {
+                Ok(Some(release)) => UpdateEvent::Found(release),
+                Ok(None) => UpdateEvent::UpToDate,
+                Err(err) => {
+                    error!("Could not check for updates: {err:?}");
+                    UpdateEvent::CheckFailed
+                }
+            };
+            let _ = gui_sender.send(GuiMessage::Update(event));
+            ctx.request_repaint();
+        });
+    }
+
+    pub fn handle(&mut self, event: UpdateEvent, toasts: &mut Toasts) {
+        let manual = self.manual;
+        if matches!(event, UpdateEvent::Found(_) | UpdateEvent::UpToDate | UpdateEvent::CheckFailed) {
+            self.checking = false;
+            self.manual = false;
+        }
+
+        match event {
+            UpdateEvent::Found(release) => {
+                self.release = Some(release);
+                self.open = true;
+            }
+            UpdateEvent::UpToDate if manual => {
+                toasts.info(t!("updates.up_to_date")).duration(Some(Duration::from_millis(5000))).closable(true);
+            }
+            UpdateEvent::CheckFailed if manual => {
+                toasts.error(t!("updates.check_failed")).duration(Some(Duration::from_millis(5000))).closable(true);
+            }
+            UpdateEvent::UpToDate | UpdateEvent::CheckFailed => {}
+            UpdateEvent::Installed => {
+                self.installing = false;
+                self.installed = true;
+            }
+            UpdateEvent::InstallFailed => {
+                self.installing = false;
+                toasts.error(t!("updates.install_failed")).duration(Some(Duration::from_millis(5000))).closable(true);
+            }
+        }
+    }
+
+    pub fn show(&mut self, ctx: &Context, toasts: &mut Toasts) {
+        let Some(release) = self.release.clone() else {
+            return;
+        };
+        let mut open = self.open;
+
+        Window::new(t!("updates.title")).open(&mut open).collapsible(false).default_width(400.0).show(ctx, |ui| {
+            ui.heading(t!("updates.available", version = release.version));
+            ui.label(t!("updates.current", version = env!("CARGO_PKG_VERSION")));
+            ui.separator();
+
+            ScrollArea::vertical().max_height(300.0).show(ui, |ui| show_changelog(ui, &release.changelog));
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                if self.installed {
+                    ui.label(t!("updates.installed"));
+                } else if self.installing {
+                    ui.spinner();
+                    ui.label(t!("updates.installing"));
+                } else {
+                    if updates::can_self_update(&release) && ui.button(t!("updates.install")).clicked() {
+                        self.install(ctx, release.clone());
+                    }
+                    if ui.button(t!("updates.download")).clicked() && open::that(release.download_link()).is_err() {
+                        toasts.error(t!("updates.open_failed")).duration(Some(Duration::from_millis(5000))).closable(true);
+                    }
+                    if release.download_url.is_some() && ui.button(t!("updates.release_page")).clicked() && open::that(&release.page_url).is_err() {
+                        toasts.error(t!("updates.open_failed")).duration(Some(Duration::from_millis(5000))).closable(true);
+                    }
+                }
+            });
+        });
+
+        self.open = open;
+    }
+
+    fn install(&mut self, ctx: &Context, release: Release) {
+        self.installing = true;
+
+        let ctx = ctx.clone();
+        let gui_sender = self.gui_sender.clone();
+        thread::spawn(move || {
+            let event = match updates::install(&release) {
+                Ok(()) => UpdateEvent::Installed,
+                Err(err) => {
+                    error!("Could not install the update: {err:?}");
+                    UpdateEvent::InstallFailed
+                }
+            };
+            let _ = gui_sender.send(GuiMessage::Update(event));
+            ctx.request_repaint();
+        });
+    }
+}
+
+/// Lay out the Markdown of the release notes well enough to read, headings in bold and list items with a bullet
+fn show_changelog(ui: &mut Ui, changelog: &str) {
+    if changelog.trim().is_empty() {
+        ui.label(t!("updates.no_changelog"));
+        return;
+    }
+
+    for line in changelog.lines().map(str::trim) {
+        if line.starts_with('#') {
+            ui.label(RichText::new(line.trim_start_matches('#').trim()).strong());
+        } else if let Some(item) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")) {
+            ui.label(format!("• {item}"));
+        } else if !line.is_empty() {
+            ui.label(line);
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+
+use crossbeam_channel::Sender;
+use eframe::egui::{ColorImage, Context, Image, RichText, ScrollArea, TextureHandle, TextureOptions, Ui, Window};
+use rust_i18n::t;
+use serde::Deserialize;
+
+use crate::util::parse_version;
+
+use super::GuiMessage;
+
+const CHANGELOG: &str = include_str!("../../res/changelog.json");
+
+/// Images the changelog can refer to by name
+fn bundled_image(name: &str) -> Option<&'static [u8]> {
+    match name {
+        "preview" => Some(include_bytes!("../../../Preview.png")),
+        _ => None,
+    }
+}
+
+#[derive(Deserialize)]
+struct Release {
+    version: String,
+    #[serde(default)]
+    image: Option<String>,
+    #[serde(default)]
+    features: Vec<Entry>,
+    #[serde(default)]
+    fixes: Vec<Entry>,
+}
+
+#[derive(Deserialize)]
+struct Entry {
+    text: String,
+    /// Where the change can be found, if it's a setting or window
+    #[serde(default)]
+    link: Option<Link>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+enum Link {
+    Preferences,
+    Palettes,
+    Statistics,
+}
+
+impl Link {
+    fn message(self) -> GuiMessage {
+        match self {
+            Self::Preferences => GuiMessage::OpenPreferences,
+            Self::Palettes => GuiMessage::OpenPaletteEditor,
+            Self::Statistics => GuiMessage::OpenStatistics,
+        }
+    }
+}
+
+/// Lists the changes made since the last version that was run
+pub struct WhatsNew {
+    pub open: bool,
+    gui_sender: Sender<GuiMessage>,
+    releases: Vec<Release>,
+    textures: HashMap<String, TextureHandle>,
+}
+
+impl WhatsNew {
+    /// Pick the releases newer than `last_seen`, only the current one if it's unknown
+    pub fn new(gui_sender: Sender<GuiMessage>, last_seen: Option<&str>) -> Self {
+        let current = parse_version(env!("CARGO_PKG_VERSION"));
+        let last_seen = last_seen.and_then(parse_version);
+
+        let releases: Vec<Release> = serde_json::from_str::<Vec<Release>>(CHANGELOG)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|release| {
+                let version = parse_version(&release.version);
+                match last_seen {
+                    Some(last_seen) => version > Some(last_seen) && version <= current,
+                    None => version == current,
+                }
+            })
+            .collect();
+
+        Self {
+            open: false,
+            gui_sender,
+            releases,
+            textures: HashMap::new(),
+        }
+    }
+
+    pub fn has_news(&self) -> bool {
+        !self.releases.is_empty()
+    }
+
+    pub fn show(&mut self, ctx: &Context) {
+        let mut open = self.open;
+
+        Window::new(t!("whats_new.title")).open(&mut open).collapsible(false).default_width(360.0).show(ctx, |ui| {
+            ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                if self.releases.is_empty() {
+                    ui.label(t!("whats_new.nothing_new"));
+                }
+
+                for release in &self.releases {
+                    ui.heading(t!("whats_new.version", version = release.version));
+
+                    if let Some(texture) = release.image.as_deref().and_then(|name| load_texture(ctx, &mut self.textures, name)) {
+                        ui.add(Image::new(texture).max_width(ui.available_width()));
+                    }
+
+                    show_entries(ui, &t!("whats_new.features"), &release.features, &self.gui_sender);
+                    show_entries(ui, &t!("whats_new.fixes"), &release.fixes, &self.gui_sender);
+                    ui.separator();
+                }
+            });
+        });
+
+        self.open = open;
+    }
+}
+
+fn show_entries(ui: &mut Ui, title: &str, entries: &[Entry], gui_sender: &Sender<GuiMessage>) {
+    if entries.is_empty() {
+        return;
+    }
+
+    ui.label(RichText::new(title).strong());
+    for entry in entries {
+        ui.horizontal_wrapped(|ui| {
+            ui.label(format!("• {}", entry.text));
+            if let Some(link) = entry.link {
+                if ui.small_button(t!("whats_new.open")).clicked() {
+                    let _ = gui_sender.send(link.message());
+                }
+            }
+        });
+    }
+}
+
+fn load_texture<'a>(ctx: &Context, textures: &'a mut HashMap<String, TextureHandle>, name: &str) -> Option<&'a TextureHandle> {
+    if !textures.contains_key(name) {
+        let image = image::load_from_memory(bundled_image(name)?).ok()?.to_rgba8();
+        let size = [image.width() as usize, image.height() as usize];
+        let texture = ctx.load_texture(name, ColorImage::from_rgba_unmultiplied(size, image.as_raw()), TextureOptions::LINEAR);
+        textures.insert(name.to_string(), texture);
+    }
+
+    textures.get(name)
+}
@@ -0,0 +1,57 @@
+//! Colors that go well together, spread around the color wheel from a single seed color.
+//!
+//! Each rule leaves some room for chance, how far apart the hues are or how much darker the shades get, so rolling
+//! again from the same seed gives a different take on it.
+
+use rand::Rng;
+use strum_macros::{EnumIter, IntoStaticStr};
+
+use crate::util::hsv_to_rgb;
+
+#[cfg(test)]
+mod tests;
+
+/// How the colors of the zones relate to the seed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, EnumIter, IntoStaticStr)]
+pub enum Harmony {
+    /// Neighbouring hues, a gentle gradient
+    #[default]
+    Analogous,
+    /// The opposite hue every other zone
+    Complementary,
+    /// Three hues evenly spread, the fourth zone a darker take on the seed
+    Triadic,
+    /// The hue of the seed throughout, darker and paler from one zone to the next
+    Monochrome,
+}
+
+impl Harmony {
+    /// The colors of the four zones, the first one being the seed, given as hue (in degrees), saturation and value
+    pub fn zones(self, seed: (f32, f32, f32), rng: &mut impl Rng) -> [[u8; 3]; 4] {
+        let (hue, saturation, value) = seed;
+
+        // Hue offset in degrees, and how much of the saturation and value of the seed each zone keeps
+        let zones: [(f32, f32, f32); 4] = match self {
+            Self::Analogous => {
+                let spread = rng.gen_range(15.0..=40.0) * if rng.gen_bool(0.5) { 1.0 } else { -1.0 };
+                [0.0, 1.0, 2.0, 3.0].map(|step| (spread * step, 1.0, 1.0))
+            }
+            Self::Complementary => {
+                // Nudge the second pair off the first, a split complement
+                let split = rng.gen_range(-25.0..=25.0);
+                [(0.0, 1.0, 1.0), (180.0, 1.0, 1.0), (split, 1.0, 0.8), (180.0 + split, 1.0, 0.8)]
+            }
+            Self::Triadic => {
+                let shade = rng.gen_range(0.4..=0.7);
+                [(0.0, 1.0, 1.0), (120.0, 1.0, 1.0), (240.0, 1.0, 1.0), (0.0, 1.0, shade)]
+            }
+            Self::Monochrome => {
+                let step = rng.gen_range(0.12..=0.25);
+                let fade = rng.gen_range(0.0..=0.15);
+                [0.0, 1.0, 2.0, 3.0].map(|i| (0.0, 1.0 - fade * i, 1.0 - step * i))
+            }
+        };
+
+        zones.map(|(offset, keep_saturation, keep_value)| hsv_to_rgb(hue + offset, saturation * keep_saturation, (value * keep_value).max(0.05)))
+    }
+}
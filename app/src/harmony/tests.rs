@@ -0,0 +1,102 @@
+//! Checks that every harmony lays its hues out as described, whatever the dice say.
+
+use rand::{rngs::StdRng, SeedableRng};
+use strum::IntoEnumIterator;
+
+use super::Harmony;
+use crate::util::{hsv_to_rgb, rgb_to_hsv};
+
+const SEED: (f32, f32, f32) = (200.0, 1.0, 1.0);
+/// Going through 8 bits a channel costs a little precision
+const HUE_TOLERANCE: f32 = 3.0;
+
+/// Degrees from `from` to `to` going the short way around, negative going backwards
+fn hue_offset(from: f32, to: f32) -> f32 {
+    (to - from + 180.0).rem_euclid(360.0) - 180.0
+}
+
+fn hues(zones: [[u8; 3]; 4]) -> [f32; 4] {
+    zones.map(|rgb| rgb_to_hsv(rgb).0)
+}
+
+/// Every harmony rolled many times over, so the checks hold for any take on it
+fn rolls(harmony: Harmony) -> impl Iterator<Item = [[u8; 3]; 4]> {
+    let mut rng = StdRng::seed_from_u64(42);
+    (0..100).map(move |_| harmony.zones(SEED, &mut rng))
+}
+
+fn assert_offset(from: f32, to: f32, expected: f32) {
+    let offset = hue_offset(from, to);
+    assert!((offset - expected).abs() <= HUE_TOLERANCE, "{to} is {offset} degrees from {from}, not {expected}");
+}
+
+#[test]
+fn first_zone_is_the_seed() {
+    for harmony in Harmony::iter() {
+        for zones in rolls(harmony) {
+            assert_eq!(zones[0], hsv_to_rgb(SEED.0, SEED.1, SEED.2), "{harmony:?}");
+        }
+    }
+}
+
+#[test]
+fn analogous_hues_step_evenly_one_way() {
+    for zones in rolls(Harmony::Analogous) {
+        let hues = hues(zones);
+        let spread = hue_offset(hues[0], hues[1]);
+
+        assert!((15.0 - HUE_TOLERANCE..=40.0 + HUE_TOLERANCE).contains(&spread.abs()), "spread of {spread}");
+        assert_offset(hues[0], hues[2], 2.0 * spread);
+        assert_offset(hues[0], hues[3], 3.0 * spread);
+    }
+}
+
+#[test]
+fn complementary_pairs_face_each_other() {
+    for zones in rolls(Harmony::Complementary) {
+        let hues = hues(zones);
+
+        assert!((hue_offset(hues[0], hues[1]).abs() - 180.0).abs() <= HUE_TOLERANCE, "hues {hues:?}");
+        assert!((hue_offset(hues[2], hues[3]).abs() - 180.0).abs() <= HUE_TOLERANCE, "hues {hues:?}");
+        assert!(hue_offset(hues[0], hues[2]).abs() <= 25.0 + HUE_TOLERANCE);
+        // The second pair is the dimmer one
+        assert!(rgb_to_hsv(zones[2]).2 < rgb_to_hsv(zones[0]).2);
+    }
+}
+
+#[test]
+fn triadic_hues_are_a_third_apart() {
+    for zones in rolls(Harmony::Triadic) {
+        let hues = hues(zones);
+
+        assert_offset(hues[0], hues[1], 120.0);
+        assert_offset(hues[0], hues[2], -120.0);
+        assert_offset(hues[0], hues[3], 0.0);
+        assert!(rgb_to_hsv(zones[3]).2 < rgb_to_hsv(zones[0]).2);
+    }
+}
+
+#[test]
+fn monochrome_keeps_the_hue_and_darkens() {
+    for zones in rolls(Harmony::Monochrome) {
+        let hues = hues(zones);
+        let values = zones.map(|rgb| rgb_to_hsv(rgb).2);
+
+        for hue in &hues[1..] {
+            assert_offset(hues[0], *hue, 0.0);
+        }
+        assert!(values.windows(2).all(|pair| pair[1] < pair[0]), "values {values:?}");
+    }
+}
+
+#[test]
+fn no_zone_goes_dark() {
+    let mut rng = StdRng::seed_from_u64(7);
+
+    for harmony in Harmony::iter() {
+        for value in [0.0, 0.05, 0.3] {
+            let zones = harmony.zones((0.0, 1.0, value), &mut rng);
+            assert!(zones.iter().all(|rgb| rgb.iter().any(|channel| *channel > 0)), "{harmony:?} at {value}: {zones:?}");
+        }
+    }
+}
@@ -0,0 +1,273 @@
+//! Global hotkeys that work while the window isn't focused.
+//!
+//! The keyboard is polled through device_query wherever it can be read directly. Wayland compositors don't allow
+//! that, so there the hotkeys are registered through the GlobalShortcuts desktop portal instead, which lets the
+//! user confirm (and rebind) them from the system settings.
+
+#[cfg(feature = "gui")]
+use std::{
+    str::FromStr,
+    sync::{LazyLock, RwLock},
+    thread,
+    time::Duration,
+};
+
+#[cfg(feature = "gui")]
+use device_query::{DeviceQuery, DeviceState, Keycode};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "gui")]
+use tracing::warn;
+
+#[cfg(feature = "gui")]
+use crate::ticks;
+
+/// How often the keyboard is polled for the combinations
+#[cfg(feature = "gui")]
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[cfg(feature = "gui")]
+static BINDINGS: LazyLock<RwLock<HotkeySettings>> = LazyLock::new(RwLock::default);
+
+/// The keys of the hotkeys that can be rebound, as key names joined by `+` like `RAlt+LMeta+L`, empty to disable them
+///
+/// Only used when polling the keyboard, the portal has the hotkeys rebound from the system settings instead.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HotkeySettings {
+    pub toggle_lights: String,
+    pub brightness_up: String,
+    pub brightness_down: String,
+}
+
+impl Default for HotkeySettings {
+    fn default() -> Self {
+        Self {
+            toggle_lights: "RAlt+LMeta+L".to_string(),
+            brightness_up: "RAlt+LMeta+Up".to_string(),
+            brightness_down: "RAlt+LMeta+Down".to_string(),
+        }
+    }
+}
+
+/// Change the keys of the hotkeys that can be rebound, taking effect on the next poll
+#[cfg(feature = "gui")]
+pub fn set_bindings(settings: &HotkeySettings) {
+    settings.clone_into(&mut BINDINGS.write().unwrap());
+}
+
+/// The keys of a combination like `RAlt+LMeta+L`, `None` if one of them isn't known
+#[cfg(feature = "gui")]
+pub fn parse(combination: &str) -> Option<Vec<Keycode>> {
+    combination.split('+').map(str::trim).filter(|key| !key.is_empty()).map(|key| Keycode::from_str(key).ok()).collect()
+}
+
+#[cfg(feature = "gui")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hotkey {
+    CycleProfiles,
+    ToggleLights,
+    BrightnessUp,
+    BrightnessDown,
+}
+
+#[cfg(feature = "gui")]
+impl Hotkey {
+    const ALL: [Self; 4] = [Self::CycleProfiles, Self::ToggleLights, Self::BrightnessUp, Self::BrightnessDown];
+
+    /// Keys that have to be held together when polling the keyboard, none if the hotkey is disabled
+    fn keys(self) -> Vec<Keycode> {
+        match self {
+            Self::CycleProfiles => vec![Keycode::LMeta, Keycode::RAlt],
+            Self::ToggleLights => parse(&BINDINGS.read().unwrap().toggle_lights).unwrap_or_default(),
+            Self::BrightnessUp => parse(&BINDINGS.read().unwrap().brightness_up).unwrap_or_default(),
+            Self::BrightnessDown => parse(&BINDINGS.read().unwrap().brightness_down).unwrap_or_default(),
+        }
+    }
+
+    /// Identifier the portal knows the hotkey by
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    fn id(self) -> &'static str {
+        match self {
+            Self::CycleProfiles => "cycle-profiles",
+            Self::ToggleLights => "toggle-lights",
+            Self::BrightnessUp => "brightness-up",
+            Self::BrightnessDown => "brightness-down",
+        }
+    }
+
+    /// Shown by the desktop when asking the user to confirm the hotkey
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    fn description(self) -> &'static str {
+        match self {
+            Self::CycleProfiles => "Switch to the next keyboard profile",
+            Self::ToggleLights => "Turn the keyboard lights off and back on",
+            Self::BrightnessUp => "Make the keyboard lights brighter",
+            Self::BrightnessDown => "Make the keyboard lights dimmer",
+        }
+    }
+
+    /// Suggested to the portal, using the XDG shortcuts format. A modifier can't be bound on its own there.
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    fn preferred_trigger(self) -> &'static str {
+        match self {
+            Self::CycleProfiles => "LOGO+ALT+k",
+            Self::ToggleLights => "LOGO+ALT+l",
+            Self::BrightnessUp => "LOGO+ALT+Up",
+            Self::BrightnessDown => "LOGO+ALT+Down",
+        }
+    }
+}
+
+/// Spawn a background thread that calls `on_hotkey` whenever one of the hotkeys is pressed
+#[cfg(feature = "gui")]
+pub fn spawn_listener<F: Fn(Hotkey) + Send + 'static>(on_hotkey: F) {
+    thread::spawn(move || {
+        #[cfg(target_os = "linux")]
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            match portal::listen(&on_hotkey) {
+                Ok(()) => return,
+                // Not every compositor implements the portal, polling still sees keys pressed in XWayland windows
+                Err(err) => warn!("Global shortcuts portal unavailable, falling back to polling: {err}"),
+            }
+        }
+
+        poll(&on_hotkey);
+    });
+}
+
+#[cfg(feature = "gui")]
+fn poll(on_hotkey: &dyn Fn(Hotkey)) {
+    // Only X11 can be missing, the other platforms always have a way to ask
+    #[cfg(target_os = "linux")]
+    let Some(state) = DeviceState::checked_new() else {
+        warn!("Hotkeys are unavailable, the keyboard can't be read without X11");
+        return;
+    };
+    #[cfg(not(target_os = "linux"))]
+    let state = DeviceState::new();
+    let mut held = Vec::new();
+    // Hotkeys held down whose keys are all part of another one, which only fire once released without it being pressed
+    let mut pending = Vec::new();
+
+    loop {
+        let keys = state.get_keys();
+        let bindings = Hotkey::ALL.map(|hotkey| (hotkey, hotkey.keys()));
+        let is_part_of = |inner: &[Keycode], outer: &[Keycode]| outer.len() > inner.len() && inner.iter().all(|key| outer.contains(key));
+
+        for (hotkey, hotkey_keys) in &bindings {
+            let pressed = !hotkey_keys.is_empty() && hotkey_keys.iter().all(|key| keys.contains(key));
+
+            // Only fire once per press
+            if pressed && !held.contains(hotkey) {
+                held.push(*hotkey);
+                pending.retain(|other: &Hotkey| !is_part_of(&other.keys(), hotkey_keys));
+
+                if bindings.iter().any(|(_, other_keys)| is_part_of(hotkey_keys, other_keys)) {
+                    pending.push(*hotkey);
+                } else {
+                    on_hotkey(*hotkey);
+                }
+            } else if !pressed && held.contains(hotkey) {
+                held.retain(|other| other != hotkey);
+
+                if pending.contains(hotkey) {
+                    pending.retain(|other| other != hotkey);
+                    on_hotkey(*hotkey);
+                }
+            }
+        }
+
+        ticks::sleep_until_next(POLL_INTERVAL);
+    }
+}
+
+#[cfg(all(feature = "gui", target_os = "linux"))]
+mod portal {
+    use std::collections::HashMap;
+
+    use zbus::{
+        blocking::{Connection, MessageIterator, Proxy},
+        message::Type,
+        zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value},
+        MatchRule,
+    };
+
+    use super::Hotkey;
+
+    const DESTINATION: &str = "org.freedesktop.portal.Desktop";
+    const PATH: &str = "/org/freedesktop/portal/desktop";
+    const INTERFACE: &str = "org.freedesktop.portal.GlobalShortcuts";
+    const TOKEN: &str = "legion_kb_rgb";
+
+    /// Register the hotkeys with the portal and wait for them to be activated, only returning if the session ends
+    pub fn listen(on_hotkey: &dyn Fn(Hotkey)) -> zbus::Result<()> {
+        let connection = Connection::session()?;
+        let proxy = Proxy::new(&connection, DESTINATION, PATH, INTERFACE)?;
+
+        let results = request(&connection, "create", |token| {
+            let options = HashMap::from([("handle_token", Value::from(token)), ("session_handle_token", Value::from(TOKEN))]);
+            proxy.call_method("CreateSession", &(options,)).map(drop)
+        })?;
+        let session = results
+            .get("session_handle")
+            .and_then(|handle| handle.downcast_ref::<&str>().ok())
+            .and_then(|handle| OwnedObjectPath::try_from(handle).ok())
+            .ok_or_else(|| zbus::Error::Failure("The portal didn't return a session".to_string()))?;
+
+        // Listen before binding, as the shortcuts might already be in use by the time the portal answers
+        let rule = MatchRule::builder().msg_type(Type::Signal).interface(INTERFACE)?.member("Activated")?.build();
+        let activations = MessageIterator::for_match_rule(rule, &connection, None)?;
+
+        request(&connection, "bind", |token| {
+            let shortcuts: Vec<(&str, HashMap<&str, Value>)> = Hotkey::ALL
+                .iter()
+                .map(|hotkey| {
+                    let properties = HashMap::from([("description", Value::from(hotkey.description())), ("preferred_trigger", Value::from(hotkey.preferred_trigger()))]);
+                    (hotkey.id(), properties)
+                })
+                .collect();
+            let options = HashMap::from([("handle_token", Value::from(token))]);
+            proxy.call_method("BindShortcuts", &(&session, shortcuts, "", options)).map(drop)
+        })?;
+
+        for message in activations {
+            let message = message?;
+            let (activated_session, id, _timestamp, _options): (OwnedObjectPath, String, u64, HashMap<String, OwnedValue>) = message.body().deserialize()?;
+
+            if activated_session == session {
+                if let Some(hotkey) = Hotkey::ALL.into_iter().find(|hotkey| hotkey.id() == id) {
+                    on_hotkey(hotkey);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Make a portal call and wait for its `Response`, returning the results if it succeeded
+    fn request(connection: &Connection, token: &str, call: impl FnOnce(&str) -> zbus::Result<()>) -> zbus::Result<HashMap<String, OwnedValue>> {
+        let token = format!("{TOKEN}_{token}");
+
+        // The path of the request is known beforehand, so the response can't be missed
+        let sender = connection.unique_name().map(|name| name.trim_start_matches(':').replace('.', "_")).unwrap_or_default();
+        let path = format!("{PATH}/request/{sender}/{token}");
+        let rule = MatchRule::builder()
+            .msg_type(Type::Signal)
+            .interface("org.freedesktop.portal.Request")?
+            .member("Response")?
+            .path(ObjectPath::try_from(path.as_str())?)?
+            .build();
+        let mut responses = MessageIterator::for_match_rule(rule, connection, Some(1))?;
+
+        call(&token)?;
+
+        let message = responses.next().ok_or_else(|| zbus::Error::Failure("The portal never answered".to_string()))??;
+        let (response, results): (u32, HashMap<String, OwnedValue>) = message.body().deserialize()?;
+
+        match response {
+            0 => Ok(results),
+            1 => Err(zbus::Error::Failure("The hotkeys were not allowed".to_string())),
+            _ => Err(zbus::Error::Failure("The portal request failed".to_string())),
+        }
+    }
+}
@@ -0,0 +1,49 @@
+//! Detecting when the user stopped using the computer, from the keyboard and mouse activity.
+
+use std::time::{Duration, Instant};
+
+use device_query::{DeviceQuery, DeviceState, Keycode};
+
+pub struct IdleMonitor {
+    /// Not available without a display server to ask
+    state: Option<DeviceState>,
+    last_keys: Vec<Keycode>,
+    last_mouse: (i32, i32),
+    last_input: Instant,
+}
+
+impl IdleMonitor {
+    pub fn new() -> Self {
+        // Only X11 can be missing, the other platforms always have a way to ask
+        #[cfg(target_os = "linux")]
+        let state = DeviceState::checked_new();
+        #[cfg(not(target_os = "linux"))]
+        let state = Some(DeviceState::new());
+
+        Self {
+            state,
+            last_keys: Vec::new(),
+            last_mouse: (0, 0),
+            last_input: Instant::now(),
+        }
+    }
+
+    /// Check for input and get how long it has been since the last one, meant to be called periodically
+    pub fn idle_time(&mut self) -> Duration {
+        let Some(state) = &self.state else {
+            return Duration::ZERO;
+        };
+
+        let keys = state.get_keys();
+        let mouse = state.get_mouse();
+
+        if !keys.is_empty() || keys != self.last_keys || mouse.coords != self.last_mouse || mouse.button_pressed.iter().any(|pressed| *pressed) {
+            self.last_input = Instant::now();
+        }
+
+        self.last_keys = keys;
+        self.last_mouse = mouse.coords;
+
+        self.last_input.elapsed()
+    }
+}
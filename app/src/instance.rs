@@ -0,0 +1,148 @@
+//! Communication with the instance that currently controls the keyboard.
+//!
+//! The running instance listens on a local port, which is written along with a random token to the user's runtime directory.
+
+use std::{
+    env, fs,
+    io::{BufRead, BufReader, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    path::PathBuf,
+    thread,
+    time::{Duration, Instant},
+};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use single_instance::SingleInstance;
+
+const TAKEOVER_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Request {
+    /// Release the keyboard and exit
+    Shutdown,
+    /// Bring the window to the front
+    ShowWindow,
+}
+
+impl Request {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Shutdown => "shutdown",
+            Self::ShowWindow => "show-window",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "shutdown" => Some(Self::Shutdown),
+            "show-window" => Some(Self::ShowWindow),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct InstanceInfo {
+    port: u16,
+    token: String,
+    /// Whether the instance has a window that can be shown
+    #[serde(default)]
+    has_window: bool,
+}
+
+/// Listen for requests from other instances in the background
+pub fn serve<F: Fn(Request) + Send + 'static>(has_window: bool, on_request: F) {
+    let Ok(listener) = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)) else {
+        return;
+    };
+    let Ok(address) = listener.local_addr() else {
+        return;
+    };
+
+    let info = InstanceInfo {
+        port: address.port(),
+        token: format!("{:032x}", rand::thread_rng().gen::<u128>()),
+        has_window,
+    };
+
+    let Ok(json) = serde_json::to_string(&info) else {
+        return;
+    };
+    if fs::write(info_location(), json).is_err() {
+        return;
+    }
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mut line = String::new();
+            if BufReader::new(stream).read_line(&mut line).is_err() {
+                continue;
+            }
+
+            let request = line.trim().split_once(' ').filter(|(token, _)| *token == info.token).and_then(|(_, request)| Request::parse(request));
+
+            if let Some(request) = request {
+                on_request(request);
+            }
+        }
+    });
+}
+
+/// Send a request to the running instance, returns whether it could be delivered
+pub fn send(request: Request) -> bool {
+    let Some(info) = read_info() else {
+        return false;
+    };
+
+    send_to(&info, request)
+}
+
+/// Ask the running instance to bring its window to the front, returns false if it has no window or can't be reached
+#[cfg(feature = "gui")]
+pub fn show_window() -> bool {
+    read_info().is_some_and(|info| info.has_window && send_to(&info, Request::ShowWindow))
+}
+
+fn send_to(info: &InstanceInfo, request: Request) -> bool {
+    let Ok(mut stream) = TcpStream::connect((Ipv4Addr::LOCALHOST, info.port)) else {
+        return false;
+    };
+
+    writeln!(stream, "{} {}", info.token, request.as_str()).is_ok()
+}
+
+/// Ask the running instance to shut down and wait for it to release the keyboard
+pub fn take_over() {
+    if is_free() {
+        return;
+    }
+
+    if !send(Request::Shutdown) {
+        println!("Could not reach the running instance.");
+        return;
+    }
+
+    let start = Instant::now();
+    while start.elapsed() < TAKEOVER_TIMEOUT {
+        if is_free() {
+            return;
+        }
+        thread::sleep(Duration::from_millis(250));
+    }
+
+    println!("The running instance did not shut down in time.");
+}
+
+fn is_free() -> bool {
+    // Dropping it right away releases it again
+    SingleInstance::new(env!("CARGO_PKG_NAME")).is_ok_and(|instance| instance.is_single())
+}
+
+fn read_info() -> Option<InstanceInfo> {
+    fs::read_to_string(info_location()).ok().and_then(|json| serde_json::from_str(&json).ok())
+}
+
+fn info_location() -> PathBuf {
+    dirs::runtime_dir().unwrap_or_else(env::temp_dir).join(concat!(env!("CARGO_PKG_NAME"), "-instance.json"))
+}
@@ -0,0 +1,79 @@
+//! Watches whether Caps Lock and Num Lock are on.
+//!
+//! On Linux the state is read from the LEDs the kernel exposes for every keyboard, which works the same under X11,
+//! Wayland and on the console, while on Windows it's asked to the system directly.
+
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LockState {
+    pub caps_lock: bool,
+    pub num_lock: bool,
+}
+
+/// Spawn a background thread that calls `on_change` with the state of the lock keys, whenever it changes
+///
+/// Only the first call has an effect.
+pub fn spawn_watcher<F: Fn(LockState) + Send + 'static>(on_change: F) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    thread::spawn(move || {
+        let mut last = None;
+
+        loop {
+            let state = read();
+            if last != Some(state) {
+                on_change(state);
+                last = Some(state);
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+#[cfg(target_os = "linux")]
+fn read() -> LockState {
+    use std::fs;
+
+    // Every keyboard gets LEDs of its own named like `input3::capslock`, any of them being lit is enough
+    let is_lit = |suffix: &str| {
+        fs::read_dir("/sys/class/leds").into_iter().flatten().flatten().any(|entry| {
+            entry.file_name().to_string_lossy().ends_with(suffix)
+                && fs::read_to_string(entry.path().join("brightness")).is_ok_and(|brightness| brightness.trim() != "0")
+        })
+    };
+
+    LockState {
+        caps_lock: is_lit("::capslock"),
+        num_lock: is_lit("::numlock"),
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn read() -> LockState {
+    use winapi::um::winuser::{GetKeyState, VK_CAPITAL, VK_NUMLOCK};
+
+    // The low bit is set while the key is toggled on
+    let is_on = |key| unsafe { GetKeyState(key) } & 1 != 0;
+
+    LockState {
+        caps_lock: is_on(VK_CAPITAL),
+        num_lock: is_on(VK_NUMLOCK),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn read() -> LockState {
+    LockState::default()
+}
@@ -0,0 +1,55 @@
+//! Diagnostics for troubleshooting, printed to stderr and optionally kept in a rotating log file next to the settings.
+//!
+//! `RUST_LOG` takes precedence over the level picked through the command line.
+
+use std::{fs, path::PathBuf};
+
+use tracing::level_filters::LevelFilter;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+use crate::persist::Settings;
+
+/// How many days worth of logs to keep around
+const MAX_LOG_FILES: usize = 7;
+
+/// Start collecting logs, at `level` if one was asked for
+///
+/// The terminal only gets warnings by default so it isn't flooded, while the log file keeps enough to follow what
+/// happened before something went wrong.
+pub fn init(level: Option<LevelFilter>, to_file: bool) {
+    let stderr = fmt::layer().with_writer(std::io::stderr).with_filter(filter(level.unwrap_or(LevelFilter::WARN)));
+
+    let file = to_file.then(open_file).flatten().map(|appender| {
+        fmt::layer()
+            .with_ansi(false)
+            .with_writer(appender)
+            .with_filter(filter(level.unwrap_or(LevelFilter::INFO)))
+    });
+
+    let _ = tracing_subscriber::registry().with(stderr).with(file).try_init();
+    tracing::info!("Starting {} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+}
+
+/// Where the log files are kept
+pub fn folder() -> PathBuf {
+    Settings::get_location().parent().map_or_else(|| PathBuf::from("logs"), |dir| dir.join("logs"))
+}
+
+/// Only our own crates get as verbose as asked for, the GUI libraries are too chatty otherwise
+fn filter(level: LevelFilter) -> EnvFilter {
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(format!("warn,legion_kb_rgb={level},legion_kb_rgb_hw={level}")))
+}
+
+fn open_file() -> Option<RollingFileAppender> {
+    let folder = folder();
+    fs::create_dir_all(&folder).ok()?;
+
+    RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(env!("CARGO_PKG_NAME"))
+        .filename_suffix("log")
+        .max_log_files(MAX_LOG_FILES)
+        .build(folder)
+        .ok()
+}
@@ -1,32 +1,94 @@
 #![cfg_attr(not(test), windows_subsystem = "windows")]
 #![cfg_attr(test, windows_subsystem = "console")]
 
+mod api;
+mod app_rules;
+mod appearance;
+mod backlight;
+#[cfg(feature = "gui")]
+mod battery;
+mod chroma;
 mod cli;
+mod community;
 #[cfg(target_os = "windows")]
 mod console;
+mod controllers;
+mod crash;
+mod diagnostics;
+mod effect_library;
 mod enums;
+mod exit;
+#[cfg(feature = "gui")]
+mod eyedropper;
+#[cfg(feature = "gui")]
+mod foreground;
+mod hotkeys;
+#[cfg(feature = "gui")]
 mod gui;
+#[cfg(feature = "gui")]
+mod harmony;
+mod idle;
+mod instance;
+#[cfg(feature = "gui")]
+mod lock_keys;
+mod logging;
 mod manager;
+#[cfg(feature = "audio")]
+mod mic;
+#[cfg(feature = "network")]
+mod mqtt;
+mod notifications;
+mod overlays;
 mod persist;
+mod pomodoro;
+#[cfg(feature = "gui")]
+mod power;
+mod reduced_motion;
+#[cfg(feature = "network")]
+mod remote;
+mod room_sync;
+mod shuffle;
+mod startup;
+mod status;
+mod ticks;
+#[cfg(feature = "tray")]
 mod tray;
+mod triggers;
+#[cfg(target_os = "linux")]
+mod udev;
+mod updates;
+mod usage;
 mod util;
+mod wallpaper;
+mod weather;
+mod window;
+
+#[cfg(feature = "gui")]
+rust_i18n::i18n!("res/locales", fallback = "en");
 
 use std::sync::LazyLock;
-#[cfg(not(target_os = "linux"))]
-use std::{cell::RefCell, rc::Rc};
 
-use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc,
-};
+#[cfg(all(not(target_os = "linux"), feature = "tray"))]
+use std::sync::atomic::Ordering;
+#[cfg(feature = "gui")]
+use std::sync::{atomic::AtomicBool, Arc};
 
-use cli::{GuiCommand, OutputType};
+#[cfg(feature = "gui")]
+use cli::OutputType;
+use cli::GuiCommand;
 use color_eyre::{eyre::eyre, Result};
+#[cfg(feature = "gui")]
 use eframe::{egui::IconData, epaint::Vec2};
+#[cfg(feature = "gui")]
 use gui::App;
+#[cfg(feature = "gui")]
+use manager::health::SharedHealth;
+#[cfg(feature = "gui")]
+use persist::Settings;
 
 const APP_ICON: &[u8; 14987] = include_bytes!("../res/trayIcon.ico");
-const WINDOW_SIZE: Vec2 = Vec2::new(500., 400.);
+#[cfg(feature = "gui")]
+const WINDOW_SIZE: Vec2 = Vec2::new(500., 480.);
 #[cfg(target_os = "linux")]
 pub static DENY_HIDING: LazyLock<bool> = LazyLock::new(|| std::env::var("WAYLAND_DISPLAY").is_ok());
 #[cfg(not(target_os = "linux"))]
@@ -36,12 +98,14 @@ fn main() {
     #[cfg(target_os = "windows")]
     {
         setup_panic().unwrap();
+        crash::install();
         run_windows();
     }
 
     #[cfg(target_os = "linux")]
     {
         color_eyre::install().unwrap();
+        crash::install();
         init().unwrap();
     }
 }
@@ -81,17 +145,28 @@ fn init() -> Result<()> {
     let cli_output = cli::try_cli().map_err(|err| eyre!("{:?}", err))?;
 
     match cli_output {
+        #[cfg(feature = "gui")]
         GuiCommand::Start { hide_window, output_type } => {
             start_ui(output_type, hide_window);
             Ok(())
         }
+        #[cfg(not(feature = "gui"))]
+        GuiCommand::Start { .. } => {
+            println!("This build does not include the GUI, use the command line instead (see --help).");
+            Ok(())
+        }
         GuiCommand::Exit => Ok(()),
     }
 }
 
+#[cfg(feature = "gui")]
 fn start_ui(output_type: OutputType, hide_window: bool) {
-    let has_tray = Arc::new(AtomicBool::new(true));
+    Settings::migrate_legacy_location();
+
+    // Set by the tray once it's actually shown
+    let has_tray = Arc::new(AtomicBool::new(false));
     let visible = Arc::new(AtomicBool::new(!hide_window));
+    let health = SharedHealth::default();
 
     let app_icon = load_icon_data(APP_ICON);
     let native_options = eframe::NativeOptions {
@@ -103,43 +178,41 @@ fn start_ui(output_type: OutputType, hide_window: bool) {
         ..eframe::NativeOptions::default()
     };
 
+    #[cfg(all(not(target_os = "linux"), feature = "tray"))]
     let has_tray_c = has_tray.clone();
 
-    // Since egui uses winit under the hood and doesn't use gtk on Linux, and we need gtk for
-    // the tray icon to show up, we need to spawn a thread
-    // where we initialize gtk and create the tray_icon
-    #[cfg(target_os = "linux")]
-    std::thread::spawn(move || {
-        gtk::init().unwrap();
-
-        let tray_icon = tray::build_tray(true);
-        has_tray_c.store(tray_icon.is_some(), Ordering::SeqCst);
-
-        gtk::main();
-    });
-
-    #[cfg(not(target_os = "linux"))]
-    let mut _tray_icon = Rc::new(RefCell::new(None));
-    #[cfg(not(target_os = "linux"))]
-    let tray_c = _tray_icon.clone();
+    // Shown before starting the app, so whoever launched it at boot can see it's waiting for the keyboard
+    #[cfg(all(target_os = "linux", feature = "tray"))]
+    let tray = {
+        let tray = tray::Tray::build(true, has_tray.clone());
+        if let Some(tray) = &tray {
+            tray.follow_health(&health);
+        }
+        tray
+    };
 
-    let app = App::new(output_type, has_tray.clone(), visible.clone());
+    let app = App::new(output_type, has_tray.clone(), visible.clone(), health);
 
     eframe::run_native(
         "Legion RGB",
         native_options,
         Box::new(move |cc| {
-            #[cfg(target_os = "windows")]
-            {
-                tray_c.borrow_mut().replace(tray::build_tray(true));
-                has_tray_c.store(tray_c.borrow().is_some(), Ordering::SeqCst);
-            }
+            // The app owns the tray so it can keep it updated
+            #[cfg(all(not(target_os = "linux"), feature = "tray"))]
+            let app = {
+                let tray = tray::Tray::build(true);
+                has_tray_c.store(tray.is_some(), Ordering::SeqCst);
+                app.with_tray(tray)
+            };
+            #[cfg(all(target_os = "linux", feature = "tray"))]
+            let app = app.with_tray(tray);
             Ok(Box::new(app.init(cc)))
         }),
     )
     .unwrap();
 }
 
+#[cfg(feature = "gui")]
 #[must_use]
 fn load_icon_data(image_data: &[u8]) -> IconData {
     let image = image::load_from_memory(image_data).unwrap();
@@ -0,0 +1,122 @@
+//! Where the effects get the time from, so every animation can be sped up or slowed down at once and tests can skip
+//! the waiting entirely.
+
+use std::{
+    ops::RangeInclusive,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+
+    fn sleep(&self, duration: Duration);
+}
+
+/// How fast every animation plays compared to its normal speed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SpeedMultiplier(pub f32);
+
+impl SpeedMultiplier {
+    pub const RANGE: RangeInclusive<f32> = 0.25..=4.0;
+
+    fn to_bits(self) -> u32 {
+        self.0.clamp(*Self::RANGE.start(), *Self::RANGE.end()).to_bits()
+    }
+}
+
+impl Default for SpeedMultiplier {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// The real time, passing as fast as the speed multiplier says
+pub struct SystemClock {
+    multiplier: AtomicU32,
+    anchor: Mutex<Anchor>,
+}
+
+/// The last point at which the multiplier changed, so the time keeps going forward smoothly when it does
+struct Anchor {
+    real: Instant,
+    scaled: Instant,
+    multiplier: f32,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        let now = Instant::now();
+
+        Self {
+            multiplier: AtomicU32::new(SpeedMultiplier::default().to_bits()),
+            anchor: Mutex::new(Anchor {
+                real: now,
+                scaled: now,
+                multiplier: SpeedMultiplier::default().0,
+            }),
+        }
+    }
+}
+
+impl SystemClock {
+    pub fn set_multiplier(&self, multiplier: SpeedMultiplier) {
+        self.multiplier.store(multiplier.to_bits(), Ordering::SeqCst);
+    }
+
+    fn multiplier(&self) -> f32 {
+        f32::from_bits(self.multiplier.load(Ordering::SeqCst))
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        let multiplier = self.multiplier();
+        let mut anchor = self.anchor.lock().unwrap();
+
+        if anchor.multiplier != multiplier {
+            let now = Instant::now();
+            anchor.scaled += now.duration_since(anchor.real).mul_f32(anchor.multiplier);
+            anchor.real = now;
+            anchor.multiplier = multiplier;
+        }
+
+        anchor.scaled + anchor.real.elapsed().mul_f32(multiplier)
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration.div_f32(self.multiplier()));
+    }
+}
+
+/// Time that only passes when slept through, so the effects run as fast as they can and always see the same timings
+pub struct ManualClock {
+    start: Instant,
+    elapsed: Mutex<Duration>,
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+            elapsed: Mutex::new(Duration::ZERO),
+        }
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.start + *self.elapsed.lock().unwrap()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        *self.elapsed.lock().unwrap() += duration;
+    }
+}
@@ -1,42 +1,401 @@
-use std::path::Path;
+//! Effects described in a file, each zone following its own track of colors.
+//!
+//! Files are versioned through a `version` field. The first format, from before the field existed, played one list of
+//! steps over the whole keyboard; such files are still read, and converted to tracks as they're loaded.
+//!
+//! An effect can instead be an [expression](super::expression) working out the colors as it goes, which then plays for
+//! as long as it's left to.
 
+use std::{fs, io::Cursor, path::Path};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
 use error_stack::{Result, ResultExt};
+use image::{ImageFormat, RgbImage};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use super::expression::{ParseError, Program};
 use crate::util::StorageTrait;
 
-#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
-pub struct EffectStep {
-    pub rgb_array: [u8; 12],
-    pub step_type: EffectType,
-    pub brightness: u8,
-    pub steps: u8,
-    pub delay_between_steps: u64,
-    pub sleep: u64,
+#[cfg(test)]
+mod tests;
+
+/// The format files are written in, older ones are converted when loaded
+pub const FORMAT_VERSION: u64 = 2;
+/// Rows of the thumbnail, each one the four zones at a point in time
+const THUMBNAIL_ROWS: u32 = 24;
+/// How much of an expression, which has no end, the thumbnail shows
+const EXPRESSION_THUMBNAIL_LENGTH: u64 = 4000;
+
+/// A color a zone goes to, fading from the one before it and then holding it
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, PartialEq, Eq)]
+pub struct Keyframe {
+    pub rgb: [u8; 3],
+    /// Milliseconds spent fading from the previous color, 0 to switch to it at once
+    #[serde(default)]
+    pub fade: u64,
+    /// Milliseconds the color is held once reached
+    #[serde(default)]
+    pub hold: u64,
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
-pub enum EffectType {
-    Set,
-    Transition,
+/// The colors a single zone goes through
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq, Eq, Default)]
+pub struct Track {
+    pub keyframes: Vec<Keyframe>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Default)]
+impl Track {
+    /// How long the track takes to play once, in milliseconds
+    pub fn length(&self) -> u64 {
+        self.keyframes.iter().map(|keyframe| keyframe.fade + keyframe.hold).sum()
+    }
+
+    /// The color `time` ms in, looping over the track if `looping` or holding the last color otherwise
+    pub fn color_at(&self, time: u64, looping: bool) -> [u8; 3] {
+        let Some(last) = self.keyframes.last() else {
+            return [0; 3];
+        };
+
+        let length = self.length();
+        let mut time = match length {
+            0 => return last.rgb,
+            length if looping => time % length,
+            length if time >= length => return last.rgb,
+            _ => time,
+        };
+
+        for (i, keyframe) in self.keyframes.iter().enumerate() {
+            if time < keyframe.fade {
+                // The first fade starts from the end of the track when looping, and from its own color otherwise
+                let from = match i {
+                    0 if looping => last.rgb,
+                    0 => keyframe.rgb,
+                    i => self.keyframes[i - 1].rgb,
+                };
+                let fraction = time as f32 / keyframe.fade as f32;
+                return [0, 1, 2].map(|c| (f32::from(keyframe.rgb[c]) - f32::from(from[c])).mul_add(fraction, f32::from(from[c])).round() as u8);
+            }
+            time -= keyframe.fade;
+
+            if time < keyframe.hold {
+                return keyframe.rgb;
+            }
+            time -= keyframe.hold;
+        }
+
+        last.rgb
+    }
+
+    /// Milliseconds from `time` until the color changes again, 0 while it's fading and `None` once it never will
+    pub fn until_change(&self, time: u64, looping: bool) -> Option<u64> {
+        let length = self.length();
+        if length == 0 || (!looping && time >= length) {
+            return None;
+        }
+
+        let mut time = time % length;
+        for keyframe in &self.keyframes {
+            if time < keyframe.fade {
+                return Some(0);
+            }
+            time -= keyframe.fade;
+
+            if time < keyframe.hold {
+                return Some(keyframe.hold - time);
+            }
+            time -= keyframe.hold;
+        }
+
+        None
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(try_from = "serde_json::Value")]
 pub struct CustomEffect {
+    /// Always [`FORMAT_VERSION`] once loaded
+    pub version: u64,
     pub name: Option<String>,
-    pub effect_steps: Vec<EffectStep>,
+    pub author: Option<String>,
+    pub description: Option<String>,
+    /// A picture of the effect as a PNG encoded in base64, made by [`Self::render_thumbnail`]
+    pub thumbnail: Option<String>,
+    pub brightness: u8,
+    /// Start every track over once it's done, each on its own, instead of holding the last colors
     pub should_loop: bool,
+    /// One for each zone, from left to right, the zones without one staying off
+    pub tracks: Vec<Track>,
+    /// A program in the [expression language](super::expression) played instead of the tracks
+    pub expression: Option<String>,
+}
+
+impl Default for CustomEffect {
+    fn default() -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            name: None,
+            author: None,
+            description: None,
+            thumbnail: None,
+            brightness: 1,
+            should_loop: false,
+            tracks: Vec::new(),
+            expression: None,
+        }
+    }
 }
 
 #[derive(Debug, Error)]
 #[error("Could not load custom effect")]
 pub struct LoadCustomEffectError;
 
+#[derive(Debug, Error)]
+#[error("Could not save custom effect")]
+pub struct SaveCustomEffectError;
+
+/// Why the contents of a file aren't a custom effect this version can play
+#[derive(Debug, Error)]
+pub enum FormatError {
+    #[error("The effect is in format {0}, which is newer than this version of the program understands")]
+    TooNew(u64),
+    #[error("The version of the effect format should be a number")]
+    InvalidVersion,
+    #[error(transparent)]
+    Invalid(#[from] serde_json::Error),
+    #[error("The expression of the effect can't be read: {0}")]
+    InvalidExpression(ParseError),
+}
+
 impl CustomEffect {
     pub fn from_file(path: &Path) -> Result<Self, LoadCustomEffectError> {
-        Self::load(path).change_context(LoadCustomEffectError)
+        let text = fs::read_to_string(path).change_context(LoadCustomEffectError)?;
+        let value: serde_json::Value = serde_json::from_str(&text).change_context(LoadCustomEffectError)?;
+
+        Self::try_from(value).change_context(LoadCustomEffectError).attach_printable_lazy(|| format!("Could not read {}", path.display()))
+    }
+
+    pub fn save_file(&self, path: &Path) -> Result<(), SaveCustomEffectError> {
+        self.save(path).change_context(SaveCustomEffectError)
+    }
+
+    /// How long it takes for every track to play once, in milliseconds
+    pub fn length(&self) -> u64 {
+        self.tracks.iter().map(Track::length).max().unwrap_or(0)
+    }
+
+    /// Whether nothing changes anymore `time` ms in, never for an expression
+    pub fn is_over(&self, time: u64) -> bool {
+        let length = self.length();
+        self.expression.is_none() && (length == 0 || (!self.should_loop && time >= length))
+    }
+
+    /// The colors of the four zones `time` ms in
+    ///
+    /// This parses the expression every time, [`Self::sampler`] does it once for playing the effect.
+    pub fn colors_at(&self, time: u64) -> [u8; 12] {
+        self.sampler().colors_at(time)
+    }
+
+    /// Something to get the colors from over and over, the expression being parsed once, or all off if it can't be
+    pub fn sampler(&self) -> Sampler {
+        match &self.expression {
+            Some(expression) => Sampler::Expression(Program::parse(expression).ok()),
+            None => Sampler::Tracks {
+                tracks: self.tracks.clone(),
+                looping: self.should_loop,
+            },
+        }
+    }
+
+    /// A picture of the effect going on over its length, the zones from left to right and time from top to bottom
+    pub fn render_thumbnail(&self) -> Option<String> {
+        let length = if self.expression.is_some() { EXPRESSION_THUMBNAIL_LENGTH } else { self.length() };
+        let sampler = self.sampler();
+        let image = RgbImage::from_fn(4, THUMBNAIL_ROWS, |zone, row| {
+            let time = length * u64::from(row) / u64::from(THUMBNAIL_ROWS);
+            let rgb_array = sampler.colors_at(time);
+            let i = zone as usize * 3;
+            image::Rgb([rgb_array[i], rgb_array[i + 1], rgb_array[i + 2]])
+        });
+
+        let mut png = Vec::new();
+        image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png).ok()?;
+        Some(STANDARD.encode(png))
+    }
+
+    /// The thumbnail embedded in the effect, decoded
+    #[cfg(feature = "gui")]
+    pub fn thumbnail_image(&self) -> Option<RgbImage> {
+        let png = STANDARD.decode(self.thumbnail.as_deref()?).ok()?;
+        Some(image::load_from_memory_with_format(&png, ImageFormat::Png).ok()?.to_rgb8())
+    }
+}
+
+/// Where the colors of an effect come from while it plays, kept apart from the effect so it can be held on to
+pub enum Sampler {
+    Tracks {
+        tracks: Vec<Track>,
+        looping: bool,
+    },
+    /// `None` if the expression couldn't be parsed, which only effects made in the program can get to
+    Expression(Option<Program>),
+}
+
+impl Sampler {
+    /// The colors of the four zones `time` ms in
+    pub fn colors_at(&self, time: u64) -> [u8; 12] {
+        match self {
+            Self::Tracks { tracks, looping } => {
+                let mut rgb_array = [0; 12];
+                for (zone, track) in rgb_array.chunks_exact_mut(3).zip(tracks) {
+                    zone.copy_from_slice(&track.color_at(time, *looping));
+                }
+                rgb_array
+            }
+            Self::Expression(Some(program)) => program.colors_at(time as f32 / 1000.0),
+            Self::Expression(None) => [0; 12],
+        }
+    }
+
+    /// Milliseconds from `time` until any of the colors changes again, 0 while they're fading or worked out by an expression
+    pub fn until_change(&self, time: u64) -> Option<u64> {
+        match self {
+            Self::Tracks { tracks, looping } => tracks.iter().filter_map(|track| track.until_change(time, *looping)).min(),
+            Self::Expression(Some(_)) => Some(0),
+            Self::Expression(None) => None,
+        }
+    }
+}
+
+impl TryFrom<serde_json::Value> for CustomEffect {
+    type Error = FormatError;
+
+    /// Read an effect in any format known so far, converting it to the current one
+    ///
+    /// The expression is parsed here too, so an effect that would only play all off doesn't load at all.
+    fn try_from(value: serde_json::Value) -> std::result::Result<Self, Self::Error> {
+        let version = match value.get("version") {
+            None => 1,
+            Some(version) => version.as_u64().ok_or(FormatError::InvalidVersion)?,
+        };
+
+        let effect: Self = match version {
+            1 => serde_json::from_value::<v1::CustomEffect>(value)?.into(),
+            FORMAT_VERSION => serde_json::from_value::<Fields>(value)?.into(),
+            version => return Err(FormatError::TooNew(version)),
+        };
+
+        if let Some(expression) = &effect.expression {
+            Program::parse(expression).map_err(FormatError::InvalidExpression)?;
+        }
+
+        Ok(effect)
+    }
+}
+
+/// What's written in a file of the current format, read separately so the version is checked first
+#[derive(Deserialize)]
+struct Fields {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    author: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    thumbnail: Option<String>,
+    brightness: u8,
+    #[serde(default)]
+    should_loop: bool,
+    #[serde(default)]
+    tracks: Vec<Track>,
+    #[serde(default)]
+    expression: Option<String>,
+}
+
+impl From<Fields> for CustomEffect {
+    fn from(fields: Fields) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            name: fields.name,
+            author: fields.author,
+            description: fields.description,
+            thumbnail: fields.thumbnail,
+            brightness: fields.brightness,
+            should_loop: fields.should_loop,
+            tracks: fields.tracks,
+            expression: fields.expression,
+        }
     }
 }
 
 impl<'a> StorageTrait<'a> for CustomEffect {}
+
+/// The first format, a list of steps each setting or fading the whole keyboard to new colors
+mod v1 {
+    use serde::Deserialize;
+
+    use super::{Keyframe, Track};
+
+    #[derive(Deserialize)]
+    pub struct EffectStep {
+        pub rgb_array: [u8; 12],
+        pub step_type: EffectType,
+        pub brightness: u8,
+        pub steps: u8,
+        pub delay_between_steps: u64,
+        pub sleep: u64,
+    }
+
+    #[derive(Deserialize, PartialEq, Eq)]
+    pub enum EffectType {
+        Set,
+        Transition,
+    }
+
+    #[derive(Deserialize)]
+    pub struct CustomEffect {
+        pub name: Option<String>,
+        pub effect_steps: Vec<EffectStep>,
+        pub should_loop: bool,
+    }
+
+    impl From<CustomEffect> for super::CustomEffect {
+        /// Every step becomes a keyframe on each of the tracks, so they stay in step
+        ///
+        /// The brightness could change from one step to the next, which tracks don't do, so the highest one is used for
+        /// the whole effect and the colors of the dimmer steps are scaled down to match.
+        fn from(effect: CustomEffect) -> Self {
+            let brightness = effect.effect_steps.iter().map(|step| step.brightness).max().unwrap_or(1);
+            let tracks = (0..4)
+                .map(|zone| Track {
+                    keyframes: effect
+                        .effect_steps
+                        .iter()
+                        .map(|step| Keyframe {
+                            rgb: [0, 1, 2].map(|c| dim(step.rgb_array[zone * 3 + c], step.brightness, brightness)),
+                            fade: if step.step_type == EffectType::Transition { u64::from(step.steps) * step.delay_between_steps } else { 0 },
+                            hold: step.sleep,
+                        })
+                        .collect(),
+                })
+                .collect();
+
+            let mut converted = Self {
+                name: effect.name,
+                brightness,
+                should_loop: effect.should_loop,
+                tracks,
+                ..Self::default()
+            };
+            converted.thumbnail = converted.render_thumbnail();
+            converted
+        }
+    }
+
+    /// A color shown at `brightness` as it looks with the keyboard at `max`
+    fn dim(value: u8, brightness: u8, max: u8) -> u8 {
+        (f32::from(value) * f32::from(brightness.min(max)) / f32::from(max.max(1))).round() as u8
+    }
+}
@@ -0,0 +1,142 @@
+//! Checks the colors a track goes through over time, and that effects of the first format play as they used to.
+
+use serde_json::json;
+
+use super::{CustomEffect, FormatError, Keyframe, Track, FORMAT_VERSION};
+
+const RED: [u8; 3] = [255, 0, 0];
+const BLUE: [u8; 3] = [0, 0, 255];
+
+/// Holds red for 100ms, fades to blue over 200ms and holds it for 100ms
+fn track() -> Track {
+    Track {
+        keyframes: vec![Keyframe { rgb: RED, fade: 0, hold: 100 }, Keyframe { rgb: BLUE, fade: 200, hold: 100 }],
+    }
+}
+
+#[test]
+fn colors_fade_then_hold() {
+    let track = track();
+
+    assert_eq!(track.length(), 400);
+    assert_eq!(track.color_at(0, false), RED);
+    assert_eq!(track.color_at(99, false), RED);
+    assert_eq!(track.color_at(100, false), RED);
+    assert_eq!(track.color_at(200, false), [128, 0, 128]);
+    assert_eq!(track.color_at(300, false), BLUE);
+    assert_eq!(track.color_at(399, false), BLUE);
+}
+
+#[test]
+fn tracks_hold_the_last_color_or_start_over() {
+    let track = track();
+
+    assert_eq!(track.color_at(10_000, false), BLUE);
+    assert_eq!(track.color_at(450, true), RED);
+    assert_eq!(track.color_at(600, true), [128, 0, 128]);
+}
+
+#[test]
+fn looping_fades_in_from_the_end_of_the_track() {
+    let track = Track {
+        keyframes: vec![Keyframe { rgb: RED, fade: 100, hold: 100 }, Keyframe { rgb: BLUE, fade: 0, hold: 100 }],
+    };
+
+    assert_eq!(track.color_at(50, false), RED);
+    assert_eq!(track.color_at(50, true), [128, 0, 128]);
+    assert_eq!(track.color_at(350, true), [128, 0, 128]);
+}
+
+#[test]
+fn empty_tracks_stay_off() {
+    let track = Track::default();
+
+    assert_eq!(track.color_at(0, true), [0; 3]);
+    assert_eq!(track.until_change(0, true), None);
+}
+
+#[test]
+fn changes_are_due_at_the_next_keyframe() {
+    let track = track();
+
+    assert_eq!(track.until_change(0, false), Some(100));
+    assert_eq!(track.until_change(60, false), Some(40));
+    assert_eq!(track.until_change(150, false), Some(0));
+    assert_eq!(track.until_change(320, false), Some(80));
+    assert_eq!(track.until_change(400, false), None);
+    assert_eq!(track.until_change(420, true), Some(80));
+}
+
+#[test]
+fn the_soonest_track_decides_when_to_look_again() {
+    let effect = CustomEffect {
+        tracks: vec![
+            track(),
+            Track {
+                keyframes: vec![Keyframe { rgb: RED, fade: 0, hold: 50 }],
+            },
+        ],
+        should_loop: true,
+        ..CustomEffect::default()
+    };
+
+    assert_eq!(effect.sampler().until_change(20), Some(30));
+    assert_eq!(effect.sampler().until_change(150), Some(0));
+
+    let expression = CustomEffect {
+        expression: Some("zone.r = 255".to_string()),
+        ..CustomEffect::default()
+    };
+    assert_eq!(expression.sampler().until_change(0), Some(0));
+}
+
+#[test]
+fn first_format_steps_become_keyframes() {
+    let effect: CustomEffect = serde_json::from_value(json!({
+        "name": "Old",
+        "should_loop": true,
+        "effect_steps": [
+            { "rgb_array": [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255], "step_type": "Set", "brightness": 2, "steps": 0, "delay_between_steps": 0, "sleep": 500 },
+            { "rgb_array": [0, 0, 255, 0, 0, 255, 0, 0, 255, 0, 0, 255], "step_type": "Transition", "brightness": 2, "steps": 10, "delay_between_steps": 30, "sleep": 200 },
+        ],
+    }))
+    .unwrap();
+
+    assert_eq!(effect.version, FORMAT_VERSION);
+    assert_eq!(effect.name.as_deref(), Some("Old"));
+    assert!(effect.should_loop);
+    assert!(effect.thumbnail.is_some());
+    assert_eq!(effect.tracks.len(), 4);
+    assert_eq!(
+        effect.tracks[3].keyframes,
+        [Keyframe { rgb: [255; 3], fade: 0, hold: 500 }, Keyframe { rgb: BLUE, fade: 300, hold: 200 }]
+    );
+    assert_eq!(effect.colors_at(0), [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255]);
+}
+
+#[test]
+fn first_format_keeps_the_brightness_of_each_step() {
+    let rgb_array = [200; 12];
+    let effect: CustomEffect = serde_json::from_value(json!({
+        "name": null,
+        "should_loop": false,
+        "effect_steps": [
+            { "rgb_array": rgb_array, "step_type": "Set", "brightness": 2, "steps": 0, "delay_between_steps": 0, "sleep": 100 },
+            { "rgb_array": rgb_array, "step_type": "Set", "brightness": 1, "steps": 0, "delay_between_steps": 0, "sleep": 100 },
+        ],
+    }))
+    .unwrap();
+
+    assert_eq!(effect.brightness, 2);
+    assert_eq!(effect.colors_at(50), [200; 12]);
+    assert_eq!(effect.colors_at(150), [100; 12]);
+}
+
+#[test]
+fn expressions_that_dont_parse_dont_load() {
+    let broken = CustomEffect::try_from(json!({ "version": FORMAT_VERSION, "brightness": 1, "expression": "zone.r = sin(" }));
+    assert!(matches!(broken, Err(FormatError::InvalidExpression(error)) if error.line == 1));
+
+    let working = CustomEffect::try_from(json!({ "version": FORMAT_VERSION, "brightness": 1, "expression": "zone.r = 255" })).unwrap();
+    assert_eq!(working.colors_at(0), [255, 0, 0, 255, 0, 0, 255, 0, 0, 255, 0, 0]);
+}
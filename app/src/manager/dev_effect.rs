@@ -0,0 +1,158 @@
+//! Effects loaded from a dynamic library and reloaded whenever it gets rebuilt, to prototype new effects without restarting.
+//!
+//! The library has to export the following functions, see `dev-effect-template` for an example:
+//! - `l5p_effect_abi_version() -> u32`, returning [`ABI_VERSION`]
+//! - `l5p_effect_frame(input: *const FrameInput, state: *mut u8, state_len: usize, rgb: *mut u8) -> u32`, writing the 12 color values
+//!   to `rgb` and returning how many milliseconds to wait before the next frame
+//!
+//! Effects can't keep anything in statics as those are lost on every reload, they get a buffer aligned to 8 bytes that's kept around instead.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use error_stack::{Result, ResultExt};
+use legion_kb_rgb_hw::BaseEffects;
+use libloading::Library;
+use notify::{Event, RecursiveMode, Watcher};
+use thiserror::Error;
+use tracing::error;
+
+use super::{profile::Profile, Inner};
+
+/// Bumped whenever the functions or [`FrameInput`] change
+pub const ABI_VERSION: u32 = 1;
+
+/// Size of the buffer effects get to keep their state in across frames and reloads
+const STATE_SIZE: usize = 4096;
+
+type AbiVersionFn = unsafe extern "C" fn() -> u32;
+type FrameFn = unsafe extern "C" fn(input: *const FrameInput, state: *mut u8, state_len: usize, rgb: *mut u8) -> u32;
+
+#[repr(C)]
+// Only read by the library
+#[allow(dead_code)]
+pub struct FrameInput {
+    /// Milliseconds since the effect started, not reset by reloads
+    pub time_ms: u64,
+    /// Incremented every time the library gets reloaded, starting at 0
+    pub generation: u32,
+    pub speed: u8,
+    pub brightness: u8,
+    /// The colors of the profile the effect was started with
+    pub colors: [u8; 12],
+}
+
+#[derive(Debug, Error)]
+#[error("Could not load the effect library")]
+pub struct LoadDevEffectError;
+
+struct DevEffectLibrary {
+    frame: FrameFn,
+    // The function above points into the library, so it has to be kept loaded
+    _library: Library,
+    /// The copy that was loaded, so the original can be overwritten by the compiler
+    copy: PathBuf,
+}
+
+impl DevEffectLibrary {
+    fn load(path: &Path, generation: u32) -> Result<Self, LoadDevEffectError> {
+        let file_name = path.file_name().ok_or(LoadDevEffectError).attach_printable("The path is not a file")?;
+        let copy = env::temp_dir().join(format!("{}-{generation}-{}", std::process::id(), file_name.to_string_lossy()));
+        fs::copy(path, &copy).change_context(LoadDevEffectError).attach_printable("Could not copy the library")?;
+
+        // SAFETY: The library is trusted to be an effect built against this ABI, which gets checked before anything else is called
+        unsafe {
+            let library = Library::new(&copy).change_context(LoadDevEffectError)?;
+
+            let abi_version = library.get::<AbiVersionFn>(b"l5p_effect_abi_version").change_context(LoadDevEffectError)?;
+            let version = abi_version();
+            if version != ABI_VERSION {
+                return Err(LoadDevEffectError).attach_printable(format!("The library was built for ABI version {version}, expected {ABI_VERSION}"));
+            }
+
+            let frame = *library.get::<FrameFn>(b"l5p_effect_frame").change_context(LoadDevEffectError)?;
+
+            Ok(Self { frame, _library: library, copy })
+        }
+    }
+
+    fn frame(&self, input: &FrameInput, state: &mut [u64], rgb: &mut [u8; 12]) -> u32 {
+        // SAFETY: All pointers are valid for the lengths given, and the library was checked to use the same ABI
+        unsafe { (self.frame)(input, state.as_mut_ptr().cast(), size_of_val(state), rgb.as_mut_ptr()) }
+    }
+}
+
+impl Drop for DevEffectLibrary {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.copy);
+    }
+}
+
+pub fn play(manager: &mut Inner, path: &Path, profile: &Profile) {
+    let mut generation = 0;
+    let mut library = match DevEffectLibrary::load(path, generation) {
+        Ok(library) => library,
+        Err(err) => {
+            error!("{err:?}");
+            return;
+        }
+    };
+
+    // Watch the folder as the file gets replaced rather than modified by most linkers
+    let changed = Arc::new(AtomicBool::new(false));
+    let changed_c = changed.clone();
+    let file_name = path.file_name().map(ToOwned::to_owned);
+    let watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        if event.is_ok_and(|event| event.paths.iter().any(|changed_path| changed_path.file_name() == file_name.as_deref())) {
+            changed_c.store(true, Ordering::SeqCst);
+        }
+    });
+    let _watcher = watcher.ok().and_then(|mut watcher| {
+        let folder = path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        watcher.watch(folder, RecursiveMode::NonRecursive).ok().map(|()| watcher)
+    });
+
+    manager.keyboard.set_effect(BaseEffects::Static).unwrap();
+
+    // Stored as u64s so whatever the effect keeps in it is aligned
+    let mut state = vec![0_u64; STATE_SIZE / 8];
+    let mut rgb = [0; 12];
+    let start = manager.clock.now();
+
+    while !manager.stop_signals.manager_stop_signal.load(Ordering::SeqCst) {
+        if changed.swap(false, Ordering::SeqCst) {
+            // Give the compiler some time to finish writing the file
+            thread::sleep(Duration::from_millis(200));
+
+            match DevEffectLibrary::load(path, generation + 1) {
+                Ok(new_library) => {
+                    generation += 1;
+                    library = new_library;
+                    println!("Reloaded {} (generation {generation})", path.display());
+                }
+                Err(err) => error!("{err:?}"),
+            }
+        }
+
+        let input = FrameInput {
+            time_ms: manager.clock.now().duration_since(start).as_millis() as u64,
+            generation,
+            speed: profile.speed,
+            brightness: profile.brightness as u8 + 1,
+            colors: profile.rgb_array(),
+        };
+
+        let delay = library.frame(&input, &mut state, &mut rgb);
+        manager.keyboard.set_colors_to(&rgb).unwrap();
+
+        manager.clock.sleep(Duration::from_millis(delay.clamp(1, 1000).into()));
+    }
+}
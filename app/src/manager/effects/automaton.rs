@@ -0,0 +1,84 @@
+use std::{collections::HashSet, sync::atomic::Ordering, time::Duration};
+
+use rand::{rngs::StdRng, Rng};
+
+use crate::{
+    enums::DEFAULT_AUTOMATON_RULE,
+    manager::{profile::Profile, Inner},
+};
+
+const FRAME: Duration = Duration::from_millis(50);
+/// Cells of the ring the automaton runs on, spread evenly across the zones
+const CELLS: u32 = 16;
+const CELLS_PER_ZONE: u32 = CELLS / 4;
+/// Lowest level of a zone, so the colors can still be told apart while its cells are dead
+const IDLE_LEVEL: f32 = 0.05;
+
+/// Run an elementary cellular automaton following `rule` on a ring of cells, each zone lit by how many of its cells live
+///
+/// The rule uses Wolfram's numbering. Every rule ends up repeating itself on so few cells, so the ring gets a new random
+/// seed as soon as it comes back to a state it already went through, and the animation never loops. Going from one
+/// generation to the next fades, and the speed sets how often that happens.
+pub fn play(manager: &mut Inner, p: &Profile, rule: u8, rng: &mut StdRng) {
+    let rule = if rule == 0 { DEFAULT_AUTOMATON_RULE } else { rule };
+    let generation = Duration::from_millis(1600 / u64::from(p.speed.max(1)));
+    let colors = p.rgb_array();
+
+    let mut cells = seed(rng);
+    let mut seen = HashSet::from([cells]);
+    let mut previous = levels(cells);
+    let mut since_generation = Duration::ZERO;
+
+    while !manager.stop_signals.manager_stop_signal.load(Ordering::SeqCst) {
+        since_generation += FRAME;
+        if since_generation >= generation {
+            since_generation = Duration::ZERO;
+            previous = levels(cells);
+
+            cells = step(cells, rule);
+            if !seen.insert(cells) {
+                cells = seed(rng);
+                seen.clear();
+                seen.insert(cells);
+            }
+        }
+
+        let mix = since_generation.as_secs_f32() / generation.as_secs_f32();
+        let current = levels(cells);
+
+        let mut arr = [0; 12];
+        for (i, value) in arr.iter_mut().enumerate() {
+            let zone = i / 3;
+            let level = (current[zone] - previous[zone]).mul_add(mix, previous[zone]).max(IDLE_LEVEL);
+            *value = (f32::from(colors[i]) * level) as u8;
+        }
+        manager.keyboard.set_colors_to(&arr).unwrap();
+
+        manager.clock.sleep(FRAME);
+    }
+}
+
+/// A random ring, never empty as most rules would keep it that way
+fn seed(rng: &mut StdRng) -> u16 {
+    rng.gen_range(1..=u16::MAX)
+}
+
+/// The next generation of the ring, each cell looking at itself and its two neighbours
+fn step(cells: u16, rule: u8) -> u16 {
+    (0..CELLS).fold(0, |next, i| {
+        let left = (cells.rotate_left(1) >> i) & 1;
+        let center = (cells >> i) & 1;
+        let right = (cells.rotate_right(1) >> i) & 1;
+        let pattern = (left << 2) | (center << 1) | right;
+
+        next | (u16::from((rule >> pattern) & 1) << i)
+    })
+}
+
+/// How much of each zone is alive, from 0 to 1
+fn levels(cells: u16) -> [f32; 4] {
+    [0, 1, 2, 3].map(|zone| {
+        let mask = ((1 << CELLS_PER_ZONE) - 1) << (zone * CELLS_PER_ZONE);
+        (cells & mask).count_ones() as f32 / CELLS_PER_ZONE as f32
+    })
+}
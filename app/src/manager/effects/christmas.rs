@@ -1,17 +1,17 @@
-use std::{sync::atomic::Ordering, thread, time::Duration};
+use std::{sync::atomic::Ordering, time::Duration};
 
 use rand::Rng;
 
 use crate::manager::Inner;
 
-pub fn play(manager: &mut Inner, thread_rng: &mut rand::rngs::ThreadRng) {
+pub fn play(manager: &mut Inner, rng: &mut rand::rngs::StdRng) {
     let xmas_color_array = [[255, 10, 10], [255, 255, 20], [30, 255, 30], [70, 70, 255]];
     let subeffect_count = 4;
     let mut last_subeffect = -1;
     while !manager.stop_signals.manager_stop_signal.load(Ordering::SeqCst) {
-        let mut subeffect = thread_rng.gen_range(0..subeffect_count);
+        let mut subeffect = rng.gen_range(0..subeffect_count);
         while last_subeffect == subeffect {
-            subeffect = thread_rng.gen_range(0..subeffect_count);
+            subeffect = rng.gen_range(0..subeffect_count);
         }
         last_subeffect = subeffect;
 
@@ -20,32 +20,32 @@ pub fn play(manager: &mut Inner, thread_rng: &mut rand::rngs::ThreadRng) {
                 for _i in 0..3 {
                     for colors in xmas_color_array {
                         manager.keyboard.solid_set_colors_to(colors).unwrap();
-                        thread::sleep(Duration::from_millis(500));
+                        manager.clock.sleep(Duration::from_millis(500));
                     }
                 }
             }
             1 => {
-                let color_1_index = thread_rng.gen_range(0..4);
+                let color_1_index = rng.gen_range(0..4);
                 let used_colors_1: [u8; 3] = xmas_color_array[color_1_index];
 
-                let mut color_2_index = thread_rng.gen_range(0..4);
+                let mut color_2_index = rng.gen_range(0..4);
                 while color_1_index == color_2_index {
-                    color_2_index = thread_rng.gen_range(0..4);
+                    color_2_index = rng.gen_range(0..4);
                 }
                 let used_colors_2: [u8; 3] = xmas_color_array[color_2_index];
 
                 for _i in 0..4 {
                     manager.keyboard.solid_set_colors_to(used_colors_1).unwrap();
-                    thread::sleep(Duration::from_millis(400));
+                    manager.clock.sleep(Duration::from_millis(400));
                     manager.keyboard.solid_set_colors_to(used_colors_2).unwrap();
-                    thread::sleep(Duration::from_millis(400));
+                    manager.clock.sleep(Duration::from_millis(400));
                 }
             }
             2 => {
                 let steps = 100;
                 manager.keyboard.transition_colors_to(&[0; 12], steps, 1).unwrap();
                 let mut used_colors_array: [u8; 12] = [0; 12];
-                let left_or_right = thread_rng.gen_range(0..2);
+                let left_or_right = rng.gen_range(0..2);
                 if left_or_right == 0 {
                     for color in xmas_color_array {
                         for j in (0..12).step_by(3) {
@@ -84,9 +84,9 @@ pub fn play(manager: &mut Inner, thread_rng: &mut rand::rngs::ThreadRng) {
                 let steps = 30;
                 for _i in 0..4 {
                     manager.keyboard.transition_colors_to(&state1, steps, 1).unwrap();
-                    thread::sleep(Duration::from_millis(400));
+                    manager.clock.sleep(Duration::from_millis(400));
                     manager.keyboard.transition_colors_to(&state2, steps, 1).unwrap();
-                    thread::sleep(Duration::from_millis(400));
+                    manager.clock.sleep(Duration::from_millis(400));
                 }
             }
             _ => unreachable!("Subeffect index for Christmas effect is out of range."),
@@ -0,0 +1,89 @@
+use std::{f32::consts::PI, sync::atomic::Ordering, time::Duration};
+
+use chrono::{Datelike, Local, Timelike, Utc};
+
+use crate::manager::Inner;
+
+const WARM_TEMPERATURE: f32 = 2000.0;
+const COOL_TEMPERATURE: f32 = 6500.0;
+// Solar elevations (in degrees) at which the shift starts and ends
+const NIGHT_ELEVATION: f32 = -6.0;
+const DAY_ELEVATION: f32 = 10.0;
+const UPDATE_INTERVAL: Duration = Duration::from_secs(60);
+
+pub fn play(manager: &mut Inner, latitude: f32, longitude: f32) {
+    let (latitude, longitude) = if latitude == 0.0 && longitude == 0.0 { guess_location() } else { (latitude, longitude) };
+
+    while !manager.stop_signals.manager_stop_signal.load(Ordering::SeqCst) {
+        let elevation = solar_elevation(latitude, longitude);
+        let progress = ((elevation - NIGHT_ELEVATION) / (DAY_ELEVATION - NIGHT_ELEVATION)).clamp(0.0, 1.0);
+        let temperature = (COOL_TEMPERATURE - WARM_TEMPERATURE).mul_add(progress, WARM_TEMPERATURE);
+
+        let rgb = kelvin_to_rgb(temperature);
+        let mut arr = [0; 12];
+        for zone in arr.chunks_exact_mut(3) {
+            zone.copy_from_slice(&rgb);
+        }
+
+        manager.keyboard.transition_colors_to(&arr, 50, 20).unwrap();
+
+        let updated = manager.clock.now();
+        while manager.clock.now().duration_since(updated) < UPDATE_INTERVAL && !manager.stop_signals.manager_stop_signal.load(Ordering::SeqCst) {
+            manager.clock.sleep(Duration::from_millis(100));
+        }
+    }
+}
+
+/// Rough guess of where the user is based on the local UTC offset, since every hour is roughly 15 degrees of longitude
+fn guess_location() -> (f32, f32) {
+    let offset_seconds = Local::now().offset().local_minus_utc();
+    let longitude = offset_seconds as f32 / 3600.0 * 15.0;
+
+    // There's no reliable way to get the latitude, so go with something that gives reasonable day lengths
+    (40.0, longitude)
+}
+
+/// Approximate elevation of the sun above the horizon in degrees, based on the NOAA general solar position equations
+fn solar_elevation(latitude: f32, longitude: f32) -> f32 {
+    let now = Utc::now();
+    let hour = now.num_seconds_from_midnight() as f32 / 3600.0;
+
+    // Fractional year in radians
+    let gamma = 2.0 * PI / 365.0 * ((now.ordinal() as f32 - 1.0) + (hour - 12.0) / 24.0);
+
+    let equation_of_time = 229.18 * (0.000075 + 0.001868 * gamma.cos() - 0.032077 * gamma.sin() - 0.014615 * (2.0 * gamma).cos() - 0.040849 * (2.0 * gamma).sin());
+    let declination = 0.006918 - 0.399912 * gamma.cos() + 0.070257 * gamma.sin() - 0.006758 * (2.0 * gamma).cos() + 0.000907 * (2.0 * gamma).sin() - 0.002697 * (3.0 * gamma).cos()
+        + 0.00148 * (3.0 * gamma).sin();
+
+    let true_solar_time = hour.mul_add(60.0, 4.0f32.mul_add(longitude, equation_of_time));
+    let hour_angle = (true_solar_time / 4.0 - 180.0).to_radians();
+
+    let latitude = latitude.to_radians();
+    let cos_zenith = latitude.sin() * declination.sin() + latitude.cos() * declination.cos() * hour_angle.cos();
+
+    90.0 - cos_zenith.clamp(-1.0, 1.0).acos().to_degrees()
+}
+
+/// Approximate the RGB color of a black body at the given temperature
+/// Adapted from https://tannerhelland.com/2012/09/18/convert-temperature-rgb-algorithm-code.html
+fn kelvin_to_rgb(temperature: f32) -> [u8; 3] {
+    let temperature = temperature / 100.0;
+
+    let red = if temperature <= 66.0 { 255.0 } else { 329.698_73 * (temperature - 60.0).powf(-0.133_204_76) };
+
+    let green = if temperature <= 66.0 {
+        99.470_8 * temperature.ln() - 161.119_57
+    } else {
+        288.122_16 * (temperature - 60.0).powf(-0.075_514_85)
+    };
+
+    let blue = if temperature >= 66.0 {
+        255.0
+    } else if temperature <= 19.0 {
+        0.0
+    } else {
+        138.517_73 * (temperature - 10.0).ln() - 305.044_8
+    };
+
+    [red, green, blue].map(|channel| channel.clamp(0.0, 255.0) as u8)
+}
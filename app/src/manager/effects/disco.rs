@@ -1,17 +1,17 @@
-use std::{sync::atomic::Ordering, thread, time::Duration};
+use std::{sync::atomic::Ordering, time::Duration};
 
 use rand::Rng;
 
 use crate::manager::{profile::Profile, Inner};
 
-pub fn play(manager: &mut Inner, p: &Profile, thread_rng: &mut rand::rngs::ThreadRng) {
+pub fn play(manager: &mut Inner, p: &Profile, rng: &mut rand::rngs::StdRng) {
     while !manager.stop_signals.manager_stop_signal.load(Ordering::SeqCst) {
         let colors = [[255, 0, 0], [255, 255, 0], [0, 255, 0], [0, 255, 255], [0, 0, 255], [255, 0, 255]];
-        let colors_index = thread_rng.gen_range(0..6);
+        let colors_index = rng.gen_range(0..6);
         let new_values = colors[colors_index];
 
-        let zone_index = thread_rng.gen_range(0..4);
+        let zone_index = rng.gen_range(0..4);
         manager.keyboard.set_zone_by_index(zone_index, new_values).unwrap();
-        thread::sleep(Duration::from_millis(2000 / (u64::from(p.speed) * 4)));
+        manager.clock.sleep(Duration::from_millis(2000 / (u64::from(p.speed) * 4)));
     }
 }
@@ -4,7 +4,7 @@ use std::{
         Arc,
     },
     thread,
-    time::{Duration, Instant},
+    time::Duration,
 };
 
 use device_query::DeviceQuery;
@@ -35,18 +35,18 @@ pub fn play(manager: &mut Inner, p: &Profile) {
         }
     });
 
-    let mut now = Instant::now();
+    let mut last_press = manager.clock.now();
     while !manager.stop_signals.manager_stop_signal.load(Ordering::SeqCst) {
         if state.get_keys().is_empty() {
-            if now.elapsed() > Duration::from_secs(20 / u64::from(p.speed)) {
+            if manager.clock.now().duration_since(last_press) > Duration::from_secs(20 / u64::from(p.speed)) {
                 manager.keyboard.transition_colors_to(&[0; 12], 230, 3).unwrap();
             } else {
-                thread::sleep(Duration::from_millis(20));
+                manager.clock.sleep(Duration::from_millis(20));
             }
         } else {
             manager.keyboard.set_colors_to(&p.rgb_array()).unwrap();
             manager.stop_signals.keyboard_stop_signal.store(false, Ordering::SeqCst);
-            now = Instant::now();
+            last_press = manager.clock.now();
         }
     }
 
@@ -0,0 +1,36 @@
+use std::{sync::atomic::Ordering, time::Duration};
+
+use rand::{rngs::StdRng, Rng};
+
+use crate::{
+    enums::{DEFAULT_FIRE_HUE, DEFAULT_FIRE_INTENSITY},
+    manager::{profile::Profile, Inner},
+    util::hsv_to_rgb,
+};
+
+pub fn play(manager: &mut Inner, p: &Profile, intensity: f32, hue: f32, rng: &mut StdRng) {
+    let (intensity, hue) = if intensity == 0.0 { (DEFAULT_FIRE_INTENSITY, DEFAULT_FIRE_HUE) } else { (intensity, hue) };
+    // How "hot" each zone is, 1.0 being a fully lit flame
+    let mut heat = [1.0_f32; 4];
+
+    while !manager.stop_signals.manager_stop_signal.load(Ordering::SeqCst) {
+        let mut arr = [0; 12];
+
+        for (i, zone_heat) in heat.iter_mut().enumerate() {
+            // Drift towards a random target instead of jumping to it so the flicker doesn't look like noise
+            let target = 1.0 - rng.gen_range(0.0..=intensity);
+            *zone_heat = (*zone_heat + target) / 2.0;
+
+            // Cooler parts of the flame shift slightly towards red
+            let zone_hue = (hue - (1.0 - *zone_heat) * 15.0).max(0.0);
+            let rgb = hsv_to_rgb(zone_hue, 1.0, *zone_heat);
+
+            arr[(i * 3)..((i * 3) + 3)].copy_from_slice(&rgb);
+        }
+
+        manager.keyboard.transition_colors_to(&arr, 10, 3).unwrap();
+
+        let sleep_time = rng.gen_range(40..=200) / u64::from(p.speed);
+        manager.clock.sleep(Duration::from_millis(sleep_time));
+    }
+}
@@ -0,0 +1,35 @@
+use std::{sync::atomic::Ordering, time::Duration};
+
+use crate::{
+    manager::{keystrokes, Inner},
+    util::hsv_to_rgb,
+};
+
+const UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+/// How dim the zones are before any key has been counted, so the keyboard doesn't go dark
+const UNLIT: f32 = 0.08;
+
+/// Color the zones by how many keys have been pressed in them, from blue for the least used to red for the most used
+///
+/// The counts are relative to the most used zone, and only grow while counting is enabled from the statistics.
+pub fn play(manager: &mut Inner) {
+    while !manager.stop_signals.manager_stop_signal.load(Ordering::SeqCst) {
+        manager.keyboard.set_colors_to(&frame(keystrokes::counts())).unwrap();
+        manager.clock.sleep(UPDATE_INTERVAL);
+    }
+}
+
+fn frame(counts: [u64; 4]) -> [u8; 12] {
+    let most = counts.iter().copied().max().unwrap_or(0);
+
+    let zones = counts.map(|count| {
+        if most == 0 {
+            hsv_to_rgb(240.0, 1.0, UNLIT)
+        } else {
+            let relative = count as f32 / most as f32;
+            hsv_to_rgb(240.0 * (1.0 - relative), 1.0, 1.0)
+        }
+    });
+
+    zones.concat().try_into().unwrap()
+}
@@ -1,18 +1,18 @@
-use std::{sync::atomic::Ordering, thread, time::Duration};
+use std::{sync::atomic::Ordering, time::Duration};
 
-use rand::{rngs::ThreadRng, Rng};
+use rand::{rngs::StdRng, Rng};
 
 use crate::manager::{profile::Profile, Inner};
 
-pub fn play(manager: &mut Inner, p: &Profile, thread_rng: &mut ThreadRng) {
+pub fn play(manager: &mut Inner, p: &Profile, rng: &mut StdRng) {
     while !manager.stop_signals.manager_stop_signal.load(Ordering::SeqCst) {
         let profile_array = p.rgb_array();
 
         if manager.stop_signals.manager_stop_signal.load(Ordering::SeqCst) {
             break;
         }
-        let zone_index = thread_rng.gen_range(0..4);
-        let steps = thread_rng.gen_range(50..=200);
+        let zone_index = rng.gen_range(0..4);
+        let steps = rng.gen_range(50..=200);
 
         let mut arr = [0; 12];
         let zone_start = zone_index * 3;
@@ -23,7 +23,7 @@ pub fn play(manager: &mut Inner, p: &Profile, thread_rng: &mut ThreadRng) {
 
         manager.keyboard.set_colors_to(&arr).unwrap();
         manager.keyboard.transition_colors_to(&[0; 12], steps / p.speed, 5).unwrap();
-        let sleep_time = thread_rng.gen_range(100..=2000);
-        thread::sleep(Duration::from_millis(sleep_time));
+        let sleep_time = rng.gen_range(100..=2000);
+        manager.clock.sleep(Duration::from_millis(sleep_time));
     }
 }
@@ -0,0 +1,75 @@
+use std::{sync::atomic::Ordering, time::Duration};
+
+use sysinfo::Networks;
+
+use crate::{
+    enums::DEFAULT_MAX_MBPS,
+    manager::{profile::Profile, Inner},
+};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+const FRAME: Duration = Duration::from_millis(50);
+/// How much of each new sample goes into the smoothed throughput, to keep bursts from flickering
+const SMOOTHING: f32 = 0.3;
+/// Lowest level of a zone, so the colors can still be told apart while idle
+const IDLE_LEVEL: f32 = 0.05;
+/// Zones per second the chase moves at full throughput
+const MAX_CHASE_SPEED: f32 = 8.0;
+
+/// Show the throughput of every network interface, scaled to `max_mbps`
+///
+/// By default the two left zones follow the download and the two right ones the upload, lighting up the more data
+/// goes through. With `chase` a single lit zone goes around the keyboard instead, faster the more data goes through
+/// either way, and stopping once nothing does. Reduced motion keeps to the first way.
+pub fn play(manager: &mut Inner, p: &Profile, max_mbps: u16, chase: bool) {
+    let max_mbps = f32::from(if max_mbps == 0 { DEFAULT_MAX_MBPS } else { max_mbps });
+    let chase = chase && !manager.reduced_motion.load(Ordering::SeqCst);
+    let colors = p.rgb_array();
+
+    let mut networks = Networks::new_with_refreshed_list();
+    let mut last_sample = manager.clock.now();
+    let mut since_sample = Duration::ZERO;
+    let (mut download, mut upload) = (0.0_f32, 0.0_f32);
+    let mut position = 0.0_f32;
+
+    while !manager.stop_signals.manager_stop_signal.load(Ordering::SeqCst) {
+        since_sample += FRAME;
+        if since_sample >= SAMPLE_INTERVAL {
+            networks.refresh(true);
+            let now = manager.clock.now();
+            let seconds = now.duration_since(last_sample).as_secs_f32().max(0.001);
+            last_sample = now;
+            since_sample = Duration::ZERO;
+
+            let (received, transmitted) = networks.list().values().fold((0, 0), |(rx, tx), data| (rx + data.received(), tx + data.transmitted()));
+            download += (mbps(received, seconds) - download) * SMOOTHING;
+            upload += (mbps(transmitted, seconds) - upload) * SMOOTHING;
+        }
+
+        let (download_level, upload_level) = ((download / max_mbps).clamp(0.0, 1.0), (upload / max_mbps).clamp(0.0, 1.0));
+
+        let levels = if chase {
+            let throughput = download_level.max(upload_level);
+            position = MAX_CHASE_SPEED.mul_add(throughput * FRAME.as_secs_f32(), position) % 4.0;
+
+            [0, 1, 2, 3].map(|zone| {
+                let distance = (zone as f32 - position).rem_euclid(4.0).min((position - zone as f32).rem_euclid(4.0));
+                (1.0 - distance).max(IDLE_LEVEL)
+            })
+        } else {
+            [download_level, download_level, upload_level, upload_level].map(|level| level.max(IDLE_LEVEL))
+        };
+
+        let mut arr = [0; 12];
+        for (i, value) in arr.iter_mut().enumerate() {
+            *value = (f32::from(colors[i]) * levels[i / 3]) as u8;
+        }
+        manager.keyboard.set_colors_to(&arr).unwrap();
+
+        manager.clock.sleep(FRAME);
+    }
+}
+
+fn mbps(bytes: u64, seconds: f32) -> f32 {
+    bytes as f32 * 8.0 / 1_000_000.0 / seconds
+}
@@ -68,7 +68,7 @@ pub fn play(manager: &mut Inner, p: &Profile) {
     let mut zone_pressed: [HashSet<Keycode>; 4] = [HashSet::new(), HashSet::new(), HashSet::new(), HashSet::new()];
     let mut zone_state: [RippleMove; 4] = [RippleMove::Off, RippleMove::Off, RippleMove::Off, RippleMove::Off];
 
-    let mut last_step_time = Instant::now();
+    let mut last_step_time = manager.clock.now();
 
     while !manager.stop_signals.manager_stop_signal.load(Ordering::SeqCst) {
         match rx.try_recv() {
@@ -97,7 +97,7 @@ pub fn play(manager: &mut Inner, p: &Profile) {
             }
         }
 
-        zone_state = advance_zone_state(zone_state, &mut last_step_time, &p.speed);
+        zone_state = advance_zone_state(zone_state, manager.clock.now(), &mut last_step_time, &p.speed);
 
         for (i, pressed) in zone_pressed.iter().enumerate() {
             if !pressed.is_empty() {
@@ -115,15 +115,13 @@ pub fn play(manager: &mut Inner, p: &Profile) {
         }
 
         manager.keyboard.transition_colors_to(&final_arr, 20, 0).unwrap();
-        thread::sleep(Duration::from_millis(50));
+        manager.clock.sleep(Duration::from_millis(50));
     }
 
     kill_thread.store(true, Ordering::SeqCst);
 }
 
-fn advance_zone_state(zone_state: [RippleMove; 4], last_step_time: &mut Instant, speed: &u8) -> [RippleMove; 4] {
-    let now = Instant::now();
-
+fn advance_zone_state(zone_state: [RippleMove; 4], now: Instant, last_step_time: &mut Instant, speed: &u8) -> [RippleMove; 4] {
     if now - *last_step_time > Duration::from_millis((200 / *speed) as u64) {
         let mut new_state: [RippleMove; 4] = [RippleMove::Off, RippleMove::Off, RippleMove::Off, RippleMove::Off];
 
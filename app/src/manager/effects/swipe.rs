@@ -1,11 +1,16 @@
-use std::{sync::atomic::Ordering, thread, time::Duration};
+use std::{sync::atomic::Ordering, time::Duration};
 
 use crate::{
     enums::Direction,
-    manager::{profile::Profile, Inner},
+    manager::{palette::Palette, profile::Profile, Inner},
 };
 
 pub fn play(manager: &mut Inner, p: &Profile) {
+    if let Some(palette) = p.active_palette() {
+        play_palette(manager, p, palette);
+        return;
+    }
+
     let mut rgb_array = p.rgb_array();
 
     while !manager.stop_signals.manager_stop_signal.load(Ordering::SeqCst) {
@@ -23,6 +28,31 @@ pub fn play(manager: &mut Inner, p: &Profile) {
         if manager.stop_signals.manager_stop_signal.load(Ordering::SeqCst) {
             break;
         }
-        thread::sleep(Duration::from_millis(20));
+        manager.clock.sleep(Duration::from_millis(20));
+    }
+}
+
+/// Slide through every color of the palette, not only the four the zones were filled with
+fn play_palette(manager: &mut Inner, p: &Profile, palette: &Palette) {
+    let steps = palette.colors.len().max(4);
+    let mut offset = 0;
+
+    while !manager.stop_signals.manager_stop_signal.load(Ordering::SeqCst) {
+        offset = match p.direction {
+            Direction::Left => (offset + steps - 1) % steps,
+            Direction::Right => (offset + 1) % steps,
+        };
+
+        let mut rgb_array = [0; 12];
+        for (zone, rgb) in rgb_array.chunks_exact_mut(3).enumerate() {
+            rgb.copy_from_slice(&palette.sample_looped(zone as f32 / 4.0 + offset as f32 / steps as f32));
+        }
+
+        manager.keyboard.transition_colors_to(&rgb_array, 150 / p.speed, 10).unwrap();
+
+        if manager.stop_signals.manager_stop_signal.load(Ordering::SeqCst) {
+            break;
+        }
+        manager.clock.sleep(Duration::from_millis(20));
     }
 }
@@ -1,4 +1,4 @@
-use std::{sync::atomic::Ordering, thread, time::Duration};
+use std::{sync::atomic::Ordering, time::Duration};
 
 use sysinfo::{Components, System};
 
@@ -38,7 +38,7 @@ pub fn play(manager: &mut Inner) {
                     }
                     manager.keyboard.transition_colors_to(&target.map(|val| val as u8), 5, 1).unwrap();
                 }
-                thread::sleep(Duration::from_millis(200));
+                manager.clock.sleep(Duration::from_millis(200));
             }
         }
     }
@@ -0,0 +1,73 @@
+use std::{collections::VecDeque, sync::atomic::Ordering, time::Duration};
+
+use crate::{
+    enums::{DEFAULT_MAX_WPM, DEFAULT_MIN_WPM},
+    manager::{keystrokes, profile::Profile, Inner},
+};
+
+const FRAME: Duration = Duration::from_millis(50);
+/// How far back key presses count towards the speed
+const WINDOW: Duration = Duration::from_secs(10);
+/// Key presses making up a word, as typing tests usually count them
+const KEYS_PER_WORD: f32 = 5.0;
+/// How much of the distance to the measured speed the gauge covers every frame, to keep it from jumping around
+const SMOOTHING: f32 = 0.1;
+/// Lowest level of a zone, so the colors can still be told apart while idle
+const IDLE_LEVEL: f32 = 0.05;
+
+/// Show the typing speed, in words per minute over the last few seconds, going from `min_wpm` to `max_wpm`
+///
+/// By default the whole keyboard goes through the colors of the zones from left to right as the speed goes up. With
+/// `gauge` the zones light up one after the other in their own color instead, like a bar.
+pub fn play(manager: &mut Inner, p: &Profile, min_wpm: u16, max_wpm: u16, gauge: bool) {
+    let (min_wpm, max_wpm) = if max_wpm == 0 { (DEFAULT_MIN_WPM, DEFAULT_MAX_WPM) } else { (min_wpm, max_wpm) };
+    let (min_wpm, max_wpm) = (f32::from(min_wpm), f32::from(max_wpm.max(min_wpm.saturating_add(1))));
+    let colors = p.rgb_array();
+
+    let keys = keystrokes::subscribe();
+    let mut presses = VecDeque::new();
+    let mut level = 0.0_f32;
+
+    while !manager.stop_signals.manager_stop_signal.load(Ordering::SeqCst) {
+        let now = manager.clock.now();
+        presses.extend(keys.try_iter().map(|_| now));
+        while presses.front().is_some_and(|pressed| now.duration_since(*pressed) > WINDOW) {
+            presses.pop_front();
+        }
+
+        let wpm = presses.len() as f32 / KEYS_PER_WORD / (WINDOW.as_secs_f32() / 60.0);
+        let target = ((wpm - min_wpm) / (max_wpm - min_wpm)).clamp(0.0, 1.0);
+        level += (target - level) * SMOOTHING;
+
+        let frame = if gauge { gauge_frame(&colors, level) } else { gradient_frame(&colors, level) };
+        manager.keyboard.set_colors_to(&frame).unwrap();
+
+        manager.clock.sleep(FRAME);
+    }
+}
+
+/// Every zone in the color found at `level` along the zones, from the first one at 0 to the last one at 1
+fn gradient_frame(colors: &[u8; 12], level: f32) -> [u8; 12] {
+    let position = level * 3.0;
+    let from = (position.floor() as usize).min(2);
+    let mix = position - from as f32;
+
+    let mut rgb = [0; 3];
+    for (channel, value) in rgb.iter_mut().enumerate() {
+        let (start, end) = (f32::from(colors[from * 3 + channel]), f32::from(colors[(from + 1) * 3 + channel]));
+        *value = (end - start).mul_add(mix, start) as u8;
+    }
+
+    rgb.repeat(4).try_into().unwrap()
+}
+
+/// The zones lit from left to right up to `level`, the zone being filled lit in proportion
+fn gauge_frame(colors: &[u8; 12], level: f32) -> [u8; 12] {
+    let mut arr = [0; 12];
+    for (i, value) in arr.iter_mut().enumerate() {
+        let zone_level = level.mul_add(4.0, -((i / 3) as f32)).clamp(IDLE_LEVEL, 1.0);
+        *value = (f32::from(colors[i]) * zone_level) as u8;
+    }
+
+    arr
+}
@@ -0,0 +1,52 @@
+use std::{
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
+
+use chrono::{Local, Timelike};
+
+use crate::{manager::Inner, util::hsv_to_rgb};
+
+const UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+/// How dim the zones still to come are, so the whole keyboard stays readable in the dark
+const UNLIT: f32 = 0.08;
+
+/// Show the time, or what's left of a countdown when `countdown_minutes` isn't 0
+///
+/// The time shows the hour as a hue going around the color wheel over 12 hours, and the minutes as a bar filling up
+/// the four zones (a quarter of an hour each). A countdown goes from green to red as the bar empties, staying red once
+/// it's over. The countdown ends at the deadline the GUI set, so changing the brightness or the speed doesn't start
+/// it over, or counts from whenever the effect starts without one. Either way it goes by the wall clock, not the
+/// effect speed.
+pub fn play(manager: &mut Inner, countdown_minutes: u16) {
+    let countdown = Duration::from_secs(u64::from(countdown_minutes) * 60);
+    let deadline = manager.countdown_deadline.lock().unwrap().unwrap_or_else(|| Instant::now() + countdown);
+
+    while !manager.stop_signals.manager_stop_signal.load(Ordering::SeqCst) {
+        let (hue, fill) = if countdown.is_zero() {
+            let now = Local::now();
+            let hue = (now.hour() % 12) as f32 * 30.0;
+            let fill = (now.minute() as f32 * 60.0 + now.second() as f32) / 3600.0;
+            (hue, fill)
+        } else {
+            let remaining = deadline.saturating_duration_since(Instant::now()).min(countdown);
+            let fill = remaining.as_secs_f32() / countdown.as_secs_f32();
+            (120.0 * fill, if remaining.is_zero() { 1.0 } else { fill })
+        };
+
+        manager.keyboard.set_colors_to(&frame(hue, fill)).unwrap();
+        manager.clock.sleep(UPDATE_INTERVAL);
+    }
+}
+
+/// The four zones as a bar filled up to `fill` (from 0 to 1), the zone being filled lit in proportion
+fn frame(hue: f32, fill: f32) -> [u8; 12] {
+    let mut arr = [0; 12];
+
+    for (zone, chunk) in arr.chunks_exact_mut(3).enumerate() {
+        let level = (fill * 4.0 - zone as f32).clamp(0.0, 1.0);
+        chunk.copy_from_slice(&hsv_to_rgb(hue, 1.0, (1.0 - UNLIT).mul_add(level, UNLIT)));
+    }
+
+    arr
+}
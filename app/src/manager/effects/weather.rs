@@ -0,0 +1,148 @@
+use std::{
+    f32::consts::TAU,
+    sync::{atomic::Ordering, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crossbeam_channel::RecvTimeoutError;
+use rand::{rngs::StdRng, Rng};
+
+use crate::{
+    manager::{profile::Profile, Inner},
+    util::hsv_to_rgb,
+    weather::{Conditions, WeatherKind, WeatherSettings},
+};
+
+/// Temperatures (in degrees Celsius) mapped to the coldest and warmest colors
+const COLD: f32 = -10.0;
+const HOT: f32 = 35.0;
+/// From this temperature on, clear skies pulse instead of staying still
+const HEAT: f32 = 28.0;
+/// How long to wait before trying again when the conditions couldn't be fetched
+const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+const FRAME: Duration = Duration::from_millis(50);
+
+pub fn play(manager: &mut Inner, p: &Profile, latitude: f32, longitude: f32, refresh_minutes: u16, rng: &mut StdRng) {
+    let conditions = Arc::new(Mutex::new(None));
+    // Dropped when the effect stops, which wakes the fetching thread up to stop too
+    let (stop_tx, stop_rx) = crossbeam_channel::bounded::<()>(0);
+
+    // Fetching can take a few seconds, which the animation shouldn't stall for
+    {
+        let settings = manager.weather.lock().unwrap().clone();
+        let conditions = conditions.clone();
+        let refresh = Duration::from_secs(u64::from(refresh_minutes.max(1)) * 60);
+
+        thread::spawn(move || loop {
+            let fetched = fetch(&settings, latitude, longitude, refresh);
+            if fetched.is_some() {
+                *conditions.lock().unwrap() = fetched;
+            }
+
+            let wait = if fetched.is_some() { refresh } else { RETRY_INTERVAL };
+            if stop_rx.recv_timeout(wait) != Err(RecvTimeoutError::Timeout) {
+                return;
+            }
+        });
+    }
+
+    let speed = f32::from(p.speed.max(1));
+    let mut phase = 0.0_f32;
+    let mut flash = 0.0_f32;
+    let mut last_sent = None;
+
+    while !manager.stop_signals.manager_stop_signal.load(Ordering::SeqCst) {
+        let current = *conditions.lock().unwrap();
+        let still = manager.reduced_motion.load(Ordering::SeqCst);
+
+        let arr = match current {
+            Some(conditions) if !still => frame(conditions, phase, &mut flash, rng),
+            Some(conditions) => fill(temperature_color(conditions.temperature, 1.0)),
+            // A dim gray until the first conditions come in
+            None => fill([40, 40, 40]),
+        };
+        // Still skies and the gray shown while waiting only need sending once
+        if last_sent != Some(arr) {
+            manager.keyboard.set_colors_to(&arr).unwrap();
+            last_sent = Some(arr);
+        }
+
+        phase = (phase + 0.01 * speed) % 1.0;
+        manager.clock.sleep(FRAME);
+    }
+
+    drop(stop_tx);
+}
+
+fn fetch(settings: &WeatherSettings, latitude: f32, longitude: f32, max_age: Duration) -> Option<Conditions> {
+    #[cfg(feature = "network")]
+    {
+        crate::weather::fetch(settings, latitude, longitude, max_age).map_err(|err| tracing::warn!("{err:?}")).ok()
+    }
+    #[cfg(not(feature = "network"))]
+    {
+        let _ = (settings, latitude, longitude, max_age);
+        None
+    }
+}
+
+/// One frame of the animation for the weather, `phase` going from 0 to 1 over a cycle
+fn frame(conditions: Conditions, phase: f32, flash: &mut f32, rng: &mut StdRng) -> [u8; 12] {
+    let base = temperature_color(conditions.temperature, 1.0);
+
+    match conditions.kind {
+        WeatherKind::Clear if conditions.temperature >= HEAT => {
+            // A slow warm pulse
+            let level = 0.6 + 0.4 * (phase * TAU).sin().abs();
+            fill(temperature_color(conditions.temperature, level))
+        }
+        WeatherKind::Clear => fill(base),
+        WeatherKind::Clouds => zones(|zone| {
+            // Patches of gray drifting over the sky color
+            let cover = 0.5 + 0.5 * ((phase + zone as f32 * 0.25) * TAU).sin();
+            mix(base, [110, 110, 120], cover * 0.7)
+        }),
+        WeatherKind::Fog => fill(mix(base, [90, 90, 90], 0.8)),
+        WeatherKind::Rain => zones(|zone| {
+            // A blue ripple going across the keyboard
+            let offset = (zone as f32 - phase * 4.0).rem_euclid(4.0);
+            let distance = offset.min(4.0 - offset);
+            let level = (1.0 - distance).max(0.0);
+            mix([0, 20, 80], [60, 140, 255], level)
+        }),
+        WeatherKind::Snow => zones(|_| {
+            let sparkle = rng.gen_range(0.0..1.0_f32).powi(4);
+            mix([150, 170, 200], [255, 255, 255], sparkle)
+        }),
+        WeatherKind::Storm => {
+            if *flash < 0.05 && rng.gen_bool(0.02) {
+                *flash = 1.0;
+            }
+            *flash *= 0.7;
+            fill(mix([10, 10, 50], [255, 255, 255], *flash))
+        }
+    }
+}
+
+/// From a cold blue to a warm orange, scaled by `level`
+fn temperature_color(temperature: f32, level: f32) -> [u8; 3] {
+    let warmth = ((temperature - COLD) / (HOT - COLD)).clamp(0.0, 1.0);
+    hsv_to_rgb(220.0 - warmth * 200.0, 0.8, level)
+}
+
+fn mix(from: [u8; 3], to: [u8; 3], amount: f32) -> [u8; 3] {
+    [0, 1, 2].map(|channel| (f32::from(from[channel]) + (f32::from(to[channel]) - f32::from(from[channel])) * amount.clamp(0.0, 1.0)) as u8)
+}
+
+fn fill(rgb: [u8; 3]) -> [u8; 12] {
+    zones(|_| rgb)
+}
+
+fn zones(mut color: impl FnMut(usize) -> [u8; 3]) -> [u8; 12] {
+    let mut arr = [0; 12];
+    for (zone, chunk) in arr.chunks_exact_mut(3).enumerate() {
+        chunk.copy_from_slice(&color(zone));
+    }
+    arr
+}
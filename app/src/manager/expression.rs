@@ -0,0 +1,516 @@
+//! A small language for custom effects written as math instead of keyframes, run once per zone for every frame.
+//!
+//! A program is a list of assignments, one per line or separated by `;`, with `#` starting a comment:
+//!
+//! ```text
+//! speed = 2
+//! zone.r = sin(t * speed + i) * 127 + 128
+//! zone.b = x * 255
+//! ```
+//!
+//! The zone gets the color left in `zone.r`, `zone.g` and `zone.b`, each clamped to 0-255 and starting at 0. Other names
+//! are variables of the zone, readable once assigned. What's known about the zone and time is in `t` (seconds since the
+//! effect started), `i` (the zone, from 0 on the left to 3) and `x` (the same from 0 to 1), along with `pi`.
+
+use std::{f32::consts::PI, iter::Peekable, str::CharIndices};
+
+use thiserror::Error;
+
+#[cfg(test)]
+mod tests;
+
+/// The names every program can read but not assign, in the order of their slots
+const INPUTS: [&str; 4] = ["t", "i", "x", "pi"];
+/// What a program assigns to color the zone, in the slots after the inputs
+const OUTPUTS: [&str; 3] = ["zone.r", "zone.g", "zone.b"];
+const ZONES: usize = 4;
+/// The most arguments a function takes
+const MAX_ARGUMENTS: usize = 3;
+
+/// Why a program couldn't be parsed, and where, counting lines and columns from 1
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Line {line}, column {column}: {kind}")]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ParseErrorKind {
+    #[error("unexpected \"{0}\"")]
+    UnexpectedChar(char),
+    #[error("expected {0}")]
+    Expected(&'static str),
+    #[error("\"{0}\" isn't known, assign it before using it")]
+    UnknownVariable(String),
+    #[error("there's no function called \"{0}\"")]
+    UnknownFunction(String),
+    #[error("{name} takes {expected} argument(s), not {found}")]
+    WrongArgumentCount { name: &'static str, expected: usize, found: usize },
+    #[error("\"{0}\" can't be assigned to")]
+    ReadOnly(String),
+}
+
+/// A parsed program, ready to color the zones
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    statements: Vec<Statement>,
+    /// How many values a zone needs, the inputs and outputs followed by the variables
+    slots: usize,
+}
+
+impl Program {
+    pub fn parse(source: &str) -> Result<Self, ParseError> {
+        Parser::new(source).program()
+    }
+
+    /// The colors of the four zones `seconds` into the effect
+    pub fn colors_at(&self, seconds: f32) -> [u8; 12] {
+        let mut rgb_array = [0; 12];
+        let mut values = vec![0.0; self.slots];
+
+        for zone in 0..ZONES {
+            values.fill(0.0);
+            values[..INPUTS.len()].copy_from_slice(&[seconds, zone as f32, zone as f32 / (ZONES - 1) as f32, PI]);
+
+            for statement in &self.statements {
+                values[statement.slot] = statement.expr.eval(&values);
+            }
+
+            for (channel, value) in rgb_array[zone * 3..zone * 3 + 3].iter_mut().zip(&values[INPUTS.len()..]) {
+                *channel = if value.is_nan() { 0 } else { value.round().clamp(0.0, 255.0) as u8 };
+            }
+        }
+
+        rgb_array
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Statement {
+    slot: usize,
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f32),
+    Slot(usize),
+    Negate(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, values: &[f32]) -> f32 {
+        match self {
+            Self::Number(number) => *number,
+            Self::Slot(slot) => values[*slot],
+            Self::Negate(expr) => -expr.eval(values),
+            Self::Binary(op, left, right) => op.apply(left.eval(values), right.eval(values)),
+            Self::Call(function, args) => {
+                let mut evaluated = [0.0; MAX_ARGUMENTS];
+                for (value, arg) in evaluated.iter_mut().zip(args) {
+                    *value = arg.eval(values);
+                }
+                function.apply(evaluated)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Power,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl BinaryOp {
+    fn apply(self, left: f32, right: f32) -> f32 {
+        let truth = |condition: bool| if condition { 1.0 } else { 0.0 };
+
+        match self {
+            Self::Add => left + right,
+            Self::Subtract => left - right,
+            Self::Multiply => left * right,
+            Self::Divide => left / right,
+            // Always positive, so going backwards keeps cycling the same way
+            Self::Remainder => left.rem_euclid(right),
+            Self::Power => left.powf(right),
+            Self::Less => truth(left < right),
+            Self::LessOrEqual => truth(left <= right),
+            Self::Greater => truth(left > right),
+            Self::GreaterOrEqual => truth(left >= right),
+            Self::Equal => truth(left == right),
+            Self::NotEqual => truth(left != right),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Abs,
+    Sqrt,
+    Floor,
+    Ceil,
+    Round,
+    Fract,
+    Min,
+    Max,
+    Clamp,
+    Mix,
+}
+
+impl Function {
+    const ALL: [Self; 13] = [
+        Self::Sin,
+        Self::Cos,
+        Self::Tan,
+        Self::Abs,
+        Self::Sqrt,
+        Self::Floor,
+        Self::Ceil,
+        Self::Round,
+        Self::Fract,
+        Self::Min,
+        Self::Max,
+        Self::Clamp,
+        Self::Mix,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Sin => "sin",
+            Self::Cos => "cos",
+            Self::Tan => "tan",
+            Self::Abs => "abs",
+            Self::Sqrt => "sqrt",
+            Self::Floor => "floor",
+            Self::Ceil => "ceil",
+            Self::Round => "round",
+            Self::Fract => "fract",
+            Self::Min => "min",
+            Self::Max => "max",
+            Self::Clamp => "clamp",
+            Self::Mix => "mix",
+        }
+    }
+
+    fn arity(self) -> usize {
+        match self {
+            Self::Min | Self::Max => 2,
+            Self::Clamp | Self::Mix => 3,
+            _ => 1,
+        }
+    }
+
+    /// Run the function on as many arguments as [`Self::arity`], which the parser made sure of, the rest being 0
+    fn apply(self, args: [f32; MAX_ARGUMENTS]) -> f32 {
+        match self {
+            Self::Sin => args[0].sin(),
+            Self::Cos => args[0].cos(),
+            Self::Tan => args[0].tan(),
+            Self::Abs => args[0].abs(),
+            Self::Sqrt => args[0].sqrt(),
+            Self::Floor => args[0].floor(),
+            Self::Ceil => args[0].ceil(),
+            Self::Round => args[0].round(),
+            Self::Fract => args[0].rem_euclid(1.0),
+            Self::Min => args[0].min(args[1]),
+            Self::Max => args[0].max(args[1]),
+            Self::Clamp => args[0].max(args[1]).min(args[2]),
+            // From the first value at 0 to the second at 1
+            Self::Mix => (args[1] - args[0]).mul_add(args[2], args[0]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f32),
+    Name(String),
+    Symbol(&'static str),
+    /// A new line or `;`
+    End,
+}
+
+/// The symbols the language knows, the longer ones first so they're matched whole
+const SYMBOLS: [&str; 16] = ["<=", ">=", "==", "!=", "+", "-", "*", "/", "%", "^", "<", ">", "=", "(", ")", ","];
+
+struct Parser<'a> {
+    source: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    /// The token coming up and where it starts, as a byte offset
+    current: Option<(Token, usize)>,
+    /// Every name with a slot, the inputs and outputs first and then the variables as they get assigned
+    names: Vec<String>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            chars: source.char_indices().peekable(),
+            current: None,
+            names: INPUTS.iter().chain(&OUTPUTS).map(ToString::to_string).collect(),
+        }
+    }
+
+    fn program(mut self) -> Result<Program, ParseError> {
+        self.advance()?;
+        let mut statements = Vec::new();
+
+        while let Some((token, offset)) = self.current.clone() {
+            match token {
+                Token::End => self.advance()?,
+                Token::Name(name) => {
+                    self.advance()?;
+                    self.expect("=", "\"=\" after the name")?;
+                    let expr = self.expr()?;
+                    if !matches!(self.current, None | Some((Token::End, _))) {
+                        return Err(self.error_here(ParseErrorKind::Expected("a new line or \";\"")));
+                    }
+
+                    // Assigned after the expression is read, so `a = a + 1` doesn't read an `a` that isn't there
+                    let slot = self.assignable_slot(&name).ok_or_else(|| self.error_at(offset, ParseErrorKind::ReadOnly(name.clone())))?;
+                    statements.push(Statement { slot, expr });
+                }
+                _ => return Err(self.error_at(offset, ParseErrorKind::Expected("a name to assign to"))),
+            }
+        }
+
+        Ok(Program {
+            statements,
+            slots: self.names.len(),
+        })
+    }
+
+    fn assignable_slot(&mut self, name: &str) -> Option<usize> {
+        if INPUTS.contains(&name) || (name.contains('.') && !OUTPUTS.contains(&name)) {
+            return None;
+        }
+
+        Some(self.slot(name).unwrap_or_else(|| {
+            self.names.push(name.to_string());
+            self.names.len() - 1
+        }))
+    }
+
+    fn slot(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|known| known == name)
+    }
+
+    fn expr(&mut self) -> Result<Expr, ParseError> {
+        let left = self.sum()?;
+        let op = match self.current_symbol() {
+            Some("<") => BinaryOp::Less,
+            Some("<=") => BinaryOp::LessOrEqual,
+            Some(">") => BinaryOp::Greater,
+            Some(">=") => BinaryOp::GreaterOrEqual,
+            Some("==") => BinaryOp::Equal,
+            Some("!=") => BinaryOp::NotEqual,
+            _ => return Ok(left),
+        };
+        self.advance()?;
+
+        Ok(Expr::Binary(op, Box::new(left), Box::new(self.sum()?)))
+    }
+
+    fn sum(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.product()?;
+        loop {
+            let op = match self.current_symbol() {
+                Some("+") => BinaryOp::Add,
+                Some("-") => BinaryOp::Subtract,
+                _ => return Ok(left),
+            };
+            self.advance()?;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.product()?));
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.unary()?;
+        loop {
+            let op = match self.current_symbol() {
+                Some("*") => BinaryOp::Multiply,
+                Some("/") => BinaryOp::Divide,
+                Some("%") => BinaryOp::Remainder,
+                _ => return Ok(left),
+            };
+            self.advance()?;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        if self.current_symbol() == Some("-") {
+            self.advance()?;
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+        self.power()
+    }
+
+    /// `^` goes from right to left, and before negating: `-2^2` is -4
+    fn power(&mut self) -> Result<Expr, ParseError> {
+        let base = self.primary()?;
+        if self.current_symbol() != Some("^") {
+            return Ok(base);
+        }
+        self.advance()?;
+
+        Ok(Expr::Binary(BinaryOp::Power, Box::new(base), Box::new(self.unary()?)))
+    }
+
+    fn primary(&mut self) -> Result<Expr, ParseError> {
+        let Some((token, offset)) = self.current.clone() else {
+            return Err(self.error_here(ParseErrorKind::Expected("a value")));
+        };
+
+        match token {
+            Token::Number(number) => {
+                self.advance()?;
+                Ok(Expr::Number(number))
+            }
+            Token::Symbol("(") => {
+                self.advance()?;
+                let expr = self.expr()?;
+                self.expect(")", "\")\"")?;
+                Ok(expr)
+            }
+            Token::Name(name) => {
+                self.advance()?;
+                if self.current_symbol() == Some("(") {
+                    return self.call(name, offset);
+                }
+
+                self.slot(&name).map(Expr::Slot).ok_or_else(|| self.error_at(offset, ParseErrorKind::UnknownVariable(name)))
+            }
+            _ => Err(self.error_at(offset, ParseErrorKind::Expected("a value"))),
+        }
+    }
+
+    fn call(&mut self, name: String, offset: usize) -> Result<Expr, ParseError> {
+        let function = Function::ALL
+            .into_iter()
+            .find(|function| function.name() == name)
+            .ok_or_else(|| self.error_at(offset, ParseErrorKind::UnknownFunction(name)))?;
+        self.advance()?;
+
+        let mut args = Vec::new();
+        if self.current_symbol() != Some(")") {
+            loop {
+                args.push(self.expr()?);
+                if self.current_symbol() != Some(",") {
+                    break;
+                }
+                self.advance()?;
+            }
+        }
+        self.expect(")", "\")\" or \",\"")?;
+
+        if args.len() != function.arity() {
+            return Err(self.error_at(
+                offset,
+                ParseErrorKind::WrongArgumentCount {
+                    name: function.name(),
+                    expected: function.arity(),
+                    found: args.len(),
+                },
+            ));
+        }
+
+        Ok(Expr::Call(function, args))
+    }
+
+    fn current_symbol(&self) -> Option<&'static str> {
+        match self.current {
+            Some((Token::Symbol(symbol), _)) => Some(symbol),
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, symbol: &'static str, expected: &'static str) -> Result<(), ParseError> {
+        if self.current_symbol() != Some(symbol) {
+            return Err(self.error_here(ParseErrorKind::Expected(expected)));
+        }
+        self.advance()
+    }
+
+    /// Move on to the next token, skipping spaces and comments
+    fn advance(&mut self) -> Result<(), ParseError> {
+        while let Some(&(offset, c)) = self.chars.peek() {
+            match c {
+                '\n' | ';' => {
+                    self.chars.next();
+                    self.current = Some((Token::End, offset));
+                    return Ok(());
+                }
+                '#' => {
+                    while self.chars.next_if(|&(_, c)| c != '\n').is_some() {}
+                }
+                c if c.is_whitespace() => {
+                    self.chars.next();
+                }
+                c if c.is_ascii_digit() || c == '.' => {
+                    let end = self.take_while(|c| c.is_ascii_digit() || c == '.');
+                    let text = &self.source[offset..end];
+                    let number = text.parse().map_err(|_| self.error_at(offset, ParseErrorKind::Expected("a number")))?;
+                    self.current = Some((Token::Number(number), offset));
+                    return Ok(());
+                }
+                c if c.is_alphabetic() || c == '_' => {
+                    let end = self.take_while(|c| c.is_alphanumeric() || c == '_' || c == '.');
+                    self.current = Some((Token::Name(self.source[offset..end].to_string()), offset));
+                    return Ok(());
+                }
+                _ => {
+                    let rest = &self.source[offset..];
+                    let symbol = SYMBOLS.into_iter().find(|symbol| rest.starts_with(symbol)).ok_or_else(|| self.error_at(offset, ParseErrorKind::UnexpectedChar(c)))?;
+                    for _ in 0..symbol.len() {
+                        self.chars.next();
+                    }
+                    self.current = Some((Token::Symbol(symbol), offset));
+                    return Ok(());
+                }
+            }
+        }
+
+        self.current = None;
+        Ok(())
+    }
+
+    /// Skip the characters matching `predicate`, returning where the first one that doesn't is
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> usize {
+        while self.chars.next_if(|&(_, c)| predicate(c)).is_some() {}
+        self.chars.peek().map_or(self.source.len(), |&(offset, _)| offset)
+    }
+
+    /// An error at the token coming up, or at the end of the program if there's none
+    fn error_here(&self, kind: ParseErrorKind) -> ParseError {
+        let offset = self.current.as_ref().map_or(self.source.len(), |(_, offset)| *offset);
+        self.error_at(offset, kind)
+    }
+
+    fn error_at(&self, offset: usize, kind: ParseErrorKind) -> ParseError {
+        let before = &self.source[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().map_or(0, |line| line.chars().count()) + 1;
+
+        ParseError { line, column, kind }
+    }
+}
@@ -0,0 +1,96 @@
+//! Checks how expressions are read, where errors are pointed at, and the colors programs come out with.
+
+use super::{ParseError, ParseErrorKind, Program};
+
+/// The value of `expr`, with every input at 0
+fn eval(expr: &str) -> f32 {
+    let program = Program::parse(&format!("a = {expr}")).unwrap();
+    program.statements[0].expr.eval(&vec![0.0; program.slots])
+}
+
+fn error(source: &str) -> (usize, usize, ParseErrorKind) {
+    let ParseError { line, column, kind } = Program::parse(source).unwrap_err();
+    (line, column, kind)
+}
+
+#[test]
+fn operators_bind_in_order() {
+    assert_eq!(eval("-2^2"), -4.0);
+    assert_eq!(eval("2^-1"), 0.5);
+    assert_eq!(eval("2^3^2"), 512.0);
+    assert_eq!(eval("1 + 2 * 3"), 7.0);
+    assert_eq!(eval("(1 + 2) * 3"), 9.0);
+    assert_eq!(eval("7 - 2 - 1"), 4.0);
+    assert_eq!(eval("-7 % 3"), 2.0);
+}
+
+#[test]
+fn comparisons_come_after_arithmetic() {
+    assert_eq!(eval("1 + 1 == 2"), 1.0);
+    assert_eq!(eval("2 * 3 < 5"), 0.0);
+    assert_eq!(eval("2 * 3 >= 6"), 1.0);
+    assert_eq!(eval("1 != 1"), 0.0);
+    assert_eq!(eval("(1 < 2) + (2 <= 2) + (3 > 4)"), 2.0);
+}
+
+#[test]
+fn functions_take_their_arguments_in_order() {
+    assert_eq!(eval("min(3, 1)"), 1.0);
+    assert_eq!(eval("clamp(5, 0, 2)"), 2.0);
+    assert_eq!(eval("mix(10, 20, 0.25)"), 12.5);
+    assert_eq!(eval("fract(-0.25)"), 0.75);
+}
+
+#[test]
+fn errors_point_at_where_they_are() {
+    assert_eq!(error("a = 1\nb = 2 +* 3"), (2, 8, ParseErrorKind::Expected("a value")));
+    assert_eq!(error("a = 1 $"), (1, 7, ParseErrorKind::UnexpectedChar('$')));
+    assert_eq!(error("# comment\n\n  a = (1"), (3, 9, ParseErrorKind::Expected("\")\"")));
+    assert_eq!(error("a = 1 2"), (1, 7, ParseErrorKind::Expected("a new line or \";\"")));
+    assert_eq!(Program::parse("a = 1\nb = 2 +* 3").unwrap_err().to_string(), "Line 2, column 8: expected a value");
+}
+
+#[test]
+fn inputs_and_unknown_outputs_cant_be_assigned() {
+    assert_eq!(error("t = 1"), (1, 1, ParseErrorKind::ReadOnly("t".to_string())));
+    assert_eq!(error("a = 1; pi = 3"), (1, 8, ParseErrorKind::ReadOnly("pi".to_string())));
+    assert_eq!(error("zone.a = 1"), (1, 1, ParseErrorKind::ReadOnly("zone.a".to_string())));
+}
+
+#[test]
+fn variables_are_only_known_once_assigned() {
+    assert_eq!(error("zone.r = speed"), (1, 10, ParseErrorKind::UnknownVariable("speed".to_string())));
+    assert_eq!(error("a = a + 1"), (1, 5, ParseErrorKind::UnknownVariable("a".to_string())));
+    assert_eq!(Program::parse("speed = 2\nzone.r = speed * 10").unwrap().colors_at(0.0)[0], 20);
+}
+
+#[test]
+fn functions_check_their_arguments() {
+    let wrong = |name, expected, found| ParseErrorKind::WrongArgumentCount { name, expected, found };
+
+    assert_eq!(error("zone.r = min(1)"), (1, 10, wrong("min", 2, 1)));
+    assert_eq!(error("zone.r = sin()"), (1, 10, wrong("sin", 1, 0)));
+    assert_eq!(error("zone.r = clamp(1, 2, 3, 4)"), (1, 10, wrong("clamp", 3, 4)));
+    assert_eq!(error("zone.r = lerp(1, 2, 3)"), (1, 10, ParseErrorKind::UnknownFunction("lerp".to_string())));
+}
+
+#[test]
+fn colors_are_rounded_into_range() {
+    let program = Program::parse("zone.r = 300; zone.g = -20; zone.b = 127.6").unwrap();
+
+    assert_eq!(program.colors_at(0.0), [255, 0, 128, 255, 0, 128, 255, 0, 128, 255, 0, 128]);
+}
+
+#[test]
+fn undefined_math_leaves_the_channel_off_and_infinity_is_capped() {
+    let program = Program::parse("zone.r = sqrt(-1); zone.g = 0 / 0; zone.b = 1 / 0").unwrap();
+
+    assert_eq!(program.colors_at(0.0), [0, 0, 255, 0, 0, 255, 0, 0, 255, 0, 0, 255]);
+}
+
+#[test]
+fn every_zone_sees_where_it_is() {
+    let program = Program::parse("zone.r = i * 10; zone.g = x * 255; zone.b = t").unwrap();
+
+    assert_eq!(program.colors_at(2.0), [0, 0, 2, 10, 85, 2, 20, 170, 2, 30, 255, 2]);
+}
@@ -0,0 +1,92 @@
+use std::{sync::atomic::Ordering, thread, time::Duration};
+
+use legion_kb_rgb_hw::BaseEffects;
+use serde::{Deserialize, Serialize};
+
+use super::Inner;
+
+/// The color each zone is shown in to tell it apart from the others, from left to right
+pub const ZONE_COLORS: [[u8; 3]; 4] = [[255, 40, 40], [40, 255, 40], [40, 120, 255], [255, 200, 0]];
+/// How long each step of identifying the zones lasts, one step per zone then one with all of them lit
+pub const IDENTIFY_STEP: Duration = Duration::from_millis(800);
+pub const IDENTIFY_STEPS: u32 = 5;
+
+/// A short blinking pattern that temporarily overrides whatever effect is playing
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlashPattern {
+    pub rgb: [u8; 3],
+    pub count: u8,
+    /// How long each on and off period lasts (In ms)
+    pub interval: u64,
+}
+
+impl Default for FlashPattern {
+    fn default() -> Self {
+        Self {
+            rgb: [255, 255, 255],
+            count: 2,
+            interval: 150,
+        }
+    }
+}
+
+/// Play the pattern, returning `false` if it was interrupted by another instruction
+pub(super) fn play(manager: &mut Inner, pattern: &FlashPattern) -> bool {
+    manager.keyboard.set_effect(BaseEffects::Static).unwrap();
+    manager.keyboard.set_brightness(2).unwrap();
+
+    for _ in 0..pattern.count {
+        for rgb in [pattern.rgb, [0; 3]] {
+            if manager.stop_signals.manager_stop_signal.load(Ordering::SeqCst) {
+                return false;
+            }
+
+            manager.keyboard.solid_set_colors_to(rgb).unwrap();
+            manager.clock.sleep(Duration::from_millis(pattern.interval));
+        }
+    }
+
+    true
+}
+
+/// What blinks `zone` (from 0 to 3) with `pattern`, leaving the others dark, see [`play_zones`]
+pub(super) fn zone_frames(zone: usize, pattern: &FlashPattern) -> Vec<[u8; 12]> {
+    let zone = zone.min(3);
+    let mut lit = [0; 12];
+    lit[zone * 3..zone * 3 + 3].copy_from_slice(&pattern.rgb);
+
+    [lit, [0; 12]].repeat(usize::from(pattern.count))
+}
+
+/// What lights each zone in turn in its own [`ZONE_COLORS`], then all of them at once, see [`play_zones`]
+pub(super) fn identify_frames() -> Vec<[u8; 12]> {
+    (0..IDENTIFY_STEPS as usize)
+        .map(|step| {
+            // Past the last zone, all of them are lit
+            let mut colors = [0; 12];
+            for (zone, rgb) in ZONE_COLORS.iter().enumerate().filter(|(zone, _)| step == *zone || step == ZONE_COLORS.len()) {
+                colors[zone * 3..zone * 3 + 3].copy_from_slice(rgb);
+            }
+            colors
+        })
+        .collect()
+}
+
+/// Show the zone colors of each frame for `hold`, returning `false` if it was interrupted by another instruction
+///
+/// These tell the zones apart while the GUI shows along, so they go by the wall clock rather than the effect speed.
+pub(super) fn play_zones(manager: &mut Inner, frames: &[[u8; 12]], hold: Duration) -> bool {
+    manager.keyboard.set_effect(BaseEffects::Static).unwrap();
+    manager.keyboard.set_brightness(2).unwrap();
+
+    for colors in frames {
+        if manager.stop_signals.manager_stop_signal.load(Ordering::SeqCst) {
+            return false;
+        }
+
+        manager.keyboard.set_colors_to(colors).unwrap();
+        thread::sleep(hold);
+    }
+
+    true
+}
@@ -0,0 +1,36 @@
+//! How often the effects are allowed to update the keyboard.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use strum_macros::{EnumIter, IntoStaticStr};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, EnumIter, IntoStaticStr)]
+pub enum FrameRate {
+    #[strum(serialize = "15 FPS")]
+    Fps15,
+    #[strum(serialize = "30 FPS")]
+    Fps30,
+    #[default]
+    #[strum(serialize = "60 FPS")]
+    Fps60,
+    #[strum(serialize = "Unlimited")]
+    Unlimited,
+}
+
+impl FrameRate {
+    /// The most frames per second, `None` for no limit
+    pub fn fps(self) -> Option<u32> {
+        match self {
+            Self::Fps15 => Some(15),
+            Self::Fps30 => Some(30),
+            Self::Fps60 => Some(60),
+            Self::Unlimited => None,
+        }
+    }
+
+    /// The shortest time between two frames
+    pub fn interval(self) -> Option<Duration> {
+        self.fps().map(|fps| Duration::from_secs(1) / fps)
+    }
+}
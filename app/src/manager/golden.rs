@@ -0,0 +1,69 @@
+//! Golden-frame snapshots of the built-in effects, so refactoring them can't change what they look like unnoticed.
+//!
+//! Every effect is recorded with [`recorder::record`], with a fixed seed, until it produced [`FRAMES`] frames or
+//! stopped producing new ones, and those are compared to its snapshot in `tests/golden`. A missing snapshot fails the
+//! test like a changed one. After adding an effect or an intended change, run the tests with `UPDATE_GOLDEN=1` to write
+//! them, then review and commit them.
+
+use std::{env, fs, path::PathBuf};
+
+use strum::IntoEnumIterator;
+
+use super::{
+    profile::{self, Profile},
+    recorder::{self, Frame},
+};
+use crate::enums::Effects;
+
+const SEED: u64 = 5;
+const FRAMES: usize = 120;
+
+fn profile_for(effect: Effects) -> Profile {
+    Profile {
+        name: Some(effect.to_string()),
+        rgb_zones: profile::arr_to_zones([255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255]),
+        effect,
+        speed: 4,
+        ..Profile::default()
+    }
+}
+
+fn snapshot_path(effect: Effects) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden").join(format!("{effect}.json"))
+}
+
+#[test]
+fn effects_match_golden_frames() {
+    let update = env::var_os("UPDATE_GOLDEN").is_some();
+    let mut mismatched = Vec::new();
+    let mut missing = Vec::new();
+
+    for effect in Effects::iter().filter(|effect| !effect.reads_environment()) {
+        let frames = recorder::record(profile_for(effect), SEED, FRAMES);
+        let path = snapshot_path(effect);
+
+        let expected: Option<Vec<Frame>> = fs::read_to_string(&path).ok().map(|json| serde_json::from_str(&json).unwrap());
+        if update {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, serde_json::to_string_pretty(&frames).unwrap()).unwrap();
+            continue;
+        }
+
+        match expected {
+            Some(expected) if expected != frames => mismatched.push(effect.to_string()),
+            Some(_) => {}
+            None => missing.push(effect.to_string()),
+        }
+    }
+
+    assert!(
+        missing.is_empty(),
+        "There are no golden frames for {}, run the tests with UPDATE_GOLDEN=1 to write them",
+        missing.join(", ")
+    );
+    assert!(
+        mismatched.is_empty(),
+        "The frames of {} changed, run the tests with UPDATE_GOLDEN=1 if that was intended",
+        mismatched.join(", ")
+    );
+}
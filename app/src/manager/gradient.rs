@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+mod tests;
+
+/// How much wider than tall the keyboard is, so an angle looks the same on it as on the preview
+const ASPECT_RATIO: f32 = 3.0;
+/// The fewest stops a gradient can have
+#[cfg(feature = "gui")]
+pub const MIN_STOPS: usize = 2;
+
+/// A color at a point of a [`Gradient`], `position` going from 0 (start) to 1 (end)
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GradientStop {
+    pub position: f32,
+    pub color: [u8; 3],
+}
+
+/// A linear gradient laid over the keyboard, kept with the profile so the zone colors it made can be edited again
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Gradient {
+    /// Direction the gradient goes in, in degrees, 0 being from the left of the keyboard to the right and 90 from the
+    /// bottom to the top
+    pub angle: f32,
+    pub stops: Vec<GradientStop>,
+}
+
+impl Gradient {
+    /// A gradient between two colors, from left to right
+    pub fn new(from: [u8; 3], to: [u8; 3]) -> Self {
+        Self {
+            angle: 0.0,
+            stops: vec![GradientStop { position: 0.0, color: from }, GradientStop { position: 1.0, color: to }],
+        }
+    }
+
+    /// The color at `position`, from 0 (start) to 1 (end)
+    pub fn sample(&self, position: f32) -> [u8; 3] {
+        let mut stops = self.stops.clone();
+        stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+
+        let (Some(first), Some(last)) = (stops.first(), stops.last()) else {
+            return [0; 3];
+        };
+        let position = position.clamp(0.0, 1.0);
+        if position <= first.position {
+            return first.color;
+        }
+        if position >= last.position {
+            return last.color;
+        }
+
+        let to = stops.iter().position(|stop| stop.position >= position).unwrap_or(stops.len() - 1);
+        let (from, to) = (stops[to.saturating_sub(1)], stops[to]);
+        let span = to.position - from.position;
+        let fraction = if span > 0.0 { (position - from.position) / span } else { 1.0 };
+
+        [0, 1, 2].map(|c| (f32::from(to.color[c]) - f32::from(from.color[c])).mul_add(fraction, f32::from(from.color[c])).round() as u8)
+    }
+
+    /// Where the gradient line, going through the middle of the keyboard at its angle, crosses the middle of each zone
+    ///
+    /// Each zone spans the whole height of the keyboard, so the steeper the gradient, the sooner the outer zones reach the
+    /// colors at its ends. Straight up or down, the left zones take the start and the right ones the end.
+    pub fn zone_positions(&self) -> [f32; 4] {
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+        // Going straight up or down, lean right so the zones still split the gradient the same way on either side of it
+        let cos = if cos.abs() < f32::EPSILON { f32::EPSILON } else { cos };
+        // How far the line goes from the middle before leaving the keyboard, through a side or through the top and bottom
+        let half_length = (ASPECT_RATIO / 2.0 / cos.abs()).min(0.5 / sin.abs());
+
+        [0.0, 1.0, 2.0, 3.0].map(|zone: f32| {
+            let x = ((zone + 0.5) / 4.0 - 0.5) * ASPECT_RATIO;
+            (x / cos / (2.0 * half_length) + 0.5).clamp(0.0, 1.0)
+        })
+    }
+
+    /// Sample the gradient into the four keyboard zones
+    pub fn to_zone_colors(&self) -> [[u8; 3]; 4] {
+        self.zone_positions().map(|position| self.sample(position))
+    }
+
+    /// Add a stop halfway along the widest gap between two others, colored as the gradient is there
+    #[cfg(feature = "gui")]
+    pub fn split_widest_gap(&mut self) {
+        let mut positions: Vec<f32> = self.stops.iter().map(|stop| stop.position).chain([0.0, 1.0]).collect();
+        positions.sort_by(f32::total_cmp);
+
+        let position = positions
+            .windows(2)
+            .max_by(|a, b| (a[1] - a[0]).total_cmp(&(b[1] - b[0])))
+            .map_or(0.5, |pair| (pair[0] + pair[1]) / 2.0);
+        self.stops.push(GradientStop { position, color: self.sample(position) });
+    }
+}
@@ -0,0 +1,65 @@
+//! Checks where the zones fall on a gradient at every angle, and the colors sampled there.
+
+use super::{Gradient, GradientStop};
+
+const RED: [u8; 3] = [255, 0, 0];
+const BLUE: [u8; 3] = [0, 0, 255];
+
+fn at(angle: f32) -> Gradient {
+    Gradient {
+        angle,
+        ..Gradient::new(RED, BLUE)
+    }
+}
+
+#[test]
+fn flat_gradients_spread_evenly() {
+    assert_eq!(at(0.0).zone_positions(), [0.125, 0.375, 0.625, 0.875]);
+    assert_eq!(at(180.0).zone_positions().map(|position| (position * 1000.0).round() / 1000.0), [0.875, 0.625, 0.375, 0.125]);
+}
+
+#[test]
+fn zones_keep_their_order_and_stay_apart() {
+    for angle in (-180..=180).map(|angle| angle as f32) {
+        let positions = at(angle).zone_positions();
+        let rightwards = angle.abs() <= 90.0;
+
+        assert!(positions.iter().all(|position| (0.0..=1.0).contains(position)), "{angle}°: {positions:?}");
+        assert!(
+            positions.windows(2).all(|pair| if rightwards { pair[0] <= pair[1] } else { pair[0] >= pair[1] }),
+            "{angle}°: {positions:?}"
+        );
+        assert!((positions[3] - positions[0]).abs() >= 0.7, "{angle}° collapses: {positions:?}");
+    }
+}
+
+#[test]
+fn vertical_gradients_split_the_keyboard() {
+    for angle in [90.0, -90.0] {
+        assert_eq!(at(angle).to_zone_colors(), [RED, RED, BLUE, BLUE], "{angle}°");
+    }
+}
+
+#[test]
+fn mirrored_angles_match() {
+    for angle in [10.0, 18.0, 45.0, 80.0] {
+        assert_eq!(at(angle).zone_positions(), at(-angle).zone_positions(), "{angle}°");
+    }
+}
+
+#[test]
+fn samples_blend_between_stops() {
+    let gradient = Gradient {
+        angle: 0.0,
+        stops: vec![
+            GradientStop { position: 1.0, color: BLUE },
+            GradientStop { position: 0.0, color: RED },
+            GradientStop { position: 0.5, color: [0, 255, 0] },
+        ],
+    };
+
+    assert_eq!(gradient.sample(0.0), RED);
+    assert_eq!(gradient.sample(0.25), [128, 128, 0]);
+    assert_eq!(gradient.sample(0.5), [0, 255, 0]);
+    assert_eq!(gradient.sample(2.0), BLUE);
+}
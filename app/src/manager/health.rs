@@ -0,0 +1,68 @@
+#[cfg(feature = "gui")]
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "gui")]
+use crossbeam_channel::{Receiver, Sender};
+
+/// How the effect engine is doing, shown to users that only interact with the tray
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Health {
+    #[default]
+    Normal,
+    /// The keyboard isn't ready yet after booting, it's being looked for again
+    Waiting { attempt: u32 },
+    /// The keyboard could not be found
+    Disconnected,
+    Error(String),
+}
+
+impl Health {
+    #[cfg(feature = "gui")]
+    pub fn describe(&self) -> Option<String> {
+        match self {
+            Self::Normal => None,
+            Self::Waiting { attempt } => Some(format!("Waiting for the keyboard (attempt {attempt})")),
+            Self::Disconnected => Some("Keyboard disconnected or not found".to_string()),
+            Self::Error(err) => Some(format!("Error: {err}")),
+        }
+    }
+}
+
+/// The health as last found out by any thread, telling whoever shows it when it changes instead of having them poll it
+#[cfg(feature = "gui")]
+#[derive(Clone, Default)]
+pub struct SharedHealth {
+    current: Arc<Mutex<Health>>,
+    subscribers: Arc<Mutex<Vec<Sender<Health>>>>,
+}
+
+#[cfg(feature = "gui")]
+impl SharedHealth {
+    pub fn get(&self) -> Health {
+        self.current.lock().unwrap().clone()
+    }
+
+    pub fn set(&self, health: Health) {
+        self.update(|current| *current = health);
+    }
+
+    /// Change the health in place, sending it to the subscribers if it changed
+    pub fn update(&self, change: impl FnOnce(&mut Health)) {
+        let mut current = self.current.lock().unwrap();
+        let previous = current.clone();
+        change(&mut current);
+        if *current == previous {
+            return;
+        }
+
+        // Subscribers that went away are dropped along the way
+        self.subscribers.lock().unwrap().retain(|subscriber| subscriber.send(current.clone()).is_ok());
+    }
+
+    /// Get every change made from now on
+    pub fn subscribe(&self) -> Receiver<Health> {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        self.subscribers.lock().unwrap().push(tx);
+        rx
+    }
+}
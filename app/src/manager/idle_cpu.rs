@@ -0,0 +1,62 @@
+//! Keeps the effect thread from going back to busy-waiting, by measuring how much CPU time it uses on a simulated
+//! keyboard while playing effects that have little or nothing to animate.
+
+use std::{os::unix::thread::JoinHandleExt, thread, time::Duration};
+
+use super::{frame_rate::FrameRate, profile::Profile, EffectManager};
+use crate::enums::Effects;
+
+/// How long to let the effect settle before measuring
+const WARM_UP: Duration = Duration::from_millis(200);
+const WINDOW: Duration = Duration::from_secs(2);
+/// Generous enough for slow machines, a thread spinning on a channel would use the whole window
+const BUDGET: Duration = Duration::from_millis(100);
+
+/// CPU time spent by the effect thread so far
+fn effect_thread_cpu_time(manager: &EffectManager) -> Duration {
+    let thread = manager.inner_handle.as_ref().unwrap().as_pthread_t();
+    let mut clock_id = 0;
+    let mut time = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+
+    // SAFETY: The thread is kept alive by the manager, and both pointers are valid for the duration of the calls
+    unsafe {
+        assert_eq!(libc::pthread_getcpuclockid(thread, &mut clock_id), 0);
+        assert_eq!(libc::clock_gettime(clock_id, &mut time), 0);
+    }
+
+    Duration::new(time.tv_sec as u64, time.tv_nsec as u32)
+}
+
+fn cpu_time_while_playing(effect: Effects, frame_rate: FrameRate) -> Duration {
+    let mut manager = EffectManager::new_virtual(|_| {});
+    manager.set_frame_rate(frame_rate);
+    manager.set_profile(Profile {
+        effect,
+        speed: 1,
+        ..Profile::default()
+    });
+    thread::sleep(WARM_UP);
+
+    let start = effect_thread_cpu_time(&manager);
+    thread::sleep(WINDOW);
+    let used = effect_thread_cpu_time(&manager) - start;
+
+    manager.stop();
+    used
+}
+
+#[test]
+fn static_profile_leaves_the_cpu_idle() {
+    let used = cpu_time_while_playing(Effects::Static, FrameRate::Unlimited);
+
+    assert!(used < BUDGET, "The effect thread used {used:?} of CPU time in {WINDOW:?} while showing a static profile");
+}
+
+#[test]
+fn slow_effects_leave_the_cpu_mostly_idle() {
+    for effect in [Effects::Disco, Effects::Lightning, Effects::Swipe] {
+        let used = cpu_time_while_playing(effect, FrameRate::Fps15);
+
+        assert!(used < BUDGET, "The effect thread used {used:?} of CPU time in {WINDOW:?} while playing {effect} at 15 FPS");
+    }
+}
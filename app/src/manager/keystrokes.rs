@@ -0,0 +1,119 @@
+//! Opt-in count of the keys pressed in each zone, which the `Heatmap` effect shows, and the key presses the effects
+//! reacting to typing listen to.
+//!
+//! Only the number of presses per zone is kept, never which keys were pressed or in which order. The counts are saved
+//! along with the rest of the usage statistics in the settings file.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use crossbeam_channel::{Receiver, Sender};
+use device_query::{DeviceEvents, DeviceState, Keycode};
+#[cfg(target_os = "linux")]
+use tracing::warn;
+
+use super::effects::zones::KEY_ZONES;
+
+static COUNTS: [AtomicU64; 4] = [AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)];
+static COUNTING: AtomicBool = AtomicBool::new(false);
+/// Told about every key pressed, see [`subscribe`]
+static SUBSCRIBERS: Mutex<Vec<Sender<Keycode>>> = Mutex::new(Vec::new());
+/// Dropped to stop the listener, `None` while it isn't running
+static LISTENER: Mutex<Option<Sender<()>>> = Mutex::new(None);
+
+/// The keys pressed since [`subscribe`] was called, until this is dropped
+pub struct Subscription {
+    tx: Sender<Keycode>,
+    rx: Receiver<Keycode>,
+}
+
+impl Subscription {
+    /// The keys pressed since the last call, without waiting for more
+    pub fn try_iter(&self) -> impl Iterator<Item = Keycode> + '_ {
+        self.rx.try_iter()
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        SUBSCRIBERS.lock().unwrap().retain(|subscriber| !subscriber.same_channel(&self.tx));
+        update_listener();
+    }
+}
+
+/// Get told about the keys pressed, sharing the one listener the counting uses
+pub fn subscribe() -> Subscription {
+    let (tx, rx) = crossbeam_channel::unbounded();
+    SUBSCRIBERS.lock().unwrap().push(tx.clone());
+    update_listener();
+
+    Subscription { tx, rx }
+}
+
+/// Start or stop counting key presses
+#[cfg(feature = "gui")]
+pub fn set_counting(enabled: bool) {
+    COUNTING.store(enabled, Ordering::SeqCst);
+    update_listener();
+}
+
+/// Listen to the keyboard only while counting or while anyone is subscribed
+fn update_listener() {
+    let mut listener = LISTENER.lock().unwrap();
+    if !COUNTING.load(Ordering::SeqCst) && SUBSCRIBERS.lock().unwrap().is_empty() {
+        *listener = None;
+        return;
+    }
+    if listener.is_some() {
+        return;
+    }
+
+    let (stop_tx, stop_rx) = crossbeam_channel::bounded::<()>(0);
+    *listener = Some(stop_tx);
+    thread::spawn(move || {
+        // Only X11 can be missing, the other platforms always have a way to ask
+        #[cfg(target_os = "linux")]
+        let Some(state) = DeviceState::checked_new() else {
+            warn!("Key presses can't be followed, the keyboard can't be read without X11");
+            return;
+        };
+        #[cfg(not(target_os = "linux"))]
+        let state = DeviceState::new();
+
+        let _guard = state.on_key_down(|key| {
+            if COUNTING.load(Ordering::SeqCst) {
+                if let Some(zone) = KEY_ZONES.iter().position(|keys| keys.contains(key)) {
+                    COUNTS[zone].fetch_add(1, Ordering::Relaxed);
+                }
+            }
+
+            SUBSCRIBERS.lock().unwrap().retain(|subscriber| subscriber.send(*key).is_ok());
+        });
+
+        // The callback lives as long as the guard does, until the listener is stopped
+        let _ = stop_rx.recv();
+    });
+}
+
+/// Key presses counted in each zone, from left to right
+pub fn counts() -> [u64; 4] {
+    [0, 1, 2, 3].map(|zone| COUNTS[zone].load(Ordering::Relaxed))
+}
+
+/// Pick up counting from what was saved
+#[cfg(feature = "gui")]
+pub fn restore(counts: [u64; 4]) {
+    for (count, saved) in COUNTS.iter().zip(counts) {
+        count.store(saved, Ordering::Relaxed);
+    }
+}
+
+#[cfg(feature = "gui")]
+pub fn reset() {
+    restore([0; 4]);
+}
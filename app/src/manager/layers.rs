@@ -0,0 +1,227 @@
+//! Plays a second effect on top of the one of a profile, such as reacting to key presses over a breathing base, and
+//! applies its modulations.
+//!
+//! Each layer runs on a virtual keyboard of its own, and the effect thread blends their latest states into what gets
+//! sent to the real one. Effects the firmware animates by itself are emulated, as their frames never leave it.
+
+use std::{
+    f32::consts::TAU,
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
+
+use legion_kb_rgb_hw::{BaseEffects, LightingState};
+use serde::{Deserialize, Serialize};
+use strum_macros::{EnumIter, IntoStaticStr};
+
+use super::{
+    clock::Clock,
+    modulation::{self, ModulatedClock},
+    preview::OutputPreview,
+    profile::{Profile, Zones},
+    EffectManager, Inner,
+};
+use crate::{enums::Effects, util};
+
+/// How often to check whether to stop while none of the layers is changing
+const IDLE_WAIT: Duration = Duration::from_millis(250);
+/// Frame rate of the emulated firmware effects when there's no limit
+const EMULATION_INTERVAL: Duration = Duration::from_millis(16);
+
+/// How the colors of the overlay are combined with those of the base
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, EnumIter, IntoStaticStr)]
+pub enum BlendMode {
+    /// The lit zones of the overlay hide the base, the dark ones let it through
+    #[strum(serialize = "Replace")]
+    Replace,
+    #[strum(serialize = "Add")]
+    Add,
+    #[default]
+    #[strum(serialize = "Max")]
+    Max,
+}
+
+impl BlendMode {
+    fn blend(self, base: [u8; 12], overlay: [u8; 12]) -> [u8; 12] {
+        let mut out = base;
+
+        for zone in 0..4 {
+            let range = zone * 3..zone * 3 + 3;
+            let (base, overlay) = (&base[range.clone()], &overlay[range.clone()]);
+
+            match self {
+                Self::Replace => {
+                    if overlay.iter().any(|channel| *channel > 0) {
+                        out[range].copy_from_slice(overlay);
+                    }
+                }
+                Self::Add => {
+                    for (i, channel) in range.enumerate() {
+                        out[channel] = base[i].saturating_add(overlay[i]);
+                    }
+                }
+                Self::Max => {
+                    for (i, channel) in range.enumerate() {
+                        out[channel] = base[i].max(overlay[i]);
+                    }
+                }
+            }
+        }
+
+        out
+    }
+}
+
+/// An effect played on top of the one of a profile, sharing its direction and brightness
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Overlay {
+    pub effect: Effects,
+    pub rgb_zones: Zones,
+    pub speed: u8,
+    #[serde(default)]
+    pub blend: BlendMode,
+}
+
+impl Default for Overlay {
+    fn default() -> Self {
+        Self {
+            effect: Effects::Ripple,
+            rgb_zones: Zones::default().map(|mut zone| {
+                zone.rgb = [255; 3];
+                zone
+            }),
+            speed: 1,
+            blend: BlendMode::default(),
+        }
+    }
+}
+
+impl Overlay {
+    /// The profile the overlay layer plays on its own
+    fn profile(&self, base: &Profile) -> Profile {
+        Profile {
+            rgb_zones: self.rgb_zones,
+            effect: self.effect,
+            direction: base.direction,
+            speed: self.speed,
+            brightness: base.brightness,
+            ..Profile::default()
+        }
+    }
+}
+
+/// An effect running on a virtual keyboard
+struct Layer {
+    manager: EffectManager,
+    output: OutputPreview,
+}
+
+impl Layer {
+    fn spawn(parent: &Inner, profile: Profile, clock: Arc<dyn Clock>, changed: crossbeam_channel::Sender<()>) -> Self {
+        let output = OutputPreview::default();
+        let mut observer = output.observer();
+
+        let observer = move |state: &LightingState| {
+            observer(state);
+            let _ = changed.try_send(());
+        };
+
+        let mut manager = EffectManager::new_layer(observer, clock);
+        manager.max_fps.store(parent.max_fps.load(Ordering::SeqCst), Ordering::SeqCst);
+        manager.set_profile(profile);
+
+        Self { manager, output }
+    }
+
+    /// What the layer is showing `elapsed` after it started
+    fn render(&self, elapsed: Duration) -> [u8; 12] {
+        self.output.latest().map_or([0; 12], |state| emulate(&state, elapsed))
+    }
+
+    fn is_animated_by_hardware(&self) -> bool {
+        self.output.latest().is_some_and(|state| !matches!(state.effect_type(), BaseEffects::Static))
+    }
+}
+
+/// The colors the firmware would be showing for `state`
+fn emulate(state: &LightingState, elapsed: Duration) -> [u8; 12] {
+    let rgb = state.rgb_values();
+    // The firmware takes a few seconds per cycle at the slowest speed
+    let period = 6.0 / f32::from(state.speed().max(1));
+    let phase = (elapsed.as_secs_f32() / period).fract();
+
+    match state.effect_type() {
+        BaseEffects::Static => rgb,
+        BaseEffects::Breath => {
+            let level = (1.0 - (phase * TAU).cos()) / 2.0;
+            rgb.map(|channel| (f32::from(channel) * level).round() as u8)
+        }
+        BaseEffects::Smooth => util::hsv_to_rgb(phase * 360.0, 1.0, 1.0).repeat(4).try_into().unwrap(),
+        BaseEffects::LeftWave | BaseEffects::RightWave => {
+            let step = if matches!(state.effect_type(), BaseEffects::LeftWave) { 90.0 } else { -90.0 };
+            [0.0, 1.0, 2.0, 3.0].map(|zone| util::hsv_to_rgb(phase.mul_add(360.0, zone * step), 1.0, 1.0)).concat().try_into().unwrap()
+        }
+    }
+}
+
+/// Whether `profile` has to be played through [`play`] rather than on its own
+pub fn needs_compositing(profile: &Profile) -> bool {
+    profile.overlay.is_some() || !profile.modulations.is_empty()
+}
+
+/// Play `profile` with its overlay on top and its modulations applied, until told to stop
+///
+/// The pulsing zones of the status overlay, see [`crate::overlays`], get drawn over the result.
+pub fn play(manager: &mut Inner, profile: &Profile) {
+    let (changed_tx, changed_rx) = crossbeam_channel::bounded(1);
+
+    // The base effect and the emulated firmware effects see the time warped by the speed modulations
+    let clock: Arc<dyn Clock> = Arc::new(ModulatedClock::new(manager.clock.clone(), profile.modulations.clone()));
+    let base = Profile {
+        overlay: None,
+        modulations: Vec::new(),
+        ..profile.clone()
+    };
+    let base_layer = Layer::spawn(manager, base, clock.clone(), changed_tx.clone());
+    let overlay_layer = profile
+        .overlay
+        .as_ref()
+        .map(|overlay| (overlay.blend, Layer::spawn(manager, overlay.profile(profile), manager.clock.clone(), changed_tx)));
+
+    let start = manager.clock.now();
+    let warped_start = clock.now();
+    let mut last_sent = None;
+    while !manager.stop_signals.manager_stop_signal.load(Ordering::SeqCst) {
+        let elapsed = manager.clock.now().saturating_duration_since(start);
+
+        let mut colors = base_layer.render(clock.now().saturating_duration_since(warped_start));
+        colors = modulation::apply(&profile.modulations, elapsed, colors);
+        if let Some((blend, overlay_layer)) = &overlay_layer {
+            colors = blend.blend(colors, overlay_layer.render(elapsed));
+        }
+        // The status overlay can change while this plays
+        colors = manager.overlay.lock().unwrap().draw_pulses(colors, elapsed);
+
+        if last_sent != Some(colors) {
+            manager.keyboard.set_colors_to(&colors).unwrap();
+            last_sent = Some(colors);
+        }
+
+        // Emulated effects and modulated colors have to be redrawn all the time, the others only when they change
+        let animated = base_layer.is_animated_by_hardware()
+            || overlay_layer.as_ref().is_some_and(|(_, layer)| layer.is_animated_by_hardware())
+            || modulation::changes_colors(&profile.modulations)
+            || status_overlay.is_animated();
+        let wait = if animated {
+            manager.max_fps().map_or(EMULATION_INTERVAL, |fps| Duration::from_secs(1) / fps)
+        } else {
+            IDLE_WAIT
+        };
+        let _ = changed_rx.recv_timeout(wait);
+    }
+
+    base_layer.manager.stop();
+    if let Some((_, overlay_layer)) = overlay_layer {
+        overlay_layer.manager.stop();
+    }
+}
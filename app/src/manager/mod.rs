@@ -1,25 +1,80 @@
-use crate::enums::{Direction, Effects, Message};
+use crate::{
+    crash,
+    enums::{Direction, Effects, Message},
+    exit::ExitSettings,
+    idle::IdleMonitor,
+    overlays::Overlay,
+    status::Status,
+    ticks,
+    weather::WeatherSettings,
+};
 
-use crossbeam_channel::{Receiver, Sender};
-use effects::{ambient, christmas, disco, fade, lightning, ripple, swipe, temperature};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+use effects::{ambient, automaton, christmas, daylight, disco, fade, fire, heatmap, lightning, network, ripple, swipe, temperature, typing_speed, wall_clock, weather};
 use error_stack::{Result, ResultExt};
-use legion_rgb_driver::{BaseEffects, Keyboard, SPEED_RANGE};
+use legion_kb_rgb_hw::{lights::Section, BaseEffects, Calibration, Keyboard, KeyboardInfo, LightingState, OutputUpdate};
 use profile::Profile;
-use rand::{rngs::ThreadRng, thread_rng};
-use single_instance::SingleInstance;
+use rand::{rngs::StdRng, SeedableRng};
 use std::{
-    sync::atomic::{AtomicBool, Ordering},
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
     thread,
-    time::Duration,
+    time::{Duration, Instant},
+};
+use std::{
+    sync::{Arc, Mutex, Weak},
+    thread::JoinHandle,
 };
-use std::{sync::Arc, thread::JoinHandle};
 use thiserror::Error;
+use tracing::{debug, error, info, warn};
 
-use self::custom_effect::{CustomEffect, EffectType};
+use self::{
+    clock::{Clock, SpeedMultiplier, SystemClock},
+    custom_effect::CustomEffect,
+    flash::FlashPattern,
+    frame_rate::FrameRate,
+    health::Health,
+    preview::OutputPreview,
+    recorder::FrameRecorder,
+};
 
+pub mod clock;
 pub mod custom_effect;
+#[cfg(feature = "dev-effects")]
+mod dev_effect;
 mod effects;
+pub mod expression;
+pub mod flash;
+pub mod frame_rate;
+pub mod gradient;
+#[cfg(test)]
+mod golden;
+pub mod health;
+pub mod keystrokes;
+pub mod layers;
+pub mod modulation;
+#[cfg(all(test, target_os = "linux"))]
+mod idle_cpu;
+pub mod palette;
+#[cfg(all(test, feature = "gui"))]
+mod palette_import;
+pub mod preview;
 pub mod profile;
+pub mod recorder;
+pub mod registry;
+pub mod swatch;
+
+/// How long to wait between attempts at finding the keyboard, also after losing it
+const RETRY_INTERVAL: Duration = Duration::from_secs(2);
+/// How often to check for input while waiting to turn the lights off
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// How long to hold off exiting after a crash for the keyboard to be restored
+const RESTORE_TIMEOUT: Duration = Duration::from_secs(1);
+/// Time between the steps of a crossfade, before the frame rate limit is applied
+const CROSSFADE_STEP: Duration = Duration::from_millis(20);
+/// How often the colors of a custom effect are sampled while they fade, only being sent when they change
+const CUSTOM_EFFECT_TICK: Duration = Duration::from_millis(20);
+/// The longest a custom effect holding its colors waits before checking whether it was stopped
+const CUSTOM_EFFECT_LONGEST_WAIT: Duration = Duration::from_millis(100);
 
 #[derive(Debug, Error, PartialEq)]
 #[error("Could not create keyboard manager")]
@@ -35,6 +90,30 @@ pub struct EffectManager {
     pub tx: Sender<Message>,
     inner_handle: Option<JoinHandle<()>>,
     stop_signals: StopSignals,
+    reduced_motion: Arc<AtomicBool>,
+    /// Most frames per second, 0 for no limit
+    max_fps: Arc<AtomicU32>,
+    /// Milliseconds to fade between profiles for, 0 to switch right away
+    crossfade: Arc<AtomicU32>,
+    /// Percentage every color is scaled to
+    brightness_scale: Arc<AtomicU32>,
+    calibration: Arc<Mutex<Calibration>>,
+    overlay: Arc<Mutex<Overlay>>,
+    /// Passes changes to the keyboard while an effect is playing
+    output_tx: Sender<OutputUpdate>,
+    synced_lights: Arc<Mutex<Vec<Section>>>,
+    weather: Arc<Mutex<WeatherSettings>>,
+    countdown_deadline: Arc<Mutex<Option<Instant>>>,
+    exit: Arc<Mutex<ExitSettings>>,
+    preview: OutputPreview,
+    /// The keyboard being driven, `None` for a virtual one
+    device: Option<KeyboardInfo>,
+    /// What the keyboard was showing when it was opened, if it could tell
+    hardware_state: Option<LightingState>,
+    connected: Arc<AtomicBool>,
+    clock: Arc<SystemClock>,
+    /// What the system calls the effect thread, 0 until it started or where there's no telling
+    thread_id: Arc<AtomicU32>,
 }
 
 /// Controls the keyboard lighting logic
@@ -43,9 +122,65 @@ struct Inner {
     rx: Receiver<Message>,
     stop_signals: StopSignals,
     last_profile: Profile,
-    // Can't drop this else it stops "reserving" whatever underlying implementation identifier it uses
-    #[allow(dead_code)]
-    single_instance: SingleInstance,
+    last_custom_effect: Option<CustomEffect>,
+    reduced_motion: Arc<AtomicBool>,
+    /// Most frames per second, 0 for no limit
+    max_fps: Arc<AtomicU32>,
+    /// Milliseconds to fade between profiles for, 0 to switch right away
+    crossfade: Arc<AtomicU32>,
+    /// Percentage every color is scaled to
+    brightness_scale: Arc<AtomicU32>,
+    calibration: Arc<Mutex<Calibration>>,
+    /// Colors shown over the effect on some zones, like the mute state of the microphone
+    overlay: Arc<Mutex<Overlay>>,
+    /// Lights of the laptop turned on and off along with the keyboard
+    synced_lights: Arc<Mutex<Vec<Section>>>,
+    /// Where the `Weather` effect gets the conditions from
+    weather: Arc<Mutex<WeatherSettings>>,
+    /// When the countdown of the `Clock` effect ends, `None` to count from whenever the effect starts
+    countdown_deadline: Arc<Mutex<Option<Instant>>>,
+    /// What to leave on the keyboard when the program goes away
+    exit: Arc<Mutex<ExitSettings>>,
+    /// Minutes without input after which to turn the lights off, 0 to keep them on
+    off_after: Arc<AtomicU32>,
+    /// Lets the idle watcher turn the lights off, `None` for a virtual keyboard as nobody is in front of it
+    idle_tx: Option<Sender<Message>>,
+    /// Whether the idle watcher is running, which it only does while there's a timeout
+    idle_watching: Arc<AtomicBool>,
+    /// Whether to publish what's being played, virtual keyboards shouldn't overwrite the real state
+    publish_status: bool,
+    /// Cleared while the keyboard is gone, until it can be opened again
+    connected: Arc<AtomicBool>,
+    /// The keyboard being driven, `None` for a virtual one
+    device: Option<KeyboardInfo>,
+    /// Makes the random effects play the same way every time, for tests
+    seed: Option<u64>,
+    /// Effects take the time from here instead of the system
+    clock: Arc<dyn Clock>,
+}
+
+/// The last of `first` and the messages queued after it, as only the latest state matters to the GUI
+///
+/// Waking up doesn't supersede anything but going to sleep, a change made while the lights were off already shows what's
+/// wanted and resuming would play what came before it instead.
+fn latest(first: Message, rx: &Receiver<Message>) -> Message {
+    rx.try_iter().fold(first, |latest, message| match message {
+        Message::Wake if !matches!(latest, Message::Sleep | Message::Wake) => latest,
+        // Anything else already sends the state along with the changes
+        Message::Refresh => latest,
+        message => message,
+    })
+}
+
+/// The ID the system knows the calling thread by, 0 where there's no telling
+fn system_thread_id() -> u32 {
+    // Links to something like "1234/task/1240"
+    #[cfg(target_os = "linux")]
+    if let Some(id) = std::fs::read_link("/proc/thread-self").ok().and_then(|path| path.file_name()?.to_str()?.parse().ok()) {
+        return id;
+    }
+
+    0
 }
 
 #[derive(Clone, Copy)]
@@ -55,72 +190,331 @@ pub enum OperationMode {
 }
 
 impl EffectManager {
-    pub fn new(operation_mode: OperationMode) -> Result<Self, ManagerCreationError> {
+    /// Open one of the keyboards listed by the driver, see [`registry::DeviceRegistry`] for finding them
+    ///
+    /// With `untouched`, nothing is sent to the keyboard until the first profile or effect.
+    fn open(info: &KeyboardInfo, operation_mode: OperationMode, publish_status: bool, untouched: bool) -> Result<Self, ManagerCreationError> {
         let stop_signals = StopSignals {
             manager_stop_signal: Arc::new(AtomicBool::new(false)),
             keyboard_stop_signal: Arc::new(AtomicBool::new(false)),
         };
 
-        // Use the crate's name as the identifier, should be unique enough
-        let single_instance = SingleInstance::new(env!("CARGO_PKG_NAME")).unwrap();
+        let keyboard = if untouched {
+            legion_kb_rgb_hw::get_keyboard_untouched_at(info, stop_signals.keyboard_stop_signal.clone())
+        } else {
+            legion_kb_rgb_hw::get_keyboard_at(info, stop_signals.keyboard_stop_signal.clone())
+        };
+        let mut keyboard = keyboard
+            .change_context(ManagerCreationError::AcquireKeyboard)
+            .attach_printable_lazy(|| format!("Could not open the {} keyboard at {}", info.model.name, info.path))?;
+
+        info!(model = info.model.name, path = %info.path, "Opened the keyboard");
 
-        if !single_instance.is_single() {
-            return Err(ManagerCreationError::InstanceAlreadyRunning.into());
+        let hardware_state = keyboard.read_back_state().cloned();
+        if hardware_state.is_none() {
+            debug!(model = info.model.name, "The keyboard couldn't tell what it was showing");
         }
 
-        let keyboard = legion_rgb_driver::get_keyboard(stop_signals.keyboard_stop_signal.clone())
-            .change_context(ManagerCreationError::AcquireKeyboard)
-            .attach_printable("Ensure that you have a supported model and that the application has access to it.")
-            .attach_printable("On Linux, see https://github.com/4JX/L5P-Keyboard-RGB#usage")?;
+        let preview = OutputPreview::default();
+        keyboard.set_observer(preview.observer());
+
+        let mut manager = Self::spawn(keyboard, stop_signals, Some(info.clone()), publish_status, operation_mode, None, None);
+        manager.preview = preview;
+        manager.hardware_state = hardware_state;
+
+        Ok(manager)
+    }
+
+    /// Drive a keyboard that only exists in memory as if it were a real one, recording every state it goes through
+    fn simulate(info: &KeyboardInfo, operation_mode: OperationMode, recorder: &FrameRecorder) -> Self {
+        let stop_signals = StopSignals {
+            manager_stop_signal: Arc::new(AtomicBool::new(false)),
+            keyboard_stop_signal: Arc::new(AtomicBool::new(false)),
+        };
+
+        let mut keyboard = legion_kb_rgb_hw::get_virtual_keyboard(stop_signals.keyboard_stop_signal.clone());
+
+        info!("Simulating a keyboard, nothing will be sent to the hardware");
+
+        let preview = OutputPreview::default();
+        let mut preview_observer = preview.observer();
+        let mut record = recorder.observer();
+        keyboard.set_observer(move |state| {
+            preview_observer(state);
+            record(state);
+        });
+
+        // Leave the status alone, it belongs to whatever is driving the real keyboard
+        let mut manager = Self::spawn(keyboard, stop_signals, Some(info.clone()), false, operation_mode, None, None);
+        manager.preview = preview;
+
+        manager
+    }
 
+    /// Run effects on a keyboard that only exists in memory, reporting every state it goes through
+    pub fn new_virtual(observer: impl FnMut(&LightingState) + Send + 'static) -> Self {
+        let stop_signals = StopSignals {
+            manager_stop_signal: Arc::new(AtomicBool::new(false)),
+            keyboard_stop_signal: Arc::new(AtomicBool::new(false)),
+        };
+
+        let mut keyboard = legion_kb_rgb_hw::get_virtual_keyboard(stop_signals.keyboard_stop_signal.clone());
+        keyboard.set_observer(observer);
+
+        Self::spawn(keyboard, stop_signals, None, false, OperationMode::Cli, None, None)
+    }
+
+    /// Same as [`Self::new_virtual`], taking the time from the manager the layer belongs to, see [`layers`]
+    fn new_layer(observer: impl FnMut(&LightingState) + Send + 'static, clock: Arc<dyn Clock>) -> Self {
+        let stop_signals = StopSignals {
+            manager_stop_signal: Arc::new(AtomicBool::new(false)),
+            keyboard_stop_signal: Arc::new(AtomicBool::new(false)),
+        };
+
+        let mut keyboard = legion_kb_rgb_hw::get_virtual_keyboard(stop_signals.keyboard_stop_signal.clone());
+        keyboard.set_observer(observer);
+
+        Self::spawn(keyboard, stop_signals, None, false, OperationMode::Cli, None, Some(clock))
+    }
+
+    /// Same as [`Self::new_virtual`], with the random effects seeded and the time taken from `clock`, so effects always
+    /// play the same way
+    pub fn new_replayable(observer: impl FnMut(&LightingState) + Send + 'static, seed: u64, clock: Arc<dyn Clock>) -> Self {
+        let stop_signals = StopSignals {
+            manager_stop_signal: Arc::new(AtomicBool::new(false)),
+            keyboard_stop_signal: Arc::new(AtomicBool::new(false)),
+        };
+
+        let mut keyboard = legion_kb_rgb_hw::get_virtual_keyboard(stop_signals.keyboard_stop_signal.clone());
+        keyboard.set_observer(observer);
+
+        Self::spawn(keyboard, stop_signals, None, false, OperationMode::Cli, Some(seed), Some(clock))
+    }
+
+    fn spawn(
+        mut keyboard: Keyboard, stop_signals: StopSignals, device: Option<KeyboardInfo>, publish_status: bool, operation_mode: OperationMode, seed: Option<u64>, clock: Option<Arc<dyn Clock>>,
+    ) -> Self {
         let (tx, rx) = crossbeam_channel::unbounded::<Message>();
+        let system_clock = Arc::new(SystemClock::default());
+        let clock = clock.unwrap_or_else(|| system_clock.clone() as Arc<dyn Clock>);
+        {
+            let clock = clock.clone();
+            keyboard.set_sleep(move |duration| clock.sleep(duration));
+        }
+        let (output_tx, output_rx) = crossbeam_channel::unbounded();
+        keyboard.set_output_updates(move || output_rx.try_recv().ok());
+        let reduced_motion = Arc::new(AtomicBool::new(false));
+        let max_fps = Arc::new(AtomicU32::new(0));
+        let crossfade = Arc::new(AtomicU32::new(0));
+        let brightness_scale = Arc::new(AtomicU32::new(100));
+        let calibration = Arc::new(Mutex::new(Calibration::default()));
+        let overlay = Arc::new(Mutex::new(Overlay::default()));
+        let synced_lights = Arc::new(Mutex::new(Vec::new()));
+        let weather = Arc::new(Mutex::new(WeatherSettings::default()));
+        let countdown_deadline = Arc::new(Mutex::new(None));
+        let exit = Arc::new(Mutex::new(ExitSettings::default()));
+        let off_after = Arc::new(AtomicU32::new(0));
+        let connected = Arc::new(AtomicBool::new(true));
+        let thread_id = Arc::new(AtomicU32::new(0));
+
+        // Whatever is playing has to stop so the effect thread can start waiting for the keyboard to come back
+        {
+            let stop_signals = stop_signals.clone();
+            let connected = connected.clone();
+            keyboard.set_on_lost(move || {
+                warn!("Lost the keyboard, waiting for it to come back");
+                connected.store(false, Ordering::SeqCst);
+                stop_signals.store_true();
+            });
+        }
 
         let mut inner = Inner {
             keyboard,
             rx,
             stop_signals: stop_signals.clone(),
             last_profile: Profile::default(),
-            single_instance,
+            last_custom_effect: None,
+            reduced_motion: reduced_motion.clone(),
+            max_fps: max_fps.clone(),
+            crossfade: crossfade.clone(),
+            brightness_scale: brightness_scale.clone(),
+            calibration: calibration.clone(),
+            overlay: overlay.clone(),
+            synced_lights: synced_lights.clone(),
+            weather: weather.clone(),
+            countdown_deadline: countdown_deadline.clone(),
+            exit: exit.clone(),
+            off_after,
+            idle_tx: device.is_some().then(|| tx.clone()),
+            idle_watching: Arc::new(AtomicBool::new(false)),
+            publish_status,
+            connected: connected.clone(),
+            device: device.clone(),
+            seed,
+            clock,
         };
 
         macro_rules! effect_thread_loop {
-            ($e: expr) => {
-                thread::spawn(move || loop {
+            ($e: expr) => {{
+                let thread_id = thread_id.clone();
+                thread::spawn(move || {
+                    thread_id.store(system_thread_id(), Ordering::SeqCst);
+                    effect_thread_loop!(@loop $e)
+                })
+            }};
+            (@loop $e: expr) => {
+                loop {
                     match $e {
                         Some(message) => match message {
-                            Message::Profile { profile } => {
-                                inner.set_profile(profile);
+                            Message::Profile { profile, crossfade, publish } => {
+                                inner.set_profile(profile, crossfade, publish);
                             }
                             Message::CustomEffect { effect } => {
                                 inner.custom_effect(&effect);
                             }
+                            Message::Flash { pattern } => {
+                                inner.flash(&pattern);
+                            }
+                            Message::FlashZones { frames, hold } => {
+                                inner.flash_zones(&frames, hold);
+                            }
+                            #[cfg(feature = "dev-effects")]
+                            Message::DevEffect { path, profile } => {
+                                inner.stop_signals.store_false();
+                                dev_effect::play(&mut inner, &path, &profile);
+                            }
+                            Message::Sleep => {
+                                inner.sleep();
+                            }
+                            Message::Wake => {
+                                inner.resume();
+                            }
+                            Message::Refresh => {
+                                // A lost keyboard gets picked up right below
+                                let _ = inner.keyboard.refresh();
+                            }
+                            Message::Restore { done } => {
+                                inner.restore();
+                                let _ = done.send(());
+                                break;
+                            }
                             Message::Exit => break,
                         },
-                        None => {
-                            thread::sleep(Duration::from_millis(20));
-                        }
+                        // The manager is gone
+                        None => break,
                     }
-                })
+
+                    if inner.keyboard.is_lost() && !inner.reconnect() {
+                        break;
+                    }
+                }
             };
         }
 
+        // Block until there's something to do, so a static profile doesn't keep waking the CPU up
         let inner_handle = match operation_mode {
-            OperationMode::Cli => effect_thread_loop!(inner.rx.try_recv().ok()),
-            OperationMode::Gui => effect_thread_loop!(inner.rx.try_iter().last()),
+            OperationMode::Cli => effect_thread_loop!(inner.rx.recv().ok()),
+            OperationMode::Gui => effect_thread_loop!(inner.rx.recv().ok().map(|message| latest(message, &inner.rx))),
         };
 
-        let manager = Self {
+        if let Some(device) = &device {
+            let tx = tx.clone();
+            let stop_signals = stop_signals.clone();
+            let effect_thread = inner_handle.thread().id();
+
+            crash::set_restore(&device.path, move || {
+                // The effect thread restores the keyboard by itself as it unwinds
+                if thread::current().id() == effect_thread {
+                    return;
+                }
+
+                stop_signals.store_true();
+                let (done_tx, done_rx) = crossbeam_channel::bounded(1);
+                if tx.send(Message::Restore { done: done_tx }).is_ok() {
+                    let _ = done_rx.recv_timeout(RESTORE_TIMEOUT);
+                }
+            });
+        }
+
+        Self {
             tx,
             inner_handle: Some(inner_handle),
             stop_signals,
-        };
+            reduced_motion,
+            max_fps,
+            crossfade,
+            brightness_scale,
+            calibration,
+            overlay,
+            output_tx,
+            synced_lights,
+            weather,
+            countdown_deadline,
+            exit,
+            preview: OutputPreview::default(),
+            device,
+            hardware_state: None,
+            connected,
+            clock: system_clock,
+            thread_id,
+        }
+    }
 
-        Ok(manager)
+    /// Emulate turning the keyboard off after a period of inactivity, as the firmware has no way of doing it by itself
+    ///
+    /// Exits once the effect thread drops the timeout it shares with it, or once there's no timeout while the lights are
+    /// on, clearing `watching`.
+    fn spawn_idle_watcher(tx: Sender<Message>, stop_signals: StopSignals, off_after: Weak<AtomicU32>, watching: Arc<AtomicBool>) {
+        thread::spawn(move || {
+            let mut monitor = IdleMonitor::new();
+            let mut asleep = false;
+
+            while let Some(off_after) = off_after.upgrade() {
+                let timeout = Duration::from_secs(u64::from(off_after.load(Ordering::SeqCst)) * 60);
+
+                if timeout.is_zero() && !asleep {
+                    watching.store(false, Ordering::SeqCst);
+                    // A timeout set in the meantime would have found the watcher still running
+                    if off_after.load(Ordering::SeqCst) > 0 && !watching.swap(true, Ordering::SeqCst) {
+                        continue;
+                    }
+                    break;
+                }
+                drop(off_after);
+
+                let idle_time = monitor.idle_time();
+
+                if !asleep && !timeout.is_zero() && idle_time >= timeout {
+                    asleep = true;
+                    stop_signals.store_true();
+                    let _ = tx.send(Message::Sleep);
+                } else if asleep && idle_time < IDLE_POLL_INTERVAL {
+                    asleep = false;
+                    stop_signals.store_true();
+                    let _ = tx.send(Message::Wake);
+                }
+
+                ticks::sleep_until_next(IDLE_POLL_INTERVAL);
+            }
+        });
     }
 
     pub fn set_profile(&mut self, profile: Profile) {
         self.stop_signals.store_true();
-        self.tx.try_send(Message::Profile { profile }).unwrap();
+        self.tx.try_send(Message::Profile { profile, crossfade: false, publish: true }).unwrap();
+    }
+
+    /// Same as [`Self::set_profile`] for a profile that only takes over for a moment, which isn't published as the status
+    #[cfg(feature = "gui")]
+    pub fn show_profile(&mut self, profile: Profile) {
+        self.stop_signals.store_true();
+        self.tx.try_send(Message::Profile { profile, crossfade: false, publish: false }).unwrap();
+    }
+
+    /// Same as [`Self::set_profile`], fading from what's currently shown to the new colors first
+    pub fn switch_profile(&mut self, profile: Profile) {
+        self.stop_signals.store_true();
+        self.tx.try_send(Message::Profile { profile, crossfade: true, publish: true }).unwrap();
     }
 
     pub fn custom_effect(&self, effect: CustomEffect) {
@@ -128,21 +522,214 @@ impl EffectManager {
         self.tx.send(Message::CustomEffect { effect }).unwrap();
     }
 
+    /// Play an effect from a library that gets reloaded whenever it's rebuilt, using the colors and speed of `profile`
+    #[cfg(feature = "dev-effects")]
+    pub fn dev_effect(&self, path: std::path::PathBuf, profile: Profile) {
+        self.stop_signals.store_true();
+        self.tx.send(Message::DevEffect { path, profile }).unwrap();
+    }
+
+    /// Play every animation faster or slower, right away
+    pub fn set_speed_multiplier(&self, multiplier: SpeedMultiplier) {
+        self.clock.set_multiplier(multiplier);
+    }
+
+    /// Correct the colors of the keyboard from the next profile on
+    pub fn set_calibration(&self, calibration: Calibration) {
+        *self.calibration.lock().unwrap() = calibration;
+    }
+
+    /// Scale every color to this percentage, right away
+    pub fn set_brightness_scale(&self, percent: u8) {
+        let percent = u32::from(percent.min(100));
+        if self.brightness_scale.swap(percent, Ordering::SeqCst) == percent {
+            return;
+        }
+
+        let _ = self.output_tx.send(OutputUpdate::BrightnessScale(percent as f32 / 100.0));
+        let _ = self.tx.send(Message::Refresh);
+    }
+
+    /// Show these colors over the effect right away
+    ///
+    /// Returns whether the effect has to be restarted for it to show, as pulsing zones are drawn along with the effect.
+    pub fn set_overlay(&self, overlay: Overlay) -> bool {
+        let previous = std::mem::replace(&mut *self.overlay.lock().unwrap(), overlay);
+        if previous == overlay {
+            return false;
+        }
+
+        let _ = self.output_tx.send(OutputUpdate::Overlay(overlay.still()));
+        let _ = self.tx.send(Message::Refresh);
+        previous.is_animated() != overlay.is_animated()
+    }
+
+    /// Turn these lights of the laptop on and off along with the keyboard from the next profile on
+    pub fn set_synced_lights(&self, sections: &[Section]) {
+        sections.clone_into(&mut self.synced_lights.lock().unwrap());
+    }
+
+    /// Get the conditions for the `Weather` effect from here from the next profile on
+    pub fn set_weather(&self, settings: WeatherSettings) {
+        *self.weather.lock().unwrap() = settings;
+    }
+
+    /// Have the countdown of the `Clock` effect end at `deadline` however often the effect restarts, or count from
+    /// whenever it starts with `None`
+    pub fn set_countdown_deadline(&self, deadline: Option<Instant>) {
+        *self.countdown_deadline.lock().unwrap() = deadline;
+    }
+
+    /// Pick what gets left on the keyboard when the program closes or goes down
+    pub fn set_exit_settings(&self, settings: ExitSettings) {
+        *self.exit.lock().unwrap() = settings;
+    }
+
+    /// Limit how often the effects update the keyboard from the next profile on
+    pub fn set_frame_rate(&self, frame_rate: FrameRate) {
+        self.max_fps.store(frame_rate.fps().unwrap_or(0), Ordering::SeqCst);
+    }
+
+    /// How long [`Self::switch_profile`] fades for, zero to switch right away
+    pub fn set_crossfade(&self, duration: Duration) {
+        self.crossfade.store(u32::try_from(duration.as_millis()).unwrap_or(u32::MAX), Ordering::SeqCst);
+    }
+
+    /// Play still or slower versions of animated effects from the next profile on
+    pub fn set_reduced_motion(&self, enabled: bool) {
+        self.reduced_motion.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Briefly override the current effect with a flash, then go back to whatever was playing
+    pub fn flash(&self, pattern: FlashPattern) {
+        self.stop_signals.store_true();
+        self.tx.send(Message::Flash { pattern }).unwrap();
+    }
+
+    /// Same as [`Self::flash`], only blinking `zone` (from 0 to 3) so it can be told apart from the others
+    pub fn flash_zone(&self, zone: usize, pattern: FlashPattern) {
+        self.stop_signals.store_true();
+        self.tx
+            .send(Message::FlashZones {
+                frames: flash::zone_frames(zone, &pattern),
+                hold: Duration::from_millis(pattern.interval),
+            })
+            .unwrap();
+    }
+
+    /// Interrupt the current effect to light the zones one after the other, then go back to it
+    pub fn identify_zones(&self) {
+        self.stop_signals.store_true();
+        self.tx
+            .send(Message::FlashZones {
+                frames: flash::identify_frames(),
+                hold: flash::IDENTIFY_STEP,
+            })
+            .unwrap();
+    }
+
+    /// What the system calls the thread playing the effects, to measure it on its own
+    pub fn effect_thread_id(&self) -> Option<u32> {
+        Some(self.thread_id.load(Ordering::SeqCst)).filter(|id| *id != 0)
+    }
+
+    /// Get what's currently being sent to the keyboard
+    pub fn preview(&self) -> &OutputPreview {
+        &self.preview
+    }
+
+    /// What the keyboard was showing before anything was sent to it, `None` if it couldn't tell
+    pub fn hardware_state(&self) -> Option<&LightingState> {
+        self.hardware_state.as_ref()
+    }
+
+    /// Check whether the effect thread is still alive, and if not, whether losing the keyboard was the likely cause
+    pub fn health(&self) -> Health {
+        if !self.connected.load(Ordering::SeqCst) {
+            return Health::Disconnected;
+        }
+
+        match &self.inner_handle {
+            Some(handle) if handle.is_finished() => {
+                if legion_kb_rgb_hw::find_keyboard_product_id().is_err() {
+                    warn!("The keyboard was disconnected");
+                    Health::Disconnected
+                } else {
+                    error!("The effect thread stopped unexpectedly");
+                    Health::Error("The effect engine stopped unexpectedly".to_string())
+                }
+            }
+            _ => Health::Normal,
+        }
+    }
+
+    /// Get a way to make the effect thread exit from elsewhere, for when the manager itself is waiting on it
+    pub fn exit_handle(&self) -> impl Fn() + Send + 'static {
+        let tx = self.tx.clone();
+        let stop_signals = self.stop_signals.clone();
+
+        move || {
+            stop_signals.store_true();
+            let _ = tx.send(Message::Exit);
+        }
+    }
+
+    /// Interrupt whatever is playing and shut down
+    pub fn stop(self) {
+        self.stop_signals.store_true();
+        self.shutdown();
+    }
+
     pub fn shutdown(mut self) {
         self.tx.send(Message::Exit).unwrap();
         if let Some(handle) = self.inner_handle.take() {
             handle.join().unwrap();
         };
     }
+
+    /// Interrupt whatever is playing and leave the keyboard as set through [`Self::set_exit_settings`], then shut down
+    pub fn exit(mut self) {
+        self.stop_signals.store_true();
+        let (done_tx, _done_rx) = crossbeam_channel::bounded(1);
+        self.tx.send(Message::Restore { done: done_tx }).unwrap();
+        if let Some(handle) = self.inner_handle.take() {
+            handle.join().unwrap();
+        };
+    }
 }
 
 impl Inner {
-    fn set_profile(&mut self, mut profile: Profile) {
+    fn set_profile(&mut self, profile: Profile, crossfade: bool, publish: bool) {
+        info!(name = profile.name.as_deref().unwrap_or("Unnamed"), effect = %profile.effect, "Applying profile");
+        let off_after = profile.off_after.unwrap_or(0);
+        self.off_after.store(off_after, Ordering::SeqCst);
+        if off_after > 0 {
+            self.watch_idle();
+        }
         self.last_profile = profile.clone();
+        self.last_custom_effect = None;
+        if self.publish_status && publish {
+            Status::from_profile(&profile).save();
+        }
+        let mut profile = profile.with_intensity_applied();
+        let reduced_motion = self.reduced_motion.load(Ordering::SeqCst);
+        if reduced_motion {
+            profile = profile.with_reduced_motion();
+        }
+        self.apply_output_settings();
+        self.sync_lights(profile.rgb_array().iter().any(|channel| *channel > 0));
         self.stop_signals.store_false();
-        let mut thread_rng = thread_rng();
+        let mut rng = self.seed.map_or_else(StdRng::from_entropy, StdRng::seed_from_u64);
+
+        if crossfade && !reduced_motion {
+            self.crossfade_to(&profile);
+        }
 
-        if profile.effect.is_built_in() {
+        // Pulsing overlays are drawn over every frame, like the overlay of a profile, but the effects the firmware animates
+        // by itself are left to it rather than emulated for their sake
+        let animated_by_firmware = matches!(profile.effect, Effects::Breath | Effects::Smooth | Effects::Wave);
+        let composited = layers::needs_compositing(&profile) || (self.overlay.lock().unwrap().is_animated() && !animated_by_firmware);
+        if profile.effect.is_built_in() && !composited {
             let clamped_speed = self.clamp_speed(profile.speed);
             self.keyboard.set_speed(clamped_speed).unwrap();
         } else {
@@ -152,15 +739,59 @@ impl Inner {
 
         self.keyboard.set_brightness(profile.brightness as u8 + 1).unwrap();
 
-        self.apply_effect(&mut profile, &mut thread_rng);
+        if composited {
+            layers::play(self, &profile);
+        } else {
+            self.apply_effect(&mut profile, &mut rng);
+        }
         self.stop_signals.store_false();
     }
 
+    /// Fade from the colors currently shown to those of `profile`, unless either side is animated by the hardware
+    fn crossfade_to(&mut self, profile: &Profile) {
+        let duration = u64::from(self.crossfade.load(Ordering::SeqCst));
+        let animated_by_hardware = matches!(profile.effect, Effects::Smooth | Effects::Wave);
+        if duration == 0 || animated_by_hardware || !matches!(self.keyboard.state().effect_type(), BaseEffects::Static) {
+            return;
+        }
+
+        let steps = (duration / CROSSFADE_STEP.as_millis() as u64).clamp(1, u64::from(u8::MAX));
+        self.keyboard.transition_colors_to(&profile.rgb_array(), steps as u8, duration / steps).unwrap();
+    }
+
+    /// The most frames per second currently allowed, `None` for no limit
+    fn max_fps(&self) -> Option<u32> {
+        Some(self.max_fps.load(Ordering::SeqCst)).filter(|fps| *fps > 0)
+    }
+
+    /// Pass on the settings that change how everything is sent to the keyboard
+    fn apply_output_settings(&mut self) {
+        let interval = self.max_fps().map(|fps| Duration::from_secs(1) / fps);
+        self.keyboard.set_frame_interval(interval);
+        self.keyboard.set_calibration(*self.calibration.lock().unwrap());
+        self.keyboard.set_brightness_scale(self.brightness_scale.load(Ordering::SeqCst) as f32 / 100.0);
+        self.keyboard.set_overlay(self.overlay.lock().unwrap().still());
+    }
+
+    /// Turn the lights of the laptop that follow the keyboard on or off
+    fn sync_lights(&self, on: bool) {
+        // Virtual keyboards don't belong to the laptop
+        if self.device.is_none() {
+            return;
+        }
+
+        for section in self.synced_lights.lock().unwrap().iter() {
+            if let Err(err) = section.set(on) {
+                warn!("Could not turn the {section:?} light {}: {err}", if on { "on" } else { "off" });
+            }
+        }
+    }
+
     fn clamp_speed(&self, speed: u8) -> u8 {
-        speed.clamp(SPEED_RANGE.min().unwrap(), SPEED_RANGE.max().unwrap())
+        speed.clamp(1, self.keyboard.model().speeds)
     }
 
-    fn apply_effect(&mut self, profile: &mut Profile, thread_rng: &mut ThreadRng) {
+    fn apply_effect(&mut self, profile: &mut Profile, rng: &mut StdRng) {
         match profile.effect {
             Effects::Static => {
                 self.keyboard.set_colors_to(&profile.rgb_array()).unwrap();
@@ -180,53 +811,224 @@ impl Inner {
                 };
                 self.keyboard.set_effect(effect).unwrap();
             }
-            Effects::Lightning => lightning::play(self, profile, thread_rng),
+            Effects::Lightning => lightning::play(self, profile, rng),
             Effects::AmbientLight { mut fps, mut saturation_boost } => {
-                fps = fps.clamp(1, 60);
+                fps = fps.clamp(1, self.max_fps().map_or(60, |max_fps| max_fps.min(60) as u8));
                 saturation_boost = saturation_boost.clamp(0.0, 1.0);
                 ambient::play(self, fps, saturation_boost);
             }
             Effects::SmoothWave => {
-                profile.rgb_zones = profile::arr_to_zones([255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 0, 255]);
+                // Cycles through the colors of the palette the zones were filled with, or the spectrum without one
+                if profile.active_palette().is_none() {
+                    profile.rgb_zones = profile::arr_to_zones([255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 0, 255]);
+                }
                 swipe::play(self, profile);
             }
             Effects::Swipe => swipe::play(self, profile),
-            Effects::Disco => disco::play(self, profile, thread_rng),
-            Effects::Christmas => christmas::play(self, thread_rng),
+            Effects::Disco => disco::play(self, profile, rng),
+            Effects::Christmas => christmas::play(self, rng),
             Effects::Fade => fade::play(self, profile),
             Effects::Temperature => temperature::play(self),
             Effects::Ripple => ripple::play(self, profile),
+            Effects::Fire { mut intensity, mut hue } => {
+                intensity = intensity.clamp(0.0, 1.0);
+                hue = hue.clamp(0.0, 60.0);
+                fire::play(self, profile, intensity, hue, rng);
+            }
+            Effects::Daylight { mut latitude, mut longitude } => {
+                latitude = latitude.clamp(-90.0, 90.0);
+                longitude = longitude.clamp(-180.0, 180.0);
+                daylight::play(self, latitude, longitude);
+            }
+            Effects::Weather {
+                mut latitude,
+                mut longitude,
+                refresh_minutes,
+            } => {
+                latitude = latitude.clamp(-90.0, 90.0);
+                longitude = longitude.clamp(-180.0, 180.0);
+                weather::play(self, profile, latitude, longitude, refresh_minutes, rng);
+            }
+            Effects::Clock { countdown_minutes } => wall_clock::play(self, countdown_minutes),
+            Effects::Network { max_mbps, chase } => network::play(self, profile, max_mbps, chase),
+            Effects::Heatmap => heatmap::play(self),
+            Effects::TypingSpeed { min_wpm, max_wpm, gauge } => typing_speed::play(self, profile, min_wpm, max_wpm, gauge),
+            Effects::Automaton { rule } => automaton::play(self, profile, rule, rng),
         }
     }
 
     fn custom_effect(&mut self, custom_effect: &CustomEffect) {
+        info!(name = custom_effect.name.as_deref().unwrap_or("Unnamed"), "Playing custom effect");
+        self.last_custom_effect = Some(custom_effect.clone());
+        if self.publish_status {
+            Status::from_custom_effect(custom_effect).save();
+        }
+        self.apply_output_settings();
+        self.sync_lights(true);
         self.stop_signals.store_false();
+        self.keyboard.set_brightness(custom_effect.brightness).unwrap();
+
+        let sampler = custom_effect.sampler();
+        let started = self.clock.now();
+        let mut last = None;
 
         loop {
-            for step in &custom_effect.effect_steps {
-                self.keyboard.set_brightness(step.brightness).unwrap();
-                match step.step_type {
-                    EffectType::Set => {
-                        self.keyboard.set_colors_to(&step.rgb_array).unwrap();
-                    }
-                    _ => {
-                        self.keyboard.transition_colors_to(&step.rgb_array, step.steps, step.delay_between_steps).unwrap();
-                    }
+            let time = self.clock.now().duration_since(started).as_millis() as u64;
+            let rgb_array = sampler.colors_at(time);
+            if last != Some(rgb_array) {
+                self.keyboard.set_colors_to(&rgb_array).unwrap();
+                last = Some(rgb_array);
+            }
+
+            if custom_effect.is_over(time) || self.stop_signals.manager_stop_signal.load(Ordering::SeqCst) {
+                return;
+            }
+
+            // Held colors are only looked at again once the next keyframe is due
+            let wait = match sampler.until_change(time) {
+                None | Some(0) => CUSTOM_EFFECT_TICK,
+                Some(ms) => Duration::from_millis(ms).min(CUSTOM_EFFECT_LONGEST_WAIT),
+            };
+            self.clock.sleep(wait);
+        }
+    }
+
+    fn flash(&mut self, pattern: &FlashPattern) {
+        debug!("Flashing the keyboard");
+        self.stop_signals.store_false();
+
+        if !flash::play(self, pattern) {
+            // Something else was requested in the meantime, let it take over
+            return;
+        }
+
+        self.resume();
+    }
+
+    fn flash_zones(&mut self, frames: &[[u8; 12]], hold: Duration) {
+        debug!("Flashing the zones");
+        self.stop_signals.store_false();
+
+        if !flash::play_zones(self, frames, hold) {
+            return;
+        }
+
+        self.resume();
+    }
+
+    /// Start the idle watcher, unless it's already running
+    fn watch_idle(&self) {
+        let Some(tx) = &self.idle_tx else {
+            return;
+        };
+
+        if !self.idle_watching.swap(true, Ordering::SeqCst) {
+            EffectManager::spawn_idle_watcher(tx.clone(), self.stop_signals.clone(), Arc::downgrade(&self.off_after), self.idle_watching.clone());
+        }
+    }
+
+    fn sleep(&mut self) {
+        info!("Turning the lights off after a period of inactivity");
+        self.stop_signals.store_false();
+        // A lost keyboard gets picked up by the effect loop once this returns
+        if let Err(err) = self.keyboard.set_colors_to(&[0; 12]).and_then(|()| self.keyboard.set_effect(BaseEffects::Static)) {
+            warn!("Could not turn the lights off: {err}");
+            return;
+        }
+        self.sync_lights(false);
+    }
+
+    /// Play whatever was last requested again, after something temporarily took over
+    fn resume(&mut self) {
+        if let Some(effect) = self.last_custom_effect.clone() {
+            self.custom_effect(&effect);
+        } else {
+            self.set_profile(self.last_profile.clone(), false, true);
+        }
+    }
+
+    /// Wait for the keyboard to come back after it was lost, then play whatever was last requested again
+    ///
+    /// Returns whether the effect thread should keep running.
+    fn reconnect(&mut self) -> bool {
+        while self.keyboard.is_lost() {
+            if self.keyboard.reconnect().is_ok() && !self.keyboard.is_lost() {
+                info!("Reconnected to the keyboard");
+                self.connected.store(true, Ordering::SeqCst);
+                self.resume();
+                continue;
+            }
+
+            match self.rx.recv_timeout(RETRY_INTERVAL) {
+                // Keep track of what was asked for in the meantime, to play it once the keyboard is back
+                Ok(Message::Profile { profile, .. }) => {
+                    self.last_profile = profile;
+                    self.last_custom_effect = None;
                 }
-                if self.stop_signals.manager_stop_signal.load(Ordering::SeqCst) {
-                    return;
+                Ok(Message::CustomEffect { effect }) => {
+                    self.last_custom_effect = Some(effect);
+                }
+                Ok(Message::Restore { done }) => {
+                    let _ = done.send(());
+                    return false;
                 }
-                thread::sleep(Duration::from_millis(step.sleep));
+                Ok(Message::Exit) | Err(RecvTimeoutError::Disconnected) => return false,
+                Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+            }
+        }
+
+        true
+    }
+
+    /// Leave what the exit settings ask for on the keyboard, by default the lighting of the last profile rather than
+    /// whatever frame an effect stopped at
+    ///
+    /// Nothing here may panic, as it also runs while the effect thread is unwinding.
+    fn restore(&mut self) {
+        let settings = self.exit.lock().map(|settings| *settings).unwrap_or_default();
+
+        let Some(colors) = settings.colors() else {
+            // The firmware keeps playing its own effects without the app
+            if self.last_custom_effect.is_none() && self.last_profile.effect.is_built_in() {
+                return;
             }
-            if !custom_effect.should_loop {
-                break;
+
+            let _ = self.keyboard.set_effect(BaseEffects::Static);
+            let _ = self.keyboard.set_colors_to(&self.last_profile.rgb_array());
+            return;
+        };
+
+        // Nothing is left to clear the status colors once the program is gone
+        self.keyboard.set_overlay([None; 4]);
+        let _ = self.keyboard.set_effect(BaseEffects::Static);
+        let _ = self.keyboard.set_colors_to(&colors);
+
+        if colors == [0; 12] && self.device.is_some() {
+            if let Ok(sections) = self.synced_lights.try_lock() {
+                for section in sections.iter() {
+                    let _ = section.set(false);
+                }
             }
         }
     }
 }
 
+impl Drop for Inner {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.restore();
+        }
+        if let Some(device) = &self.device {
+            info!(model = device.model.name, path = %device.path, "Closed the keyboard");
+        }
+    }
+}
+
 impl Drop for EffectManager {
     fn drop(&mut self) {
+        if let Some(device) = &self.device {
+            crash::clear_restore(&device.path);
+        }
         let _ = self.tx.send(Message::Exit);
     }
 }
@@ -0,0 +1,181 @@
+//! Drives the speed, brightness or hue of a profile with low-frequency oscillators, for livelier versions of the
+//! built-in effects without writing a custom one.
+//!
+//! Speed is modulated by warping the time the effect sees, brightness and hue by adjusting the colors it outputs, see
+//! [`super::layers`] for how they get to the keyboard.
+
+use std::{
+    f32::consts::TAU,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use strum_macros::{EnumIter, IntoStaticStr};
+
+use super::clock::Clock;
+use crate::util;
+
+/// Slowest the speed can be modulated down to, so time never stops
+const MIN_SPEED_FACTOR: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, EnumIter, IntoStaticStr)]
+pub enum ModulationTarget {
+    #[default]
+    #[strum(serialize = "Speed")]
+    Speed,
+    #[strum(serialize = "Brightness")]
+    Brightness,
+    #[strum(serialize = "Hue offset")]
+    HueOffset,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, EnumIter, IntoStaticStr)]
+pub enum Waveform {
+    #[default]
+    #[strum(serialize = "Sine")]
+    Sine,
+    #[strum(serialize = "Triangle")]
+    Triangle,
+    /// A new random value every period
+    #[strum(serialize = "Random")]
+    Random,
+}
+
+/// One parameter of a profile following an oscillator
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Modulation {
+    pub target: ModulationTarget,
+    pub waveform: Waveform,
+    /// Seconds per cycle
+    pub period: f32,
+    /// How far the parameter moves, from 0 to 1
+    pub depth: f32,
+}
+
+impl Default for Modulation {
+    fn default() -> Self {
+        Self {
+            target: ModulationTarget::default(),
+            waveform: Waveform::default(),
+            period: 4.0,
+            depth: 0.5,
+        }
+    }
+}
+
+impl Modulation {
+    /// The oscillator at `elapsed`, from -1 to 1
+    fn value(&self, elapsed: Duration) -> f32 {
+        let cycles = elapsed.as_secs_f32() / self.period.max(0.1);
+        let phase = cycles.fract();
+
+        match self.waveform {
+            Waveform::Sine => (phase * TAU).sin(),
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+            Waveform::Random => {
+                // Always the same sequence, so a profile looks the same every time it's played
+                let hash = splitmix64(cycles as u64);
+                (hash >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+            }
+        }
+        .clamp(-1.0, 1.0)
+    }
+
+    fn depth(&self) -> f32 {
+        self.depth.clamp(0.0, 1.0)
+    }
+}
+
+fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// How fast time should go at `elapsed`, 1 being the normal speed
+fn speed_factor(modulations: &[Modulation], elapsed: Duration) -> f32 {
+    modulations
+        .iter()
+        .filter(|modulation| modulation.target == ModulationTarget::Speed)
+        .fold(1.0, |factor, modulation| factor * modulation.depth().mul_add(modulation.value(elapsed), 1.0))
+        .max(MIN_SPEED_FACTOR)
+}
+
+/// Change the brightness and hue of `colors` as the modulations say at `elapsed`
+pub fn apply(modulations: &[Modulation], elapsed: Duration, colors: [u8; 12]) -> [u8; 12] {
+    let mut brightness = 1.0;
+    let mut hue_offset = 0.0;
+
+    for modulation in modulations {
+        match modulation.target {
+            ModulationTarget::Speed => {}
+            // Dims down from the chosen brightness, never above it
+            ModulationTarget::Brightness => brightness *= 1.0 - modulation.depth() * (modulation.value(elapsed) + 1.0) / 2.0,
+            ModulationTarget::HueOffset => hue_offset += modulation.depth() * 180.0 * modulation.value(elapsed),
+        }
+    }
+
+    if brightness == 1.0 && hue_offset == 0.0 {
+        return colors;
+    }
+
+    let mut out = colors;
+    for zone in out.chunks_exact_mut(3) {
+        let (hue, saturation, value) = util::rgb_to_hsv([zone[0], zone[1], zone[2]]);
+        zone.copy_from_slice(&util::hsv_to_rgb(hue + hue_offset, saturation, value * brightness));
+    }
+
+    out
+}
+
+/// Whether the modulations change anything over time on their own, so the output has to be redrawn continuously
+pub fn changes_colors(modulations: &[Modulation]) -> bool {
+    modulations.iter().any(|modulation| modulation.target != ModulationTarget::Speed && modulation.depth() > 0.0)
+}
+
+/// Time that speeds up and slows down following the speed modulations, on top of another clock
+pub struct ModulatedClock {
+    inner: Arc<dyn Clock>,
+    modulations: Vec<Modulation>,
+    start: Instant,
+    anchor: Mutex<Anchor>,
+}
+
+/// The last time the warped time was worked out, it's integrated from there
+struct Anchor {
+    real: Instant,
+    warped: Instant,
+}
+
+impl ModulatedClock {
+    pub fn new(inner: Arc<dyn Clock>, modulations: Vec<Modulation>) -> Self {
+        let now = inner.now();
+
+        Self {
+            inner,
+            modulations,
+            start: now,
+            anchor: Mutex::new(Anchor { real: now, warped: now }),
+        }
+    }
+}
+
+impl Clock for ModulatedClock {
+    fn now(&self) -> Instant {
+        let now = self.inner.now();
+        let mut anchor = self.anchor.lock().unwrap();
+
+        let factor = speed_factor(&self.modulations, anchor.real.saturating_duration_since(self.start));
+        anchor.warped += now.saturating_duration_since(anchor.real).mul_f32(factor);
+        anchor.real = now;
+
+        anchor.warped
+    }
+
+    fn sleep(&self, duration: Duration) {
+        let factor = speed_factor(&self.modulations, self.inner.now().saturating_duration_since(self.start));
+        self.inner.sleep(duration.div_f32(factor));
+    }
+}
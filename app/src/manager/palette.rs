@@ -0,0 +1,198 @@
+#[cfg(feature = "gui")]
+use std::path::Path;
+
+#[cfg(feature = "gui")]
+use error_stack::{Result, ResultExt};
+#[cfg(feature = "gui")]
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "gui")]
+use thiserror::Error;
+#[cfg(feature = "gui")]
+use tracing::warn;
+
+#[cfg(feature = "gui")]
+const KMEANS_ITERATIONS: usize = 10;
+/// The palettes shipped with the app, in the import format
+#[cfg(feature = "gui")]
+const BUILT_IN: &str = include_str!("../../res/palettes.txt");
+
+/// The lines of [`BUILT_IN`] that hold a palette, along with their line number
+#[cfg(feature = "gui")]
+fn built_in_entries() -> impl Iterator<Item = (usize, &'static str)> {
+    BUILT_IN
+        .lines()
+        .map(str::trim)
+        .enumerate()
+        .map(|(index, line)| (index + 1, line))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with("//"))
+}
+
+/// A named set of colors that can be spread over the keyboard zones
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct Palette {
+    pub name: String,
+    pub colors: Vec<[u8; 3]>,
+}
+
+#[cfg(feature = "gui")]
+#[derive(Debug, Error)]
+#[error("Could not import palette")]
+pub struct ImportPaletteError;
+
+impl Palette {
+    #[cfg(feature = "gui")]
+    pub fn new(name: String, colors: Vec<[u8; 3]>) -> Self {
+        Self { name, colors }
+    }
+
+    /// Parse a list of hex colors such as `#264653, #2a9d8f` or a coolors.co URL like `https://coolors.co/264653-2a9d8f-e9c46a`
+    #[cfg(feature = "gui")]
+    pub fn from_hex_list(name: String, input: &str) -> Result<Self, ImportPaletteError> {
+        let colors: Vec<[u8; 3]> = input
+            .split(|c: char| !c.is_ascii_hexdigit())
+            .filter(|token| token.len() == 6)
+            .filter_map(|token| {
+                let value = u32::from_str_radix(token, 16).ok()?;
+                let [_, r, g, b] = value.to_be_bytes();
+                Some([r, g, b])
+            })
+            .collect();
+
+        if colors.is_empty() {
+            return Err(ImportPaletteError.into());
+        }
+
+        Ok(Self::new(name, colors))
+    }
+
+    /// Parse a palette in the import format, an optional `Name =` followed by hex colors such as
+    /// `Sunset = #264653, #2a9d8f`, using `fallback_name` when there's no name
+    #[cfg(feature = "gui")]
+    pub fn from_import(fallback_name: String, input: &str) -> Result<Self, ImportPaletteError> {
+        match input.split_once('=') {
+            Some((name, colors)) if !name.trim().is_empty() => Self::from_hex_list(name.trim().to_string(), colors),
+            _ => Self::from_hex_list(fallback_name, input),
+        }
+    }
+
+    /// The palettes shipped with the app, including ones that stay distinguishable with color vision deficiencies
+    #[cfg(feature = "gui")]
+    pub fn built_in() -> Vec<Self> {
+        built_in_entries()
+            .filter_map(|(number, line)| match Self::from_import(String::new(), line) {
+                Ok(palette) => Some(palette),
+                Err(err) => {
+                    warn!("Skipped the built-in palette on line {number}, \"{line}\": {err:?}");
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Extract the `count` most dominant colors of an image
+    #[cfg(feature = "gui")]
+    pub fn from_image(name: String, path: &Path, count: usize) -> Result<Self, ImportPaletteError> {
+        let image = image::open(path).change_context(ImportPaletteError)?;
+        let colors = dominant_colors(&image, count);
+
+        if colors.is_empty() {
+            return Err(ImportPaletteError.into());
+        }
+
+        Ok(Self::new(name, colors))
+    }
+
+    /// Sample the palette as a gradient, `position` going from 0 (first color) to 1 (last color)
+    pub fn sample(&self, position: f32) -> [u8; 3] {
+        match self.colors.len() {
+            0 => [0; 3],
+            1 => self.colors[0],
+            len => {
+                let scaled = position.clamp(0.0, 1.0) * (len - 1) as f32;
+                let index = (scaled.floor() as usize).min(len - 2);
+                let fraction = scaled - index as f32;
+
+                lerp(self.colors[index], self.colors[index + 1], fraction)
+            }
+        }
+    }
+
+    /// Sample the palette as a gradient going back to the first color after the last one, so moving `position` past 1
+    /// keeps cycling through the colors without jumping
+    pub fn sample_looped(&self, position: f32) -> [u8; 3] {
+        match self.colors.len() {
+            0 => [0; 3],
+            1 => self.colors[0],
+            len => {
+                let scaled = position.rem_euclid(1.0) * len as f32;
+                let index = (scaled.floor() as usize).min(len - 1);
+                let fraction = scaled - index as f32;
+
+                lerp(self.colors[index], self.colors[(index + 1) % len], fraction)
+            }
+        }
+    }
+
+    /// Spread the palette over the four keyboard zones
+    pub fn to_zone_colors(&self) -> [[u8; 3]; 4] {
+        [0, 1, 2, 3].map(|i| self.sample(i as f32 / 3.0))
+    }
+}
+
+fn lerp(from: [u8; 3], to: [u8; 3], fraction: f32) -> [u8; 3] {
+    [0, 1, 2].map(|c| (f32::from(to[c]) - f32::from(from[c])).mul_add(fraction, f32::from(from[c])).round() as u8)
+}
+
+/// Find the dominant colors of an image through k-means clustering, sorted from most to least common
+#[cfg(feature = "gui")]
+pub fn dominant_colors(image: &DynamicImage, count: usize) -> Vec<[u8; 3]> {
+    // No need to go through every pixel of a large image to get a decent result
+    let pixels: Vec<[f32; 3]> = image.thumbnail(64, 64).to_rgb8().pixels().map(|pixel| pixel.0.map(f32::from)).collect();
+
+    if pixels.is_empty() || count == 0 {
+        return Vec::new();
+    }
+
+    let count = count.min(pixels.len());
+
+    // Spread the initial centroids over the image so the result is deterministic
+    let mut centroids: Vec<[f32; 3]> = (0..count).map(|i| pixels[i * pixels.len() / count]).collect();
+    let mut sizes = vec![0_usize; count];
+
+    for _ in 0..KMEANS_ITERATIONS {
+        let mut sums = vec![[0.0_f32; 3]; count];
+        sizes.fill(0);
+
+        for pixel in &pixels {
+            let nearest = nearest_centroid(&centroids, pixel);
+
+            for c in 0..3 {
+                sums[nearest][c] += pixel[c];
+            }
+            sizes[nearest] += 1;
+        }
+
+        for ((centroid, sum), size) in centroids.iter_mut().zip(&sums).zip(&sizes) {
+            if *size > 0 {
+                *centroid = sum.map(|channel| channel / *size as f32);
+            }
+        }
+    }
+
+    let mut clusters: Vec<([f32; 3], usize)> = centroids.into_iter().zip(sizes).filter(|(_, size)| *size > 0).collect();
+    clusters.sort_by(|a, b| b.1.cmp(&a.1));
+
+    clusters.into_iter().map(|(centroid, _)| centroid.map(|channel| channel.round() as u8)).collect()
+}
+
+#[cfg(feature = "gui")]
+fn nearest_centroid(centroids: &[[f32; 3]], pixel: &[f32; 3]) -> usize {
+    let distance = |centroid: &[f32; 3]| (0..3).map(|c| (centroid[c] - pixel[c]).powi(2)).sum::<f32>();
+
+    centroids
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| distance(a).total_cmp(&distance(b)))
+        .map_or(0, |(i, _)| i)
+}
@@ -0,0 +1,39 @@
+//! Checks the palette import format, and that none of the palettes shipped with the app get skipped for not following it.
+
+use super::palette::Palette;
+
+#[test]
+fn every_built_in_palette_parses() {
+    let entries = include_str!("../../res/palettes.txt")
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("//"))
+        .count();
+
+    let palettes = Palette::built_in();
+
+    assert_eq!(palettes.len(), entries, "Some built-in palettes were skipped, see the warnings logged");
+    assert!(palettes.iter().all(|palette| !palette.name.is_empty() && !palette.colors.is_empty()));
+}
+
+#[test]
+fn import_takes_the_name_before_the_colors() {
+    let palette = Palette::from_import("Fallback".to_string(), "Sunset = #264653, #2a9d8f").unwrap();
+
+    assert_eq!(palette.name, "Sunset");
+    assert_eq!(palette.colors, vec![[0x26, 0x46, 0x53], [0x2a, 0x9d, 0x8f]]);
+}
+
+#[test]
+fn import_without_a_name_uses_the_fallback() {
+    let palette = Palette::from_import("Fallback".to_string(), "https://coolors.co/264653-2a9d8f-e9c46a").unwrap();
+
+    assert_eq!(palette.name, "Fallback");
+    assert_eq!(palette.colors.len(), 3);
+}
+
+#[test]
+fn import_without_colors_fails() {
+    assert!(Palette::from_import(String::new(), "Empty = no colors here").is_err());
+    assert!(Palette::from_import(String::new(), "").is_err());
+}
@@ -0,0 +1,70 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::{Receiver, Sender};
+use legion_kb_rgb_hw::{BaseEffects, LightingState};
+
+/// Over how long the frame rate is measured
+const FPS_WINDOW: Duration = Duration::from_secs(1);
+
+/// Keeps the last state sent to the keyboard, so what's being played can be shown elsewhere
+#[derive(Clone, Default)]
+pub struct OutputPreview {
+    latest: Arc<Mutex<Option<LightingState>>>,
+    /// When each state of the last [`FPS_WINDOW`] was sent
+    sent_at: Arc<Mutex<VecDeque<Instant>>>,
+    subscribers: Arc<Mutex<Vec<Sender<LightingState>>>>,
+}
+
+impl OutputPreview {
+    /// Get a callback for the keyboard to report every state it goes through
+    pub fn observer(&self) -> impl FnMut(&LightingState) + Send + 'static {
+        let latest = self.latest.clone();
+        let sent_at = self.sent_at.clone();
+        let subscribers = self.subscribers.clone();
+
+        move |state| {
+            *latest.lock().unwrap() = Some(state.clone());
+            // Subscribers that went away are dropped along the way
+            subscribers.lock().unwrap().retain(|subscriber| subscriber.send(state.clone()).is_ok());
+
+            let now = Instant::now();
+            let mut sent_at = sent_at.lock().unwrap();
+            sent_at.push_back(now);
+            while sent_at.front().is_some_and(|time| now.duration_since(*time) > FPS_WINDOW) {
+                sent_at.pop_front();
+            }
+        }
+    }
+
+    /// How many states were sent over the last second
+    pub fn fps(&self) -> usize {
+        let sent_at = self.sent_at.lock().unwrap();
+
+        sent_at.iter().filter(|time| time.elapsed() <= FPS_WINDOW).count()
+    }
+
+    /// Get every state sent from now on
+    pub fn subscribe(&self) -> Receiver<LightingState> {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        self.subscribers.lock().unwrap().push(tx);
+        rx
+    }
+
+    /// The state last sent, if any
+    pub fn latest(&self) -> Option<LightingState> {
+        self.latest.lock().unwrap().clone()
+    }
+
+    /// The colors of each zone as last sent, unless the hardware is animating them by itself
+    pub fn zone_colors(&self) -> Option<[[u8; 3]; 4]> {
+        let latest = self.latest.lock().unwrap();
+        let state = latest.as_ref().filter(|state| matches!(state.effect_type(), BaseEffects::Static))?;
+        let rgb = state.rgb_values();
+
+        Some([0, 1, 2, 3].map(|zone| [rgb[zone * 3], rgb[zone * 3 + 1], rgb[zone * 3 + 2]]))
+    }
+}
@@ -1,8 +1,18 @@
 use std::{convert::TryInto, path::Path};
 
+use legion_kb_rgb_hw::BaseEffects;
+#[cfg(feature = "gui")]
+use legion_kb_rgb_hw::LightingState;
+
+use super::{
+    gradient::Gradient,
+    layers::{self, Overlay},
+    modulation::Modulation,
+    palette::Palette,
+};
 use crate::{
-    enums::{Brightness, Direction, Effects},
-    util::StorageTrait,
+    enums::{Brightness, Direction, Effects, DEFAULT_FIRE_HUE},
+    util::{self, StorageTrait},
 };
 
 use error_stack::{Result, ResultExt};
@@ -24,7 +34,7 @@ impl Default for KeyboardZone {
     }
 }
 
-type Zones = [KeyboardZone; 4];
+pub type Zones = [KeyboardZone; 4];
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Profile {
@@ -34,6 +44,42 @@ pub struct Profile {
     pub direction: Direction,
     pub speed: u8,
     pub brightness: Brightness,
+    /// A 0-100 knob that scales the parameters given by [`Effects::intensity_mapping`]
+    #[serde(default = "default_intensity")]
+    pub intensity: u8,
+    /// Folder the profile is shown under in the profile list
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Seconds to wait before applying the profile at boot, instead of the delay set in the preferences
+    #[serde(default)]
+    pub startup_delay: Option<u32>,
+    /// Minutes without keyboard or mouse input after which to turn the lights off
+    #[serde(default)]
+    pub off_after: Option<u32>,
+    /// Another effect played on top of this one
+    #[serde(default)]
+    pub overlay: Option<Overlay>,
+    /// Oscillators driving the speed, brightness or hue
+    #[serde(default)]
+    pub modulations: Vec<Modulation>,
+    /// Leave the low battery and charging pulses out while this profile plays
+    #[serde(default)]
+    pub hide_battery_overlay: bool,
+    /// Shown in the favorites bar and at the top of the tray menu
+    #[serde(default)]
+    pub pinned: bool,
+    /// The gradient the zone colors were last sampled from, to pick up where it was left
+    #[serde(default)]
+    pub gradient: Option<Gradient>,
+    /// The palette the zone colors were last taken from, which the cycling effects go through in full
+    #[serde(default)]
+    pub palette: Option<Palette>,
+}
+
+fn default_intensity() -> u8 {
+    100
 }
 
 impl Default for Profile {
@@ -45,10 +91,30 @@ impl Default for Profile {
             direction: Direction::default(),
             speed: 1,
             brightness: Brightness::default(),
+            intensity: default_intensity(),
+            group: None,
+            tags: Vec::new(),
+            startup_delay: None,
+            off_after: None,
+            overlay: None,
+            modulations: Vec::new(),
+            hide_battery_overlay: false,
+            pinned: false,
+            gradient: None,
+            palette: None,
         }
     }
 }
 
+/// The options a profile had for a given effect, to restore them when switching back to it
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EffectOptions {
+    pub effect: Effects,
+    pub rgb_zones: Zones,
+    pub direction: Direction,
+    pub speed: u8,
+}
+
 #[derive(Debug, Error)]
 #[error("Could not load profile")]
 pub struct LoadProfileError;
@@ -69,6 +135,151 @@ impl Profile {
         self.save(path).change_context(SaveProfileError)
     }
 
+    /// The palette the zones were filled with, as long as they haven't been changed by hand since
+    pub fn active_palette(&self) -> Option<&Palette> {
+        self.palette
+            .as_ref()
+            .filter(|palette| palette.colors.len() > 1 && palette.to_zone_colors() == self.rgb_zones.map(|zone| zone.rgb))
+    }
+
+    /// Whether the keyboard keeps playing the profile by itself once it's sent, without the program running
+    pub fn plays_on_keyboard(&self) -> bool {
+        self.effect.is_built_in() && !layers::needs_compositing(self) && self.off_after.is_none()
+    }
+
+    /// An unnamed profile showing what the keyboard was read back to be showing, see
+    /// [`legion_kb_rgb_hw::Keyboard::read_back_state`]
+    #[cfg(feature = "gui")]
+    pub fn from_hardware(state: &LightingState) -> Self {
+        let (effect, direction) = match state.effect_type() {
+            BaseEffects::Static => (Effects::Static, Direction::default()),
+            BaseEffects::Breath => (Effects::Breath, Direction::default()),
+            BaseEffects::Smooth => (Effects::Smooth, Direction::default()),
+            BaseEffects::LeftWave => (Effects::Wave, Direction::Left),
+            BaseEffects::RightWave => (Effects::Wave, Direction::Right),
+        };
+
+        let mut rgb_zones = Zones::default();
+        for (zone, rgb) in rgb_zones.iter_mut().zip(state.rgb_values().chunks_exact(3)) {
+            zone.rgb = rgb.try_into().unwrap();
+        }
+
+        Self {
+            rgb_zones,
+            effect,
+            direction,
+            speed: state.speed(),
+            brightness: if state.brightness() > 1 { Brightness::High } else { Brightness::Low },
+            ..Self::default()
+        }
+    }
+
+    /// Get a copy of the profile with the intensity knob baked into the underlying parameters
+    pub fn with_intensity_applied(&self) -> Self {
+        let mut profile = self.clone();
+        let mapping = self.effect.intensity_mapping();
+        let factor = f32::from(self.intensity.min(100)) / 100.0;
+
+        if mapping.brightness {
+            if factor <= 0.5 {
+                profile.brightness = Brightness::Low;
+            }
+
+            if self.effect.takes_color_array() {
+                for zone in &mut profile.rgb_zones {
+                    zone.rgb = zone.rgb.map(|channel| (f32::from(channel) * factor).round() as u8);
+                }
+            }
+        }
+
+        if mapping.speed {
+            profile.speed = ((f32::from(self.speed) * factor).round() as u8).max(1);
+        }
+
+        if mapping.saturation {
+            for zone in &mut profile.rgb_zones {
+                let [r, g, b] = zone.rgb.map(f32::from);
+                let gray = 0.299f32.mul_add(r, 0.587f32.mul_add(g, 0.114 * b));
+                zone.rgb = [r, g, b].map(|channel| (factor.mul_add(channel - gray, gray)).round() as u8);
+            }
+
+            if let Effects::AmbientLight { saturation_boost, .. } = &mut profile.effect {
+                *saturation_boost *= factor;
+            }
+        }
+
+        profile
+    }
+
+    /// Replace animated effects with still or slower equivalents, for people sensitive to motion
+    pub fn with_reduced_motion(&self) -> Self {
+        let mut profile = self.clone();
+
+        let colors = match self.effect {
+            Effects::Static
+            | Effects::AmbientLight { .. }
+            | Effects::Temperature
+            | Effects::Daylight { .. }
+            | Effects::Weather { .. }
+            | Effects::Clock { .. }
+            | Effects::Network { .. }
+            | Effects::Heatmap
+            | Effects::TypingSpeed { .. } => None,
+            // Steady versions of whatever colors were picked
+            Effects::Breath | Effects::Lightning | Effects::Swipe | Effects::Fade | Effects::Ripple | Effects::Automaton { .. } => Some(self.rgb_zones.map(|zone| zone.rgb)),
+            // Effects that go through the whole spectrum become a gradient
+            Effects::SmoothWave if self.active_palette().is_some() => Some(self.rgb_zones.map(|zone| zone.rgb)),
+            Effects::Wave | Effects::Smooth | Effects::SmoothWave | Effects::Disco => {
+                let mut gradient = [0.0, 90.0, 180.0, 270.0].map(|hue| util::hsv_to_rgb(hue, 1.0, 1.0));
+                if self.direction == Direction::Right {
+                    gradient.reverse();
+                }
+                Some(gradient)
+            }
+            Effects::Christmas => Some([[255, 0, 0], [0, 255, 0], [255, 0, 0], [0, 255, 0]]),
+            Effects::Fire { intensity, hue } => {
+                let hue = if intensity == 0.0 { DEFAULT_FIRE_HUE } else { hue };
+                Some([0.0, 8.0, 16.0, 24.0].map(|offset| util::hsv_to_rgb(hue + offset, 1.0, 1.0)))
+            }
+        };
+
+        if let Some(colors) = colors {
+            profile.effect = Effects::Static;
+            for (zone, rgb) in profile.rgb_zones.iter_mut().zip(colors) {
+                zone.rgb = rgb;
+            }
+        }
+
+        profile.speed = 1;
+        profile.overlay = None;
+        profile.modulations.clear();
+        profile
+    }
+
+    /// Check whether the name, group or any tag contains the (lowercase) query
+    pub fn matches_search(&self, query: &str) -> bool {
+        let contains = |value: &str| value.to_lowercase().contains(query);
+
+        query.is_empty() || self.name.as_deref().is_some_and(contains) || self.group.as_deref().is_some_and(contains) || self.tags.iter().any(|tag| contains(tag))
+    }
+
+    pub fn effect_options(&self) -> EffectOptions {
+        EffectOptions {
+            effect: self.effect,
+            rgb_zones: self.rgb_zones,
+            direction: self.direction,
+            speed: self.speed,
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn apply_effect_options(&mut self, options: &EffectOptions) {
+        self.effect = options.effect;
+        self.rgb_zones = options.rgb_zones;
+        self.direction = options.direction;
+        self.speed = options.speed;
+    }
+
     pub fn rgb_array(&self) -> [u8; 12] {
         self.rgb_zones.map(|zone| if zone.enabled { zone.rgb } else { [0; 3] }).concat().try_into().unwrap()
     }
@@ -0,0 +1,177 @@
+use std::{
+    fs,
+    path::Path,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use error_stack::{Result, ResultExt};
+use legion_kb_rgb_hw::{BaseEffects, LightingState};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{
+    clock::{Clock, ManualClock},
+    custom_effect::{CustomEffect, Keyframe, Track},
+    profile::Profile,
+    EffectManager,
+};
+
+/// How long an effect can go without sending anything before it's considered done, in real time
+const IDLE: Duration = Duration::from_millis(500);
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A single state the keyboard went through
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Frame {
+    /// Milliseconds since the recording started
+    pub time: u64,
+    /// The mode the keyboard was in, animated modes are handled by the hardware itself
+    pub mode: String,
+    pub speed: u8,
+    pub brightness: u8,
+    pub rgb_array: [u8; 12],
+}
+
+impl Frame {
+    fn from_state(time: u64, state: &LightingState) -> Self {
+        let mode = match state.effect_type() {
+            BaseEffects::Static => "Static",
+            BaseEffects::Breath => "Breath",
+            BaseEffects::Smooth => "Smooth",
+            BaseEffects::LeftWave => "LeftWave",
+            BaseEffects::RightWave => "RightWave",
+        };
+
+        Self {
+            time,
+            mode: mode.to_string(),
+            speed: state.speed(),
+            brightness: state.brightness(),
+            rgb_array: state.rgb_values(),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("Could not save the recorded frames")]
+pub struct SaveFramesError;
+
+/// Collects the frames sent to a keyboard
+#[derive(Clone, Default)]
+pub struct FrameRecorder {
+    frames: Arc<Mutex<Vec<Frame>>>,
+}
+
+impl FrameRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get a callback that records every state it gets called with, timed from the moment it was created
+    pub fn observer(&self) -> impl FnMut(&LightingState) + Send + 'static {
+        self.observer_timed(Instant::now)
+    }
+
+    /// Same as [`Self::observer`], taking the time from `now`
+    pub fn observer_timed(&self, now: impl Fn() -> Instant + Send + 'static) -> impl FnMut(&LightingState) + Send + 'static {
+        let frames = self.frames.clone();
+        let start = now();
+
+        move |state| {
+            let time = now().duration_since(start).as_millis() as u64;
+            frames.lock().unwrap().push(Frame::from_state(time, state));
+        }
+    }
+
+    pub fn frames(&self) -> Vec<Frame> {
+        self.frames.lock().unwrap().clone()
+    }
+
+    /// Turn what was recorded into a custom effect that plays it back, holding the last frame until `length` ms
+    ///
+    /// Only the colors are kept, with the highest brightness seen, and frames of the animations handled by the hardware
+    /// itself all look the same and are merged together.
+    pub fn to_custom_effect(&self, name: Option<String>, length: u64, should_loop: bool) -> CustomEffect {
+        let frames = self.frames();
+
+        let mut tracks = vec![Track::default(); 4];
+        for (i, frame) in frames.iter().enumerate() {
+            let until = frames.get(i + 1).map_or(length, |next| next.time).max(frame.time);
+
+            for (zone, track) in tracks.iter_mut().enumerate() {
+                let rgb = [frame.rgb_array[zone * 3], frame.rgb_array[zone * 3 + 1], frame.rgb_array[zone * 3 + 2]];
+                match track.keyframes.last_mut() {
+                    Some(last) if last.rgb == rgb => last.hold += until - frame.time,
+                    _ => track.keyframes.push(Keyframe {
+                        rgb,
+                        fade: 0,
+                        hold: until - frame.time,
+                    }),
+                }
+            }
+        }
+
+        let mut effect = CustomEffect {
+            name,
+            brightness: frames.iter().map(|frame| frame.brightness).max().unwrap_or(1),
+            should_loop,
+            tracks,
+            ..CustomEffect::default()
+        };
+        effect.thumbnail = effect.render_thumbnail();
+        effect
+    }
+
+    /// Whether any of the frames relied on an animation the hardware plays by itself
+    pub fn has_hardware_animation(&self) -> bool {
+        self.frames.lock().unwrap().iter().any(|frame| !matches!(frame.mode.as_str(), "Static" | "Breath"))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SaveFramesError> {
+        let json = serde_json::to_string(&self.frames()).change_context(SaveFramesError)?;
+        fs::write(path, json).change_context(SaveFramesError)?;
+
+        Ok(())
+    }
+}
+
+/// Play `profile` on a virtual keyboard with the random effects seeded by `seed`, until it sent `frames` frames or stopped
+/// sending new ones
+///
+/// Time only passes as the effect sleeps through it, so this takes about as long as the effect needs to compute its
+/// frames, plus a bit of real time to tell it went idle.
+pub fn record(profile: Profile, seed: u64, frames: usize) -> Vec<Frame> {
+    let clock = Arc::new(ManualClock::default());
+    let recorder = FrameRecorder::new();
+    // Nothing holds the effects back, so stop recording once there's enough instead of piling frames up
+    let observer = {
+        let clock = clock.clone();
+        let mut record = recorder.observer_timed(move || clock.now());
+        let mut recorded = 0;
+        move |state: &LightingState| {
+            if recorded < frames {
+                record(state);
+                recorded += 1;
+            }
+        }
+    };
+    let mut manager = EffectManager::new_replayable(observer, seed, clock);
+    manager.set_profile(profile);
+
+    let mut count = 0;
+    let mut last_change = Instant::now();
+    while count < frames && last_change.elapsed() < IDLE {
+        thread::sleep(POLL_INTERVAL);
+
+        let new_count = recorder.frames().len();
+        if new_count != count {
+            count = new_count;
+            last_change = Instant::now();
+        }
+    }
+    manager.stop();
+
+    recorder.frames()
+}
@@ -0,0 +1,427 @@
+//! Every supported keyboard that was found, each driven by an [`EffectManager`] of its own so they can play
+//! different profiles at the same time.
+
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    str::FromStr,
+    sync::OnceLock,
+    thread,
+    time::{Duration, Instant},
+};
+
+use error_stack::{Result, ResultExt};
+use legion_kb_rgb_hw::{lights::Section, models, Calibration, KeyboardInfo, LightingState};
+use single_instance::SingleInstance;
+use tracing::{debug, error, info, warn};
+
+use super::{
+    clock::SpeedMultiplier, custom_effect::CustomEffect, flash::FlashPattern, frame_rate::FrameRate, health::Health, preview::OutputPreview, profile::Profile, recorder::FrameRecorder, EffectManager,
+    ManagerCreationError, OperationMode, RETRY_INTERVAL,
+};
+use crate::{exit::ExitSettings, overlays::Overlay, startup::StartupSettings, weather::WeatherSettings};
+
+/// The device picked from the command line, if any
+static SELECTOR: OnceLock<DeviceSelector> = OnceLock::new();
+
+/// Set when a simulated keyboard replaces the real ones
+static SIMULATION: OnceLock<Simulation> = OnceLock::new();
+
+pub struct DeviceRegistry {
+    devices: Vec<Device>,
+    /// Which device commands go to, `None` for all of them
+    selected: Option<usize>,
+    // Can't drop this else it stops "reserving" whatever underlying implementation identifier it uses
+    // A simulation doesn't take the keyboard from anyone, so it can run next to a real instance
+    #[allow(dead_code)]
+    single_instance: Option<SingleInstance>,
+}
+
+struct Simulation {
+    recorder: FrameRecorder,
+    /// Where to save the recorded frames once done
+    out: Option<PathBuf>,
+}
+
+struct Device {
+    info: KeyboardInfo,
+    manager: EffectManager,
+}
+
+/// Picks one of the devices from the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceSelector {
+    /// Position in the list of devices, starting at 1
+    Index(usize),
+    ProductId(u16),
+}
+
+impl FromStr for DeviceSelector {
+    type Err = String;
+
+    /// Short numbers are positions, anything else is taken as a product ID in hex (such as `c995`)
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.parse::<usize>() {
+            Ok(index) if s.len() < 4 => Ok(Self::Index(index)),
+            _ => u16::from_str_radix(s.trim_start_matches("0x"), 16)
+                .map(Self::ProductId)
+                .map_err(|_| format!("\"{s}\" is neither a device number nor a product ID, see \"list devices\"")),
+        }
+    }
+}
+
+impl DeviceSelector {
+    fn matches(self, index: usize, info: &KeyboardInfo) -> bool {
+        match self {
+            Self::Index(position) => position == index + 1,
+            Self::ProductId(product_id) => product_id == info.product_id,
+        }
+    }
+}
+
+impl DeviceRegistry {
+    pub fn new(operation_mode: OperationMode) -> Result<Self, ManagerCreationError> {
+        Self::new_at_startup(operation_mode, StartupSettings::immediate(), |_| {})
+    }
+
+    /// Only open the given device from now on
+    pub fn set_selector(selector: DeviceSelector) {
+        let _ = SELECTOR.set(selector);
+    }
+
+    /// Whether a device was picked from the command line
+    #[cfg(feature = "gui")]
+    pub fn has_selector() -> bool {
+        SELECTOR.get().is_some()
+    }
+
+    /// Replace the keyboards with a simulated one from now on, saving the frames it goes through to `out` if given
+    pub fn simulate(out: Option<PathBuf>) {
+        let _ = SIMULATION.set(Simulation { recorder: FrameRecorder::new(), out });
+    }
+
+    fn new_simulated(operation_mode: OperationMode, simulation: &Simulation) -> Self {
+        let info = KeyboardInfo {
+            product_id: 0,
+            path: "simulated".to_string(),
+            model: &models::VIRTUAL,
+        };
+        let manager = EffectManager::simulate(&info, operation_mode, &simulation.recorder);
+
+        Self {
+            devices: vec![Device { info, manager }],
+            selected: Some(0),
+            single_instance: None,
+        }
+    }
+
+    /// Write down what the simulated keyboard went through, if asked to
+    fn save_simulated_frames() {
+        let Some(Simulation { recorder, out: Some(out) }) = SIMULATION.get() else {
+            return;
+        };
+
+        match recorder.save(out) {
+            Ok(()) => info!("Saved the simulated frames to {}", out.display()),
+            Err(err) => error!("Could not save the simulated frames: {err:?}"),
+        }
+    }
+
+    /// Whether another instance already controls the keyboards, which can be told right away unlike finding them
+    #[cfg(feature = "gui")]
+    pub fn other_instance_running() -> bool {
+        SIMULATION.get().is_none() && SingleInstance::new(env!("CARGO_PKG_NAME")).is_ok_and(|instance| !instance.is_single())
+    }
+
+    /// Open the keyboards, waiting for them as configured in case they aren't ready yet
+    ///
+    /// Only the one picked through [`Self::set_selector`] is opened if any. Otherwise the GUI controls the first one
+    /// until another is selected, while the CLI controls all of them at once. `on_retry` gets called with the attempt
+    /// number every time no keyboard could be found.
+    pub fn new_at_startup(operation_mode: OperationMode, startup: StartupSettings, mut on_retry: impl FnMut(u32)) -> Result<Self, ManagerCreationError> {
+        if let Some(simulation) = SIMULATION.get() {
+            return Ok(Self::new_simulated(operation_mode, simulation));
+        }
+
+        let selector = SELECTOR.get().copied();
+
+        // Use the crate's name as the identifier, should be unique enough
+        let single_instance = SingleInstance::new(env!("CARGO_PKG_NAME")).unwrap();
+
+        if !single_instance.is_single() {
+            return Err(ManagerCreationError::InstanceAlreadyRunning.into());
+        }
+
+        thread::sleep(Duration::from_secs(startup.delay.into()));
+
+        let deadline = Instant::now() + Duration::from_secs(startup.retry_timeout.into());
+        let mut attempt = 0;
+
+        let found = loop {
+            let found = legion_kb_rgb_hw::find_keyboards().map(|keyboards| {
+                keyboards
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(index, info)| selector.map_or(true, |selector| selector.matches(*index, info)))
+                    .map(|(_, info)| info)
+                    .collect::<Vec<_>>()
+            });
+
+            match found {
+                Ok(found) if !found.is_empty() => break found,
+                _ if startup.retry && Instant::now() < deadline => {
+                    attempt += 1;
+                    debug!(attempt, "Keyboard not found yet");
+                    on_retry(attempt);
+                    thread::sleep(RETRY_INTERVAL);
+                }
+                result => {
+                    let err = result.err().unwrap_or(legion_kb_rgb_hw::error::Error::DeviceNotFound);
+                    error!("Could not find a keyboard: {err}");
+                    return Err(err)
+                        .change_context(ManagerCreationError::AcquireKeyboard)
+                        .attach_printable("Ensure that you have a supported model and that the application has access to it.")
+                        .attach_printable("On Linux, see https://github.com/4JX/L5P-Keyboard-RGB#usage");
+                }
+            }
+        };
+
+        let mut devices = Vec::new();
+        for info in found {
+            // Only the first keyboard publishes what it's playing, as there's a single status
+            match EffectManager::open(&info, operation_mode, devices.is_empty(), startup.leave_untouched) {
+                Ok(manager) => devices.push(Device { info, manager }),
+                // The others are a bonus, don't give up on all of them because of one
+                Err(err) if !devices.is_empty() => warn!("Could not open the {} keyboard: {err:?}", info.model.name),
+                Err(err) => return Err(err),
+            }
+        }
+
+        let selected = match (operation_mode, selector) {
+            (OperationMode::Cli, None) => None,
+            _ => Some(0),
+        };
+
+        Ok(Self {
+            devices,
+            selected,
+            single_instance: Some(single_instance),
+        })
+    }
+
+    pub fn devices(&self) -> impl Iterator<Item = &KeyboardInfo> {
+        self.devices.iter().map(|device| &device.info)
+    }
+
+    pub fn selected(&self) -> Option<&KeyboardInfo> {
+        self.selected.map(|index| &self.devices[index].info)
+    }
+
+    /// Send what comes next to the device with the given [`KeyboardInfo::id`], returning whether it was found
+    #[cfg(feature = "gui")]
+    pub fn select(&mut self, id: &str) -> bool {
+        match self.devices.iter().position(|device| device.info.id() == id) {
+            Some(index) => {
+                self.selected = Some(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn targets(&mut self) -> impl Iterator<Item = &mut EffectManager> {
+        let selected = self.selected;
+        self.devices
+            .iter_mut()
+            .enumerate()
+            .filter(move |(index, _)| selected.map_or(true, |selected| selected == *index))
+            .map(|(_, device)| &mut device.manager)
+    }
+
+    pub fn set_profile(&mut self, profile: Profile) {
+        for manager in self.targets() {
+            manager.set_profile(profile.clone());
+        }
+    }
+
+    /// Play a profile that only takes over for a moment, see [`EffectManager::show_profile`]
+    #[cfg(feature = "gui")]
+    pub fn show_profile(&mut self, profile: Profile) {
+        for manager in self.targets() {
+            manager.show_profile(profile.clone());
+        }
+    }
+
+    /// Go to another profile, fading to its colors on the way
+    pub fn switch_profile(&mut self, profile: Profile) {
+        for manager in self.targets() {
+            manager.switch_profile(profile.clone());
+        }
+    }
+
+    /// Play a profile on a device other than the selected one
+    #[cfg(feature = "gui")]
+    pub fn set_profile_of(&mut self, id: &str, profile: Profile) {
+        if let Some(device) = self.devices.iter_mut().find(|device| device.info.id() == id) {
+            device.manager.set_profile(profile);
+        }
+    }
+
+    pub fn custom_effect(&mut self, effect: CustomEffect) {
+        for manager in self.targets() {
+            manager.custom_effect(effect.clone());
+        }
+    }
+
+    #[cfg(feature = "dev-effects")]
+    pub fn dev_effect(&mut self, path: std::path::PathBuf, profile: Profile) {
+        for manager in self.targets() {
+            manager.dev_effect(path.clone(), profile.clone());
+        }
+    }
+
+    pub fn set_reduced_motion(&self, enabled: bool) {
+        for device in &self.devices {
+            device.manager.set_reduced_motion(enabled);
+        }
+    }
+
+    pub fn set_frame_rate(&self, frame_rate: FrameRate) {
+        for device in &self.devices {
+            device.manager.set_frame_rate(frame_rate);
+        }
+    }
+
+    /// Correct the colors of each device with its calibration, by id, leaving those without one as they are
+    pub fn set_calibrations(&self, calibrations: &BTreeMap<String, Calibration>) {
+        for device in &self.devices {
+            device.manager.set_calibration(calibrations.get(&device.info.id()).copied().unwrap_or_default());
+        }
+    }
+
+    pub fn set_brightness_scale(&self, percent: u8) {
+        for device in &self.devices {
+            device.manager.set_brightness_scale(percent);
+        }
+    }
+
+    /// Returns whether the effect has to be restarted for the overlay to show, see [`EffectManager::set_overlay`]
+    pub fn set_overlay(&self, overlay: Overlay) -> bool {
+        self.devices.iter().fold(false, |restart, device| device.manager.set_overlay(overlay) || restart)
+    }
+
+    pub fn set_synced_lights(&self, sections: &[Section]) {
+        for device in &self.devices {
+            device.manager.set_synced_lights(sections);
+        }
+    }
+
+    pub fn set_weather(&self, settings: &WeatherSettings) {
+        for device in &self.devices {
+            device.manager.set_weather(settings.clone());
+        }
+    }
+
+    pub fn set_countdown_deadline(&self, deadline: Option<Instant>) {
+        for device in &self.devices {
+            device.manager.set_countdown_deadline(deadline);
+        }
+    }
+
+    pub fn set_exit_settings(&self, settings: ExitSettings) {
+        for device in &self.devices {
+            device.manager.set_exit_settings(settings);
+        }
+    }
+
+    pub fn set_crossfade(&self, duration: Duration) {
+        for device in &self.devices {
+            device.manager.set_crossfade(duration);
+        }
+    }
+
+    pub fn set_speed_multiplier(&self, multiplier: SpeedMultiplier) {
+        for device in &self.devices {
+            device.manager.set_speed_multiplier(multiplier);
+        }
+    }
+
+    /// Flash every device, as notifications aren't about any one of them
+    pub fn flash(&self, pattern: FlashPattern) {
+        for device in &self.devices {
+            device.manager.flash(pattern);
+        }
+    }
+
+    /// Blink one zone of the selected device, or of every device when none is, to tell which part of the keyboard it is
+    pub fn flash_zone(&mut self, zone: usize, pattern: FlashPattern) {
+        for manager in self.targets() {
+            manager.flash_zone(zone, pattern);
+        }
+    }
+
+    /// Light the zones of the selected device, or of every device when none is, one after the other to tell which is which
+    pub fn identify_zones(&mut self) {
+        for manager in self.targets() {
+            manager.identify_zones();
+        }
+    }
+
+    /// Get what's currently being sent to the selected device
+    pub fn preview(&self) -> &OutputPreview {
+        self.devices[self.selected.unwrap_or(0)].manager.preview()
+    }
+
+    /// What the system calls the thread playing the effects of the selected device, see [`EffectManager::effect_thread_id`]
+    pub fn effect_thread_id(&self) -> Option<u32> {
+        self.devices[self.selected.unwrap_or(0)].manager.effect_thread_id()
+    }
+
+    /// What the selected device was showing when it was opened, if it could tell
+    pub fn hardware_state(&self) -> Option<&LightingState> {
+        self.devices[self.selected.unwrap_or(0)].manager.hardware_state()
+    }
+
+    /// The first problem found with any of the devices
+    pub fn health(&self) -> Health {
+        self.devices
+            .iter()
+            .map(|device| device.manager.health())
+            .find(|health| *health != Health::Normal)
+            .unwrap_or_default()
+    }
+
+    /// Get a way to make every effect thread exit from elsewhere
+    pub fn exit_handle(&self) -> impl Fn() + Send + 'static {
+        let handles: Vec<Box<dyn Fn() + Send>> = self.devices.iter().map(|device| Box::new(device.manager.exit_handle()) as Box<dyn Fn() + Send>).collect();
+
+        move || {
+            for handle in &handles {
+                handle();
+            }
+        }
+    }
+
+    /// Interrupt whatever is playing and shut down
+    pub fn stop(self) {
+        for device in self.devices {
+            device.manager.stop();
+        }
+        Self::save_simulated_frames();
+    }
+
+    pub fn shutdown(self) {
+        for device in self.devices {
+            device.manager.shutdown();
+        }
+        Self::save_simulated_frames();
+    }
+
+    /// Leave every device as set through [`Self::set_exit_settings`] and shut down, for when the program closes
+    pub fn exit(self) {
+        for device in self.devices {
+            device.manager.exit();
+        }
+        Self::save_simulated_frames();
+    }
+}
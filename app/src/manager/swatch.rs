@@ -0,0 +1,121 @@
+use std::{fmt::Write as _, fs, path::Path};
+
+use error_stack::{Result, ResultExt};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// A saved color that can be applied to any profile
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Swatch {
+    pub name: String,
+    pub rgb: [u8; 3],
+}
+
+#[derive(Debug, Error)]
+#[error("Could not import swatches")]
+pub struct ImportSwatchesError;
+
+#[derive(Debug, Error)]
+#[error("Could not export swatches")]
+pub struct ExportSwatchesError;
+
+impl Swatch {
+    pub fn new(name: String, rgb: [u8; 3]) -> Self {
+        Self { name, rgb }
+    }
+
+    /// A swatch named after its color
+    pub fn unnamed(rgb: [u8; 3]) -> Self {
+        let mut swatch = Self::new(String::new(), rgb);
+        swatch.name = swatch.hex();
+        swatch
+    }
+
+    pub fn hex(&self) -> String {
+        to_hex(self.rgb)
+    }
+}
+
+/// Format a color as `#rrggbb`
+pub fn to_hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// Parse a `#RRGGBB` color, the `#` being optional
+#[cfg(feature = "gui")]
+pub fn parse_hex(text: &str) -> Option<[u8; 3]> {
+    let hex = text.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok();
+    Some([channel(0)?, channel(1)?, channel(2)?])
+}
+
+/// Load swatches from a GIMP palette (`.gpl`), or from the JSON written by [`export`] otherwise
+pub fn import(path: &Path) -> Result<Vec<Swatch>, ImportSwatchesError> {
+    let contents = fs::read_to_string(path).change_context(ImportSwatchesError)?;
+
+    let swatches = if is_gpl(path) {
+        parse_gpl(&contents).attach_printable("Invalid GIMP palette")?
+    } else {
+        serde_json::from_str(&contents).change_context(ImportSwatchesError)?
+    };
+
+    Ok(swatches)
+}
+
+/// Save swatches as a GIMP palette if the path ends in `.gpl`, or as JSON otherwise
+pub fn export(swatches: &[Swatch], path: &Path) -> Result<(), ExportSwatchesError> {
+    let contents = if is_gpl(path) {
+        to_gpl(swatches)
+    } else {
+        serde_json::to_string_pretty(swatches).change_context(ExportSwatchesError)?
+    };
+
+    fs::write(path, contents).change_context(ExportSwatchesError)?;
+
+    Ok(())
+}
+
+fn is_gpl(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("gpl"))
+}
+
+/// Colors are lines of three numbers optionally followed by a name, everything before the first color is the header
+fn parse_gpl(contents: &str) -> Result<Vec<Swatch>, ImportSwatchesError> {
+    let mut lines = contents.lines();
+
+    if lines.next().map(str::trim) != Some("GIMP Palette") {
+        return Err(ImportSwatchesError.into());
+    }
+
+    let swatches: Vec<Swatch> = lines
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let mut channel = || parts.next()?.parse::<u8>().ok();
+            let rgb = [channel()?, channel()?, channel()?];
+
+            let name = parts.collect::<Vec<_>>().join(" ");
+            Some(if name.is_empty() { Swatch::unnamed(rgb) } else { Swatch::new(name, rgb) })
+        })
+        .collect();
+
+    if swatches.is_empty() {
+        return Err(ImportSwatchesError.into());
+    }
+
+    Ok(swatches)
+}
+
+fn to_gpl(swatches: &[Swatch]) -> String {
+    let mut gpl = String::from("GIMP Palette\nName: Legion Keyboard Control\nColumns: 0\n#\n");
+
+    for swatch in swatches {
+        let [r, g, b] = swatch.rgb;
+        let _ = writeln!(gpl, "{r:3} {g:3} {b:3}\t{}", swatch.name);
+    }
+
+    gpl
+}
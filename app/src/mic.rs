@@ -0,0 +1,161 @@
+//! Watches whether the default microphone is muted.
+//!
+//! On Linux the default source is asked through `pactl` whenever `pactl subscribe` reports a change to the sources or
+//! the server, which PipeWire answers too when `pipewire-pulse` is running. On Windows the default communications
+//! capture device is read through Core Audio.
+
+#[cfg(target_os = "linux")]
+use std::{
+    io::{BufRead, BufReader},
+    process::{Child, Command, Stdio},
+    sync::{Arc, Mutex},
+};
+use std::{thread, time::Duration};
+
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+
+/// How long to wait before subscribing again when the sound server went away
+#[cfg(target_os = "linux")]
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+/// Core Audio is only asked every so often, as it would take a COM callback to be told about changes
+#[cfg(not(target_os = "linux"))]
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Watches the microphone until dropped
+pub struct MicWatcher {
+    /// Dropped to tell the watching thread to stop
+    stop: Option<Sender<()>>,
+    /// The `pactl subscribe` being read, killed to stop waiting on it
+    #[cfg(target_os = "linux")]
+    subscription: Arc<Mutex<Option<Child>>>,
+}
+
+impl Drop for MicWatcher {
+    fn drop(&mut self) {
+        self.stop.take();
+
+        #[cfg(target_os = "linux")]
+        if let Some(mut child) = self.subscription.lock().unwrap().take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Spawn a background thread that calls `on_change` with whether the microphone is muted, whenever that changes
+///
+/// Nothing is reported while there's no microphone, or no way to tell.
+pub fn spawn_watcher<F: Fn(bool) + Send + 'static>(on_change: F) -> MicWatcher {
+    let (stop_tx, stop_rx) = crossbeam_channel::bounded(0);
+    let watcher = MicWatcher {
+        stop: Some(stop_tx),
+        #[cfg(target_os = "linux")]
+        subscription: Arc::new(Mutex::new(None)),
+    };
+
+    #[cfg(target_os = "linux")]
+    let subscription = watcher.subscription.clone();
+    thread::spawn(move || {
+        let mut last = None;
+        let report = move || {
+            let muted = is_muted();
+            if let Some(muted) = muted.filter(|muted| last != Some(*muted)) {
+                on_change(muted);
+            }
+            last = muted;
+        };
+
+        #[cfg(target_os = "linux")]
+        watch(&stop_rx, &subscription, report);
+        #[cfg(not(target_os = "linux"))]
+        watch(&stop_rx, report);
+    });
+
+    watcher
+}
+
+/// Check the microphone whenever the sound server says something about the sources, until stopped
+#[cfg(target_os = "linux")]
+fn watch(stop: &Receiver<()>, subscription: &Mutex<Option<Child>>, mut report: impl FnMut()) {
+    loop {
+        report();
+
+        if let Ok(mut child) = Command::new("pactl").arg("subscribe").stdout(Stdio::piped()).stderr(Stdio::null()).spawn() {
+            let stdout = child.stdout.take();
+            {
+                // Checked while holding the lock, so the watcher either finds the child to kill or it's never read
+                let mut subscription = subscription.lock().unwrap();
+                if stop.try_recv() == Err(crossbeam_channel::TryRecvError::Disconnected) {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return;
+                }
+                *subscription = Some(child);
+            }
+
+            // Lines like `Event 'change' on source #53`, a new default source being a change of the server
+            for line in stdout.into_iter().flat_map(|stdout| BufReader::new(stdout).lines().map_while(Result::ok)) {
+                if line.contains(" on source ") || line.contains(" on server ") {
+                    report();
+                }
+            }
+
+            if let Some(mut child) = subscription.lock().unwrap().take() {
+                let _ = child.wait();
+            }
+        }
+
+        if stop.recv_timeout(RETRY_INTERVAL) != Err(RecvTimeoutError::Timeout) {
+            return;
+        }
+    }
+}
+
+/// Check the microphone every [`POLL_INTERVAL`] until stopped
+#[cfg(not(target_os = "linux"))]
+fn watch(stop: &Receiver<()>, mut report: impl FnMut()) {
+    loop {
+        report();
+
+        if stop.recv_timeout(POLL_INTERVAL) != Err(RecvTimeoutError::Timeout) {
+            return;
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn is_muted() -> Option<bool> {
+    let output = Command::new("pactl").args(["get-source-mute", "@DEFAULT_SOURCE@"]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    // Answers with `Mute: yes` or `Mute: no`
+    let output = String::from_utf8_lossy(&output.stdout);
+    Some(output.trim().ends_with("yes"))
+}
+
+#[cfg(target_os = "windows")]
+fn is_muted() -> Option<bool> {
+    use windows::Win32::{
+        Media::Audio::{eCapture, eCommunications, Endpoints::IAudioEndpointVolume, IMMDeviceEnumerator, MMDeviceEnumerator},
+        System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_ALL, COINIT_MULTITHREADED},
+    };
+
+    // The device is looked up every time, as the default one can change while the app runs
+    let muted = unsafe {
+        let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+
+        let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL).ok()?;
+        let device = enumerator.GetDefaultAudioEndpoint(eCapture, eCommunications).ok()?;
+        let volume: IAudioEndpointVolume = device.Activate(CLSCTX_ALL, None).ok()?;
+        volume.GetMute().ok()?
+    };
+
+    Some(muted.as_bool())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn is_muted() -> Option<bool> {
+    None
+}
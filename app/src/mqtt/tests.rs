@@ -0,0 +1,114 @@
+//! Checks the packets sent to and read from the broker, mostly that a packet arriving in pieces is put back together.
+
+use std::{
+    collections::VecDeque,
+    io::{self, Read},
+};
+
+use super::{packet, publish_packet, publish_payload, split_packet, Broker, Command, PacketReader, PUBLISH};
+
+/// Hands out the given chunks one read at a time, timing out wherever a chunk is `None`
+struct Chunked(VecDeque<Option<Vec<u8>>>);
+
+impl Read for Chunked {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.0.pop_front() {
+            Some(Some(chunk)) => {
+                buf[..chunk.len()].copy_from_slice(&chunk);
+                Ok(chunk.len())
+            }
+            Some(None) => Err(io::ErrorKind::WouldBlock.into()),
+            None => Ok(0),
+        }
+    }
+}
+
+#[test]
+fn packets_split_back_into_what_was_sent() {
+    for len in [0, 1, 127, 128, 16_383, 16_384] {
+        let body = vec![7; len];
+        let bytes = packet(PUBLISH, &body);
+
+        assert_eq!(split_packet(&bytes).unwrap(), Some(((PUBLISH, body), bytes.len())), "body of {len} bytes");
+    }
+}
+
+#[test]
+fn incomplete_packets_wait_for_the_rest() {
+    let bytes = packet(PUBLISH, &[1; 200]);
+
+    for end in 0..bytes.len() {
+        assert_eq!(split_packet(&bytes[..end]).unwrap(), None, "cut at {end}");
+    }
+}
+
+#[test]
+fn overlong_lengths_are_rejected() {
+    assert!(split_packet(&[PUBLISH, 0xff, 0xff, 0xff, 0xff, 0x01]).is_err());
+}
+
+#[test]
+fn reader_keeps_partial_packets_across_timeouts() {
+    let first = publish_packet("legion-kb-rgb/set", b"Gaming", false);
+    let second = publish_packet("legion-kb-rgb/set", b"Work", false);
+    let mut stream = Chunked(VecDeque::from([
+        Some(first[..1].to_vec()),
+        None,
+        Some(first[1..5].to_vec()),
+        None,
+        Some([&first[5..], &second[..3]].concat()),
+        None,
+        Some(second[3..].to_vec()),
+    ]));
+    let mut reader = PacketReader::default();
+
+    let mut packets = Vec::new();
+    while packets.len() < 2 {
+        match reader.read(&mut stream) {
+            Ok(packet) => packets.push(packet),
+            Err(err) => assert_eq!(err.kind(), io::ErrorKind::WouldBlock),
+        }
+    }
+
+    let payloads: Vec<_> = packets.iter().map(|(kind, body)| publish_payload(*kind, body).unwrap()).collect();
+    assert_eq!(payloads, [b"Gaming".as_slice(), b"Work".as_slice()]);
+    assert_eq!(reader.read(&mut stream).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn payloads_skip_the_packet_id_above_qos_0() {
+    let mut body = vec![0, 3];
+    body.extend(b"a/b");
+    body.extend([0, 42]);
+    body.extend(b"Gaming");
+
+    assert_eq!(publish_payload(PUBLISH | 0b0010, &body), Some(b"Gaming".as_slice()));
+    assert_eq!(publish_payload(PUBLISH, &body[..5]), Some([].as_slice()));
+    assert_eq!(publish_payload(PUBLISH, &[0]), None);
+}
+
+#[test]
+fn broker_urls_parse() {
+    let broker = Broker::parse("mqtt://user:p@ss@broker.local:1884/home/legion/").unwrap();
+    assert_eq!(broker.host, "broker.local");
+    assert_eq!(broker.port, 1884);
+    assert_eq!(broker.credentials, Some(("user".to_string(), "p@ss".to_string())));
+    assert_eq!(broker.topic("set"), "home/legion/set");
+
+    let broker = Broker::parse("tcp://192.168.1.2").unwrap();
+    assert_eq!(broker.port, 1883);
+    assert_eq!(broker.credentials, None);
+    assert_eq!(broker.topic("set"), "legion-kb-rgb/set");
+
+    assert!(Broker::parse("http://broker.local").is_err());
+    assert!(Broker::parse("mqtt://broker.local:port").is_err());
+    assert!(Broker::parse("mqtt://:1883").is_err());
+}
+
+#[test]
+fn commands_take_bare_profile_names() {
+    assert!(matches!(Command::parse(b" Gaming \n"), Some(Command::Profile { profile }) if profile == "Gaming"));
+    assert!(matches!(Command::parse(br#"{"profile": "Work"}"#), Some(Command::Profile { profile }) if profile == "Work"));
+    assert!(Command::parse(b"{not json").is_none());
+    assert!(Command::parse(b"  ").is_none());
+}
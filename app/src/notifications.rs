@@ -0,0 +1,105 @@
+//! Listens for desktop notifications so the keyboard can flash when one comes in.
+//!
+//! On Linux this eavesdrops on `Notify` calls made to the session bus notification daemon, while on Windows
+//! the toast notification center is polled for new entries (the user has to grant access to it first).
+
+#[cfg(feature = "gui")]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "gui")]
+use tracing::warn;
+
+use crate::manager::flash::FlashPattern;
+
+#[cfg(feature = "gui")]
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub enabled: bool,
+    pub flash: FlashPattern,
+}
+
+/// Spawn a background thread that calls `on_notification` every time a desktop notification is shown
+///
+/// Only the first call has an effect.
+#[cfg(feature = "gui")]
+pub fn spawn_listener<F: Fn() + Send + 'static>(on_notification: F) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    std::thread::spawn(move || {
+        if let Err(err) = listen(&on_notification) {
+            warn!("Notification listener stopped: {err}");
+        }
+    });
+}
+
+#[cfg(all(feature = "gui", target_os = "linux"))]
+fn listen(on_notification: &dyn Fn()) -> zbus::Result<()> {
+    use zbus::blocking::{Connection, MessageIterator};
+
+    let connection = Connection::session()?;
+
+    let rules = ["type='method_call',interface='org.freedesktop.Notifications',member='Notify'"];
+    connection.call_method(
+        Some("org.freedesktop.DBus"),
+        "/org/freedesktop/DBus",
+        Some("org.freedesktop.DBus.Monitoring"),
+        "BecomeMonitor",
+        &(&rules[..], 0u32),
+    )?;
+
+    for message in MessageIterator::from(&connection) {
+        let message = message?;
+
+        if message.header().member().is_some_and(|member| member.as_str() == "Notify") {
+            on_notification();
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(all(feature = "gui", target_os = "windows"))]
+fn listen(on_notification: &dyn Fn()) -> windows::core::Result<()> {
+    use std::{collections::HashSet, time::Duration};
+    use windows::UI::Notifications::{
+        Management::{UserNotificationListener, UserNotificationListenerAccessStatus},
+        NotificationKinds,
+    };
+
+    use crate::ticks;
+
+    let listener = UserNotificationListener::Current()?;
+
+    if listener.RequestAccessAsync()?.get()? != UserNotificationListenerAccessStatus::Allowed {
+        return Ok(());
+    }
+
+    let current_ids = || -> windows::core::Result<HashSet<u32>> {
+        let notifications = listener.GetNotificationsAsync(NotificationKinds::Toast)?.get()?;
+        notifications.into_iter().map(|notification| notification.Id()).collect()
+    };
+
+    let mut seen = current_ids()?;
+
+    loop {
+        let ids = current_ids()?;
+
+        if ids.difference(&seen).next().is_some() {
+            on_notification();
+        }
+
+        seen = ids;
+        ticks::sleep_until_next(Duration::from_secs(1));
+    }
+}
+
+#[cfg(all(feature = "gui", not(any(target_os = "linux", target_os = "windows"))))]
+fn listen(_on_notification: &dyn Fn()) -> std::io::Result<()> {
+    Ok(())
+}
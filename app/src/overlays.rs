@@ -0,0 +1,193 @@
+//! Colors shown on single zones over whatever is playing, to tell the state of the system at a glance.
+//!
+//! The sources of each overlay are watched by the GUI, which combines what they report into the colors sent along with
+//! every profile.
+
+use std::{f32::consts::TAU, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "gui")]
+use crate::{battery::BatteryState, lock_keys::LockState, manager::profile::Profile};
+
+/// What gets shown over the effect, zones without a color being left to it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Overlay {
+    pub zones: [Option<ZoneOverlay>; 4],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZoneOverlay {
+    pub rgb: [u8; 3],
+    /// How long a pulse of the color takes, `None` to keep it still
+    pub pulse: Option<Duration>,
+}
+
+impl Overlay {
+    /// Whether some zone pulses, which needs the keyboard to be redrawn all the time
+    pub fn is_animated(&self) -> bool {
+        self.zones.iter().flatten().any(|zone| zone.pulse.is_some())
+    }
+
+    /// The colors of the zones that stay still, for the keyboard to show over anything sent to it
+    pub fn still(&self) -> [Option<[u8; 3]>; 4] {
+        self.zones.map(|zone| zone.filter(|zone| zone.pulse.is_none()).map(|zone| zone.rgb))
+    }
+
+    /// Draw the pulsing zones over `colors`, as they are `elapsed` after the effect started
+    pub fn draw_pulses(&self, mut colors: [u8; 12], elapsed: Duration) -> [u8; 12] {
+        for (i, zone) in self.zones.iter().enumerate() {
+            let Some(ZoneOverlay { rgb, pulse: Some(period) }) = zone else {
+                continue;
+            };
+
+            let phase = (elapsed.as_secs_f32() / period.as_secs_f32().max(0.1)).fract();
+            let level = (1.0 - (phase * TAU).cos()) / 2.0;
+            for (channel, value) in rgb.iter().enumerate() {
+                colors[i * 3 + channel] = (f32::from(*value) * level).round() as u8;
+            }
+        }
+
+        colors
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OverlaySettings {
+    pub mic: MicIndicator,
+    pub caps_lock: LockIndicator,
+    pub num_lock: LockIndicator,
+    pub battery: BatteryIndicator,
+}
+
+/// Tints a zone while on calls, depending on whether the microphone is muted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MicIndicator {
+    pub enabled: bool,
+    /// From 0 (left) to 3 (right)
+    pub zone: usize,
+    pub muted: [u8; 3],
+    pub live: [u8; 3],
+}
+
+impl Default for MicIndicator {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            zone: 0,
+            muted: [255, 0, 0],
+            live: [0, 255, 0],
+        }
+    }
+}
+
+/// Lights a zone up while a lock key is on, as the keyboard has no indicator that can be seen in the dark
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LockIndicator {
+    pub enabled: bool,
+    /// From 0 (left) to 3 (right)
+    pub zone: usize,
+    pub color: [u8; 3],
+}
+
+impl Default for LockIndicator {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            zone: 0,
+            color: [255, 255, 255],
+        }
+    }
+}
+
+/// Pulses a zone while the battery runs low, and breathes slowly while it charges
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BatteryIndicator {
+    pub enabled: bool,
+    /// From 0 (left) to 3 (right)
+    pub zone: usize,
+    /// Charge under which the battery counts as low, in percent
+    pub low_below: u8,
+    pub low_color: [u8; 3],
+    pub charging_color: [u8; 3],
+}
+
+impl Default for BatteryIndicator {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            zone: 3,
+            low_below: 20,
+            low_color: [255, 100, 0],
+            charging_color: [0, 255, 0],
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl BatteryIndicator {
+    const LOW_PULSE: Duration = Duration::from_secs(1);
+    const CHARGING_PULSE: Duration = Duration::from_secs(4);
+
+    fn zone_overlay(&self, state: BatteryState) -> Option<ZoneOverlay> {
+        if state.charging && state.percent < 100 {
+            Some(ZoneOverlay {
+                rgb: self.charging_color,
+                pulse: Some(Self::CHARGING_PULSE),
+            })
+        } else if !state.charging && state.percent < self.low_below {
+            Some(ZoneOverlay {
+                rgb: self.low_color,
+                pulse: Some(Self::LOW_PULSE),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// What the watchers last reported, `None` until they do
+#[cfg(feature = "gui")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SystemState {
+    pub mic_muted: Option<bool>,
+    pub lock_keys: LockState,
+    pub battery: Option<BatteryState>,
+}
+
+#[cfg(feature = "gui")]
+impl OverlaySettings {
+    /// Whether the lock keys need watching
+    pub fn any_lock_enabled(&self) -> bool {
+        self.caps_lock.enabled || self.num_lock.enabled
+    }
+
+    /// The colors to show over `profile` for the state of the system, the later overlays winning over the earlier ones
+    /// on the same zone
+    pub fn overlay(&self, state: &SystemState, profile: &Profile) -> Overlay {
+        let mut overlay = Overlay::default();
+        let mut set = |zone: usize, rgb: [u8; 3]| overlay.zones[zone.min(3)] = Some(ZoneOverlay { rgb, pulse: None });
+
+        if let (true, Some(muted)) = (self.mic.enabled, state.mic_muted) {
+            set(self.mic.zone, if muted { self.mic.muted } else { self.mic.live });
+        }
+
+        for (indicator, on) in [(self.caps_lock, state.lock_keys.caps_lock), (self.num_lock, state.lock_keys.num_lock)] {
+            if indicator.enabled && on {
+                set(indicator.zone, indicator.color);
+            }
+        }
+
+        if let (true, false, Some(battery)) = (self.battery.enabled, profile.hide_battery_overlay, state.battery) {
+            if let Some(zone) = self.battery.zone_overlay(battery) {
+                overlay.zones[self.battery.zone.min(3)] = Some(zone);
+            }
+        }
+
+        overlay
+    }
+}
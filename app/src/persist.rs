@@ -1,49 +1,400 @@
 use std::{
+    collections::BTreeMap,
     env,
+    ffi::OsString,
     fs::{self, File},
     io::Write,
-    path::PathBuf,
+    path::{Path, PathBuf},
+    sync::OnceLock,
 };
 
-use crate::manager::{custom_effect::CustomEffect, profile::Profile};
+use crate::{
+    api::ApiSettings,
+    app_rules::{AppRule, FullscreenSettings},
+    appearance::Appearance,
+    backlight::BacklightSettings,
+    chroma::ChromaSettings,
+    community::CommunitySettings,
+    controllers::{ControlAction, ControllerSettings},
+    exit::ExitSettings,
+    hotkeys::HotkeySettings,
+    manager::{
+        clock::SpeedMultiplier,
+        custom_effect::CustomEffect,
+        frame_rate::FrameRate,
+        palette::Palette,
+        profile::{EffectOptions, Profile},
+        swatch::Swatch,
+    },
+    notifications::NotificationSettings,
+    overlays::OverlaySettings,
+    pomodoro::PomodoroSettings,
+    reduced_motion::ReducedMotion,
+    room_sync::RoomSyncSettings,
+    shuffle::ShuffleSettings,
+    startup::StartupSettings,
+    triggers::Trigger,
+    usage::UsageStats,
+    wallpaper::WallpaperSettings,
+    weather::WeatherSettings,
+    window::WindowSettings,
+};
+use error_stack::{Result, ResultExt};
+use legion_kb_rgb_hw::{lights::Section, Calibration};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+/// Bumped whenever the format changes in a way that needs migrating
+const SETTINGS_VERSION: u32 = 1;
+
+/// The file older versions kept the settings in, next to the program
+const LEGACY_FILE_NAME: &str = "settings.json";
+
+/// Set through the `--config` flag
+static LOCATION_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
 
 #[derive(Debug, Deserialize, Serialize, Default)]
 pub struct Settings {
+    /// Files from before versioning was introduced are version 0
+    #[serde(default)]
+    pub version: u32,
     pub profiles: Vec<Profile>,
     pub effects: Vec<CustomEffect>,
     // Up to 0.19.5
     #[serde(alias = "ui_state")]
     pub current_profile: Profile,
+    #[serde(default)]
+    pub palettes: Vec<Palette>,
+    /// Favorite colors, shared across profiles
+    #[serde(default)]
+    pub swatches: Vec<Swatch>,
+    /// Version of the app that last saved the settings, to tell what changed since
+    #[serde(default)]
+    pub last_seen_version: Option<String>,
+    /// The options last used with each effect, by effect name
+    #[serde(default)]
+    pub effect_options: BTreeMap<String, EffectOptions>,
+    #[serde(flatten)]
+    pub preferences: Preferences,
+    #[serde(default)]
+    pub usage: UsageStats,
+    /// The keyboard `current_profile` belongs to, by [`legion_kb_rgb_hw::KeyboardInfo::id`]
+    #[serde(default)]
+    pub selected_device: Option<String>,
+    /// What every other keyboard was last playing, by the same id
+    #[serde(default)]
+    pub device_profiles: BTreeMap<String, Profile>,
+    /// Color correction of each keyboard, by the same id
+    #[serde(default)]
+    pub calibrations: BTreeMap<String, Calibration>,
+    /// How the settings were obtained by [`Settings::load`]
+    #[serde(skip)]
+    pub load_outcome: LoadOutcome,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LoadOutcome {
+    #[default]
+    Loaded,
+    /// There was no settings file yet, this is the first launch
+    Created,
+    /// The settings file was corrupted and the backup was loaded instead
+    RecoveredFromBackup,
+    /// Neither the settings file nor the backup could be read, the broken file was set aside
+    Reset,
+}
+
+#[derive(Debug, Error)]
+#[error("Could not save the settings")]
+pub struct SaveSettingsError;
+
+#[derive(Debug, Error)]
+#[error("Could not read the settings")]
+pub struct ReadSettingsError;
+
+#[derive(Debug, Error)]
+#[error("Could not repair the settings")]
+pub struct RepairSettingsError;
+
+/// General app behavior options, stored alongside the rest of the settings
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+#[serde(default)]
+pub struct Preferences {
+    pub notifications: NotificationSettings,
+    pub fullscreen: FullscreenSettings,
+    pub app_rules: Vec<AppRule>,
+    /// Name of the keyboard layout shown in the preview, picked from the detected model if empty
+    pub preview_layout: Option<String>,
+    pub reduced_motion: ReducedMotion,
+    /// Scales the speed of every animation at once
+    pub effect_speed: SpeedMultiplier,
+    /// Caps how often the effects update the keyboard
+    pub frame_rate: FrameRate,
+    /// Milliseconds to fade for when switching to another profile, 0 to switch right away
+    pub crossfade_ms: u32,
+    /// Lights of the laptop other than the keyboard that get turned on and off along with it
+    pub synced_lights: Vec<Section>,
+    /// Let games drive the keyboard through Razer Chroma
+    pub chroma: ChromaSettings,
+    /// Mirror the zone colors to the lights of the room
+    pub room_sync: RoomSyncSettings,
+    /// Serve an HTTP API to control the keyboard from scripts
+    pub api: ApiSettings,
+    /// What the buttons of Stream Decks and MIDI controllers do
+    pub controllers: ControllerSettings,
+    /// Where the `Weather` effect gets the conditions from
+    pub weather: WeatherSettings,
+    /// Where the community effects are listed
+    pub community: CommunitySettings,
+    /// Taking the zone colors from the desktop wallpaper
+    pub wallpaper: WallpaperSettings,
+    /// Durations and colors of the focus timer
+    pub pomodoro: PomodoroSettings,
+    /// Zones tinted to show the state of the system
+    pub overlays: OverlaySettings,
+    /// Named alerts fired through the HTTP API or by polling a URL
+    pub triggers: Vec<Trigger>,
+    /// Keys of the global hotkeys that can be rebound
+    pub hotkeys: HotkeySettings,
+    /// Dimming on top of the profiles, and whether to follow the backlight key of the laptop
+    pub backlight: BacklightSettings,
+    /// Count the keys pressed in each zone for the `Heatmap` effect, off unless asked for
+    pub count_keystrokes: bool,
+    /// Switching to a random saved profile every so often
+    pub shuffle: ShuffleSettings,
+    pub startup: StartupSettings,
+    /// What to leave on the keyboard when the program closes or goes down
+    pub exit: ExitSettings,
+    /// Never reach the internet, for update checks, the weather or polled triggers alike
+    pub offline: bool,
+    pub appearance: Appearance,
+    pub window: WindowSettings,
+    /// Language of the GUI, following the system one if empty
+    pub language: Option<String>,
+}
+
+impl Preferences {
+    /// Point the rules that refer to a profile by name to its new name
+    pub fn rename_profile(&mut self, old: &str, new: &str) {
+        for rule in self.app_rules.iter_mut().filter(|rule| rule.profile == old) {
+            rule.profile = new.to_string();
+        }
+
+        if self.fullscreen.profile.as_deref() == Some(old) {
+            self.fullscreen.profile = Some(new.to_string());
+        }
+
+        for binding in &mut self.controllers.bindings {
+            if let ControlAction::Profile(name) = &mut binding.action {
+                if name == old {
+                    *name = new.to_string();
+                }
+            }
+        }
+    }
 }
 
 impl Settings {
-    pub fn new(profiles: Vec<Profile>, effects: Vec<CustomEffect>, current_profile: Profile) -> Self {
-        Self { profiles, effects, current_profile }
+    pub fn new(
+        profiles: Vec<Profile>, effects: Vec<CustomEffect>, current_profile: Profile, palettes: Vec<Palette>, effect_options: BTreeMap<String, EffectOptions>, preferences: Preferences,
+        usage: UsageStats,
+    ) -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            profiles,
+            effects,
+            current_profile,
+            palettes,
+            swatches: Vec::new(),
+            last_seen_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            effect_options,
+            preferences,
+            usage,
+            selected_device: None,
+            device_profiles: BTreeMap::new(),
+            calibrations: BTreeMap::new(),
+            load_outcome: LoadOutcome::Loaded,
+        }
     }
 
-    /// Load the settings from the configured path or generate default ones if an error occurs
+    /// Load the settings from the configured path, falling back to the backup if they are corrupted, or generate default ones if there's nothing usable
     pub fn load() -> Self {
-        let mut persist: Self = Self::default();
+        let location = Self::get_location();
+
+        // A missing file simply means this is the first launch
+        let Ok(string) = fs::read_to_string(&location) else {
+            return Self {
+                load_outcome: LoadOutcome::Created,
+                ..Self::default()
+            };
+        };
+
+        if let Ok(persist) = serde_json::from_str(&string) {
+            return persist;
+        }
+
+        let backup = fs::read_to_string(with_extension_suffix(&location, "bak"))
+            .ok()
+            .and_then(|string| serde_json::from_str::<Self>(&string).ok());
 
-        if let Ok(string) = fs::read_to_string(Self::get_location()) {
-            persist = serde_json::from_str(&string).unwrap_or_default();
+        if let Some(mut persist) = backup {
+            persist.load_outcome = LoadOutcome::RecoveredFromBackup;
+            return persist;
         }
 
-        persist
+        // Keep the broken file around instead of overwriting it on the next save
+        let _ = fs::rename(&location, with_extension_suffix(&location, "corrupt"));
+
+        Self {
+            load_outcome: LoadOutcome::Reset,
+            ..Self::default()
+        }
+    }
+
+    /// Read the settings file as it is, without any of the recovery done by [`Settings::load`]
+    pub fn read() -> Result<Self, ReadSettingsError> {
+        let string = fs::read_to_string(Self::get_location()).change_context(ReadSettingsError)?;
+        serde_json::from_str(&string).change_context(ReadSettingsError)
+    }
+
+    /// Salvage whatever can still be read from the settings file, or its backup if the file isn't valid JSON at all
+    ///
+    /// Values that can't be read are dropped, one element at a time for lists, and the original file is kept as `settings.json.corrupt`.
+    /// Returns how many values were dropped.
+    pub fn repair() -> Result<usize, RepairSettingsError> {
+        let location = Self::get_location();
+
+        let original = [location.clone(), with_extension_suffix(&location, "bak")]
+            .iter()
+            .find_map(|path| fs::read_to_string(path).ok().and_then(|string| serde_json::from_str::<Map<String, Value>>(&string).ok()))
+            .ok_or(RepairSettingsError)
+            .attach_printable("Neither the settings file nor its backup contain a JSON object")?;
+
+        let Value::Object(mut repaired) = serde_json::to_value(Self::default()).change_context(RepairSettingsError)? else {
+            unreachable!("Settings are always serialized as an object");
+        };
+        let is_valid = |repaired: &Map<String, Value>| serde_json::from_value::<Self>(Value::Object(repaired.clone())).is_ok();
+
+        let mut dropped = 0;
+        for (key, value) in original {
+            match value {
+                Value::Array(elements) => {
+                    let previous = repaired.insert(key.clone(), Value::Array(Vec::new()));
+                    if !is_valid(&repaired) {
+                        restore(&mut repaired, key, previous);
+                        dropped += 1;
+                        continue;
+                    }
+
+                    for element in elements {
+                        if let Some(Value::Array(kept)) = repaired.get_mut(&key) {
+                            kept.push(element);
+                        }
+                        if !is_valid(&repaired) {
+                            if let Some(Value::Array(kept)) = repaired.get_mut(&key) {
+                                kept.pop();
+                            }
+                            dropped += 1;
+                        }
+                    }
+                }
+                value => {
+                    let previous = repaired.insert(key.clone(), value);
+                    if !is_valid(&repaired) {
+                        restore(&mut repaired, key, previous);
+                        dropped += 1;
+                    }
+                }
+            }
+        }
+
+        let mut settings: Self = serde_json::from_value(Value::Object(repaired)).change_context(RepairSettingsError)?;
+
+        if location.exists() {
+            fs::rename(&location, with_extension_suffix(&location, "corrupt")).change_context(RepairSettingsError)?;
+        }
+        settings.save().change_context(RepairSettingsError)?;
+
+        Ok(dropped)
+    }
+
+    /// Replace the settings with the default ones, keeping the current file as `settings.json.old`
+    pub fn reset() -> Result<(), SaveSettingsError> {
+        let location = Self::get_location();
+
+        if location.exists() {
+            fs::rename(&location, with_extension_suffix(&location, "old")).change_context(SaveSettingsError)?;
+        }
+
+        Self::default().save()
+    }
+
+    /// Call `on_change` whenever the settings file is modified by something else, as long as the returned watcher is kept alive
+    pub fn watch<F: Fn() + Send + 'static>(on_change: F) -> Option<RecommendedWatcher> {
+        let location = Self::get_location();
+        let file_name = location.file_name()?.to_owned();
+        let directory = location.parent()?.to_owned();
+
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+
+            // The whole directory is watched as saving replaces the file instead of writing to it
+            let is_settings = event.paths.iter().any(|path| path.file_name() == Some(file_name.as_os_str()));
+            if is_settings && (event.kind.is_create() || event.kind.is_modify()) {
+                on_change();
+            }
+        })
+        .ok()?;
+
+        watcher.watch(&directory, RecursiveMode::NonRecursive).ok()?;
+
+        Some(watcher)
     }
 
     /// Save the settings to the configured path
-    pub fn save(&mut self) {
-        let mut file = File::create(Self::get_location()).unwrap();
+    ///
+    /// The settings are written to a temporary file that then replaces the current one, which is kept as a backup
+    pub fn save(&mut self) -> Result<(), SaveSettingsError> {
+        self.version = SETTINGS_VERSION;
+
+        let location = Self::get_location();
+        if let Some(parent) = location.parent() {
+            fs::create_dir_all(parent).change_context(SaveSettingsError)?;
+        }
 
-        let stringified_json = serde_json::to_string(&self).unwrap();
+        let stringified_json = serde_json::to_string(&self).change_context(SaveSettingsError)?;
 
-        file.write_all(stringified_json.as_bytes()).unwrap();
+        let temp_location = with_extension_suffix(&location, "tmp");
+        let mut file = File::create(&temp_location).change_context(SaveSettingsError)?;
+        file.write_all(stringified_json.as_bytes()).change_context(SaveSettingsError)?;
+        file.sync_all().change_context(SaveSettingsError)?;
+        drop(file);
+
+        // Only back up files that can actually be loaded, so a corrupted file never replaces a good backup
+        if fs::read_to_string(&location).is_ok_and(|string| serde_json::from_str::<Self>(&string).is_ok()) {
+            fs::copy(&location, with_extension_suffix(&location, "bak")).change_context(SaveSettingsError)?;
+        }
+
+        fs::rename(&temp_location, &location).change_context(SaveSettingsError)?;
+
+        Ok(())
     }
 
-    fn get_location() -> PathBuf {
-        let default = PathBuf::from("./settings.json");
+    /// Use the given file for the settings instead of the default location
+    pub fn set_location(path: PathBuf) {
+        let _ = LOCATION_OVERRIDE.set(path);
+    }
+
+    pub fn get_location() -> PathBuf {
+        if let Some(path) = LOCATION_OVERRIDE.get() {
+            return path.clone();
+        }
+
+        let default = Self::default_location();
 
         if let Ok(maybe_path) = env::var("LEGION_KEYBOARD_CONFIG") {
             let path = PathBuf::from(maybe_path);
@@ -56,4 +407,51 @@ impl Settings {
             default
         }
     }
+
+    fn default_location() -> PathBuf {
+        dirs::config_dir().map_or_else(|| PathBuf::from(LEGACY_FILE_NAME), |dir| dir.join("l5p-keyboard-rgb").join("settings.json"))
+    }
+
+    /// Copy settings left next to the program by older versions to the default location, unless another one was picked
+    ///
+    /// Only meant for when the window opens, as the command line never saves the settings and would look for them on
+    /// every run. The file has to read as settings, and is left where it was.
+    #[cfg(feature = "gui")]
+    pub fn migrate_legacy_location() {
+        let Some(legacy) = env::current_exe().ok().map(|exe| exe.with_file_name(LEGACY_FILE_NAME)) else {
+            return;
+        };
+        let location = Self::default_location();
+
+        if !legacy.is_file() || location.exists() || Self::get_location() != location {
+            return;
+        }
+
+        if !fs::read_to_string(&legacy).is_ok_and(|string| serde_json::from_str::<Self>(&string).is_ok()) {
+            return;
+        }
+
+        if let Some(parent) = location.parent() {
+            if fs::create_dir_all(parent).is_err() {
+                return;
+            }
+        }
+
+        let _ = fs::copy(&legacy, &location);
+    }
+}
+
+fn restore(map: &mut Map<String, Value>, key: String, previous: Option<Value>) {
+    match previous {
+        Some(previous) => map.insert(key, previous),
+        None => map.remove(&key),
+    };
+}
+
+/// Turn `settings.json` into `settings.json.<suffix>`
+fn with_extension_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = path.file_name().map(OsString::from).unwrap_or_default();
+    file_name.push(".");
+    file_name.push(suffix);
+    path.with_file_name(file_name)
 }
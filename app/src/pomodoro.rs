@@ -0,0 +1,165 @@
+//! A focus timer shown on the keyboard: one color while working, another during breaks, pulsing through the last
+//! minute of either.
+
+#[cfg(feature = "gui")]
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "gui")]
+use crate::{
+    enums::{Brightness, Effects},
+    manager::profile::{self, Profile},
+};
+
+/// From when the phase color starts pulsing
+#[cfg(feature = "gui")]
+const LAST_MINUTE: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PomodoroSettings {
+    pub work_minutes: u32,
+    pub break_minutes: u32,
+    pub work_color: [u8; 3],
+    pub break_color: [u8; 3],
+}
+
+impl Default for PomodoroSettings {
+    fn default() -> Self {
+        Self {
+            work_minutes: 25,
+            break_minutes: 5,
+            work_color: [255, 40, 0],
+            break_color: [0, 200, 80],
+        }
+    }
+}
+
+/// What the controls of the GUI and the tray ask for
+#[cfg(feature = "gui")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PomodoroCommand {
+    /// Start the timer, or pause and resume it once started
+    StartPause,
+    /// Go to the next phase right away
+    Skip,
+    Stop,
+}
+
+#[cfg(feature = "gui")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Work,
+    Break,
+}
+
+#[cfg(feature = "gui")]
+#[derive(Debug, Clone, Copy)]
+struct Running {
+    phase: Phase,
+    /// When the phase ends, unless paused
+    end: Instant,
+    /// How much of the phase was left when paused
+    paused_with: Option<Duration>,
+}
+
+#[cfg(feature = "gui")]
+#[derive(Debug, Default)]
+pub struct Pomodoro {
+    running: Option<Running>,
+}
+
+#[cfg(feature = "gui")]
+impl Pomodoro {
+    pub fn is_active(&self) -> bool {
+        self.running.is_some()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.running.is_some_and(|running| running.paused_with.is_some())
+    }
+
+    pub fn phase(&self) -> Option<Phase> {
+        self.running.map(|running| running.phase)
+    }
+
+    /// How long until the current phase ends
+    pub fn remaining(&self) -> Option<Duration> {
+        self.running.map(|running| running.paused_with.unwrap_or_else(|| running.end.saturating_duration_since(Instant::now())))
+    }
+
+    pub fn handle(&mut self, command: PomodoroCommand, settings: &PomodoroSettings) {
+        match command {
+            PomodoroCommand::StartPause => match &mut self.running {
+                None => self.running = Some(Self::phase_from_now(Phase::Work, settings)),
+                Some(running) => match running.paused_with.take() {
+                    Some(remaining) => running.end = Instant::now() + remaining,
+                    None => running.paused_with = Some(running.end.saturating_duration_since(Instant::now())),
+                },
+            },
+            PomodoroCommand::Skip => {
+                if let Some(running) = self.running {
+                    self.running = Some(Self::phase_from_now(Self::next(running.phase), settings));
+                }
+            }
+            PomodoroCommand::Stop => self.running = None,
+        }
+    }
+
+    /// Move on to the next phase once the current one is over, returns whether what the keyboard shows changed
+    pub fn tick(&mut self, settings: &PomodoroSettings) -> bool {
+        let before = self.look();
+
+        if let Some(running) = self.running.filter(|running| running.paused_with.is_none() && Instant::now() >= running.end) {
+            self.running = Some(Self::phase_from_now(Self::next(running.phase), settings));
+        }
+
+        self.look() != before
+    }
+
+    /// What the keyboard should show while the timer runs
+    pub fn profile(&self, settings: &PomodoroSettings, brightness: Brightness) -> Option<Profile> {
+        let (phase, pulsing) = self.look()?;
+        let rgb = match phase {
+            Phase::Work => settings.work_color,
+            Phase::Break => settings.break_color,
+        };
+
+        Some(Profile {
+            rgb_zones: profile::arr_to_zones([rgb; 4].concat().try_into().unwrap()),
+            effect: if pulsing { Effects::Breath } else { Effects::Static },
+            speed: 3,
+            brightness,
+            ..Profile::default()
+        })
+    }
+
+    /// The phase shown and whether it's pulsing, which is all that changes on the keyboard
+    fn look(&self) -> Option<(Phase, bool)> {
+        let running = self.running?;
+        let pulsing = running.paused_with.is_none() && self.remaining().is_some_and(|remaining| remaining <= LAST_MINUTE);
+
+        Some((running.phase, pulsing))
+    }
+
+    fn phase_from_now(phase: Phase, settings: &PomodoroSettings) -> Running {
+        let minutes = match phase {
+            Phase::Work => settings.work_minutes,
+            Phase::Break => settings.break_minutes,
+        };
+
+        Running {
+            phase,
+            end: Instant::now() + Duration::from_secs(u64::from(minutes.max(1)) * 60),
+            paused_with: None,
+        }
+    }
+
+    const fn next(phase: Phase) -> Phase {
+        match phase {
+            Phase::Work => Phase::Break,
+            Phase::Break => Phase::Work,
+        }
+    }
+}
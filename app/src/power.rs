@@ -0,0 +1,121 @@
+//! Very rough estimation of how much power the lighting setup draws, to help pick efficient settings while on battery.
+
+use std::time::{Duration, Instant};
+
+use sysinfo::{Pid, ProcessesToUpdate, System};
+
+use crate::{
+    enums::{Brightness, Effects},
+    manager::profile::Profile,
+};
+
+/// Approximate draw of the backlight with all zones in white at the highest brightness
+const MAX_LED_WATTS: f32 = 1.5;
+/// Approximate draw of a laptop CPU core at full load
+const CORE_WATTS: f32 = 5.0;
+/// Cost of each write to the keyboard, including waking up the CPU for it
+const WATTS_PER_UPDATE: f32 = 0.01;
+/// Screen capturing is a lot more expensive than anything else
+const SCREEN_CAPTURE_WATTS: f32 = 2.0;
+pub const CPU_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Copy, Debug)]
+pub struct PowerEstimate {
+    pub led_watts: f32,
+    pub cpu_watts: f32,
+}
+
+impl PowerEstimate {
+    pub fn total(&self) -> f32 {
+        self.led_watts + self.cpu_watts
+    }
+
+    pub fn level(&self) -> &'static str {
+        match self.total() {
+            total if total < 0.5 => "Low",
+            total if total < 1.5 => "Medium",
+            _ => "High",
+        }
+    }
+}
+
+/// Estimate the power impact of a profile, using the measured CPU usage (in percent of a core) if available
+pub fn estimate(profile: &Profile, cpu_usage: Option<f32>) -> PowerEstimate {
+    let profile = profile.with_intensity_applied();
+
+    let brightness = match profile.brightness {
+        Brightness::Low => 0.5,
+        Brightness::High => 1.0,
+    };
+
+    // Effects that pick their own colors average out to something in the middle
+    let average_level = if profile.effect.takes_color_array() {
+        let rgb = profile.rgb_array();
+        rgb.iter().map(|channel| f32::from(*channel)).sum::<f32>() / (rgb.len() as f32 * 255.0)
+    } else {
+        0.6
+    };
+
+    let led_watts = MAX_LED_WATTS * brightness * average_level;
+
+    let cpu_watts = cpu_usage.map_or_else(
+        || {
+            let capture = if matches!(profile.effect, Effects::AmbientLight { .. }) { SCREEN_CAPTURE_WATTS } else { 0.0 };
+            profile.effect.update_rate(profile.speed).mul_add(WATTS_PER_UPDATE, capture)
+        },
+        |usage| usage / 100.0 * CORE_WATTS,
+    );
+
+    PowerEstimate { led_watts, cpu_watts }
+}
+
+/// Measures the CPU usage of the thread playing the effects, leaving out what the GUI uses to show it
+///
+/// Only Linux lets threads be looked at on their own, elsewhere the estimate goes by the update rate of the effect.
+pub struct CpuMonitor {
+    system: System,
+    thread: Option<Pid>,
+    last_refresh: Option<Instant>,
+    usage: Option<f32>,
+}
+
+impl CpuMonitor {
+    pub fn new() -> Self {
+        Self {
+            system: System::new(),
+            thread: None,
+            last_refresh: None,
+            usage: None,
+        }
+    }
+
+    /// Get the latest measurement of `thread`, see [`crate::manager::EffectManager::effect_thread_id`], refreshing it if
+    /// enough time has passed
+    pub fn usage(&mut self, thread: Option<u32>) -> Option<f32> {
+        let thread = thread.filter(|_| cfg!(target_os = "linux")).map(Pid::from_u32);
+        // Start over when the manager and its thread are replaced
+        if thread != self.thread {
+            *self = Self { thread, ..Self::new() };
+        }
+        let pid = self.thread?;
+
+        match self.last_refresh {
+            Some(last_refresh) if last_refresh.elapsed() < CPU_REFRESH_INTERVAL => {}
+            previous => {
+                self.system.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+                // The first refresh only sets a baseline to measure against
+                if previous.is_some() {
+                    self.usage = self.system.process(pid).map(sysinfo::Process::cpu_usage);
+                }
+                self.last_refresh = Some(Instant::now());
+            }
+        }
+
+        self.usage
+    }
+
+    /// Whether there's a thread being measured, the usage changing over time
+    pub fn is_measuring(&self) -> bool {
+        self.thread.is_some()
+    }
+}
@@ -0,0 +1,64 @@
+//! Reduced motion preference, following the OS setting where it can be detected.
+
+use serde::{Deserialize, Serialize};
+use strum_macros::{EnumIter, IntoStaticStr};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, EnumIter, IntoStaticStr)]
+pub enum ReducedMotion {
+    #[default]
+    #[strum(serialize = "Follow system")]
+    System,
+    #[strum(serialize = "Always")]
+    Always,
+    #[strum(serialize = "Never")]
+    Never,
+}
+
+impl ReducedMotion {
+    pub fn is_active(self) -> bool {
+        match self {
+            Self::System => system_prefers_reduced_motion(),
+            Self::Always => true,
+            Self::Never => false,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn system_prefers_reduced_motion() -> bool {
+    use std::process::Command;
+
+    let read = |program: &str, args: &[&str]| {
+        Command::new(program)
+            .args(args)
+            .output()
+            .ok()
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    // GNOME and derivatives
+    if read("gsettings", &["get", "org.gnome.desktop.interface", "enable-animations"]).is_some_and(|value| value == "false") {
+        return true;
+    }
+
+    // KDE Plasma sets the animation speed to 0 to disable them
+    read("kreadconfig5", &["--group", "KDE", "--key", "AnimationDurationFactor"]).is_some_and(|value| value == "0")
+}
+
+#[cfg(target_os = "windows")]
+fn system_prefers_reduced_motion() -> bool {
+    use winapi::{
+        shared::minwindef::{BOOL, TRUE},
+        um::winuser::{SystemParametersInfoW, SPI_GETCLIENTAREAANIMATION},
+    };
+
+    let mut animations_enabled: BOOL = TRUE;
+    let success = unsafe { SystemParametersInfoW(SPI_GETCLIENTAREAANIMATION, 0, std::ptr::addr_of_mut!(animations_enabled).cast(), 0) };
+
+    success != 0 && animations_enabled == 0
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn system_prefers_reduced_motion() -> bool {
+    false
+}
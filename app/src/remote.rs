@@ -0,0 +1,35 @@
+//! What can be asked of the program from the outside, shared by the ways of controlling it over the network.
+
+use serde::Deserialize;
+
+use crate::{
+    enums::{Brightness, Effects},
+    manager::profile::{self, Profile},
+    persist::Settings,
+};
+
+/// An effect to play, like `{"effect": "Static", "colors": [255, 0, 0, ...], "brightness": "High", "speed": 2}`
+#[derive(Debug, Deserialize)]
+pub struct EffectRequest {
+    pub effect: Effects,
+    pub colors: Option<[u8; 12]>,
+    pub brightness: Option<Brightness>,
+    pub speed: Option<u8>,
+}
+
+impl EffectRequest {
+    pub fn into_profile(self) -> Profile {
+        Profile {
+            rgb_zones: self.colors.map(profile::arr_to_zones).unwrap_or_default(),
+            effect: self.effect,
+            brightness: self.brightness.unwrap_or_default(),
+            speed: self.speed.unwrap_or(1),
+            ..Profile::default()
+        }
+    }
+}
+
+/// The saved profile with the given name, as last written to the settings
+pub fn saved_profile(name: &str) -> Option<Profile> {
+    Settings::load().profiles.into_iter().find(|profile| profile.name.as_deref() == Some(name))
+}
@@ -0,0 +1,219 @@
+//! Mirrors the zone colors to the lights of the room, through Home Assistant or a Philips Hue bridge.
+//!
+//! A background thread looks at what's being sent to the keyboard a few times per second and updates each mapped light
+//! when its color changes, as both services take a while to go through every request.
+
+use serde::{Deserialize, Serialize};
+use strum_macros::{EnumIter, IntoStaticStr};
+
+/// Where the colors are sent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, EnumIter, IntoStaticStr)]
+pub enum RoomService {
+    /// Through the REST API, with a long-lived access token
+    #[default]
+    #[strum(serialize = "Home Assistant")]
+    HomeAssistant,
+    /// Through the local API of the bridge, with the username it handed out
+    #[strum(serialize = "Philips Hue")]
+    Hue,
+}
+
+/// Which part of the keyboard a light follows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, EnumIter, IntoStaticStr)]
+pub enum ZoneSource {
+    #[strum(serialize = "Zone 1")]
+    Zone1,
+    #[strum(serialize = "Zone 2")]
+    Zone2,
+    #[strum(serialize = "Zone 3")]
+    Zone3,
+    #[strum(serialize = "Zone 4")]
+    Zone4,
+    /// The mix of the four zones
+    #[default]
+    #[strum(serialize = "Average")]
+    Average,
+}
+
+#[cfg(feature = "gui")]
+impl ZoneSource {
+    pub fn pick(self, zones: [[u8; 3]; 4]) -> [u8; 3] {
+        match self {
+            Self::Zone1 => zones[0],
+            Self::Zone2 => zones[1],
+            Self::Zone3 => zones[2],
+            Self::Zone4 => zones[3],
+            Self::Average => [0, 1, 2].map(|channel| (zones.iter().map(|zone| u16::from(zone[channel])).sum::<u16>() / 4) as u8),
+        }
+    }
+}
+
+/// A light of the room and the zone it follows
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LightMapping {
+    /// The entity id like `light.desk` for Home Assistant, the light number for Hue
+    pub light: String,
+    pub zone: ZoneSource,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoomSyncSettings {
+    pub enabled: bool,
+    pub service: RoomService,
+    /// Like `http://homeassistant.local:8123` or `http://192.168.1.2` for a Hue bridge
+    pub endpoint: String,
+    /// The long-lived access token for Home Assistant, the username for Hue
+    pub token: String,
+    pub lights: Vec<LightMapping>,
+}
+
+#[cfg(all(feature = "gui", feature = "network"))]
+pub use sync::RoomSync;
+
+#[cfg(all(feature = "gui", feature = "network"))]
+mod sync {
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, Mutex,
+        },
+        thread,
+        time::Duration,
+    };
+
+    use error_stack::{Report, Result};
+    use serde_json::json;
+    use thiserror::Error;
+    use tracing::warn;
+
+    use super::{RoomService, RoomSyncSettings};
+    use crate::{manager::preview::OutputPreview, ticks};
+
+    /// How often the colors are checked for changes, both services struggle with more than a few updates per second
+    const INTERVAL: Duration = Duration::from_millis(500);
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[derive(Debug, Error)]
+    #[error("Could not update the light")]
+    struct UpdateLightError;
+
+    /// Keeps the lights of the room in sync until dropped
+    pub struct RoomSync {
+        settings: Arc<Mutex<RoomSyncSettings>>,
+        stop: Arc<AtomicBool>,
+    }
+
+    impl RoomSync {
+        /// Follow what `preview` shows with the lights in `settings`
+        pub fn spawn(preview: OutputPreview, settings: RoomSyncSettings) -> Self {
+            let settings = Arc::new(Mutex::new(settings));
+            let stop = Arc::new(AtomicBool::new(false));
+
+            {
+                let settings = settings.clone();
+                let stop = stop.clone();
+                thread::spawn(move || {
+                    // What each light was last set to, so only the changes are sent
+                    let mut sent: HashMap<String, [u8; 3]> = HashMap::new();
+
+                    while !stop.load(Ordering::SeqCst) {
+                        let settings = settings.lock().unwrap().clone();
+                        if let (true, Some(zones)) = (settings.enabled, preview.zone_colors()) {
+                            for mapping in &settings.lights {
+                                let rgb = mapping.zone.pick(zones);
+                                if mapping.light.is_empty() || sent.get(&mapping.light) == Some(&rgb) {
+                                    continue;
+                                }
+
+                                match send(&settings, &mapping.light, rgb) {
+                                    Ok(()) => {
+                                        sent.insert(mapping.light.clone(), rgb);
+                                    }
+                                    Err(err) => warn!("Could not update {}: {err:?}", mapping.light),
+                                }
+                            }
+                        } else {
+                            sent.clear();
+                        }
+
+                        ticks::sleep_until_next(INTERVAL);
+                    }
+                });
+            }
+
+            Self { settings, stop }
+        }
+
+        pub fn set_settings(&self, settings: RoomSyncSettings) {
+            *self.settings.lock().unwrap() = settings;
+        }
+    }
+
+    impl Drop for RoomSync {
+        fn drop(&mut self) {
+            self.stop.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn send(settings: &RoomSyncSettings, light: &str, rgb: [u8; 3]) -> Result<(), UpdateLightError> {
+        let endpoint = settings.endpoint.trim_end_matches('/');
+        let brightness = rgb.into_iter().max().unwrap_or(0);
+        let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+
+        let (request, body) = match settings.service {
+            RoomService::HomeAssistant => {
+                let authorization = format!("Bearer {}", settings.token);
+                let (service, body) = if brightness == 0 {
+                    ("turn_off", json!({ "entity_id": light }))
+                } else {
+                    ("turn_on", json!({ "entity_id": light, "rgb_color": rgb, "brightness": brightness }))
+                };
+
+                (agent.post(&format!("{endpoint}/api/services/light/{service}")).set("Authorization", &authorization), body)
+            }
+            RoomService::Hue => {
+                let body = if brightness == 0 {
+                    json!({ "on": false })
+                } else {
+                    json!({ "on": true, "xy": to_xy(rgb), "bri": brightness.clamp(1, 254) })
+                };
+
+                (agent.put(&format!("{endpoint}/api/{}/lights/{light}/state", settings.token)), body)
+            }
+        };
+
+        match request.set("Content-Type", "application/json").send_string(&body.to_string()) {
+            Ok(_) => Ok(()),
+            Err(ureq::Error::Status(status, response)) => {
+                let body = response.into_string().unwrap_or_default();
+                Err(Report::new(UpdateLightError).attach_printable(format!("The server answered with {status}: {body}")))
+            }
+            Err(err) => Err(Report::new(err).change_context(UpdateLightError)),
+        }
+    }
+
+    /// The CIE xy coordinates Hue lights take colors as, for an sRGB color
+    fn to_xy(rgb: [u8; 3]) -> [f32; 2] {
+        let [r, g, b] = rgb.map(|channel| {
+            let channel = f32::from(channel) / 255.0;
+            if channel > 0.04045 {
+                ((channel + 0.055) / 1.055).powf(2.4)
+            } else {
+                channel / 12.92
+            }
+        });
+
+        let x = r.mul_add(0.4124, g.mul_add(0.3576, b * 0.1805));
+        let y = r.mul_add(0.2126, g.mul_add(0.7152, b * 0.0722));
+        let z = r.mul_add(0.0193, g.mul_add(0.1192, b * 0.9505));
+        let sum = x + y + z;
+
+        if sum == 0.0 {
+            [0.0, 0.0]
+        } else {
+            [x / sum, y / sum]
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! Switching to another saved profile every so often, and making up new profiles out of random colors that go well
+//! together.
+
+#[cfg(feature = "gui")]
+use std::{thread, time::Duration};
+
+#[cfg(feature = "gui")]
+use crossbeam_channel::{RecvTimeoutError, Sender};
+#[cfg(feature = "gui")]
+use rand::{
+    seq::{IteratorRandom, SliceRandom},
+    Rng,
+};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "gui")]
+use strum::IntoEnumIterator;
+
+#[cfg(feature = "gui")]
+use crate::{
+    enums::Effects,
+    harmony::Harmony,
+    manager::profile::{self, Profile},
+};
+
+#[cfg(all(test, feature = "gui"))]
+mod tests;
+
+/// The effects a surprise profile can get, the ones that show its colors without getting in the way
+#[cfg(feature = "gui")]
+const SURPRISE_EFFECTS: [Effects; 3] = [Effects::Static, Effects::Breath, Effects::Swipe];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShuffleSettings {
+    pub enabled: bool,
+    /// How long each profile stays on
+    pub minutes: u32,
+    /// Only pick from the pinned profiles
+    pub pinned_only: bool,
+}
+
+impl Default for ShuffleSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            minutes: 30,
+            pinned_only: false,
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl ShuffleSettings {
+    /// How long each profile stays on, `None` when not shuffling
+    pub fn interval(&self) -> Option<Duration> {
+        self.enabled.then(|| Duration::from_secs(u64::from(self.minutes.max(1)) * 60))
+    }
+}
+
+/// Counts down to the next switch on a thread of its own, so it keeps going while the window is hidden
+#[cfg(feature = "gui")]
+#[derive(Default)]
+pub struct Shuffle {
+    /// The interval being counted down, and what stops the countdown once dropped
+    timer: Option<(Duration, Sender<()>)>,
+}
+
+#[cfg(feature = "gui")]
+impl Shuffle {
+    /// Start counting down with the interval of `settings`, calling `on_due` every time it's up
+    ///
+    /// The countdown keeps going if the interval didn't change, and starts over if it did. It stops when shuffling is
+    /// turned off.
+    pub fn apply(&mut self, settings: &ShuffleSettings, on_due: impl Fn() + Send + 'static) {
+        let interval = settings.interval();
+        if self.timer.as_ref().map(|(running, _)| *running) == interval {
+            return;
+        }
+
+        self.timer = interval.map(|interval| (interval, spawn_timer(interval, on_due)));
+    }
+}
+
+/// Call `on_due` every `interval` until the returned sender is dropped
+#[cfg(feature = "gui")]
+fn spawn_timer(interval: Duration, on_due: impl Fn() + Send + 'static) -> Sender<()> {
+    let (stop_tx, stop_rx) = crossbeam_channel::bounded::<()>(0);
+
+    thread::spawn(move || {
+        while stop_rx.recv_timeout(interval) == Err(RecvTimeoutError::Timeout) {
+            on_due();
+        }
+    });
+
+    stop_tx
+}
+
+/// A saved profile other than the current one, picked at random
+#[cfg(feature = "gui")]
+pub fn pick<'a>(profiles: &'a [Profile], current: &Profile, settings: &ShuffleSettings, rng: &mut impl Rng) -> Option<&'a Profile> {
+    let candidates: Vec<&Profile> = profiles
+        .iter()
+        .filter(|profile| !settings.pinned_only || profile.pinned)
+        .filter(|profile| profile.name != current.name)
+        .collect();
+
+    candidates.choose(rng).copied()
+}
+
+/// A new profile with colors following a random [`Harmony`] from a random hue, kept bright and fairly saturated
+#[cfg(feature = "gui")]
+pub fn surprise(rng: &mut impl Rng) -> Profile {
+    let harmony = Harmony::iter().choose(rng).unwrap();
+    let colors = harmony.zones((rng.gen_range(0.0..360.0), rng.gen_range(0.65..=1.0), rng.gen_range(0.85..=1.0)), rng);
+
+    Profile {
+        rgb_zones: profile::arr_to_zones(colors.concat().try_into().unwrap()),
+        effect: *SURPRISE_EFFECTS.choose(rng).unwrap(),
+        speed: rng.gen_range(1..=3),
+        ..Profile::default()
+    }
+}
@@ -0,0 +1,109 @@
+//! Checks which profiles the shuffle picks from, and that its countdown keeps going on its own and stops when asked.
+
+use std::time::Duration;
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use super::{pick, spawn_timer, surprise, Shuffle, ShuffleSettings, SURPRISE_EFFECTS};
+use crate::manager::profile::Profile;
+
+fn profile(name: &str, pinned: bool) -> Profile {
+    Profile {
+        name: Some(name.to_string()),
+        pinned,
+        ..Profile::default()
+    }
+}
+
+#[test]
+fn never_picks_the_current_profile() {
+    let profiles = [profile("Gaming", false), profile("Work", false)];
+    let settings = ShuffleSettings::default();
+    let mut rng = StdRng::seed_from_u64(0);
+
+    for _ in 0..50 {
+        assert_eq!(pick(&profiles, &profiles[0], &settings, &mut rng).unwrap().name.as_deref(), Some("Work"));
+    }
+    assert!(pick(&profiles[..1], &profiles[0], &settings, &mut rng).is_none());
+}
+
+#[test]
+fn pinned_only_skips_the_others() {
+    let profiles = [profile("Gaming", false), profile("Work", true), profile("Night", false)];
+    let settings = ShuffleSettings {
+        pinned_only: true,
+        ..ShuffleSettings::default()
+    };
+    let mut rng = StdRng::seed_from_u64(0);
+
+    for _ in 0..50 {
+        assert_eq!(pick(&profiles, &profiles[0], &settings, &mut rng).unwrap().name.as_deref(), Some("Work"));
+    }
+    assert!(pick(&profiles, &profiles[1], &settings, &mut rng).is_none());
+}
+
+#[test]
+fn interval_only_while_enabled() {
+    let mut settings = ShuffleSettings {
+        minutes: 0,
+        ..ShuffleSettings::default()
+    };
+    assert_eq!(settings.interval(), None);
+
+    settings.enabled = true;
+    assert_eq!(settings.interval(), Some(Duration::from_secs(60)));
+    settings.minutes = 30;
+    assert_eq!(settings.interval(), Some(Duration::from_secs(30 * 60)));
+}
+
+#[test]
+fn timer_keeps_firing_until_dropped() {
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let stop = spawn_timer(Duration::from_millis(10), move || {
+        let _ = tx.send(());
+    });
+
+    for _ in 0..3 {
+        rx.recv_timeout(Duration::from_secs(5)).expect("the timer stopped firing");
+    }
+
+    // The thread lets go of the sender once it's done, which disconnects the channel
+    drop(stop);
+    while rx.recv_timeout(Duration::from_secs(5)).is_ok() {}
+    assert!(rx.is_empty());
+}
+
+#[test]
+fn countdown_only_restarts_when_the_interval_changes() {
+    let mut shuffle = Shuffle::default();
+    let mut settings = ShuffleSettings {
+        enabled: true,
+        ..ShuffleSettings::default()
+    };
+
+    shuffle.apply(&settings, || {});
+    let (_, first) = shuffle.timer.clone().unwrap();
+
+    settings.pinned_only = true;
+    shuffle.apply(&settings, || {});
+    assert!(shuffle.timer.as_ref().unwrap().1.same_channel(&first));
+
+    settings.minutes += 1;
+    shuffle.apply(&settings, || {});
+    assert!(!shuffle.timer.as_ref().unwrap().1.same_channel(&first));
+
+    settings.enabled = false;
+    shuffle.apply(&settings, || {});
+    assert!(shuffle.timer.is_none());
+}
+
+#[test]
+fn surprises_use_the_calm_effects() {
+    let mut rng = StdRng::seed_from_u64(7);
+
+    for _ in 0..50 {
+        let profile = surprise(&mut rng);
+        assert!(SURPRISE_EFFECTS.contains(&profile.effect));
+        assert!((1..=3).contains(&profile.speed));
+    }
+}
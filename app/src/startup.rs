@@ -0,0 +1,80 @@
+//! Applying the lighting right after the system boots, when the keyboard may not be ready yet.
+
+use serde::{Deserialize, Serialize};
+use strum_macros::{EnumIter, IntoStaticStr};
+use sysinfo::System;
+
+/// Launches within this many seconds of booting are considered to be part of the boot
+const BOOT_WINDOW_SECS: u64 = 300;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct StartupSettings {
+    /// Seconds to wait before applying the lighting, profiles can override it
+    pub delay: u32,
+    /// Keep looking for the keyboard if it isn't there yet
+    pub retry: bool,
+    /// Seconds after which to stop looking for the keyboard
+    pub retry_timeout: u32,
+    /// Whether to show the saved profile or what the keyboard is already showing when the GUI opens
+    pub initial_state: InitialState,
+    /// Send nothing to the keyboard when the GUI opens, leaving it to the firmware until a profile is picked
+    pub leave_untouched: bool,
+}
+
+/// What the GUI starts with, for keyboards that can be asked what they're showing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, EnumIter, IntoStaticStr)]
+pub enum InitialState {
+    /// The profile last used in the program, applied right away
+    #[default]
+    #[strum(serialize = "Saved")]
+    Saved,
+    /// Whatever the keyboard kept from before, like an effect set from Lenovo's own software
+    #[strum(serialize = "Keyboard")]
+    Keyboard,
+}
+
+impl Default for StartupSettings {
+    fn default() -> Self {
+        Self {
+            delay: 0,
+            retry: true,
+            retry_timeout: 60,
+            initial_state: InitialState::default(),
+            leave_untouched: false,
+        }
+    }
+}
+
+impl StartupSettings {
+    /// What to use when the program wasn't launched as part of the boot, the keyboard is expected to be ready by then
+    pub fn immediate() -> Self {
+        Self {
+            delay: 0,
+            retry: false,
+            retry_timeout: 0,
+            initial_state: InitialState::default(),
+            leave_untouched: false,
+        }
+    }
+
+    /// Get the settings that apply to this launch, with the delay of the profile about to be applied
+    pub fn for_launch(self, profile_delay: Option<u32>) -> Self {
+        if !is_boot() {
+            return Self {
+                initial_state: self.initial_state,
+                ..Self::immediate()
+            };
+        }
+
+        Self {
+            delay: profile_delay.unwrap_or(self.delay),
+            ..self
+        }
+    }
+}
+
+/// Whether the system booted recently enough for this to be the boot-time apply
+fn is_boot() -> bool {
+    System::uptime() < BOOT_WINDOW_SECS
+}
@@ -0,0 +1,82 @@
+//! The state of the lighting as last applied by any instance, so it can be queried from the outside (e.g. status bars)
+
+use std::{fs, path::PathBuf, sync::Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    enums::Brightness,
+    manager::{custom_effect::CustomEffect, profile::Profile},
+    persist::Settings,
+};
+
+/// What was last written, so re-applying the same state doesn't touch the file
+static LAST_SAVED: Mutex<Option<String>> = Mutex::new(None);
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Status {
+    pub profile: Option<String>,
+    pub effect: String,
+    /// Set when a custom effect is playing instead of a profile
+    pub custom_effect: Option<String>,
+    pub brightness: Brightness,
+    pub speed: u8,
+    pub rgb_array: [u8; 12],
+    /// Everything needed to apply the same state again
+    #[serde(default)]
+    pub applied: Option<Applied>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub enum Applied {
+    Profile(Profile),
+    CustomEffect(CustomEffect),
+}
+
+impl Status {
+    pub fn from_profile(profile: &Profile) -> Self {
+        Self {
+            profile: profile.name.clone(),
+            effect: profile.effect.to_string(),
+            custom_effect: None,
+            brightness: profile.brightness,
+            speed: profile.speed,
+            rgb_array: profile.rgb_array(),
+            applied: Some(Applied::Profile(profile.clone())),
+        }
+    }
+
+    pub fn from_custom_effect(effect: &CustomEffect) -> Self {
+        Self {
+            profile: None,
+            effect: "Custom".to_string(),
+            custom_effect: Some(effect.name.clone().unwrap_or_else(|| "Unnamed".to_string())),
+            brightness: if effect.brightness > 1 { Brightness::High } else { Brightness::Low },
+            speed: 1,
+            rgb_array: effect.colors_at(0),
+            applied: Some(Applied::CustomEffect(effect.clone())),
+        }
+    }
+
+    /// Load the last published status, if there is any
+    pub fn load() -> Option<Self> {
+        let string = fs::read_to_string(Self::get_location()).ok()?;
+        serde_json::from_str(&string).ok()
+    }
+
+    /// Publish the status if it changed since it was last published, failing silently as it's purely informative
+    pub fn save(&self) {
+        let Ok(json) = serde_json::to_string(self) else {
+            return;
+        };
+
+        let mut last_saved = LAST_SAVED.lock().unwrap();
+        if last_saved.as_ref() != Some(&json) && fs::write(Self::get_location(), &json).is_ok() {
+            *last_saved = Some(json);
+        }
+    }
+
+    fn get_location() -> PathBuf {
+        Settings::get_location().with_file_name("status.json")
+    }
+}
@@ -0,0 +1,85 @@
+//! Shared timer wheels for the periodic work done in the background.
+//!
+//! Instead of sleeping for their interval from whenever they last ran, periodic tasks sleep until the next multiple of it counted from
+//! a point shared by the whole process. Intervals are multiples of [`BASE_TICK`], or whole fractions of it for the short ones, so tasks
+//! with related intervals wake up at the same time and the CPU gets to stay idle in between, rather than being woken up by each of them
+//! in turn.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        LazyLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Every interval gets rounded up to a multiple of this, or to a whole fraction of it if it's shorter
+pub const BASE_TICK: Duration = Duration::from_millis(250);
+
+static EPOCH: LazyLock<Instant> = LazyLock::new(Instant::now);
+static WAKEUPS: AtomicU64 = AtomicU64::new(0);
+
+/// Time left until the next tick of a wheel turning every `interval`
+pub fn until_next(interval: Duration) -> Duration {
+    let interval = align(interval).as_nanos();
+    let elapsed = EPOCH.elapsed().as_nanos();
+
+    Duration::from_nanos((interval - elapsed % interval) as u64)
+}
+
+/// Sleep until the next tick of a wheel turning every `interval`
+pub fn sleep_until_next(interval: Duration) {
+    thread::sleep(until_next(interval));
+    record_wakeup();
+}
+
+/// Count a wakeup that didn't come from [`sleep_until_next`], like a GUI frame
+pub fn record_wakeup() {
+    WAKEUPS.fetch_add(1, Ordering::Relaxed);
+}
+
+fn align(interval: Duration) -> Duration {
+    if interval < BASE_TICK {
+        // Round up to the next whole fraction, so every tick of the base wheel is also one of this one
+        let fraction = (BASE_TICK.as_nanos() / interval.as_nanos().max(1)).max(1);
+        return BASE_TICK / fraction as u32;
+    }
+
+    let ticks = interval.as_nanos().div_ceil(BASE_TICK.as_nanos());
+    BASE_TICK * ticks as u32
+}
+
+/// Measures how often the process gets woken up, to check the wheels are doing their job
+#[cfg(feature = "gui")]
+pub struct WakeupMeter {
+    count: u64,
+    since: Instant,
+    rate: f32,
+}
+
+#[cfg(feature = "gui")]
+impl WakeupMeter {
+    /// Time over which the wakeups are averaged
+    const WINDOW: Duration = Duration::from_secs(2);
+
+    pub fn new() -> Self {
+        Self {
+            count: WAKEUPS.load(Ordering::Relaxed),
+            since: Instant::now(),
+            rate: 0.0,
+        }
+    }
+
+    /// Wakeups per second, over the last complete window
+    pub fn rate(&mut self) -> f32 {
+        let elapsed = self.since.elapsed();
+        if elapsed >= Self::WINDOW {
+            let count = WAKEUPS.load(Ordering::Relaxed);
+            self.rate = (count - self.count) as f32 / elapsed.as_secs_f32();
+            self.count = count;
+            self.since = Instant::now();
+        }
+        self.rate
+    }
+}
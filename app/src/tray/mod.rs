@@ -0,0 +1,75 @@
+//! The tray icon, through StatusNotifierItem on Linux and the native tray elsewhere.
+//!
+//! Both report what was picked in their menu as [`TrayEvent`]s, and only redraw when the health, the saved profiles
+//! or whether the lights are off change from what they were last shown with.
+
+use std::sync::LazyLock;
+
+use crossbeam_channel::{Receiver, Sender};
+use image::RgbaImage;
+
+use crate::{manager::health::Health, pomodoro::PomodoroCommand, APP_ICON};
+
+#[cfg(not(target_os = "linux"))]
+mod native;
+#[cfg(target_os = "linux")]
+mod sni;
+
+#[cfg(not(target_os = "linux"))]
+pub use native::Tray;
+#[cfg(target_os = "linux")]
+pub use sni::Tray;
+
+const TOOLTIP: &str = "Legion Keyboard Control";
+
+static EVENTS: LazyLock<(Sender<TrayEvent>, Receiver<TrayEvent>)> = LazyLock::new(crossbeam_channel::unbounded);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrayEvent {
+    Show,
+    /// A saved profile was picked, by name
+    LoadProfile(String),
+    ToggleLights,
+    Pomodoro(PomodoroCommand),
+    Quit,
+}
+
+/// What was picked in the tray menu, in order
+pub fn events() -> &'static Receiver<TrayEvent> {
+    &EVENTS.1
+}
+
+fn send(event: TrayEvent) {
+    let _ = EVENTS.0.send(event);
+}
+
+fn tooltip(health: &Health) -> String {
+    health.describe().map_or_else(|| TOOLTIP.to_string(), |description| format!("{TOOLTIP}\n{description}"))
+}
+
+/// The app icon, with a colored dot on its bottom right corner if something is wrong
+fn icon(health: &Health) -> RgbaImage {
+    let badge = match health {
+        Health::Normal => None,
+        Health::Waiting { .. } => Some([80, 160, 255]),
+        Health::Disconnected => Some([255, 170, 0]),
+        Health::Error(_) => Some([220, 40, 40]),
+    };
+
+    let mut image_buffer = image::load_from_memory(APP_ICON).unwrap().to_rgba8();
+
+    if let Some([r, g, b]) = badge {
+        let (width, height) = image_buffer.dimensions();
+        let radius = width.min(height) as f32 / 4.0;
+        let center = (width as f32 - radius, height as f32 - radius);
+
+        for (x, y, pixel) in image_buffer.enumerate_pixels_mut() {
+            let distance = (x as f32 + 0.5 - center.0).hypot(y as f32 + 0.5 - center.1);
+            if distance <= radius {
+                *pixel = image::Rgba([r, g, b, 255]);
+            }
+        }
+    }
+
+    image_buffer
+}
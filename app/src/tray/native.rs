@@ -0,0 +1,142 @@
+use rust_i18n::t;
+use tray_icon::{
+    menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu},
+    Icon, TrayIcon, TrayIconBuilder,
+};
+
+use super::TrayEvent;
+use crate::{manager::health::Health, pomodoro::PomodoroCommand, DENY_HIDING};
+
+const SHOW_ID: &str = "tray-show";
+const QUIT_ID: &str = "tray-quit";
+const LIGHTS_OFF_ID: &str = "tray-lights-off";
+const FOCUS_START_PAUSE_ID: &str = "tray-focus-start-pause";
+const FOCUS_SKIP_ID: &str = "tray-focus-skip";
+const FOCUS_STOP_ID: &str = "tray-focus-stop";
+/// Followed by the name of the profile
+const PROFILE_ID_PREFIX: &str = "tray-profile:";
+
+/// Lives on the GUI thread, which has to keep it updated
+pub struct Tray {
+    icon: TrayIcon,
+    has_gui: bool,
+    shown_health: Health,
+    shown_profiles: Vec<String>,
+    shown_favorites: Vec<String>,
+    shown_lights_off: bool,
+}
+
+impl Tray {
+    pub fn build(has_gui: bool) -> Option<Self> {
+        MenuEvent::set_event_handler(Some(|event: MenuEvent| {
+            let id = event.id.as_ref();
+            let event = match id {
+                SHOW_ID => TrayEvent::Show,
+                QUIT_ID => TrayEvent::Quit,
+                LIGHTS_OFF_ID => TrayEvent::ToggleLights,
+                FOCUS_START_PAUSE_ID => TrayEvent::Pomodoro(PomodoroCommand::StartPause),
+                FOCUS_SKIP_ID => TrayEvent::Pomodoro(PomodoroCommand::Skip),
+                FOCUS_STOP_ID => TrayEvent::Pomodoro(PomodoroCommand::Stop),
+                _ => match id.strip_prefix(PROFILE_ID_PREFIX) {
+                    Some(name) => TrayEvent::LoadProfile(name.to_string()),
+                    None => return,
+                },
+            };
+            super::send(event);
+        }));
+
+        let icon = TrayIconBuilder::new()
+            .with_tooltip(super::TOOLTIP)
+            .with_icon(load_icon(&Health::Normal))
+            .with_menu(Box::new(build_menu(has_gui, &[], &[], false)))
+            .build()
+            .ok()?;
+
+        Some(Self {
+            icon,
+            has_gui,
+            shown_health: Health::Normal,
+            shown_profiles: Vec::new(),
+            shown_favorites: Vec::new(),
+            shown_lights_off: false,
+        })
+    }
+
+    /// Reflect the state of the effect engine on the icon and tooltip
+    pub fn show_health(&mut self, health: &Health) {
+        if self.shown_health == *health {
+            return;
+        }
+
+        let _ = self.icon.set_icon(Some(load_icon(health)));
+        let _ = self.icon.set_tooltip(Some(super::tooltip(health)));
+        self.shown_health.clone_from(health);
+    }
+
+    /// List the saved profiles in the menu so they can be switched to, the favorites right at its top
+    pub fn show_profiles(&mut self, profiles: Vec<String>, favorites: Vec<String>) {
+        if self.shown_profiles == profiles && self.shown_favorites == favorites {
+            return;
+        }
+
+        self.icon.set_menu(Some(Box::new(build_menu(self.has_gui, &profiles, &favorites, self.shown_lights_off))));
+        self.shown_profiles = profiles;
+        self.shown_favorites = favorites;
+    }
+
+    /// Tick the item turning the lights off while they are
+    pub fn show_lights_off(&mut self, lights_off: bool) {
+        if self.shown_lights_off == lights_off {
+            return;
+        }
+
+        self.icon.set_menu(Some(Box::new(build_menu(self.has_gui, &self.shown_profiles, &self.shown_favorites, lights_off))));
+        self.shown_lights_off = lights_off;
+    }
+}
+
+fn build_menu(has_gui: bool, profiles: &[String], favorites: &[String], lights_off: bool) -> Menu {
+    let menu = Menu::new();
+
+    if has_gui && !*DENY_HIDING {
+        menu.append(&MenuItem::with_id(SHOW_ID, t!("tray.show"), true, None)).unwrap();
+    }
+
+    if !favorites.is_empty() {
+        menu.append(&PredefinedMenuItem::separator()).unwrap();
+        for name in favorites {
+            menu.append(&MenuItem::with_id(format!("{PROFILE_ID_PREFIX}{name}"), name, true, None)).unwrap();
+        }
+    }
+
+    if !profiles.is_empty() {
+        let submenu = Submenu::new(t!("tray.profiles"), true);
+        for name in profiles {
+            submenu.append(&MenuItem::with_id(format!("{PROFILE_ID_PREFIX}{name}"), name, true, None)).unwrap();
+        }
+        menu.append_items(&[&submenu, &PredefinedMenuItem::separator()]).unwrap();
+    }
+
+    menu.append(&CheckMenuItem::with_id(LIGHTS_OFF_ID, t!("tray.lights_off"), true, lights_off, None)).unwrap();
+
+    let focus_timer = Submenu::new(t!("tray.focus_timer"), true);
+    focus_timer
+        .append_items(&[
+            &MenuItem::with_id(FOCUS_START_PAUSE_ID, t!("tray.focus_start_pause"), true, None),
+            &MenuItem::with_id(FOCUS_SKIP_ID, t!("tray.focus_skip"), true, None),
+            &MenuItem::with_id(FOCUS_STOP_ID, t!("tray.focus_stop"), true, None),
+        ])
+        .unwrap();
+    menu.append_items(&[&focus_timer, &PredefinedMenuItem::separator()]).unwrap();
+
+    menu.append(&MenuItem::with_id(QUIT_ID, t!("tray.quit"), true, None)).unwrap();
+    menu
+}
+
+#[must_use]
+fn load_icon(health: &Health) -> Icon {
+    let image = super::icon(health);
+    let (width, height) = image.dimensions();
+
+    Icon::from_rgba(image.into_raw(), width, height).unwrap()
+}
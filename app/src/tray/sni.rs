@@ -0,0 +1,246 @@
+//! Talks to the desktop's StatusNotifierItem host over D-Bus from its own thread, so unlike the other trays it
+//! doesn't need an event loop (or GTK) on our side.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use ksni::{
+    menu::{CheckmarkItem, StandardItem, SubMenu},
+    Handle, MenuItem, ToolTip, TrayService,
+};
+use rust_i18n::t;
+
+use super::TrayEvent;
+use crate::{
+    manager::health::{Health, SharedHealth},
+    pomodoro::PomodoroCommand,
+    DENY_HIDING,
+};
+
+pub struct Tray {
+    handle: Handle<Item>,
+    shown_profiles: Vec<String>,
+    shown_favorites: Vec<String>,
+    shown_lights_off: bool,
+}
+
+/// What the D-Bus thread reads from whenever the host asks for the icon or menu
+struct Item {
+    has_gui: bool,
+    /// Whether a host is there to show the item, without one the window can't be hidden as there would be no way to bring it back
+    shown: Arc<AtomicBool>,
+    health: Health,
+    profiles: Vec<String>,
+    favorites: Vec<String>,
+    lights_off: bool,
+}
+
+impl Tray {
+    /// Start offering the item over D-Bus, `shown` being kept up to date with whether a host is showing it. Hosts can come and go,
+    /// like when the panel gets restarted, so the item stays around waiting for one either way.
+    pub fn build(has_gui: bool, shown: Arc<AtomicBool>) -> Option<Self> {
+        let service = TrayService::new(Item {
+            has_gui,
+            shown,
+            health: Health::Normal,
+            profiles: Vec::new(),
+            favorites: Vec::new(),
+            lights_off: false,
+        });
+        let handle = service.handle();
+        service.spawn();
+
+        Some(Self {
+            handle,
+            shown_profiles: Vec::new(),
+            shown_favorites: Vec::new(),
+            shown_lights_off: false,
+        })
+    }
+
+    /// Reflect the state of the effect engine published by the app on the icon and tooltip. This happens from a
+    /// thread of its own, so the tray can show the app is waiting for the keyboard while it's still starting up.
+    pub fn follow_health(&self, health: &SharedHealth) {
+        let handle = self.handle.clone();
+        let changes = health.subscribe();
+        let current = health.get();
+
+        thread::spawn(move || {
+            handle.update(move |item| item.health = current);
+            for health in changes {
+                handle.update(move |item| item.health = health);
+            }
+        });
+    }
+
+    /// List the saved profiles in the menu so they can be switched to, the favorites right at its top
+    pub fn show_profiles(&mut self, profiles: Vec<String>, favorites: Vec<String>) {
+        if self.shown_profiles == profiles && self.shown_favorites == favorites {
+            return;
+        }
+
+        let (shown_profiles, shown_favorites) = (profiles.clone(), favorites.clone());
+        self.handle.update(move |item| {
+            item.profiles = shown_profiles;
+            item.favorites = shown_favorites;
+        });
+        self.shown_profiles = profiles;
+        self.shown_favorites = favorites;
+    }
+
+    /// Tick the item turning the lights off while they are
+    pub fn show_lights_off(&mut self, lights_off: bool) {
+        if self.shown_lights_off == lights_off {
+            return;
+        }
+
+        self.handle.update(move |item| item.lights_off = lights_off);
+        self.shown_lights_off = lights_off;
+    }
+}
+
+impl ksni::Tray for Item {
+    fn id(&self) -> String {
+        env!("CARGO_PKG_NAME").to_string()
+    }
+
+    fn title(&self) -> String {
+        super::TOOLTIP.to_string()
+    }
+
+    fn watcher_online(&self) {
+        self.shown.store(true, Ordering::SeqCst);
+    }
+
+    fn watcher_offine(&self) -> bool {
+        self.shown.store(false, Ordering::SeqCst);
+        // Keep waiting for a host to come back
+        true
+    }
+
+    fn icon_pixmap(&self) -> Vec<ksni::Icon> {
+        let image = super::icon(&self.health);
+        let (width, height) = image.dimensions();
+
+        // The spec wants ARGB32 in network byte order
+        let data = image.pixels().flat_map(|pixel| {
+            let [r, g, b, a] = pixel.0;
+            [a, r, g, b]
+        });
+
+        vec![ksni::Icon {
+            width: width as i32,
+            height: height as i32,
+            data: data.collect(),
+        }]
+    }
+
+    fn tool_tip(&self) -> ToolTip {
+        ToolTip {
+            title: super::tooltip(&self.health),
+            ..ToolTip::default()
+        }
+    }
+
+    /// Clicking the icon itself brings the window back
+    fn activate(&mut self, _x: i32, _y: i32) {
+        if self.has_gui && !*DENY_HIDING {
+            super::send(TrayEvent::Show);
+        }
+    }
+
+    fn menu(&self) -> Vec<MenuItem<Self>> {
+        let mut menu = Vec::new();
+
+        if self.has_gui && !*DENY_HIDING {
+            menu.push(
+                StandardItem {
+                    label: t!("tray.show"),
+                    activate: Box::new(|_| super::send(TrayEvent::Show)),
+                    ..StandardItem::default()
+                }
+                .into(),
+            );
+        }
+
+        let profile_item = |name: &String| -> MenuItem<Self> {
+            let event = TrayEvent::LoadProfile(name.clone());
+            StandardItem {
+                label: name.clone(),
+                activate: Box::new(move |_| super::send(event.clone())),
+                ..StandardItem::default()
+            }
+            .into()
+        };
+
+        if !self.favorites.is_empty() {
+            menu.push(MenuItem::Separator);
+            menu.extend(self.favorites.iter().map(profile_item));
+        }
+
+        if !self.profiles.is_empty() {
+            let submenu = self.profiles.iter().map(profile_item).collect();
+
+            menu.push(
+                SubMenu {
+                    label: t!("tray.profiles"),
+                    submenu,
+                    ..SubMenu::default()
+                }
+                .into(),
+            );
+            menu.push(MenuItem::Separator);
+        }
+
+        menu.push(
+            CheckmarkItem {
+                label: t!("tray.lights_off"),
+                checked: self.lights_off,
+                activate: Box::new(|_| super::send(TrayEvent::ToggleLights)),
+                ..CheckmarkItem::default()
+            }
+            .into(),
+        );
+
+        let focus_timer = [
+            (t!("tray.focus_start_pause"), PomodoroCommand::StartPause),
+            (t!("tray.focus_skip"), PomodoroCommand::Skip),
+            (t!("tray.focus_stop"), PomodoroCommand::Stop),
+        ]
+        .into_iter()
+        .map(|(label, command)| {
+            StandardItem {
+                label,
+                activate: Box::new(move |_| super::send(TrayEvent::Pomodoro(command))),
+                ..StandardItem::default()
+            }
+            .into()
+        })
+        .collect();
+        menu.push(
+            SubMenu {
+                label: t!("tray.focus_timer"),
+                submenu: focus_timer,
+                ..SubMenu::default()
+            }
+            .into(),
+        );
+        menu.push(MenuItem::Separator);
+
+        menu.push(
+            StandardItem {
+                label: t!("tray.quit"),
+                activate: Box::new(|_| super::send(TrayEvent::Quit)),
+                ..StandardItem::default()
+            }
+            .into(),
+        );
+
+        menu
+    }
+}
@@ -0,0 +1,142 @@
+//! Named alerts that flash the keyboard and then go back to what was playing.
+//!
+//! A trigger fires either when its webhook is called through the HTTP API (`POST /trigger/{name}`), or when a URL it
+//! polls starts answering with some text in it, like an unread count going above zero on a mail or calendar feed.
+
+use serde::{Deserialize, Serialize};
+use strum_macros::{EnumIter, IntoStaticStr};
+
+use crate::manager::flash::FlashPattern;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, EnumIter, IntoStaticStr)]
+pub enum TriggerKind {
+    #[default]
+    #[strum(serialize = "Webhook")]
+    Webhook,
+    #[strum(serialize = "Polled URL")]
+    PolledUrl,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Trigger {
+    /// What the webhook is called by, and how the trigger is shown
+    pub name: String,
+    pub kind: TriggerKind,
+    /// The `http://` URL to poll
+    pub url: String,
+    /// Text that fires the trigger when it shows up in the response, any successful response does if empty
+    pub contains: String,
+    pub interval_secs: u32,
+    pub pattern: FlashPattern,
+}
+
+impl Default for Trigger {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            kind: TriggerKind::default(),
+            url: String::new(),
+            contains: String::new(),
+            interval_secs: 60,
+            pattern: FlashPattern {
+                rgb: [255, 0, 0],
+                count: 3,
+                interval: 200,
+            },
+        }
+    }
+}
+
+#[cfg(all(feature = "gui", feature = "network"))]
+pub use poller::TriggerPoller;
+
+#[cfg(all(feature = "gui", feature = "network"))]
+mod poller {
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, Mutex,
+        },
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use tracing::warn;
+
+    use super::{Trigger, TriggerKind};
+    use crate::{ticks, util};
+
+    const TICK: Duration = Duration::from_secs(1);
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Polls the URLs of the triggers until dropped
+    pub struct TriggerPoller {
+        triggers: Arc<Mutex<Vec<Trigger>>>,
+        stop: Arc<AtomicBool>,
+    }
+
+    impl TriggerPoller {
+        /// Call `on_fire` with the name of every polled trigger that matches after not matching before
+        pub fn spawn(triggers: Vec<Trigger>, on_fire: impl Fn(String) + Send + 'static) -> Self {
+            let triggers = Arc::new(Mutex::new(triggers));
+            let stop = Arc::new(AtomicBool::new(false));
+
+            {
+                let triggers = triggers.clone();
+                let stop = stop.clone();
+                thread::spawn(move || {
+                    // When each trigger was last polled and whether it matched, so a match only fires once
+                    let mut polled: HashMap<String, (Instant, bool)> = HashMap::new();
+
+                    while !stop.load(Ordering::SeqCst) {
+                        let triggers = triggers.lock().unwrap().clone();
+                        polled.retain(|name, _| triggers.iter().any(|trigger| &trigger.name == name));
+
+                        // Nothing gets polled while working offline, picking up again once back online
+                        for trigger in triggers.iter().filter(|trigger| trigger.kind == TriggerKind::PolledUrl && !trigger.url.is_empty() && util::is_online()) {
+                            let interval = Duration::from_secs(u64::from(trigger.interval_secs.max(5)));
+                            let previous = polled.get(&trigger.name).copied();
+                            if previous.is_some_and(|(at, _)| at.elapsed() < interval) {
+                                continue;
+                            }
+
+                            let matched = matches(trigger);
+                            if matched && !previous.is_some_and(|(_, matched)| matched) {
+                                on_fire(trigger.name.clone());
+                            }
+                            polled.insert(trigger.name.clone(), (Instant::now(), matched));
+                        }
+
+                        ticks::sleep_until_next(TICK);
+                    }
+                });
+            }
+
+            Self { triggers, stop }
+        }
+
+        pub fn set_triggers(&self, triggers: Vec<Trigger>) {
+            *self.triggers.lock().unwrap() = triggers;
+        }
+    }
+
+    impl Drop for TriggerPoller {
+        fn drop(&mut self) {
+            self.stop.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn matches(trigger: &Trigger) -> bool {
+        match ureq::get(&trigger.url).timeout(TIMEOUT).call().and_then(|response| Ok(response.into_string()?)) {
+            Ok(body) => body.contains(&trigger.contains),
+            // Answering with an error is an answer too, just not a match
+            Err(ureq::Error::Status(..)) => false,
+            Err(err) => {
+                warn!("Could not poll {}: {err:?}", trigger.url);
+                false
+            }
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! Gives regular users access to the keyboard on Linux, through a udev rule covering every supported model along with the
+//! logo and lightbar LEDs.
+
+use std::{
+    collections::BTreeSet,
+    fmt::Write as _,
+    fs,
+    io::Write as _,
+    process::{Command, Stdio},
+};
+
+use error_stack::{Report, Result, ResultExt};
+use legion_kb_rgb_hw::{lights::Section, models::MODELS};
+use thiserror::Error;
+
+/// Where the rule is installed, the same place the README suggests
+pub const RULE_PATH: &str = "/etc/udev/rules.d/99-kblight.rules";
+
+/// Runs as root through pkexec with the destination as argument, reading the rule from stdin so there's no file in between that
+/// another user could swap out
+const INSTALL_SCRIPT: &str = r#"tee "$1" > /dev/null && chmod 644 "$1" && udevadm control --reload-rules && udevadm trigger"#;
+
+#[derive(Debug, Error)]
+#[error("Could not install the udev rule")]
+pub struct InstallRuleError;
+
+/// The rule for every supported model, covering both the USB device used by hidapi and its hidraw nodes, and for the LEDs of
+/// the logo and lightbar
+pub fn rule() -> String {
+    let ids: BTreeSet<(u16, u16)> = MODELS.iter().map(|model| (model.vendor_id, model.product_id)).collect();
+
+    let mut rule = String::from("# Installed by Legion Keyboard Control, lets regular users control the keyboard lighting\n");
+    for (vendor_id, product_id) in ids {
+        let _ = writeln!(rule, r#"SUBSYSTEM=="usb", ATTR{{idVendor}}=="{vendor_id:04x}", ATTR{{idProduct}}=="{product_id:04x}", MODE="0666""#);
+        let _ = writeln!(rule, r#"KERNEL=="hidraw*", ATTRS{{idVendor}}=="{vendor_id:04x}", ATTRS{{idProduct}}=="{product_id:04x}", MODE="0666""#);
+    }
+
+    // Sysfs attributes don't take a MODE, their permissions have to be changed once the LED shows up
+    let leds: Vec<&str> = Section::ALL.iter().map(|section| section.led_name()).collect();
+    let _ = writeln!(rule, r#"SUBSYSTEM=="leds", KERNEL=="{}", RUN+="/bin/chmod a+w /sys%p/brightness""#, leds.join("|"));
+
+    rule
+}
+
+/// Write the rule to [`RULE_PATH`] and reload the rules, asking for the password through pkexec unless already root
+pub fn install() -> Result<(), InstallRuleError> {
+    let rule = rule();
+
+    if fs::write(RULE_PATH, &rule).is_ok() {
+        return run(Command::new("sh").args(["-c", "udevadm control --reload-rules && udevadm trigger"]), None);
+    }
+
+    run(Command::new("pkexec").args(["sh", "-c", INSTALL_SCRIPT, "sh", RULE_PATH]), Some(&rule))
+        .attach_printable_lazy(|| format!("The rule can also be installed by hand, save the following to {RULE_PATH}:\n{rule}"))
+}
+
+/// Run the command to completion, writing `input` to its stdin if given
+fn run(command: &mut Command, input: Option<&str>) -> Result<(), InstallRuleError> {
+    let mut child = command
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .spawn()
+        .change_context(InstallRuleError)
+        .attach_printable("Ensure that pkexec (polkit) and udevadm are installed")?;
+
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        // Dropped right after, so the script sees the end of the rule
+        stdin.write_all(input.as_bytes()).change_context(InstallRuleError)?;
+    }
+
+    let status = child.wait().change_context(InstallRuleError)?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Report::new(InstallRuleError).attach_printable(format!("The command exited with {status}")))
+    }
+}
@@ -0,0 +1,183 @@
+//! Looks for a newer release on GitHub, to show what changed and get it from within the app.
+//!
+//! The portable Windows build, the executable on its own, can also replace itself with the new one. Builds put in place
+//! by the installer or a package manager are left to it, and only get a link to the download. Nothing is checked while
+//! working offline.
+
+/// A release newer than the one running
+#[cfg(feature = "gui")]
+#[cfg_attr(not(feature = "network"), allow(dead_code))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    pub version: String,
+    /// The release notes, written in Markdown
+    pub changelog: String,
+    /// The page of the release on GitHub
+    pub page_url: String,
+    /// The file standing in for the running executable, if the release has one for this platform
+    pub download_url: Option<String>,
+    /// The SHA-256 GitHub published for that file, in hex, which it has to match to be installed
+    pub sha256: Option<String>,
+}
+
+#[cfg(feature = "gui")]
+#[cfg_attr(not(feature = "network"), allow(dead_code))]
+impl Release {
+    /// Where to get the release from in a browser, the page of the release if there's no file for this platform
+    pub fn download_link(&self) -> &str {
+        self.download_url.as_deref().unwrap_or(&self.page_url)
+    }
+}
+
+#[cfg(all(feature = "gui", feature = "network"))]
+pub use client::{can_self_update, check, install, UpdateError};
+
+#[cfg(all(feature = "gui", feature = "network"))]
+mod client {
+    use std::{
+        env,
+        path::{Path, PathBuf},
+        time::Duration,
+    };
+
+    use error_stack::{Report, Result, ResultExt};
+    use serde::Deserialize;
+    use thiserror::Error;
+
+    use super::Release;
+    use crate::util::{is_online, parse_version};
+
+    const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/4JX/L5P-Keyboard-RGB/releases/latest";
+    const TIMEOUT: Duration = Duration::from_secs(15);
+
+    #[derive(Debug, Error)]
+    #[error("Could not update")]
+    pub struct UpdateError;
+
+    #[derive(Deserialize)]
+    struct GithubRelease {
+        tag_name: String,
+        #[serde(default)]
+        body: Option<String>,
+        html_url: String,
+        #[serde(default)]
+        assets: Vec<GithubAsset>,
+    }
+
+    #[derive(Deserialize)]
+    struct GithubAsset {
+        name: String,
+        browser_download_url: String,
+        /// `sha256:` followed by the hash of the file, filled in by GitHub for every upload
+        #[serde(default)]
+        digest: Option<String>,
+    }
+
+    fn agent() -> ureq::Agent {
+        ureq::AgentBuilder::new()
+            .timeout(TIMEOUT)
+            .user_agent(concat!("legion-kb-rgb/", env!("CARGO_PKG_VERSION")))
+            .build()
+    }
+
+    /// Ask GitHub for the latest release, `None` if it isn't newer than the one running
+    pub fn check() -> Result<Option<Release>, UpdateError> {
+        if !is_online() {
+            return Err(Report::new(UpdateError)).attach_printable("Working offline");
+        }
+
+        let body = agent()
+            .get(LATEST_RELEASE_URL)
+            .set("Accept", "application/vnd.github+json")
+            .call()
+            .change_context(UpdateError)?
+            .into_string()
+            .change_context(UpdateError)?;
+        let latest: GithubRelease = serde_json::from_str(&body).change_context(UpdateError).attach_printable("Unexpected response from GitHub")?;
+
+        let version = latest.tag_name.trim_start_matches('v').to_string();
+        let current = parse_version(env!("CARGO_PKG_VERSION"));
+        if !parse_version(&version).is_some_and(|version| current.is_some_and(|current| version > current)) {
+            return Ok(None);
+        }
+
+        // The assets are named after the executable they replace, a renamed one on Windows still finds the `.exe`
+        let executable = current_exe().and_then(|exe| exe.file_name().map(|name| name.to_string_lossy().into_owned()));
+        let asset = latest
+            .assets
+            .iter()
+            .find(|asset| executable.as_deref() == Some(asset.name.as_str()))
+            .or_else(|| latest.assets.iter().find(|asset| cfg!(target_os = "windows") && asset.name.ends_with(".exe")));
+
+        Ok(Some(Release {
+            version,
+            changelog: latest.body.unwrap_or_default(),
+            page_url: latest.html_url,
+            download_url: asset.map(|asset| asset.browser_download_url.clone()),
+            sha256: asset.and_then(|asset| asset.digest.as_deref()?.strip_prefix("sha256:")).map(str::to_ascii_lowercase),
+        }))
+    }
+
+    fn current_exe() -> Option<PathBuf> {
+        env::current_exe().ok()
+    }
+
+    /// Whether the running executable can be swapped for the one of `release`, only for the portable Windows build as
+    /// the installed one belongs to the installer
+    pub fn can_self_update(release: &Release) -> bool {
+        cfg!(target_os = "windows") && release.download_url.is_some() && current_exe().is_some_and(|exe| !is_installed(&exe))
+    }
+
+    fn is_installed(exe: &Path) -> bool {
+        ["ProgramFiles", "ProgramFiles(x86)", "ProgramW6432"]
+            .into_iter()
+            .filter_map(env::var_os)
+            .any(|folder| exe.starts_with(folder))
+    }
+
+    /// Download the executable of `release` and put it in place of the running one, which keeps going until restarted
+    ///
+    /// The download has to match the hash published with the release, and is kept in a temporary file only this user
+    /// can get to until then, so nothing else gets a chance to swap it.
+    #[cfg(target_os = "windows")]
+    pub fn install(release: &Release) -> Result<(), UpdateError> {
+        use std::io::{Read, Write};
+
+        use sha2::{Digest, Sha256};
+
+        if !is_online() {
+            return Err(Report::new(UpdateError)).attach_printable("Working offline");
+        }
+
+        let url = release
+            .download_url
+            .as_deref()
+            .ok_or_else(|| Report::new(UpdateError))
+            .attach_printable("The release has no executable for Windows")?;
+        let expected = release
+            .sha256
+            .as_deref()
+            .ok_or_else(|| Report::new(UpdateError))
+            .attach_printable("The release doesn't publish the hash of its executable")?;
+
+        let mut bytes = Vec::new();
+        agent().get(url).call().change_context(UpdateError)?.into_reader().read_to_end(&mut bytes).change_context(UpdateError)?;
+
+        let hash: String = Sha256::digest(&bytes).iter().map(|byte| format!("{byte:02x}")).collect();
+        if hash != expected {
+            return Err(Report::new(UpdateError)).attach_printable(format!("The download doesn't match the published hash, got {hash} instead of {expected}"));
+        }
+
+        // Removed once dropped, after it's been copied in place
+        let mut file = tempfile::Builder::new().prefix("legion-kb-rgb-").suffix(".exe").tempfile().change_context(UpdateError)?;
+        file.write_all(&bytes).change_context(UpdateError)?;
+        file.as_file().sync_all().change_context(UpdateError)?;
+
+        self_update::self_replace::self_replace(file.path()).change_context(UpdateError)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    pub fn install(_release: &Release) -> Result<(), UpdateError> {
+        Err(Report::new(UpdateError)).attach_printable("Only the portable Windows build can update itself")
+    }
+}
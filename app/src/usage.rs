@@ -0,0 +1,135 @@
+//! Local-only record of how long each profile and effect has been in use, and of how many keys have been pressed in each
+//! zone when that's enabled.
+
+use std::collections::BTreeMap;
+#[cfg(feature = "gui")]
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "gui")]
+use crate::manager::keystrokes;
+
+/// Accumulated usage time, in milliseconds so switching often doesn't lose the time in between whole seconds
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsageStats {
+    pub profile_ms: BTreeMap<String, u64>,
+    pub effect_ms: BTreeMap<String, u64>,
+    /// Keys pressed in each zone, from left to right
+    pub keystrokes: [u64; 4],
+    /// Seconds, as kept by older versions, added to the milliseconds when loaded
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    #[serde(rename = "profiles", skip_serializing)]
+    legacy_profiles: BTreeMap<String, u64>,
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    #[serde(rename = "effects", skip_serializing)]
+    legacy_effects: BTreeMap<String, u64>,
+}
+
+#[cfg(feature = "gui")]
+impl UsageStats {
+    /// The most used profile and how many seconds it was used for
+    pub fn most_used_profile(&self) -> Option<(&str, u64)> {
+        self.profile_ms.iter().max_by_key(|(_, ms)| **ms).map(|(name, ms)| (name.as_str(), ms / 1000))
+    }
+
+    /// Effects and how many seconds they were used for, sorted from most to least used
+    pub fn effects_by_usage(&self) -> Vec<(&str, u64)> {
+        let mut effects: Vec<(&str, u64)> = self.effect_ms.iter().map(|(name, ms)| (name.as_str(), ms / 1000)).collect();
+        effects.sort_by(|a, b| b.1.cmp(&a.1));
+        effects
+    }
+
+    fn migrate(&mut self) {
+        for (name, secs) in std::mem::take(&mut self.legacy_profiles) {
+            *self.profile_ms.entry(name).or_default() += secs * 1000;
+        }
+        for (name, secs) in std::mem::take(&mut self.legacy_effects) {
+            *self.effect_ms.entry(name).or_default() += secs * 1000;
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+struct Span {
+    profile: Option<String>,
+    effect: String,
+    start: Instant,
+}
+
+#[cfg(feature = "gui")]
+pub struct UsageRecorder {
+    stats: UsageStats,
+    current: Option<Span>,
+}
+
+#[cfg(feature = "gui")]
+impl UsageRecorder {
+    pub fn new(mut stats: UsageStats) -> Self {
+        stats.migrate();
+        keystrokes::restore(stats.keystrokes);
+        Self { stats, current: None }
+    }
+
+    /// Start counting time for a new profile/effect combination, closing the previous one. Nothing changes if it's the
+    /// one already being counted.
+    pub fn record(&mut self, profile: Option<String>, effect: String) {
+        if self.current.as_ref().is_some_and(|span| span.profile == profile && span.effect == effect) {
+            return;
+        }
+
+        self.close_span();
+        self.current = Some(Span {
+            profile,
+            effect,
+            start: Instant::now(),
+        });
+    }
+
+    /// Get the accumulated stats, including the time spent on what's currently active
+    pub fn snapshot(&self) -> UsageStats {
+        let mut stats = self.stats.clone();
+        if let Some(span) = &self.current {
+            Self::add_span(&mut stats, span);
+        }
+        stats.keystrokes = keystrokes::counts();
+        stats
+    }
+
+    /// Carry the time spent on a profile over to its new name
+    pub fn rename_profile(&mut self, old: &str, new: &str) {
+        if let Some(ms) = self.stats.profile_ms.remove(old) {
+            *self.stats.profile_ms.entry(new.to_string()).or_default() += ms;
+        }
+
+        if let Some(span) = &mut self.current {
+            if span.profile.as_deref() == Some(old) {
+                span.profile = Some(new.to_string());
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.stats = UsageStats::default();
+        keystrokes::reset();
+        if let Some(span) = &mut self.current {
+            span.start = Instant::now();
+        }
+    }
+
+    fn close_span(&mut self) {
+        if let Some(span) = self.current.take() {
+            Self::add_span(&mut self.stats, &span);
+        }
+    }
+
+    fn add_span(stats: &mut UsageStats, span: &Span) {
+        let ms = span.start.elapsed().as_millis() as u64;
+
+        if let Some(profile) = &span.profile {
+            *stats.profile_ms.entry(profile.clone()).or_default() += ms;
+        }
+        *stats.effect_ms.entry(span.effect.clone()).or_default() += ms;
+    }
+}
@@ -1,4 +1,3 @@
-use eframe::egui::Ui;
 use error_stack::{Result, ResultExt};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{fs::File, io::Write, path::Path};
@@ -36,8 +35,92 @@ where
     }
 }
 
-pub fn clickable_link(ui: &mut Ui, url: &str) {
-    if ui.link(url).clicked() {
-        open::that(url).unwrap();
+/// Convert a color from HSV (hue in degrees, saturation and value from 0 to 1) to RGB
+pub fn hsv_to_rgb(hue: f32, saturation: f32, value: f32) -> [u8; 3] {
+    let hue = hue.rem_euclid(360.0);
+    let saturation = saturation.clamp(0.0, 1.0);
+    let value = value.clamp(0.0, 1.0);
+
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+    let m = value - chroma;
+
+    let (r, g, b) = match (hue / 60.0) as u8 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+
+    [r, g, b].map(|channel| ((channel + m) * 255.0).round() as u8)
+}
+
+/// Convert a color from RGB to HSV, the inverse of [`hsv_to_rgb`]
+pub fn rgb_to_hsv(rgb: [u8; 3]) -> (f32, f32, f32) {
+    let [r, g, b] = rgb.map(|channel| f32::from(channel) / 255.0);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    let saturation = if max == 0.0 { 0.0 } else { delta / max };
+
+    (hue, saturation, max)
+}
+
+/// Cleared while working offline, see [`set_online`]
+#[cfg(feature = "network")]
+static ONLINE: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(true);
+
+/// Let everything that fetches from the internet do so, from the update checks to the weather and polled triggers
+#[cfg(feature = "network")]
+pub fn set_online(online: bool) {
+    ONLINE.store(online, std::sync::atomic::Ordering::SeqCst);
+}
+
+/// Whether the internet can be reached, anything fetching from it checks this first
+#[cfg(feature = "network")]
+pub fn is_online() -> bool {
+    ONLINE.load(std::sync::atomic::Ordering::SeqCst)
+}
+
+/// Versions are compared by their numbers, e.g. `0.20.1` or `v0.20.1`
+pub fn parse_version(version: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = version.trim_start_matches('v').splitn(3, '.').map(|part| part.parse::<u32>().ok());
+    Some((parts.next()??, parts.next()??, parts.next()??))
+}
+
+/// Decode `%20` and the like, as used in URL paths and `file://` URIs. A `+` is left alone, as it only stands for a space
+/// in form data
+#[cfg(all(feature = "gui", any(feature = "network", target_os = "linux")))]
+pub fn percent_decode(input: &str) -> String {
+    let mut bytes = Vec::with_capacity(input.len());
+    let mut rest = input.as_bytes();
+
+    while let Some((&byte, tail)) = rest.split_first() {
+        let decoded = (byte == b'%').then(|| tail.get(..2)).flatten().and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+
+        match decoded {
+            Some(decoded) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            None => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
     }
+
+    String::from_utf8_lossy(&bytes).into_owned()
 }
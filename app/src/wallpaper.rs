@@ -0,0 +1,137 @@
+//! Finds the picture on the desktop, so the zone colors can be taken from it and follow it as it changes.
+//!
+//! On Linux the wallpaper is asked to GNOME (and the desktops built on it) through `gsettings`, or read from the
+//! configuration of KDE Plasma. On Windows it's asked to the system directly.
+
+#[cfg(feature = "gui")]
+use std::{
+    path::PathBuf,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+#[cfg(feature = "gui")]
+use crossbeam_channel::{RecvTimeoutError, Sender};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "gui")]
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WallpaperSettings {
+    /// Take the zone colors from the wallpaper, again whenever it changes
+    pub follow: bool,
+}
+
+/// Watches the wallpaper until dropped
+#[cfg(feature = "gui")]
+pub struct WallpaperWatcher {
+    /// Dropped to tell the watching thread to stop
+    stop: Option<Sender<()>>,
+}
+
+#[cfg(feature = "gui")]
+impl Drop for WallpaperWatcher {
+    fn drop(&mut self) {
+        self.stop.take();
+    }
+}
+
+/// Spawn a background thread that calls `on_change` with the path of the wallpaper when starting and whenever it changes
+///
+/// The same file being replaced, as some desktops do with slideshows, counts as a change too.
+#[cfg(feature = "gui")]
+pub fn spawn_watcher<F: Fn(PathBuf) + Send + 'static>(on_change: F) -> WallpaperWatcher {
+    let (stop_tx, stop_rx) = crossbeam_channel::bounded::<()>(0);
+
+    thread::spawn(move || {
+        let mut last: Option<(PathBuf, Option<SystemTime>)> = None;
+
+        loop {
+            let found = current().map(|path| {
+                let modified = path.metadata().and_then(|metadata| metadata.modified()).ok();
+                (path, modified)
+            });
+            if let Some((path, _)) = found.as_ref().filter(|found| last.as_ref() != Some(*found)) {
+                on_change(path.clone());
+            }
+            last = found;
+
+            if stop_rx.recv_timeout(POLL_INTERVAL) != Err(RecvTimeoutError::Timeout) {
+                return;
+            }
+        }
+    });
+
+    WallpaperWatcher { stop: Some(stop_tx) }
+}
+
+/// The picture currently on the desktop, if it can be found
+#[cfg(feature = "gui")]
+pub fn current() -> Option<PathBuf> {
+    read().filter(|path| path.is_file())
+}
+
+#[cfg(all(feature = "gui", target_os = "linux"))]
+fn read() -> Option<PathBuf> {
+    use std::{fs, process::Command};
+
+    let gsettings = |key: &str| {
+        let output = Command::new("gsettings").args(["get", "org.gnome.desktop.background", key]).output().ok()?;
+        let value = String::from_utf8_lossy(&output.stdout).trim().trim_matches('\'').to_string();
+        (!value.is_empty()).then_some(value)
+    };
+
+    // GNOME keeps a separate picture for the dark style
+    let dark = Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.interface", "color-scheme"])
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains("dark"));
+    let gnome = if dark { gsettings("picture-uri-dark").or_else(|| gsettings("picture-uri")) } else { gsettings("picture-uri") };
+    if let Some(path) = gnome.map(|uri| from_uri(&uri)).filter(|path| path.is_file()) {
+        return Some(path);
+    }
+
+    // KDE Plasma keeps it with the settings of the desktop, the last one listed being the most recent
+    let config = fs::read_to_string(dirs::config_dir()?.join("plasma-org.kde.plasma.desktop-appletsrc")).ok()?;
+    config.lines().filter_map(|line| line.strip_prefix("Image=")).last().map(from_uri)
+}
+
+#[cfg(all(feature = "gui", target_os = "windows"))]
+fn read() -> Option<PathBuf> {
+    use std::{ffi::OsString, os::windows::ffi::OsStringExt};
+
+    use winapi::{
+        shared::minwindef::MAX_PATH,
+        um::winuser::{SystemParametersInfoW, SPI_GETDESKWALLPAPER},
+    };
+
+    let mut buffer = [0_u16; MAX_PATH];
+    let success = unsafe { SystemParametersInfoW(SPI_GETDESKWALLPAPER, MAX_PATH as u32, buffer.as_mut_ptr().cast(), 0) };
+    if success == 0 {
+        return None;
+    }
+
+    let len = buffer.iter().position(|c| *c == 0).unwrap_or(buffer.len());
+    let path = PathBuf::from(OsString::from_wide(&buffer[..len]));
+    if path.is_file() {
+        return Some(path);
+    }
+
+    // Slideshows and pictures from Windows Spotlight only leave the copy Windows makes of them
+    Some(dirs::config_dir()?.join("Microsoft").join("Windows").join("Themes").join("TranscodedWallpaper"))
+}
+
+#[cfg(all(feature = "gui", not(any(target_os = "linux", target_os = "windows"))))]
+fn read() -> Option<PathBuf> {
+    None
+}
+
+/// Turn a `file://` URI into a path, undoing the escaping of spaces and other characters
+#[cfg(all(feature = "gui", target_os = "linux"))]
+fn from_uri(uri: &str) -> PathBuf {
+    let path = uri.strip_prefix("file://").unwrap_or(uri);
+
+    PathBuf::from(crate::util::percent_decode(path))
+}
@@ -0,0 +1,131 @@
+//! Current weather conditions for the `Weather` effect, from Open-Meteo (no key needed) or OpenWeatherMap.
+
+use serde::{Deserialize, Serialize};
+use strum_macros::{EnumIter, IntoStaticStr};
+
+/// Where the conditions are fetched from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, EnumIter, IntoStaticStr)]
+pub enum WeatherProvider {
+    #[default]
+    #[strum(serialize = "Open-Meteo")]
+    OpenMeteo,
+    #[strum(serialize = "OpenWeatherMap")]
+    OpenWeatherMap,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WeatherSettings {
+    pub provider: WeatherProvider,
+    /// Only needed by OpenWeatherMap
+    pub api_key: String,
+}
+
+/// The broad kind of weather, which picks the animation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeatherKind {
+    Clear,
+    Clouds,
+    Fog,
+    Rain,
+    Snow,
+    Storm,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Conditions {
+    pub kind: WeatherKind,
+    /// In degrees Celsius
+    pub temperature: f32,
+}
+
+impl WeatherKind {
+    /// From the WMO weather interpretation codes used by Open-Meteo
+    fn from_wmo(code: u32) -> Self {
+        match code {
+            0 => Self::Clear,
+            1..=3 => Self::Clouds,
+            45 | 48 => Self::Fog,
+            71..=77 | 85 | 86 => Self::Snow,
+            95..=99 => Self::Storm,
+            _ => Self::Rain,
+        }
+    }
+
+    /// From the condition ids of OpenWeatherMap, grouped by their first digit
+    fn from_owm(id: u32) -> Self {
+        match id {
+            200..=299 => Self::Storm,
+            600..=699 => Self::Snow,
+            700..=799 => Self::Fog,
+            800 => Self::Clear,
+            801.. => Self::Clouds,
+            _ => Self::Rain,
+        }
+    }
+}
+
+#[cfg(feature = "network")]
+pub use fetch::{fetch, WeatherError};
+
+#[cfg(feature = "network")]
+mod fetch {
+    use std::{
+        sync::Mutex,
+        time::{Duration, Instant},
+    };
+
+    use error_stack::{Report, Result, ResultExt};
+    use serde_json::Value;
+    use thiserror::Error;
+
+    use super::{Conditions, WeatherKind, WeatherProvider, WeatherSettings};
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// The URL last fetched, when, and what it answered, so an effect restarting doesn't have to fetch the conditions again
+    static CACHE: Mutex<Option<(String, Instant, Conditions)>> = Mutex::new(None);
+
+    #[derive(Debug, Error)]
+    #[error("Could not get the current weather")]
+    pub struct WeatherError;
+
+    /// The current conditions, reusing the ones fetched less than `max_age` ago for the same place and provider
+    pub fn fetch(settings: &WeatherSettings, latitude: f32, longitude: f32, max_age: Duration) -> Result<Conditions, WeatherError> {
+        let url = match settings.provider {
+            WeatherProvider::OpenMeteo => format!("https://api.open-meteo.com/v1/forecast?latitude={latitude}&longitude={longitude}&current=temperature_2m,weather_code"),
+            WeatherProvider::OpenWeatherMap => {
+                if settings.api_key.is_empty() {
+                    return Err(Report::new(WeatherError).attach_printable("OpenWeatherMap needs an API key"));
+                }
+                format!("https://api.openweathermap.org/data/2.5/weather?lat={latitude}&lon={longitude}&units=metric&appid={}", settings.api_key)
+            }
+        };
+
+        if !crate::util::is_online() {
+            return Err(Report::new(WeatherError).attach_printable("Working offline"));
+        }
+        if let Some((_, _, conditions)) = CACHE.lock().unwrap().as_ref().filter(|(cached, fetched_at, _)| *cached == url && fetched_at.elapsed() < max_age) {
+            return Ok(*conditions);
+        }
+
+        let body = ureq::get(&url).timeout(TIMEOUT).call().change_context(WeatherError)?.into_string().change_context(WeatherError)?;
+        let json: Value = serde_json::from_str(&body).change_context(WeatherError)?;
+
+        let conditions = match settings.provider {
+            WeatherProvider::OpenMeteo => json["current"]["weather_code"].as_u64().zip(json["current"]["temperature_2m"].as_f64()).map(|(code, temperature)| Conditions {
+                kind: WeatherKind::from_wmo(code as u32),
+                temperature: temperature as f32,
+            }),
+            WeatherProvider::OpenWeatherMap => json["weather"][0]["id"].as_u64().zip(json["main"]["temp"].as_f64()).map(|(id, temperature)| Conditions {
+                kind: WeatherKind::from_owm(id as u32),
+                temperature: temperature as f32,
+            }),
+        };
+
+        let conditions = conditions.ok_or_else(|| Report::new(WeatherError)).attach_printable_lazy(|| format!("Unexpected response: {body}"))?;
+        *CACHE.lock().unwrap() = Some((url, Instant::now(), conditions));
+
+        Ok(conditions)
+    }
+}
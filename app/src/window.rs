@@ -0,0 +1,21 @@
+//! What happens to the window when it's minimized or closed while the tray icon is around.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct WindowSettings {
+    /// Hide the window instead of leaving it in the taskbar when minimized
+    pub minimize_to_tray: bool,
+    /// Hide the window instead of exiting when closed
+    pub close_to_tray: bool,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            minimize_to_tray: false,
+            close_to_tray: true,
+        }
+    }
+}
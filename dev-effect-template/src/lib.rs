@@ -0,0 +1,51 @@
+//! A starting point for prototyping effects, see `app/src/manager/dev_effect.rs` for how it gets loaded.
+
+/// Has to match the version in the app
+const ABI_VERSION: u32 = 1;
+
+#[repr(C)]
+pub struct FrameInput {
+    /// Milliseconds since the effect started, not reset by reloads
+    pub time_ms: u64,
+    /// Incremented every time the library gets reloaded, starting at 0
+    pub generation: u32,
+    pub speed: u8,
+    pub brightness: u8,
+    pub colors: [u8; 12],
+}
+
+/// Anything that has to outlive a frame goes here, statics are lost on every reload
+#[repr(C)]
+struct State {
+    offset: f32,
+}
+
+#[no_mangle]
+pub extern "C" fn l5p_effect_abi_version() -> u32 {
+    ABI_VERSION
+}
+
+/// # Safety
+///
+/// `input` has to be valid, `state` valid for `state_len` bytes and aligned to 8 bytes, and `rgb` valid for 12 bytes.
+#[no_mangle]
+pub unsafe extern "C" fn l5p_effect_frame(input: *const FrameInput, state: *mut u8, state_len: usize, rgb: *mut u8) -> u32 {
+    let input = &*input;
+    let rgb = std::slice::from_raw_parts_mut(rgb, 12);
+
+    if state_len < std::mem::size_of::<State>() {
+        rgb.fill(0);
+        return 1000;
+    }
+    let state = &mut *state.cast::<State>();
+
+    // Slide the profile's colors across the zones
+    state.offset = (state.offset + 0.01 * f32::from(input.speed)) % 4.0;
+    for zone in 0..4 {
+        let source = (zone + state.offset as usize) % 4;
+        rgb[zone * 3..zone * 3 + 3].copy_from_slice(&input.colors[source * 3..source * 3 + 3]);
+    }
+
+    // Milliseconds until the next frame
+    50
+}
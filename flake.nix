@@ -51,12 +51,6 @@
           xorg.libXi
           libusb1
           expat
-
-          # Tray icon
-          pango
-          gtk3
-          gdk-pixbuf
-          xdotool
         ];
 
         # Libraries needed at runtime
@@ -68,9 +62,6 @@
           libGL
           wayland
           libxkbcommon
-
-          # Tray icon
-          libayatana-appindicator
         ] ++ sharedDeps;
 
         envVars = rec {
@@ -0,0 +1,51 @@
+//! Color correction for keyboards that show colors differently than they were picked.
+
+/// Per channel gain and gamma, applied to the zone colors right before they're sent
+///
+/// Each channel goes through `255 * gain * (value / 255) ^ gamma`, so the default of 1 for both leaves colors as is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Calibration {
+    /// Red, green and blue multipliers, from 0 to 1 to tone down the channels that come out too strong
+    pub gain: [f32; 3],
+    /// Red, green and blue exponents, above 1 to darken the mid tones
+    pub gamma: [f32; 3],
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self { gain: [1.0; 3], gamma: [1.0; 3] }
+    }
+}
+
+impl Calibration {
+    /// Whether the calibration leaves every color as is
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Correct the colors of the four zones
+    pub fn apply(&self, rgb_values: [u8; 12]) -> [u8; 12] {
+        if self.is_identity() {
+            return rgb_values;
+        }
+
+        let mut corrected = rgb_values;
+        for (i, value) in corrected.iter_mut().enumerate() {
+            *value = self.correct(i % 3, *value);
+        }
+
+        corrected
+    }
+
+    /// Correct the color of a single zone
+    pub fn apply_zone(&self, rgb: [u8; 3]) -> [u8; 3] {
+        [0, 1, 2].map(|channel| self.correct(channel, rgb[channel]))
+    }
+
+    fn correct(&self, channel: usize, value: u8) -> u8 {
+        let normalized = f32::from(value) / 255.0;
+        let gamma = self.gamma[channel].max(0.01);
+        (normalized.powf(gamma) * self.gain[channel].clamp(0.0, 1.0) * 255.0).round() as u8
+    }
+}
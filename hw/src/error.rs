@@ -6,6 +6,8 @@ use thiserror::Error;
 pub enum Error {
     #[error("HidError: {}", .0)]
     HidError(#[from] HidError),
+    #[error("IoError: {}", .0)]
+    IoError(#[from] std::io::Error),
     #[error("Error: Couldn't find device")]
     DeviceNotFound,
     #[error("Error: {}", .0)]
@@ -22,6 +24,8 @@ pub struct RangeError {
 
 #[derive(Debug)]
 pub enum RangeErrorKind {
+    /// The model has no such hardware effect
+    Effect,
     Zone,
     Speed,
     Brightness,
@@ -0,0 +1,131 @@
+//! Talks to the keyboard through `/dev/hidraw*` directly, without hidapi or libusb.
+//!
+//! The kernel already exposes the lighting interface as a hidraw node, which only needs a udev rule to be opened by
+//! regular users. Everything else needed to pick the right node is read from sysfs.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io,
+    os::fd::AsRawFd,
+    path::Path,
+};
+
+use crate::{models::DeviceIds, Candidate};
+
+const SYSFS_HIDRAW: &str = "/sys/class/hidraw";
+
+/// `HIDIOCSFEATURE(len)` from `linux/hidraw.h`
+const fn hidiocsfeature(len: usize) -> libc::c_ulong {
+    const IOC_READ_WRITE: libc::c_ulong = 3;
+
+    (IOC_READ_WRITE << 30) | ((len as libc::c_ulong) << 16) | ((b'H' as libc::c_ulong) << 8) | 0x06
+}
+
+/// `HIDIOCGFEATURE(len)` from `linux/hidraw.h`
+const fn hidiocgfeature(len: usize) -> libc::c_ulong {
+    const IOC_READ_WRITE: libc::c_ulong = 3;
+
+    (IOC_READ_WRITE << 30) | ((len as libc::c_ulong) << 16) | ((b'H' as libc::c_ulong) << 8) | 0x07
+}
+
+pub(crate) struct HidrawDevice {
+    file: File,
+}
+
+impl HidrawDevice {
+    pub(crate) fn open(path: &str) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+
+        Ok(Self { file })
+    }
+
+    pub(crate) fn send_feature_report(&self, data: &[u8]) -> io::Result<()> {
+        // SAFETY: The buffer outlives the call and its length is part of the request
+        let result = unsafe { libc::ioctl(self.file.as_raw_fd(), hidiocsfeature(data.len()) as _, data.as_ptr()) };
+
+        if result < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Read a feature report into `buf`, whose first byte is the report ID, returning how many bytes were read
+    pub(crate) fn get_feature_report(&self, buf: &mut [u8]) -> io::Result<usize> {
+        // SAFETY: The buffer outlives the call and its length is part of the request
+        let result = unsafe { libc::ioctl(self.file.as_raw_fd(), hidiocgfeature(buf.len()) as _, buf.as_mut_ptr()) };
+
+        if result < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(result as usize)
+        }
+    }
+}
+
+/// Every hidraw node whose device could be identified
+pub(crate) fn devices() -> io::Result<Vec<Candidate>> {
+    let mut devices = Vec::new();
+
+    for entry in fs::read_dir(SYSFS_HIDRAW)? {
+        let entry = entry?;
+        let node = entry.path();
+
+        let Ok(uevent) = fs::read_to_string(node.join("device/uevent")) else {
+            continue;
+        };
+        let Some((vendor_id, product_id)) = uevent.lines().find_map(|line| line.strip_prefix("HID_ID=")).and_then(parse_hid_id) else {
+            continue;
+        };
+
+        // The USB device the interface belongs to knows the controller revision
+        let release = fs::read_to_string(node.join("device/../../bcdDevice")).ok().and_then(|bcd| u16::from_str_radix(bcd.trim(), 16).ok());
+
+        devices.push(Candidate {
+            path: Path::new("/dev").join(entry.file_name()).to_string_lossy().into_owned(),
+            ids: DeviceIds {
+                vendor_id,
+                product_id,
+                release,
+                usage: read_usage(&node),
+            },
+            serial_number: uevent.lines().find_map(|line| line.strip_prefix("HID_UNIQ=")).filter(|uniq| !uniq.is_empty()).map(str::to_string),
+        });
+    }
+
+    devices.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(devices)
+}
+
+/// Parse the vendor and product IDs out of something like `0003:0000048D:0000C995`
+fn parse_hid_id(hid_id: &str) -> Option<(u16, u16)> {
+    let mut parts = hid_id.split(':').skip(1).map(|part| u32::from_str_radix(part, 16).ok().and_then(|id| u16::try_from(id).ok()));
+
+    Some((parts.next()??, parts.next()??))
+}
+
+/// The first usage page and usage of the report descriptor, which is what hidapi reports as those of the interface
+fn read_usage(node: &Path) -> Option<(u16, u16)> {
+    let descriptor = fs::read(node.join("device/report_descriptor")).ok()?;
+
+    let mut usage_page = None;
+    let mut i = 0;
+    while i < descriptor.len() {
+        let prefix = descriptor[i];
+        let size = match prefix & 0x03 {
+            3 => 4,
+            size => size as usize,
+        };
+        let value = descriptor.get(i + 1..i + 1 + size)?.iter().rev().fold(0_u32, |value, byte| value << 8 | u32::from(*byte));
+
+        match prefix & 0xfc {
+            0x04 => usage_page = Some(value as u16),
+            0x08 => return usage_page.map(|usage_page| (usage_page, value as u16)),
+            _ => {}
+        }
+
+        i += 1 + size;
+    }
+
+    None
+}
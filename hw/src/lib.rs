@@ -0,0 +1,782 @@
+//! Control the four zone RGB keyboards found on Lenovo Legion and Ideapad Gaming laptops.
+//!
+//! This is the hardware layer of [Legion Keyboard Control](https://github.com/4JX/L5P-Keyboard-RGB), with nothing
+//! in it about the GUI or the software effects. It finds the keyboards, builds the reports they understand and sends
+//! them the effect, speed, brightness and zone colors.
+//!
+//! ```no_run
+//! use std::sync::{atomic::AtomicBool, Arc};
+//!
+//! use legion_kb_rgb_hw::BaseEffects;
+//!
+//! # fn main() -> legion_kb_rgb_hw::error::Result<()> {
+//! let mut keyboard = legion_kb_rgb_hw::get_keyboard(Arc::new(AtomicBool::new(false)))?;
+//!
+//! keyboard.set_effect(BaseEffects::Static)?;
+//! keyboard.set_brightness(2)?;
+//! keyboard.set_colors_to(&[255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255])?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! What each model supports is listed in [`models`]. On Linux, the user needs access to the device, which is usually
+//! granted through a udev rule. Devices are reached through hidapi by default, see [`set_backend`] for the
+//! alternatives.
+//!
+//! The logo and rear lightbar some models have are controlled separately, see [`lights`].
+
+use error::{RangeError, RangeErrorKind, Result};
+use hidapi::{DeviceInfo, HidApi, HidDevice};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, OnceLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+mod calibration;
+pub mod error;
+#[cfg(target_os = "linux")]
+mod hidraw;
+pub mod lights;
+pub mod models;
+
+pub use calibration::Calibration;
+use models::{DeviceIds, Model, Protocol};
+
+/// Set through [`set_backend`]
+static BACKEND: OnceLock<Backend> = OnceLock::new();
+
+/// The ranges of the four zone models, see [`Model`] for those of a specific one
+pub const SPEED_RANGE: std::ops::RangeInclusive<u8> = 1..=4;
+pub const BRIGHTNESS_RANGE: std::ops::RangeInclusive<u8> = 1..=2;
+pub const ZONE_RANGE: std::ops::RangeInclusive<u8> = 0..=3;
+
+/// The effects built into the keyboard, which keep running without anything being sent
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BaseEffects {
+    Static,
+    Breath,
+    Smooth,
+    LeftWave,
+    RightWave,
+}
+
+/// Everything that is sent to the keyboard at once
+#[derive(Clone)]
+pub struct LightingState {
+    effect_type: BaseEffects,
+    speed: u8,
+    brightness: u8,
+    rgb_values: [u8; 12],
+    /// Only sent to models with a fifth zone
+    fifth_zone: [u8; 3],
+}
+
+impl LightingState {
+    pub fn effect_type(&self) -> BaseEffects {
+        self.effect_type
+    }
+
+    pub fn speed(&self) -> u8 {
+        self.speed
+    }
+
+    pub fn brightness(&self) -> u8 {
+        self.brightness
+    }
+
+    /// The red, green and blue values of each zone, from left to right
+    pub fn rgb_values(&self) -> [u8; 12] {
+        self.rgb_values
+    }
+
+    /// The color of the zone after the four others, on the models that have one
+    pub fn fifth_zone(&self) -> [u8; 3] {
+        self.fifth_zone
+    }
+}
+
+/// How the devices are reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backend {
+    /// Through hidapi, which works everywhere
+    #[default]
+    Hidapi,
+    /// Through `/dev/hidraw*` directly, without needing libusb
+    #[cfg(target_os = "linux")]
+    Hidraw,
+}
+
+/// Reach the devices through the given backend from now on, only the first call has an effect
+pub fn set_backend(backend: Backend) {
+    let _ = BACKEND.set(backend);
+}
+
+fn backend() -> Backend {
+    BACKEND.get().copied().unwrap_or_default()
+}
+
+/// An opened device, whichever backend it came from
+enum Handle {
+    Hidapi(HidDevice),
+    #[cfg(target_os = "linux")]
+    Hidraw(hidraw::HidrawDevice),
+}
+
+impl Handle {
+    fn open(path: &str) -> Result<Self> {
+        match backend() {
+            Backend::Hidapi => {
+                let api: HidApi = HidApi::new()?;
+                let path = std::ffi::CString::new(path).map_err(|_| error::Error::DeviceNotFound)?;
+                Ok(Self::Hidapi(api.open_path(&path)?))
+            }
+            #[cfg(target_os = "linux")]
+            Backend::Hidraw => Ok(Self::Hidraw(hidraw::HidrawDevice::open(path)?)),
+        }
+    }
+
+    fn send_feature_report(&self, payload: &[u8]) -> Result<()> {
+        match self {
+            Self::Hidapi(device) => device.send_feature_report(payload)?,
+            #[cfg(target_os = "linux")]
+            Self::Hidraw(device) => device.send_feature_report(payload)?,
+        }
+
+        Ok(())
+    }
+
+    fn get_feature_report(&self, buf: &mut [u8]) -> Result<usize> {
+        let read = match self {
+            Self::Hidapi(device) => device.get_feature_report(buf)?,
+            #[cfg(target_os = "linux")]
+            Self::Hidraw(device) => device.get_feature_report(buf)?,
+        };
+
+        Ok(read)
+    }
+
+    /// Ask the controller for the state it's in, `None` if it doesn't answer with something that makes sense
+    ///
+    /// The protocol has no request for this, but the controllers seen so far answer a read of the report with the last
+    /// one they were sent. Those that don't, or answer with zeros until set, are left alone.
+    fn read_state(&self, model: &Model) -> Option<LightingState> {
+        let mut payload = [0; 33];
+        payload[0] = model.protocol.report_id();
+        let read = self.get_feature_report(&mut payload).ok()?;
+        if read < payload.len() || payload[1] != 0x16 {
+            return None;
+        }
+
+        let effect_type = match payload[2] {
+            0x01 => BaseEffects::Static,
+            0x03 => BaseEffects::Breath,
+            0x06 => BaseEffects::Smooth,
+            0x04 if payload[18] == 0x1 => BaseEffects::RightWave,
+            0x04 => BaseEffects::LeftWave,
+            _ => return None,
+        };
+        let (speed, brightness) = (payload[3], payload[4]);
+        if !model.supports(effect_type) || !(1..=model.speeds).contains(&speed) || !(1..=model.brightness_levels).contains(&brightness) {
+            return None;
+        }
+
+        let mut rgb_values = [0; 12];
+        rgb_values.copy_from_slice(&payload[5..(12 + 5)]);
+        let mut fifth_zone = [0; 3];
+        if let Protocol::FiveZone { .. } = model.protocol {
+            fifth_zone.copy_from_slice(&payload[20..23]);
+        }
+
+        Some(LightingState {
+            effect_type,
+            speed,
+            brightness,
+            rgb_values,
+            fifth_zone,
+        })
+    }
+}
+
+/// A device as listed by the backend in use
+pub(crate) struct Candidate {
+    path: String,
+    ids: DeviceIds,
+    serial_number: Option<String>,
+}
+
+fn candidates() -> Result<Vec<Candidate>> {
+    match backend() {
+        Backend::Hidapi => {
+            let api: HidApi = HidApi::new()?;
+
+            Ok(api
+                .device_list()
+                .map(|d| Candidate {
+                    path: d.path().to_string_lossy().into_owned(),
+                    ids: d.into(),
+                    serial_number: d.serial_number().map(str::to_string),
+                })
+                .collect())
+        }
+        #[cfg(target_os = "linux")]
+        Backend::Hidraw => Ok(hidraw::devices()?),
+    }
+}
+
+type StateObserver = Box<dyn FnMut(&LightingState) + Send>;
+type LostHandler = Box<dyn FnMut() + Send>;
+type Sleeper = Box<dyn Fn(Duration) + Send>;
+type OutputUpdates = Box<dyn FnMut() -> Option<OutputUpdate> + Send>;
+
+/// A change to how the colors are sent, see [`Keyboard::set_output_updates`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputUpdate {
+    /// Same as [`Keyboard::set_overlay`]
+    Overlay([Option<[u8; 3]>; 4]),
+    /// Same as [`Keyboard::set_brightness_scale`]
+    BrightnessScale(f32),
+}
+
+/// An opened keyboard, which remembers its state so each setting can be changed on its own
+///
+/// Every change is sent right away. The stop signal interrupts [`Keyboard::transition_colors_to`], so an effect can be
+/// stopped from another thread.
+pub struct Keyboard {
+    /// `None` for a virtual keyboard that only keeps track of the state
+    keyboard_hid: Option<Handle>,
+    current_state: LightingState,
+    stop_signal: Arc<AtomicBool>,
+    observer: Option<StateObserver>,
+    on_lost: Option<LostHandler>,
+    /// Waits between the steps of a transition, `None` to just sleep
+    sleep: Option<Sleeper>,
+    /// Hands out the changes to make before the next state is sent
+    output_updates: Option<OutputUpdates>,
+    /// Shortest time between two states sent, `None` for no limit
+    frame_interval: Option<Duration>,
+    /// When the last state was sent, to keep to the frame interval
+    last_sent: Option<Instant>,
+    calibration: Calibration,
+    /// Colors shown on some zones over whatever is set, like status indicators
+    overlay: [Option<[u8; 3]>; 4],
+    /// Multiplies every color sent, from 0 to 1, to dim the keyboard finer than its brightness levels do
+    brightness_scale: f32,
+    /// What the keyboard was showing when it was opened, if it could tell
+    read_back: Option<LightingState>,
+    /// The device stopped responding, nothing gets sent until it's reconnected
+    lost: bool,
+    model: &'static Model,
+}
+
+#[allow(dead_code)]
+impl Keyboard {
+    fn build_payload(&self) -> Result<[u8; 33]> {
+        let keyboard_state = &self.current_state;
+
+        if !(1..=self.model.speeds).contains(&keyboard_state.speed) {
+            return Err(RangeError { kind: RangeErrorKind::Speed }.into());
+        }
+        if !(1..=self.model.brightness_levels).contains(&keyboard_state.brightness) {
+            return Err(RangeError { kind: RangeErrorKind::Brightness }.into());
+        }
+
+        let mut payload: [u8; 33] = [0; 33];
+        payload[0] = self.model.protocol.report_id();
+        payload[1] = 0x16;
+        payload[2] = match keyboard_state.effect_type {
+            BaseEffects::Static => 0x01,
+            BaseEffects::Breath => 0x03,
+            BaseEffects::Smooth => 0x06,
+            BaseEffects::LeftWave => {
+                payload[19] = 0x1;
+                0x04
+            }
+            BaseEffects::RightWave => {
+                payload[18] = 0x1;
+                0x04
+            }
+        };
+
+        payload[3] = keyboard_state.speed;
+        payload[4] = keyboard_state.brightness;
+
+        if self.takes_colors() {
+            let mut rgb_values = keyboard_state.rgb_values;
+            for (zone, rgb) in self.overlay.iter().enumerate() {
+                if let Some(rgb) = rgb {
+                    rgb_values[zone * 3..zone * 3 + 3].copy_from_slice(rgb);
+                }
+            }
+            if self.brightness_scale < 1.0 {
+                rgb_values = rgb_values.map(|value| (f32::from(value) * self.brightness_scale).round() as u8);
+            }
+            payload[5..(12 + 5)].copy_from_slice(&self.calibration.apply(rgb_values));
+
+            if let Protocol::FiveZone { .. } = self.model.protocol {
+                let fifth_zone = keyboard_state.fifth_zone.map(|value| (f32::from(value) * self.brightness_scale).round() as u8);
+                payload[20..23].copy_from_slice(&self.calibration.apply_zone(fifth_zone));
+            }
+        };
+
+        Ok(payload)
+    }
+
+    /// Send the current state again
+    ///
+    /// Waits first if the last state was sent less than the frame interval ago, see [`Keyboard::set_frame_interval`].
+    pub fn refresh(&mut self) -> Result<()> {
+        self.take_output_updates();
+        let payload = self.build_payload()?;
+
+        if let Some(left) = self.frame_interval.zip(self.last_sent).and_then(|(interval, last_sent)| interval.checked_sub(last_sent.elapsed())) {
+            thread::sleep(left);
+        }
+        self.last_sent = Some(Instant::now());
+
+        if let Some(keyboard_hid) = &self.keyboard_hid {
+            // Effects keep running until they're told to stop, so rather than failing every call, keep track of
+            // the state without sending it and let whoever is listening know
+            if keyboard_hid.send_feature_report(&payload).is_err() {
+                self.keyboard_hid = None;
+                self.lost = true;
+                self.stop_signal.store(true, Ordering::SeqCst);
+                if let Some(on_lost) = &mut self.on_lost {
+                    on_lost();
+                }
+            }
+        }
+
+        if let Some(observer) = &mut self.observer {
+            observer(&self.current_state);
+        }
+
+        Ok(())
+    }
+
+    /// Get notified of every state sent to the keyboard
+    pub fn set_observer(&mut self, observer: impl FnMut(&LightingState) + Send + 'static) {
+        self.observer = Some(Box::new(observer));
+    }
+
+    /// Get notified when the device stops responding, after which it has to be reconnected
+    pub fn set_on_lost(&mut self, on_lost: impl FnMut() + Send + 'static) {
+        self.on_lost = Some(Box::new(on_lost));
+    }
+
+    /// Wait through the given function between the steps of a transition instead of sleeping, to speed transitions
+    /// up or slow them down, or to skip the waiting entirely in tests
+    pub fn set_sleep(&mut self, sleep: impl Fn(Duration) + Send + 'static) {
+        self.sleep = Some(Box::new(sleep));
+    }
+
+    /// Take the changes `updates` hands out before every state sent, until it runs out, so they show with the next frame of
+    /// whatever effect is playing instead of having to restart it
+    pub fn set_output_updates(&mut self, updates: impl FnMut() -> Option<OutputUpdate> + Send + 'static) {
+        self.output_updates = Some(Box::new(updates));
+    }
+
+    fn take_output_updates(&mut self) {
+        let Some(updates) = &mut self.output_updates else {
+            return;
+        };
+
+        while let Some(update) = updates() {
+            match update {
+                OutputUpdate::Overlay(overlay) => self.overlay = overlay,
+                OutputUpdate::BrightnessScale(scale) => self.set_brightness_scale(scale),
+            }
+        }
+    }
+
+    /// Correct every color sent from now on, the state reported to the observer keeps the colors as they were set
+    pub fn set_calibration(&mut self, calibration: Calibration) {
+        self.calibration = calibration;
+    }
+
+    /// Show a color on the zones that have one instead of what gets set, until changed again
+    ///
+    /// Like the calibration, the overlay only applies to the effects whose colors are sent and isn't reported to the
+    /// observer.
+    pub fn set_overlay(&mut self, overlay: [Option<[u8; 3]>; 4]) {
+        self.overlay = overlay;
+    }
+
+    /// Scale every color sent from now on, overlay included, from 0 (off) to 1 (as set)
+    ///
+    /// Like the calibration, this only applies to the effects whose colors are sent and isn't reported to the observer.
+    pub fn set_brightness_scale(&mut self, scale: f32) {
+        self.brightness_scale = scale.clamp(0.0, 1.0);
+    }
+
+    /// Send at most one state every `interval`, so slow hardware and the CPU aren't kept busy with changes nobody can see
+    ///
+    /// Sending a state any sooner waits for the interval to be up, while transitions merge their steps instead so they
+    /// still take as long as asked.
+    pub fn set_frame_interval(&mut self, interval: Option<Duration>) {
+        self.frame_interval = interval;
+    }
+
+    /// The steps and delay to actually use for a transition, taking just as long with fewer steps if they're too close
+    fn limit_steps(&self, steps: u8, delay_between_steps: u64) -> (u8, u64) {
+        let Some(interval) = self.frame_interval else {
+            return (steps, delay_between_steps);
+        };
+        let interval = u64::try_from(interval.as_millis()).unwrap_or(u64::MAX).max(1);
+        if steps == 0 || delay_between_steps >= interval {
+            return (steps, delay_between_steps);
+        }
+
+        let total = u64::from(steps) * delay_between_steps;
+        let limited_steps = (total / interval).clamp(1, u64::from(steps));
+
+        (limited_steps as u8, total / limited_steps)
+    }
+
+    fn sleep(&self, duration: Duration) {
+        match &self.sleep {
+            Some(sleep) => sleep(duration),
+            None => thread::sleep(duration),
+        }
+    }
+
+    /// Whether the device stopped responding, see [`Keyboard::reconnect`]
+    pub fn is_lost(&self) -> bool {
+        self.lost
+    }
+
+    /// Open the device again after it was lost, and send it the last known state
+    pub fn reconnect(&mut self) -> Result<()> {
+        if !self.lost {
+            return Ok(());
+        }
+
+        let candidate = candidates()?
+            .into_iter()
+            .find(|candidate| models::find_ids(&candidate.ids) == Some(self.model))
+            .ok_or(error::Error::DeviceNotFound)?;
+
+        self.keyboard_hid = Some(Handle::open(&candidate.path)?);
+        self.lost = false;
+        self.refresh()
+    }
+
+    /// What's known about the model of the keyboard
+    pub fn model(&self) -> &'static Model {
+        self.model
+    }
+
+    /// The state last sent, or about to be sent while the keyboard is lost
+    pub fn state(&self) -> &LightingState {
+        &self.current_state
+    }
+
+    /// The state the keyboard was in when it was opened, `None` if the controller couldn't be asked
+    ///
+    /// The colors are those the controller was sent, so they went through whatever calibration was in use then.
+    pub fn read_back_state(&self) -> Option<&LightingState> {
+        self.read_back.as_ref()
+    }
+
+    /// Whether the zone colors can be changed with the current effect
+    fn takes_colors(&self) -> bool {
+        !self.model.quirks.colors_need_static || matches!(self.current_state.effect_type, BaseEffects::Static | BaseEffects::Breath)
+    }
+
+    /// Fails if the model doesn't have the effect
+    pub fn set_effect(&mut self, effect: BaseEffects) -> Result<()> {
+        if !self.model.supports(effect) {
+            return Err(RangeError { kind: RangeErrorKind::Effect }.into());
+        }
+
+        self.current_state.effect_type = effect;
+        self.refresh()?;
+
+        Ok(())
+    }
+
+    /// Speed of the built in effects, from 1 up to [`Model::speeds`]
+    pub fn set_speed(&mut self, speed: u8) -> Result<()> {
+        if !(1..=self.model.speeds).contains(&speed) {
+            return Err(RangeError { kind: RangeErrorKind::Speed }.into());
+        }
+
+        self.current_state.speed = speed;
+        self.refresh()?;
+
+        Ok(())
+    }
+
+    /// From 1 up to [`Model::brightness_levels`]
+    pub fn set_brightness(&mut self, brightness: u8) -> Result<()> {
+        if !(1..=self.model.brightness_levels).contains(&brightness) {
+            return Err(RangeError { kind: RangeErrorKind::Brightness }.into());
+        }
+        self.current_state.brightness = brightness;
+        self.refresh()?;
+
+        Ok(())
+    }
+
+    /// Set the color of a single zone, starting at 0 on the left
+    pub fn set_zone_by_index(&mut self, zone_index: u8, new_values: [u8; 3]) -> Result<()> {
+        if zone_index >= self.model.zones {
+            return Err(RangeError { kind: RangeErrorKind::Zone }.into());
+        }
+
+        if zone_index == 4 {
+            self.current_state.fifth_zone = new_values;
+            return self.refresh();
+        }
+
+        for (i, _) in new_values.iter().enumerate() {
+            let full_index = (zone_index * 3 + i as u8) as usize;
+            self.current_state.rgb_values[full_index] = new_values[i];
+        }
+        self.refresh()?;
+
+        Ok(())
+    }
+
+    /// Set the colors of every zone, laid out like [`LightingState::rgb_values`]
+    ///
+    /// Ignored while an effect that doesn't take colors is active on models with that quirk.
+    pub fn set_colors_to(&mut self, new_values: &[u8; 12]) -> Result<()> {
+        if self.takes_colors() {
+            for (i, _) in new_values.iter().enumerate() {
+                self.current_state.rgb_values[i] = new_values[i];
+            }
+            self.refresh()?;
+        }
+
+        Ok(())
+    }
+
+    /// Set every zone to the same color
+    pub fn solid_set_colors_to(&mut self, new_values: [u8; 3]) -> Result<()> {
+        if self.takes_colors() {
+            for i in (0..12).step_by(3) {
+                self.current_state.rgb_values[i] = new_values[0];
+                self.current_state.rgb_values[i + 1] = new_values[1];
+                self.current_state.rgb_values[i + 2] = new_values[2];
+            }
+            self.refresh()?;
+        }
+
+        Ok(())
+    }
+
+    /// Fade to the given colors in `steps` steps, waiting `delay_between_steps` milliseconds after each one
+    ///
+    /// Blocks until done or until the stop signal is set. Steps closer together than the frame interval are merged, see
+    /// [`Keyboard::set_frame_interval`].
+    pub fn transition_colors_to(&mut self, target_colors: &[u8; 12], steps: u8, delay_between_steps: u64) -> Result<()> {
+        if self.takes_colors() {
+            let (steps, delay_between_steps) = self.limit_steps(steps, delay_between_steps);
+            let mut new_values = self.current_state.rgb_values.map(f32::from);
+            let mut color_differences: [f32; 12] = [0.0; 12];
+            for index in 0..12 {
+                color_differences[index] = (f32::from(target_colors[index]) - f32::from(self.current_state.rgb_values[index])) / f32::from(steps);
+            }
+            if !self.stop_signal.load(Ordering::SeqCst) {
+                for _step_num in 1..=steps {
+                    if self.stop_signal.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    for (index, _) in color_differences.iter().enumerate() {
+                        new_values[index] += color_differences[index];
+                    }
+                    self.current_state.rgb_values = new_values.map(|val| val as u8);
+
+                    self.refresh()?;
+                    self.sleep(Duration::from_millis(delay_between_steps));
+                }
+                self.set_colors_to(target_colors)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn is_known_device(d: &DeviceInfo) -> bool {
+    models::find(d).is_some()
+}
+
+/// A supported keyboard that was found, before opening it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyboardInfo {
+    pub product_id: u16,
+    pub path: String,
+    pub model: &'static Model,
+}
+
+impl KeyboardInfo {
+    /// Identifies the model across restarts, unlike the path
+    pub fn id(&self) -> String {
+        format!("{:04x}", self.product_id)
+    }
+}
+
+/// List every supported keyboard, each one only once even if it exposes several interfaces
+pub fn find_keyboards() -> Result<Vec<KeyboardInfo>> {
+    let mut keyboards: Vec<(KeyboardInfo, Option<String>)> = Vec::new();
+
+    for candidate in candidates()? {
+        let Some(model) = models::find_ids(&candidate.ids) else {
+            continue;
+        };
+
+        if keyboards
+            .iter()
+            .any(|(info, serial)| info.product_id == candidate.ids.product_id && *serial == candidate.serial_number)
+        {
+            continue;
+        }
+
+        let info = KeyboardInfo {
+            product_id: candidate.ids.product_id,
+            path: candidate.path,
+            model,
+        };
+        keyboards.push((info, candidate.serial_number));
+    }
+
+    Ok(keyboards.into_iter().map(|(info, _)| info).collect())
+}
+
+/// Open the first supported keyboard that can be found
+pub fn get_keyboard(stop_signal: Arc<AtomicBool>) -> Result<Keyboard> {
+    let info = find_keyboards()?.into_iter().next().ok_or(error::Error::DeviceNotFound)?;
+
+    get_keyboard_at(&info, stop_signal)
+}
+
+/// Open a keyboard listed by [`find_keyboards`]
+///
+/// The state the keyboard is in gets read back and sent again as is where possible, rather than resetting it to black
+/// until something else is set, see [`Keyboard::read_back_state`].
+pub fn get_keyboard_at(info: &KeyboardInfo, stop_signal: Arc<AtomicBool>) -> Result<Keyboard> {
+    let mut keyboard = get_keyboard_untouched_at(info, stop_signal)?;
+
+    keyboard.refresh()?;
+    Ok(keyboard)
+}
+
+/// Same as [`get_keyboard_at`] without sending anything, so the keyboard keeps showing what it was until something is set
+pub fn get_keyboard_untouched_at(info: &KeyboardInfo, stop_signal: Arc<AtomicBool>) -> Result<Keyboard> {
+    let handle = Handle::open(&info.path)?;
+    let read_back = handle.read_state(info.model);
+
+    Ok(Keyboard {
+        keyboard_hid: Some(handle),
+        current_state: read_back.clone().unwrap_or_else(initial_state),
+        stop_signal,
+        observer: None,
+        sleep: None,
+        output_updates: None,
+        frame_interval: None,
+        last_sent: None,
+        calibration: Calibration::default(),
+        overlay: [None; 4],
+        brightness_scale: 1.0,
+        read_back,
+        on_lost: None,
+        lost: false,
+        model: info.model,
+    })
+}
+
+/// Get a keyboard that isn't backed by any device, useful to run effects without the hardware
+pub fn get_virtual_keyboard(stop_signal: Arc<AtomicBool>) -> Keyboard {
+    Keyboard {
+        keyboard_hid: None,
+        current_state: initial_state(),
+        stop_signal,
+        observer: None,
+        sleep: None,
+        output_updates: None,
+        frame_interval: None,
+        last_sent: None,
+        calibration: Calibration::default(),
+        overlay: [None; 4],
+        brightness_scale: 1.0,
+        read_back: None,
+        on_lost: None,
+        lost: false,
+        model: &models::VIRTUAL,
+    }
+}
+
+fn initial_state() -> LightingState {
+    LightingState {
+        effect_type: BaseEffects::Static,
+        speed: 1,
+        brightness: 1,
+        rgb_values: [0; 12],
+        fifth_zone: [0; 3],
+    }
+}
+
+/// Get the product ID of the first supported keyboard found, without opening it
+pub fn find_keyboard_product_id() -> Result<u16> {
+    find_keyboards()?.first().map(|info| info.product_id).ok_or(error::Error::DeviceNotFound)
+}
+
+/// A HID device as seen while looking for the keyboard
+#[derive(Debug, Clone)]
+pub struct DeviceSummary {
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub usage_page: u16,
+    pub usage: u16,
+    pub product: Option<String>,
+    pub path: String,
+    /// Whether it's one of the supported keyboards
+    pub supported: bool,
+}
+
+/// List every HID device, including the ones that aren't supported
+pub fn list_devices() -> Result<Vec<DeviceSummary>> {
+    let api: HidApi = HidApi::new()?;
+
+    let devices = api
+        .device_list()
+        .map(|d| DeviceSummary {
+            vendor_id: d.vendor_id(),
+            product_id: d.product_id(),
+            usage_page: d.usage_page(),
+            usage: d.usage(),
+            product: d.product_string().map(str::to_string),
+            path: d.path().to_string_lossy().into_owned(),
+            supported: is_known_device(d),
+        })
+        .collect();
+
+    Ok(devices)
+}
+
+/// Check whether the keyboard can be opened, without sending anything to it
+pub fn check_access() -> Result<()> {
+    let info = find_keyboards()?.into_iter().next().ok_or(error::Error::DeviceNotFound)?;
+    Handle::open(&info.path)?;
+
+    Ok(())
+}
+
+/// List the vendor and product IDs of every HID device, to find out the IDs of an unsupported model
+pub fn find_possible_keyboards() -> Result<Vec<String>> {
+    let api: HidApi = HidApi::new()?;
+
+    let mut list = api
+        .device_list()
+        // .filter(|d| d.vendor_id() == 0x048d)
+        .map(|d| format!("{:#06x}:{:#06x}", d.vendor_id(), d.product_id()))
+        .collect::<Vec<String>>();
+
+    list.dedup();
+    Ok(list)
+}
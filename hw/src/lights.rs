@@ -0,0 +1,67 @@
+//! The other lights of the laptop, the logo on the lid and the lightbar around the rear ports.
+//!
+//! They can only be turned on or off. On Linux they're reached through the `platform::ylogo` and `platform::ioport`
+//! LEDs added by the Legion kernel module (LenovoLegionLinux), other platforms don't expose them to regular programs.
+
+use crate::error::Result;
+
+#[cfg(target_os = "linux")]
+const SYSFS_LEDS: &str = "/sys/class/leds";
+
+/// A light of the laptop other than the keyboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Section {
+    /// The Legion logo on the lid
+    Logo,
+    /// The lightbar around the rear ports
+    Lightbar,
+}
+
+impl Section {
+    pub const ALL: [Self; 2] = [Self::Logo, Self::Lightbar];
+
+    /// The name of the LED under `/sys/class/leds`
+    #[cfg(target_os = "linux")]
+    pub fn led_name(self) -> &'static str {
+        match self {
+            Self::Logo => "platform::ylogo",
+            Self::Lightbar => "platform::ioport",
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn brightness_path(self) -> std::path::PathBuf {
+        std::path::Path::new(SYSFS_LEDS).join(self.led_name()).join("brightness")
+    }
+
+    /// Whether the laptop has the light and it can be controlled
+    pub fn is_available(self) -> bool {
+        #[cfg(target_os = "linux")]
+        {
+            self.brightness_path().exists()
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            false
+        }
+    }
+
+    pub fn set(self, on: bool) -> Result<()> {
+        #[cfg(target_os = "linux")]
+        {
+            std::fs::write(self.brightness_path(), if on { "1" } else { "0" })?;
+            Ok(())
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = on;
+            Err(crate::error::Error::DeviceNotFound)
+        }
+    }
+}
+
+/// Every section the laptop has
+pub fn available() -> Vec<Section> {
+    Section::ALL.into_iter().filter(|section| section.is_available()).collect()
+}
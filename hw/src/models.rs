@@ -0,0 +1,222 @@
+//! What is known about every supported model.
+//!
+//! Adding support for a new model should mostly be a matter of adding an entry to [`MODELS`], only needing changes
+//! elsewhere if it talks a new [`Protocol`] or has a quirk that isn't covered yet.
+
+use std::sync::OnceLock;
+
+use hidapi::DeviceInfo;
+
+use crate::BaseEffects;
+
+/// Set through [`force`], along with the product ID of the only device to drive if one was given
+static FORCED: OnceLock<(&'static Model, Option<u16>)> = OnceLock::new();
+
+const ALL_EFFECTS: &[BaseEffects] = &[BaseEffects::Static, BaseEffects::Breath, BaseEffects::Smooth, BaseEffects::LeftWave, BaseEffects::RightWave];
+
+/// How the lighting controller expects to be talked to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    /// A single 33 byte feature report that sets the effect, speed, brightness and the colors of four zones at once.
+    /// Every model so far uses `0xcc` as the report ID, while some newer controllers are known to expect another one.
+    FourZone { report_id: u8 },
+    /// The same report with the color of a fifth zone at bytes 20 to 22, right after the wave direction, for controllers
+    /// that light the lightbar of some Slim models along with the keyboard
+    FiveZone { report_id: u8 },
+}
+
+impl Protocol {
+    pub fn report_id(self) -> u8 {
+        match self {
+            Self::FourZone { report_id } | Self::FiveZone { report_id } => report_id,
+        }
+    }
+
+    pub fn zones(self) -> u8 {
+        match self {
+            Self::FourZone { .. } => 4,
+            Self::FiveZone { .. } => 5,
+        }
+    }
+
+    /// Parse `four-zone` or `five-zone`, optionally followed by `@` and the report ID in hex (such as `five-zone@ce`)
+    pub fn parse(spec: &str) -> Option<Self> {
+        let (name, report_id) = match spec.split_once('@') {
+            Some((name, report_id)) => (name, u8::from_str_radix(report_id.trim_start_matches("0x"), 16).ok()?),
+            None => (spec, 0xcc),
+        };
+
+        match name.to_ascii_lowercase().as_str() {
+            "four-zone" => Some(Self::FourZone { report_id }),
+            "five-zone" => Some(Self::FiveZone { report_id }),
+            _ => None,
+        }
+    }
+}
+
+/// Behavior that differs between models speaking the same protocol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    /// The zone colors are only taken in while a static or breathing effect is active
+    pub colors_need_static: bool,
+}
+
+impl Quirks {
+    const FOUR_ZONE: Self = Self { colors_need_static: true };
+}
+
+/// What identifies a device, as far as the backend in use can tell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct DeviceIds {
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub release: Option<u16>,
+    /// The usage page and usage of the interface, `None` to accept any of them
+    pub usage: Option<(u16, u16)>,
+}
+
+impl From<&DeviceInfo> for DeviceIds {
+    fn from(d: &DeviceInfo) -> Self {
+        Self {
+            vendor_id: d.vendor_id(),
+            product_id: d.product_id(),
+            release: Some(d.release_number()),
+            // Linux doesn't always report the usage, and there's only one interface to pick from anyway
+            usage: cfg!(target_os = "windows").then(|| (d.usage_page(), d.usage())),
+        }
+    }
+}
+
+/// A keyboard model, how to recognize it and what it can do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Model {
+    pub name: &'static str,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    /// The interface to use on Windows, where every device exposes several of them
+    pub usage_page: u16,
+    pub usage: u16,
+    /// Only match a specific revision of the embedded controller, for when it changes without the product ID
+    pub release: Option<u16>,
+    pub protocol: Protocol,
+    pub zones: u8,
+    pub effects: &'static [BaseEffects],
+    pub brightness_levels: u8,
+    pub speeds: u8,
+    pub quirks: Quirks,
+}
+
+impl Model {
+    /// A four zone keyboard made by ITE, like every model supported so far
+    const fn ite_four_zone(name: &'static str, product_id: u16) -> Self {
+        Self {
+            name,
+            vendor_id: 0x048d,
+            product_id,
+            usage_page: 0xff89,
+            usage: 0x00cc,
+            release: None,
+            protocol: Protocol::FourZone { report_id: 0xcc },
+            zones: 4,
+            effects: ALL_EFFECTS,
+            brightness_levels: 2,
+            speeds: 4,
+            quirks: Quirks::FOUR_ZONE,
+        }
+    }
+
+    /// Whether the model has the given hardware effect
+    pub fn supports(&self, effect: BaseEffects) -> bool {
+        self.effects.contains(&effect)
+    }
+
+    fn matches(&self, ids: &DeviceIds) -> bool {
+        if ids.product_id != self.product_id {
+            return false;
+        }
+
+        if self.release.zip(ids.release).is_some_and(|(expected, release)| expected != release) {
+            return false;
+        }
+
+        self.matches_interface(ids, Some(self.product_id))
+    }
+
+    /// Whether the device is made by the same vendor and is the lighting interface, regardless of the model
+    ///
+    /// Without the usage every interface of the vendor looks the same, including per-key and touchpad controllers, so
+    /// only the one with `product_id` is taken then, and none if it wasn't given.
+    fn matches_interface(&self, ids: &DeviceIds, product_id: Option<u16>) -> bool {
+        ids.vendor_id == self.vendor_id && product_id.map_or(true, |product_id| product_id == ids.product_id) && ids.usage.map_or(product_id.is_some(), |usage| usage == (self.usage_page, self.usage))
+    }
+}
+
+/// Every supported model, newest first. Entries for a specific controller revision go before the general one.
+pub const MODELS: &[Model] = &[
+    Model::ite_four_zone("2024 Pro", 0xc995),
+    Model::ite_four_zone("2024", 0xc994),
+    Model::ite_four_zone("2024 LOQ", 0xc993),
+    Model::ite_four_zone("2023 Pro", 0xc985),
+    Model::ite_four_zone("2023", 0xc984),
+    Model::ite_four_zone("2023 LOQ", 0xc983),
+    Model::ite_four_zone("2022", 0xc975),
+    Model::ite_four_zone("2022 Ideapad", 0xc973),
+    Model::ite_four_zone("2021", 0xc965),
+    Model::ite_four_zone("2021 Ideapad", 0xc963),
+    Model::ite_four_zone("2020", 0xc955),
+];
+
+/// What virtual keyboards behave like, as they aren't any model in particular
+pub static VIRTUAL: Model = Model {
+    vendor_id: 0,
+    usage_page: 0,
+    usage: 0,
+    ..Model::ite_four_zone("Virtual", 0)
+};
+
+/// Find the model of a device, if it's supported
+pub fn find(d: &DeviceInfo) -> Option<&'static Model> {
+    find_ids(&d.into())
+}
+
+/// Same as [`find`], for backends that don't go through hidapi
+pub(crate) fn find_ids(ids: &DeviceIds) -> Option<&'static Model> {
+    if let Some((model, product_id)) = FORCED.get() {
+        return model.matches_interface(ids, *product_id).then_some(*model);
+    }
+
+    MODELS.iter().find(|model| model.matches(ids))
+}
+
+/// Treat the lighting interfaces from the same vendor as `model` from now on, to try out models that aren't known yet
+///
+/// Where the backend can't tell which interface does the lighting (hidapi on Linux), only the device with `product_id`
+/// is taken, and nothing if it's `None`, rather than sending lighting reports to every other controller of the vendor.
+pub fn force(model: &'static Model, product_id: Option<u16>) {
+    let _ = FORCED.set((model, product_id));
+}
+
+/// A model that isn't in [`MODELS`], talking the given protocol and otherwise like the four zone ones, to [`force`] on
+/// controllers that don't behave like any known model
+pub fn custom(protocol: Protocol) -> &'static Model {
+    // Only made once from the command line, so it can live for the rest of the program
+    Box::leak(Box::new(Model {
+        protocol,
+        zones: protocol.zones(),
+        ..Model::ite_four_zone("Custom", 0)
+    }))
+}
+
+/// Look a model up by its product ID in hex (such as `c995`) or by its name, ignoring case
+pub fn lookup(query: &str) -> Option<&'static Model> {
+    let product_id = u16::from_str_radix(query.trim_start_matches("0x"), 16).ok();
+
+    MODELS
+        .iter()
+        .find(|model| Some(model.product_id) == product_id || model.name.eq_ignore_ascii_case(query))
+}
+
+/// Find a model by its product ID alone, for when the device isn't at hand
+pub fn by_product_id(product_id: u16) -> Option<&'static Model> {
+    MODELS.iter().find(|model| model.product_id == product_id)
+}